@@ -0,0 +1,91 @@
+// Compares the old per-day rescan of `daily.log` with the one-pass
+// `CompletionIndex` for the workload behind `daily habits --days 365`.
+//
+// Run with: cargo bench --bench completion_log
+use chrono::NaiveDate;
+use criterion::{criterion_group, criterion_main, Criterion};
+use daily::storage::{Storage, TaskStore};
+use std::fs;
+use std::hint::black_box;
+use std::path::Path;
+use tempfile::TempDir;
+
+const HABITS: u32 = 20;
+const DAYS: u32 = 365;
+
+fn as_of() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 4, 13).unwrap()
+}
+
+// A year of history for 20 habits, each skipping a different day of the week.
+fn populate(dir: &Path) {
+    let storage = Storage::new(dir.to_path_buf()).unwrap();
+    for day in (0..DAYS).rev() {
+        let date = as_of() - chrono::Duration::days(day as i64);
+        for habit in 0..HABITS {
+            if (day + habit) % 7 != 0 {
+                storage.log_daily_completion(&habit.to_string(), "Habit", date, None).unwrap();
+            }
+        }
+    }
+}
+
+// The pre-index implementation: read and scan the whole log for every day checked.
+fn rescan_is_completed(dir: &Path, task_id: &str, date: NaiveDate) -> bool {
+    let content = fs::read_to_string(dir.join("daily.log")).unwrap();
+    let date_str = date.to_string();
+    content.lines().any(|line| {
+        let mut parts = line.splitn(3, " | ");
+        parts.next() == Some(date_str.as_str()) && parts.next() == Some(task_id)
+    })
+}
+
+fn rescan_grid(dir: &Path) -> usize {
+    let mut done = 0;
+    for habit in 0..HABITS {
+        let id = habit.to_string();
+        for day in 0..DAYS {
+            if rescan_is_completed(dir, &id, as_of() - chrono::Duration::days(day as i64)) {
+                done += 1;
+            }
+        }
+    }
+    done
+}
+
+fn indexed_grid(dir: &Path) -> usize {
+    // A fresh store per iteration, so the one-time parse is part of the measurement.
+    let storage = Storage::new(dir.to_path_buf()).unwrap();
+    (0..HABITS)
+        .map(|habit| {
+            storage.get_habit_grid(&habit.to_string(), as_of(), DAYS).unwrap()
+                .iter()
+                .filter(|&&d| d)
+                .count()
+        })
+        .sum()
+}
+
+fn indexed_streaks(dir: &Path) -> u32 {
+    let storage = Storage::new(dir.to_path_buf()).unwrap();
+    (0..HABITS)
+        .map(|habit| storage.get_streak_for_task(&habit.to_string(), as_of()).unwrap())
+        .sum()
+}
+
+fn bench_habit_grid(c: &mut Criterion) {
+    let dir = TempDir::new().unwrap();
+    populate(dir.path());
+    assert_eq!(rescan_grid(dir.path()), indexed_grid(dir.path()));
+
+    let mut group = c.benchmark_group("habit_grid_365_days_20_habits");
+    group.sample_size(10);
+    group.bench_function("rescan_per_day", |b| b.iter(|| black_box(rescan_grid(dir.path()))));
+    group.bench_function("completion_index", |b| b.iter(|| black_box(indexed_grid(dir.path()))));
+    group.finish();
+
+    c.bench_function("streaks_20_habits_indexed", |b| b.iter(|| black_box(indexed_streaks(dir.path()))));
+}
+
+criterion_group!(benches, bench_habit_grid);
+criterion_main!(benches);
@@ -28,9 +28,17 @@ struct ContentBlock {
     text: String,
 }
 
+/// Model used unless `ai.model` is set in config.toml.
+pub const DEFAULT_MODEL: &str = "claude-opus-4-6";
+
+/// Response length limit unless `ai.max_tokens` is set in config.toml.
+pub const DEFAULT_MAX_TOKENS: u32 = 1024;
+
 pub struct ClaudeClient {
     api_key: String,
     client: Client,
+    model: String,
+    max_tokens: u32,
 }
 
 // Structured output from the plan command
@@ -87,9 +95,21 @@ impl ClaudeClient {
         Ok(Self {
             api_key,
             client: Client::new(),
+            model: DEFAULT_MODEL.to_string(),
+            max_tokens: DEFAULT_MAX_TOKENS,
         })
     }
 
+    pub fn with_model(mut self, model: String) -> Self {
+        self.model = model;
+        self
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
     pub async fn chat(&self, prompt: &str, context: Option<&str>) -> Result<String> {
         let content = match context {
             Some(ctx) => format!("Context:\n{}\n\nRequest: {}", ctx, prompt),
@@ -97,8 +117,8 @@ impl ClaudeClient {
         };
 
         let request = ClaudeRequest {
-            model: "claude-opus-4-6".to_string(),
-            max_tokens: 1024,
+            model: self.model.clone(),
+            max_tokens: self.max_tokens,
             system: "You are a helpful task management assistant.".to_string(),
             messages: vec![Message { role: "user".to_string(), content }],
         };
@@ -155,8 +175,8 @@ Rules:
         );
 
         let request = ClaudeRequest {
-            model: "claude-opus-4-6".to_string(),
-            max_tokens: 1024,
+            model: self.model.clone(),
+            max_tokens: self.max_tokens,
             system,
             messages: vec![Message {
                 role: "user".to_string(),
@@ -211,14 +231,16 @@ pub fn format_task_list(tasks: &[crate::models::Task]) -> String {
     }
     let mut context = String::new();
     for task in tasks {
+        let tags = if task.tags.is_empty() { String::new() } else { format!(", Tags: {}", task.tags.join(", ")) };
         context.push_str(&format!(
-            "- [{}] {} (ID: {}, Priority: {}, Category: {}, Daily: {})\n",
+            "- [{}] {} (ID: {}, Priority: {}, Category: {}, Daily: {}{})\n",
             if task.completed { "x" } else { " " },
             task.title,
-            task.id,
-            task.priority.to_string(),
+            task.short_id(),
+            task.priority,
             task.category,
             task.is_daily,
+            tags,
         ));
     }
     context
@@ -401,6 +423,13 @@ mod tests {
         assert!(result.contains("[ ]"));
     }
 
+    #[test]
+    fn test_format_task_list_includes_tags() {
+        let t = task("1", "Report").with_tags(vec!["work".to_string(), "q3".to_string()]);
+        assert!(format_task_list(&[t]).contains("Tags: work, q3"));
+        assert!(!format_task_list(&[task("2", "Plain")]).contains("Tags"));
+    }
+
     #[test]
     fn test_format_task_list_multiple_tasks() {
         let tasks = vec![task("1", "Task A"), task("2", "Task B")];
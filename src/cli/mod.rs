@@ -8,10 +8,16 @@ pub struct Cli {
     #[arg(long, global = true, hide = true)]
     pub data_dir: Option<String>,
 
+    /// Profile to use instead of the active one (or set DAILY_PROFILE)
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+// Parsed once per run; boxing `Add`'s flags would only complicate matching.
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 pub enum Commands {
     /// Add a new task
@@ -19,13 +25,13 @@ pub enum Commands {
         /// Task title
         title: String,
 
-        /// Task priority (low, medium, high, critical)
-        #[arg(short, long, default_value = "medium")]
-        priority: String,
+        /// Task priority (low, medium, high, critical) [default: defaults.priority, or medium]
+        #[arg(short, long)]
+        priority: Option<String>,
 
-        /// Task category
-        #[arg(short, long, default_value = "default")]
-        category: String,
+        /// Task category [default: defaults.category, or default]
+        #[arg(short, long)]
+        category: Option<String>,
 
         /// Task description
         #[arg(short, long)]
@@ -59,6 +65,28 @@ pub enum Commands {
         #[arg(long)]
         days: Option<String>,
 
+        /// Recurring habit on a rule: "2 weeks on tue", "month on the first monday",
+        /// "year on mar 14", "3 times per week", or an RRULE like "FREQ=MONTHLY;BYMONTHDAY=15".
+        /// With --due, a one-off task that comes back with a new due date when completed
+        #[arg(long, conflicts_with = "days")]
+        every: Option<String>,
+
+        /// Count the next due date from when the task was done, not from its due date
+        #[arg(long, requires = "due", requires = "every")]
+        from_completion: bool,
+
+        /// First day the rule applies (YYYY-MM-DD) [default: today]
+        #[arg(long, requires = "every")]
+        starting: Option<String>,
+
+        /// Last day the rule applies (YYYY-MM-DD)
+        #[arg(long, requires = "every")]
+        until: Option<String>,
+
+        /// A day the rule skips (YYYY-MM-DD, repeatable)
+        #[arg(long, requires = "every")]
+        except: Vec<String>,
+
         /// Enable numeric tracking (e.g. count reps, minutes, pages)
         #[arg(long)]
         numeric: bool,
@@ -70,6 +98,14 @@ pub enum Commands {
         /// Daily target for numeric tasks (e.g. 100)
         #[arg(long)]
         target: Option<f64>,
+
+        /// Tag the task (repeat for several tags)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+
+        /// Make this a subtask of another one-off task
+        #[arg(long)]
+        parent: Option<String>,
     },
 
     /// List tasks
@@ -93,6 +129,28 @@ pub enum Commands {
         /// Randomly select one task from each category
         #[arg(short, long)]
         random: bool,
+
+        /// List archived tasks instead of active ones
+        #[arg(long)]
+        archived: bool,
+
+        /// Only tasks with this tag, or without it when prefixed with '-' (repeatable)
+        #[arg(long = "tag", allow_hyphen_values = true)]
+        tags: Vec<String>,
+
+        /// Show only open tasks waiting on an unfinished prerequisite
+        #[arg(long, conflicts_with_all = ["ready", "completed"])]
+        blocked: bool,
+
+        /// Show only open tasks whose prerequisites are all done
+        #[arg(long, conflicts_with = "completed")]
+        ready: bool,
+    },
+
+    /// Show everything about one task, including what it depends on and blocks
+    Show {
+        /// Task ID
+        id: String,
     },
 
     /// Complete a task
@@ -113,14 +171,43 @@ pub enum Commands {
     /// Mark all tasks as incomplete
     UncompleteAll,
 
-    /// Delete a task
+    /// Move a task to the trash
     Delete {
         /// Task ID
         id: String,
     },
 
-    /// Delete all tasks
-    DeleteAll,
+    /// Move all tasks to the trash
+    DeleteAll {
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Move completed one-off tasks out of the task list
+    ///
+    /// Without IDs, archives every completed one-off task (optionally only
+    /// those completed longer ago than --older-than).
+    Archive {
+        /// Task IDs to archive
+        ids: Vec<String>,
+
+        /// Only archive tasks completed longer ago than this (e.g. 30d, 2w)
+        #[arg(long, conflicts_with = "ids")]
+        older_than: Option<String>,
+    },
+
+    /// Move an archived task back into the task list
+    Unarchive {
+        /// Task ID
+        id: String,
+    },
+
+    /// List, restore or permanently remove deleted tasks
+    Trash {
+        #[command(subcommand)]
+        action: TrashCommand,
+    },
 
     /// Update task priority
     Priority {
@@ -140,6 +227,26 @@ pub enum Commands {
         category: String,
     },
 
+    /// Add or remove a task's tags
+    Tag {
+        #[command(subcommand)]
+        action: TagCommand,
+    },
+
+    /// Make a task wait for others to be done first
+    Depend {
+        /// Task ID
+        id: String,
+
+        /// Task that must be done first (repeatable)
+        #[arg(long, required = true)]
+        on: Vec<String>,
+
+        /// Drop these prerequisites instead of adding them
+        #[arg(long)]
+        remove: bool,
+    },
+
     /// Update task daily status
     Daily {
         /// Task ID
@@ -176,6 +283,10 @@ pub enum Commands {
         /// Show all tasks (including completed)
         #[arg(short = 'a', long)]
         all: bool,
+
+        /// Show today's tasks from every profile
+        #[arg(long)]
+        all_profiles: bool,
     },
 
     /// Show tasks for a specific date
@@ -186,7 +297,7 @@ pub enum Commands {
 
     /// Generate PDF of today's tasks
     TodayPdf {
-        /// Output file path (optional, defaults to ~/daily-YYYY-MM-DD.pdf)
+        /// Output file path [default: pdf.path, or ~/daily-YYYY-MM-DD.pdf]
         #[arg(short, long)]
         output: Option<String>,
     },
@@ -202,9 +313,9 @@ pub enum Commands {
 
     /// Start the daily prompt daemon
     Daemon {
-        /// Time to show daily prompt (HH:MM format, 24-hour)
-        #[arg(short, long, default_value = "09:00")]
-        time: String,
+        /// Time to show daily prompt (HH:MM format, 24-hour) [default: daemon.time, or 09:00]
+        #[arg(short, long)]
+        time: Option<String>,
     },
 
     /// [Atomic Habits] Show streak counts for daily habits
@@ -215,9 +326,9 @@ pub enum Commands {
 
     /// [Atomic Habits] Show visual habit grid for daily tasks
     Habits {
-        /// Number of days to display (default: 21)
-        #[arg(short, long, default_value = "21")]
-        days: u32,
+        /// Number of days to display [default: habits.grid_days, or 21]
+        #[arg(short, long)]
+        days: Option<u32>,
     },
 
     /// [Atomic Habits] Natural language habit/task planning via Claude AI
@@ -236,4 +347,186 @@ pub enum Commands {
         /// Prompt for Claude
         prompt: String,
     },
+
+    /// Convert the data directory to another storage backend
+    Migrate {
+        /// Target backend (text, sqlite)
+        #[arg(long)]
+        to: String,
+    },
+
+    /// Reverse the most recent command
+    Undo,
+
+    /// Re-apply the most recently undone command
+    Redo,
+
+    /// Show recent commands and what they changed
+    Journal {
+        /// Number of commands to show
+        #[arg(short = 'n', long, default_value = "10")]
+        limit: usize,
+    },
+
+    /// Write the whole data directory to a single backup file
+    Backup {
+        /// Output file path (optional, defaults to ~/daily-backup-YYYYMMDD-HHMMSS.json)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Restore data from a backup file
+    Restore {
+        /// Backup file written by `daily backup`
+        file: String,
+
+        /// Make the data directory hold exactly what the backup holds,
+        /// rather than adding the backup's data to what is already here
+        #[arg(long)]
+        replace: bool,
+    },
+
+    /// Bring tasks, plans and logs from another data directory into this one
+    Merge {
+        /// The other data directory (left unchanged)
+        dir: String,
+    },
+
+    /// Encrypt the data directory with a passphrase
+    Encrypt,
+
+    /// Turn an encrypted data directory back into plain text
+    Decrypt,
+
+    /// Sync the data directory with a git remote (`sync init <remote>` first)
+    Sync {
+        #[command(subcommand)]
+        action: Option<SyncCommand>,
+    },
+
+    /// Show or change settings in config.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+
+    /// Create, list, switch between or delete profiles
+    Profile {
+        #[command(subcommand)]
+        action: ProfileCommand,
+    },
+
+    /// Check the data directory for broken records and references
+    Doctor {
+        /// Repair everything that can be fixed without losing data
+        #[arg(long)]
+        fix: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TrashCommand {
+    /// List tasks in the trash, most recently deleted first
+    List,
+
+    /// Bring a task back out of the trash
+    Restore {
+        /// Task ID
+        id: String,
+    },
+
+    /// Permanently remove tasks from the trash
+    Empty {
+        /// Only remove tasks deleted longer ago than this (e.g. 30d, 2w)
+        #[arg(long)]
+        older_than: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TagCommand {
+    /// Tag a task
+    Add {
+        /// Task ID
+        id: String,
+
+        /// Tag to add
+        tag: String,
+    },
+
+    /// Remove a tag from a task
+    Remove {
+        /// Task ID
+        id: String,
+
+        /// Tag to remove
+        tag: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SyncCommand {
+    /// Put the data directory under git and sync it with a remote
+    Init {
+        /// Path to a bare git repository, created if it doesn't exist
+        remote: String,
+    },
+
+    /// Merge one record file (run by git during `daily sync`)
+    #[command(hide = true)]
+    MergeDriver {
+        base: String,
+        ours: String,
+        theirs: String,
+        path: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProfileCommand {
+    /// Create a new, empty profile
+    Create {
+        /// Profile name (letters, digits, '-' and '_')
+        name: String,
+    },
+
+    /// List profiles, marking the active one
+    List,
+
+    /// Make a profile the active one
+    Switch {
+        /// Profile name
+        name: String,
+    },
+
+    /// Delete a profile and all of its data
+    Delete {
+        /// Profile name
+        name: String,
+
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Show every setting and its current value
+    List,
+
+    /// Show one setting
+    Get {
+        /// Setting name, e.g. defaults.priority
+        key: String,
+    },
+
+    /// Change one setting
+    Set {
+        /// Setting name, e.g. defaults.priority
+        key: String,
+
+        /// New value
+        value: String,
+    },
 }
@@ -0,0 +1,375 @@
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use crate::claude::{day_str_to_num, DEFAULT_MAX_TOKENS, DEFAULT_MODEL};
+use crate::models::Priority;
+
+/// Settings file, relative to the data dir.
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Every setting `daily config` knows, as `section.name`.
+pub const KEYS: &[&str] = &[
+    "defaults.priority",
+    "defaults.category",
+    "daemon.time",
+    "habits.grid_days",
+    "pdf.path",
+    "display.week_start",
+    "display.date_format",
+    "display.color",
+    "ai.model",
+    "ai.max_tokens",
+    "archive.auto_after_days",
+    "backup.auto_keep",
+    "encryption.keyfile",
+];
+
+/// Contents of `config.toml`. Missing sections and settings fall back to the
+/// built-in defaults, and command-line flags override both.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub defaults: Defaults,
+    pub daemon: DaemonConfig,
+    pub habits: HabitsConfig,
+    pub pdf: PdfConfig,
+    pub display: DisplayConfig,
+    pub ai: AiConfig,
+    pub archive: ArchiveConfig,
+    pub backup: BackupConfig,
+    pub encryption: EncryptionConfig,
+}
+
+/// Values `daily add` uses when no flag is given.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Defaults {
+    pub priority: String,
+    pub category: String,
+}
+
+impl Default for Defaults {
+    fn default() -> Self {
+        Self { priority: "medium".to_string(), category: "default".to_string() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    /// When the daemon shows its prompt, "HH:MM".
+    pub time: String,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self { time: "09:00".to_string() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HabitsConfig {
+    /// Days shown by `daily habits`.
+    pub grid_days: u32,
+}
+
+impl Default for HabitsConfig {
+    fn default() -> Self {
+        Self { grid_days: 21 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfConfig {
+    /// Where `daily today-pdf` writes. `{date}` becomes the date and a
+    /// leading `~/` the home directory.
+    pub path: String,
+}
+
+impl Default for PdfConfig {
+    fn default() -> Self {
+        Self { path: "~/daily-{date}.pdf".to_string() }
+    }
+}
+
+impl PdfConfig {
+    pub fn path_for(&self, date: NaiveDate) -> Result<PathBuf> {
+        let path = self.path.replace("{date}", &date.format("%Y-%m-%d").to_string());
+        match path.strip_prefix("~/") {
+            Some(rest) => Ok(dirs::home_dir().context("Could not find home directory")?.join(rest)),
+            None => Ok(PathBuf::from(path)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplayConfig {
+    /// First day of the week in habit grids and weekly counts.
+    pub week_start: String,
+    /// strftime format for dates shown in output. Dates typed on the
+    /// command line are always YYYY-MM-DD.
+    pub date_format: String,
+    /// "auto" (when writing to a terminal and NO_COLOR is unset), "always"
+    /// or "never".
+    pub color: String,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            week_start: "monday".to_string(),
+            date_format: "%Y-%m-%d".to_string(),
+            color: "auto".to_string(),
+        }
+    }
+}
+
+impl DisplayConfig {
+    pub fn week_start(&self) -> Weekday {
+        day_str_to_num(&self.week_start)
+            .and_then(|n| Weekday::try_from(n).ok())
+            .unwrap_or(Weekday::Mon)
+    }
+
+    /// `date` in the configured format, or as YYYY-MM-DD if that format
+    /// can't format a plain date (it asks for a time, say).
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        try_format_date(date, &self.date_format).unwrap_or_else(|| date.format("%Y-%m-%d").to_string())
+    }
+
+    pub fn use_color(&self) -> bool {
+        match self.color.as_str() {
+            "always" => true,
+            "never" => false,
+            _ => std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AiConfig {
+    pub model: String,
+    pub max_tokens: u32,
+}
+
+impl Default for AiConfig {
+    fn default() -> Self {
+        Self { model: DEFAULT_MODEL.to_string(), max_tokens: DEFAULT_MAX_TOKENS }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    /// Archive completed one-off tasks this many days after completion,
+    /// before every command. 0 turns it off.
+    pub auto_after_days: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    /// Automatic backups to keep before destructive commands. 0 turns them
+    /// off; `DAILY_AUTO_BACKUPS` overrides it.
+    pub auto_keep: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EncryptionConfig {
+    /// File holding the passphrase of an encrypted data dir, used when
+    /// `DAILY_PASSPHRASE` is unset. A leading `~/` is the home directory.
+    pub keyfile: String,
+}
+
+impl EncryptionConfig {
+    pub fn keyfile_path(&self) -> Result<Option<PathBuf>> {
+        if self.keyfile.is_empty() {
+            return Ok(None);
+        }
+        match self.keyfile.strip_prefix("~/") {
+            Some(rest) => Ok(Some(dirs::home_dir().context("Could not find home directory")?.join(rest))),
+            None => Ok(Some(PathBuf::from(&self.keyfile))),
+        }
+    }
+}
+
+impl Config {
+    /// Reads `config.toml` from `data_dir`, or the defaults if there is none.
+    pub fn load(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        let config: Config = toml::from_str(&content)
+            .with_context(|| format!("Invalid settings in {}", path.display()))?;
+        for key in KEYS {
+            config.validate(key, &config.get(key)?)
+                .with_context(|| format!("Invalid settings in {}", path.display()))?;
+        }
+        Ok(config)
+    }
+
+    pub fn save(&self, data_dir: &Path) -> Result<()> {
+        fs::create_dir_all(data_dir)?;
+        crate::storage::write_atomic(&data_dir.join(CONFIG_FILE), toml::to_string_pretty(self)?.as_bytes())
+    }
+
+    pub fn get(&self, key: &str) -> Result<String> {
+        Ok(match key {
+            "defaults.priority" => self.defaults.priority.clone(),
+            "defaults.category" => self.defaults.category.clone(),
+            "daemon.time" => self.daemon.time.clone(),
+            "habits.grid_days" => self.habits.grid_days.to_string(),
+            "pdf.path" => self.pdf.path.clone(),
+            "display.week_start" => self.display.week_start.clone(),
+            "display.date_format" => self.display.date_format.clone(),
+            "display.color" => self.display.color.clone(),
+            "ai.model" => self.ai.model.clone(),
+            "ai.max_tokens" => self.ai.max_tokens.to_string(),
+            "archive.auto_after_days" => self.archive.auto_after_days.to_string(),
+            "backup.auto_keep" => self.backup.auto_keep.to_string(),
+            "encryption.keyfile" => self.encryption.keyfile.clone(),
+            _ => return Err(unknown_key(key)),
+        })
+    }
+
+    /// Changes one setting after checking that `value` is usable.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        self.validate(key, value)?;
+        let value = value.trim();
+        match key {
+            "defaults.priority" => self.defaults.priority = value.to_lowercase(),
+            "defaults.category" => self.defaults.category = value.to_string(),
+            "daemon.time" => self.daemon.time = value.to_string(),
+            "habits.grid_days" => self.habits.grid_days = value.parse()?,
+            "pdf.path" => self.pdf.path = value.to_string(),
+            "display.week_start" => self.display.week_start = value.to_lowercase(),
+            "display.date_format" => self.display.date_format = value.to_string(),
+            "display.color" => self.display.color = value.to_lowercase(),
+            "ai.model" => self.ai.model = value.to_string(),
+            "ai.max_tokens" => self.ai.max_tokens = value.parse()?,
+            "archive.auto_after_days" => self.archive.auto_after_days = value.parse()?,
+            "backup.auto_keep" => self.backup.auto_keep = value.parse()?,
+            "encryption.keyfile" => self.encryption.keyfile = value.to_string(),
+            _ => return Err(unknown_key(key)),
+        }
+        Ok(())
+    }
+
+    fn validate(&self, key: &str, value: &str) -> Result<()> {
+        let value = value.trim();
+        let valid = match key {
+            "defaults.priority" => Priority::from_str(value).is_some(),
+            "defaults.category" | "pdf.path" | "ai.model" => !value.is_empty(),
+            "daemon.time" => NaiveTime::parse_from_str(value, "%H:%M").is_ok(),
+            "habits.grid_days" | "ai.max_tokens" => value.parse::<u32>().is_ok_and(|n| n > 0),
+            "display.week_start" => day_str_to_num(value).is_some(),
+            "display.date_format" => {
+                let sample = NaiveDate::from_ymd_opt(2026, 4, 13).expect("valid date");
+                !value.is_empty() && try_format_date(sample, value).is_some()
+            }
+            "display.color" => matches!(value.to_lowercase().as_str(), "auto" | "always" | "never"),
+            "archive.auto_after_days" => value.parse::<u32>().is_ok(),
+            "backup.auto_keep" => value.parse::<usize>().is_ok(),
+            // Empty turns the keyfile off.
+            "encryption.keyfile" => true,
+            _ => return Err(unknown_key(key)),
+        };
+        if !valid {
+            anyhow::bail!("Invalid value '{}' for {}", value, key);
+        }
+        Ok(())
+    }
+}
+
+fn unknown_key(key: &str) -> anyhow::Error {
+    anyhow::anyhow!("Unknown setting '{}'. Known settings: {}", key, KEYS.join(", "))
+}
+
+/// `date` formatted with `format`, or `None` if the format is invalid or
+/// needs more than a date.
+fn try_format_date(date: NaiveDate, format: &str) -> Option<String> {
+    let mut out = String::new();
+    write!(out, "{}", date.format(format)).ok()?;
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_file_gives_defaults() {
+        let dir = TempDir::new().unwrap();
+        let config = Config::load(dir.path()).unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(config.get("habits.grid_days").unwrap(), "21");
+        assert_eq!(config.get("ai.model").unwrap(), DEFAULT_MODEL);
+    }
+
+    #[test]
+    fn test_set_save_and_load() {
+        let dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.set("defaults.priority", "High").unwrap();
+        config.set("display.week_start", "sun").unwrap();
+        config.set("habits.grid_days", "28").unwrap();
+        config.save(dir.path()).unwrap();
+
+        let loaded = Config::load(dir.path()).unwrap();
+        assert_eq!(loaded.defaults.priority, "high");
+        assert_eq!(loaded.display.week_start(), Weekday::Sun);
+        assert_eq!(loaded.habits.grid_days, 28);
+    }
+
+    #[test]
+    fn test_partial_file_keeps_other_defaults() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(CONFIG_FILE), "[daemon]\ntime = \"07:30\"\n").unwrap();
+        let config = Config::load(dir.path()).unwrap();
+        assert_eq!(config.daemon.time, "07:30");
+        assert_eq!(config.defaults.category, "default");
+    }
+
+    #[test]
+    fn test_rejects_bad_values_and_keys() {
+        let mut config = Config::default();
+        assert!(config.set("defaults.priority", "urgent").is_err());
+        assert!(config.set("daemon.time", "9am").is_err());
+        assert!(config.set("habits.grid_days", "0").is_err());
+        assert!(config.set("display.date_format", "%Q").is_err());
+        assert!(config.set("display.date_format", "%d.%m.%Y %H:%M").is_err());
+        assert!(config.set("display.color", "sometimes").is_err());
+        assert!(config.set("nope.key", "1").is_err());
+        assert!(config.get("nope.key").is_err());
+        assert_eq!(config, Config::default());
+
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(CONFIG_FILE), "[daemon]\ntime = \"late\"\n").unwrap();
+        assert!(Config::load(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_pdf_path_and_date_format() {
+        let date = NaiveDate::from_ymd_opt(2026, 4, 13).unwrap();
+        let pdf = PdfConfig { path: "/tmp/plan-{date}.pdf".to_string() };
+        assert_eq!(pdf.path_for(date).unwrap(), PathBuf::from("/tmp/plan-2026-04-13.pdf"));
+
+        let display = DisplayConfig { date_format: "%d/%m/%Y".to_string(), ..Default::default() };
+        assert_eq!(display.format_date(date), "13/04/2026");
+        // A format that needs a time falls back instead of panicking.
+        let display = DisplayConfig { date_format: "%d.%m.%Y %H:%M".to_string(), ..Default::default() };
+        assert_eq!(display.format_date(date), "2026-04-13");
+    }
+}
@@ -0,0 +1,6 @@
+pub mod models;
+pub mod config;
+pub mod storage;
+pub mod cli;
+pub mod scheduler;
+pub mod claude;
@@ -1,30 +1,459 @@
-mod models;
-mod storage;
-mod cli;
-mod scheduler;
-mod claude;
-
 use anyhow::{Context, Result};
-use chrono::{Datelike, Local, NaiveDate};
+use chrono::{Datelike, Duration, Local, NaiveDate, Utc, Weekday};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use clap::Parser;
-use models::{Priority, Task, Category};
-use storage::Storage;
-use cli::{Cli, Commands};
-use scheduler::Scheduler;
-use claude::ClaudeClient;
+use daily::{claude, storage};
+use daily::models::{Priority, Task, Category, TagFilter, Recurrence};
+use daily::models::task::{ancestors, dependency_path, descendants, open_prerequisites, parse_tag, subtask_progress, subtask_tree};
+use daily::storage::{Backend, Cipher, DirLock, HistoryEntry, TaskStore, DEFAULT_LOCK_TIMEOUT};
+use daily::storage::crypto;
+use daily::storage::backup::RestoreMode;
+use daily::storage::profile::Profiles;
+use daily::storage::journal::{Journal, JournalEntry, Recorder};
+use daily::cli::{Cli, Commands, ConfigCommand, ProfileCommand, SyncCommand, TagCommand, TrashCommand};
+use daily::config::{Config, DisplayConfig};
+use daily::scheduler::Scheduler;
+use daily::claude::ClaudeClient;
 use rand::seq::SliceRandom;
+use uuid::Uuid;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
-    let data_dir = if let Some(ref dir) = cli.data_dir {
-        std::path::PathBuf::from(dir)
+
+    // Git runs the merge driver at the top of the repository, i.e. in the data
+    // dir; it only touches the files it is given. `daily sync` hands it the
+    // passphrase of an encrypted dir through `DAILY_PASSPHRASE`.
+    if let Commands::Sync { action: Some(SyncCommand::MergeDriver { base, ours, theirs, path }) } = cli.command {
+        let data_dir = std::env::current_dir()?;
+        let cipher = unlock(&data_dir, &Config::load(&data_dir)?)?;
+        let merged = storage::sync::merge_driver(Path::new(&base), Path::new(&ours), Path::new(&theirs), &path, cipher.as_ref())?;
+        std::process::exit(if merged { 0 } else { 1 });
+    }
+
+    let root = if let Some(ref dir) = cli.data_dir {
+        PathBuf::from(dir)
     } else {
-        Storage::default_dir()?
+        storage::default_dir()?
     };
-    let storage = Storage::new(data_dir)?;
+    let profiles = Profiles::new(&root);
+
+    // Profile management works on the root rather than on one profile's data.
+    if let Commands::Profile { action } = cli.command {
+        return run_profile_command(&profiles, action);
+    }
+
+    let requested = cli.profile.clone()
+        .or_else(|| std::env::var("DAILY_PROFILE").ok().filter(|p| !p.trim().is_empty()));
+    let (_, data_dir) = profiles.resolve(requested.as_deref())?;
+
+    if let Commands::Config { action } = cli.command {
+        return run_config_command(&data_dir, action);
+    }
+
+    let config = Config::load(&data_dir)?;
+    upgrade_data_dir(&data_dir, &config)?;
+
+    if let Commands::Today { completed, all, all_profiles: true } = cli.command {
+        let today = Local::now().date_naive();
+        let filter = if all { DayFilter::All } else if completed { DayFilter::Completed } else { DayFilter::Incomplete };
+        for name in profiles.list()? {
+            let dir = profiles.dir(&name)?;
+            let profile_config = Config::load(&dir)?;
+            upgrade_data_dir(&dir, &profile_config)?;
+            println!("\n##### Profile: {} #####", name);
+            let cipher = unlock(&dir, &profile_config)?;
+            show_day_tasks(storage::open_with(dir, cipher)?.as_ref(), today, filter, &config.display)?;
+        }
+        return Ok(());
+    }
 
+    // Syncing merges files underneath the store, so it runs before one is opened.
+    if let Commands::Sync { action } = cli.command {
+        return run_sync_command(&data_dir, &config, action);
+    }
+
+    // Encrypting and decrypting rewrite every file, so they run before a store is opened.
+    if let Commands::Encrypt = cli.command {
+        let passphrase = if crypto::is_encrypted(&data_dir) {
+            passphrase(&config, "Passphrase: ")?
+        } else {
+            new_passphrase(&config)?
+        };
+        let report = crypto::encrypt_dir(&data_dir, &passphrase)?;
+        println!("Encrypted {} file(s) in {}.", report.files, data_dir.display());
+        if !report.plaintext.is_empty() {
+            eprintln!("WARNING: these backups are databases that can't be encrypted and still hold your data in plain text.");
+            eprintln!("Delete them once you no longer need them:");
+            for path in &report.plaintext {
+                eprintln!("  {}", path.display());
+            }
+        }
+        println!("Keep the passphrase safe: without it this data can't be recovered.");
+        commit_for_sync(&data_dir, "daily encrypt");
+        return Ok(());
+    }
+    if let Commands::Decrypt = cli.command {
+        let report = crypto::decrypt_dir(&data_dir, &passphrase(&config, "Passphrase: ")?)?;
+        println!("Decrypted {} file(s) in {}.", report.files, data_dir.display());
+        commit_for_sync(&data_dir, "daily decrypt");
+        return Ok(());
+    }
+
+    // Migration swaps the backend underneath us, so it runs before a store is opened.
+    if let Commands::Migrate { ref to } = cli.command {
+        let target = Backend::from_str(to)
+            .context("Invalid backend. Use: text or sqlite")?;
+        let (report, backup_dir) = storage::migrate::migrate(&data_dir, target)?;
+        println!("Migrated to {} backend:", target);
+        println!("  {} task(s), {} day(s), {} categor{}", report.tasks, report.days, report.categories,
+            if report.categories == 1 { "y" } else { "ies" });
+        println!("  {} daily log entr{}, {} history entr{}",
+            report.daily_entries, if report.daily_entries == 1 { "y" } else { "ies" },
+            report.history_entries, if report.history_entries == 1 { "y" } else { "ies" });
+        println!("  {} task(s) in the trash, {} archived", report.trashed, report.archived);
+        println!("Previous data kept in {}", backup_dir.display());
+        return Ok(());
+    }
+
+    let cipher = unlock(&data_dir, &config)?;
+    // Commands load, change and save records. Holding the lock throughout
+    // keeps another process from saving in between and having its change
+    // overwritten. The daemon runs until stopped, so it doesn't hold it.
+    let _lock = match cli.command {
+        Commands::Daemon { .. } => None,
+        _ => Some(DirLock::new(&data_dir, DEFAULT_LOCK_TIMEOUT).acquire()?),
+    };
+    let store = storage::open_with(data_dir.clone(), cipher.clone())?;
+    let journal = match &cipher {
+        Some(cipher) => Journal::new(&data_dir).with_cipher(cipher.clone()),
+        None => Journal::new(&data_dir),
+    };
+
+    // Undo, redo and the journal listing act on the journal rather than being recorded in it.
     match cli.command {
+        Commands::Undo => {
+            match journal.undo(store.as_ref())? {
+                Some(entry) => {
+                    print_journal_entry("Undid", &entry);
+                    commit_for_sync(&data_dir, "daily undo");
+                }
+                None => println!("Nothing to undo."),
+            }
+            Ok(())
+        }
+
+        Commands::Redo => {
+            match journal.redo(store.as_ref())? {
+                Some(entry) => {
+                    print_journal_entry("Redid", &entry);
+                    commit_for_sync(&data_dir, "daily redo");
+                }
+                None => println!("Nothing to redo."),
+            }
+            Ok(())
+        }
+
+        Commands::Journal { limit } => {
+            let entries = journal.entries()?;
+            if entries.is_empty() {
+                println!("The journal is empty.");
+            }
+            for entry in entries.iter().rev().take(limit) {
+                let status = if entry.undone { " [undone]" } else { "" };
+                println!("#{}  {}  {}{}", entry.seq, entry.timestamp, entry.command, status);
+                for change in entry.changes.iter().take(JOURNAL_CHANGES_SHOWN) {
+                    println!("      {}", change);
+                }
+                if entry.changes.len() > JOURNAL_CHANGES_SHOWN {
+                    println!("      ... and {} more", entry.changes.len() - JOURNAL_CHANGES_SHOWN);
+                }
+            }
+            Ok(())
+        }
+
+        command => {
+            if config.archive.auto_after_days > 0 {
+                auto_archive(store.as_ref(), &journal, config.archive.auto_after_days)?;
+            }
+            if is_destructive(&command) {
+                if let Some(keep) = auto_backups(&config) {
+                    storage::backup::auto_backup(store.as_ref(), &data_dir, keep, cipher.as_ref())
+                        .context("Automatic backup failed; nothing was changed")?;
+                }
+            }
+            let recorder = Recorder::new(store.as_ref());
+            let result = run(command, &recorder, &config, cipher.as_ref()).await;
+            // Record partial work too, so a command that failed halfway can be undone.
+            let changes = recorder.into_changes();
+            if !changes.is_empty() {
+                journal.record(&command_line(), changes)?;
+                commit_for_sync(&data_dir, &command_line());
+            }
+            result
+        }
+    }
+}
+
+/// Brings `data_dir` up to the current data format, reporting any upgrade on
+/// stderr. An encrypted data dir is unlocked first if it needs upgrading.
+fn upgrade_data_dir(data_dir: &Path, config: &Config) -> Result<()> {
+    let cipher = if storage::upgrade::is_pending(data_dir)? { unlock(data_dir, config)? } else { None };
+    if let Some(report) = storage::upgrade::ensure_current(data_dir, cipher.as_ref())? {
+        eprintln!("Upgraded data in {} from format {} to {}:", data_dir.display(), report.from, report.to);
+        for step in &report.steps {
+            eprintln!("  - {}", step);
+        }
+        eprintln!("Previous data kept in {}", report.backup_dir.display());
+    }
+    Ok(())
+}
+
+fn run_profile_command(profiles: &Profiles, action: ProfileCommand) -> Result<()> {
+    match action {
+        ProfileCommand::Create { name } => {
+            let dir = profiles.create(&name)?;
+            println!("Profile '{}' created in {}.", name, dir.display());
+            println!("Use it with `daily --profile {} ...` or `daily profile switch {}`.", name, name);
+        }
+
+        ProfileCommand::List => {
+            let active = profiles.active()?;
+            for name in profiles.list()? {
+                let marker = if name == active { "*" } else { " " };
+                println!("{} {}", marker, name);
+            }
+        }
+
+        ProfileCommand::Switch { name } => {
+            profiles.switch(&name)?;
+            println!("Switched to profile '{}'.", name);
+        }
+
+        ProfileCommand::Delete { name, yes } => {
+            if !profiles.exists(&name) {
+                anyhow::bail!("No profile named '{}'", name);
+            }
+            if !yes && !confirm(&format!("Delete profile '{}' and all of its data?", name))? {
+                println!("Nothing deleted.");
+                return Ok(());
+            }
+            profiles.delete(&name)?;
+            println!("Profile '{}' deleted.", name);
+        }
+    }
+    Ok(())
+}
+
+fn run_config_command(data_dir: &Path, action: ConfigCommand) -> Result<()> {
+    match action {
+        ConfigCommand::List => {
+            let config = Config::load(data_dir)?;
+            for key in daily::config::KEYS {
+                println!("{} = {}", key, config.get(key)?);
+            }
+        }
+
+        ConfigCommand::Get { key } => {
+            println!("{}", Config::load(data_dir)?.get(&key)?);
+        }
+
+        ConfigCommand::Set { key, value } => {
+            let mut config = Config::load(data_dir)?;
+            config.set(&key, &value)?;
+            config.save(data_dir)?;
+            println!("{} = {}", key, config.get(&key)?);
+        }
+    }
+    Ok(())
+}
+
+fn run_sync_command(data_dir: &Path, config: &Config, action: Option<SyncCommand>) -> Result<()> {
+    let exe = std::env::current_exe().context("Could not find the daily executable")?;
+    // Asked for once, checked, and handed to the merge driver.
+    let passphrase = if crypto::is_encrypted(data_dir) {
+        let passphrase = passphrase(config, &format!("Passphrase for {}: ", data_dir.display()))?;
+        crypto::unlock(data_dir, &passphrase)?;
+        Some(passphrase)
+    } else {
+        None
+    };
+    let passphrase = passphrase.as_deref();
+    let report = match action {
+        Some(SyncCommand::Init { remote }) => {
+            let report = storage::sync::init(data_dir, Path::new(&remote), &exe, passphrase)?;
+            println!("Syncing {} with {}.", data_dir.display(), remote);
+            report
+        }
+        None => storage::sync::sync(data_dir, &exe, passphrase)?,
+        Some(SyncCommand::MergeDriver { .. }) => unreachable!("handled before the data dir is resolved"),
+    };
+    if report.pulled > 0 {
+        println!("Pulled {} change(s).", report.pulled);
+        renumber_duplicate_handles(data_dir, config, &exe, passphrase)?;
+    }
+    for path in &report.kept {
+        println!("  {} was changed here and deleted there (or the other way round); kept the changed copy.", path);
+    }
+    if report.pushed {
+        println!("Pushed local changes.");
+    }
+    if report.pulled == 0 && !report.pushed {
+        println!("Already up to date.");
+    }
+    Ok(())
+}
+
+/// Tasks added on two machines between syncs can get the same handle. The
+/// newer ones get fresh handles, and the result is pushed so every machine
+/// agrees on them.
+fn renumber_duplicate_handles(data_dir: &Path, config: &Config, exe: &Path, passphrase: Option<&str>) -> Result<()> {
+    // A first sync may have just pulled the encryption header.
+    let cipher = match passphrase {
+        Some(passphrase) => Some(crypto::unlock(data_dir, passphrase)?),
+        None => unlock(data_dir, config)?,
+    };
+    let store = storage::open_with(data_dir.to_path_buf(), cipher)?;
+    let duplicates: Vec<_> = storage::doctor::check(store.as_ref())?.issues
+        .into_iter()
+        .filter(|i| matches!(i, storage::doctor::Issue::DuplicateHandle { .. }))
+        .collect();
+    if duplicates.is_empty() {
+        return Ok(());
+    }
+    for issue in storage::doctor::repair(store.as_ref(), &duplicates)? {
+        println!("  {}; renumbered the newer task(s).", issue);
+    }
+    drop(store);
+    storage::sync::sync(data_dir, exe, passphrase)?;
+    Ok(())
+}
+
+/// Commits the data dir after a command changed it, once sync is set up.
+/// Failing to commit doesn't fail the command; `daily sync` picks it up.
+fn commit_for_sync(data_dir: &Path, message: &str) {
+    if storage::sync::is_enabled(data_dir) {
+        if let Err(e) = storage::sync::commit(data_dir, message) {
+            eprintln!("Warning: could not commit for sync: {:#}", e);
+        }
+    }
+}
+
+/// The cipher for `data_dir` if it is encrypted.
+fn unlock(data_dir: &Path, config: &Config) -> Result<Option<Cipher>> {
+    if !crypto::is_encrypted(data_dir) {
+        return Ok(None);
+    }
+    let passphrase = passphrase(config, &format!("Passphrase for {}: ", data_dir.display()))?;
+    crypto::unlock(data_dir, &passphrase).map(Some)
+}
+
+/// The passphrase from `DAILY_PASSPHRASE`, else from the keyfile named by
+/// `DAILY_KEYFILE` or `encryption.keyfile`, else typed at a prompt.
+fn passphrase(config: &Config, prompt: &str) -> Result<String> {
+    if let Some(passphrase) = std::env::var("DAILY_PASSPHRASE").ok().filter(|p| !p.is_empty()) {
+        return Ok(passphrase);
+    }
+    let keyfile = match std::env::var_os("DAILY_KEYFILE").filter(|p| !p.is_empty()) {
+        Some(path) => Some(PathBuf::from(path)),
+        None => config.encryption.keyfile_path()?,
+    };
+    if let Some(path) = keyfile {
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Could not read keyfile {}", path.display()))?;
+        return Ok(content.trim_end_matches(['\r', '\n']).to_string());
+    }
+    rpassword::prompt_password(prompt).context("Could not read the passphrase")
+}
+
+/// Like `passphrase`, but a typed passphrase has to be typed twice.
+fn new_passphrase(config: &Config) -> Result<String> {
+    if std::env::var_os("DAILY_PASSPHRASE").is_some_and(|p| !p.is_empty())
+        || std::env::var_os("DAILY_KEYFILE").is_some_and(|p| !p.is_empty())
+        || config.encryption.keyfile_path()?.is_some()
+    {
+        return passphrase(config, "");
+    }
+    let first = rpassword::prompt_password("New passphrase: ").context("Could not read the passphrase")?;
+    let second = rpassword::prompt_password("Repeat it: ").context("Could not read the passphrase")?;
+    if first != second {
+        anyhow::bail!("The passphrases don't match");
+    }
+    Ok(first)
+}
+
+/// Archives completed one-off tasks finished more than `days` days ago, as
+/// its own journal entry so undoing the next command doesn't undo it too.
+fn auto_archive(store: &dyn TaskStore, journal: &Journal, days: u32) -> Result<()> {
+    let cutoff = Utc::now() - Duration::days(days as i64);
+    let recorder = Recorder::new(store);
+    for task in store.list_all_tasks()? {
+        if task.completed && !task.is_daily && task.updated_at < cutoff {
+            recorder.archive_task(&task.id)?;
+        }
+    }
+    let changes = recorder.into_changes();
+    if !changes.is_empty() {
+        journal.record(&format!("daily archive --older-than {}d (automatic)", days), changes)?;
+    }
+    Ok(())
+}
+
+/// How many automatic backups to keep: `DAILY_AUTO_BACKUPS` if set, else
+/// `backup.auto_keep`. 0 turns them off.
+fn auto_backups(config: &Config) -> Option<usize> {
+    let keep = std::env::var("DAILY_AUTO_BACKUPS").ok()
+        .and_then(|n| n.trim().parse().ok())
+        .unwrap_or(config.backup.auto_keep);
+    Some(keep).filter(|&n| n > 0)
+}
+
+/// Commands that can throw data away, which get an automatic backup first.
+fn is_destructive(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::DeleteAll { .. }
+            | Commands::UncompleteAll
+            | Commands::Trash { action: TrashCommand::Empty { .. } }
+            | Commands::Restore { .. }
+            | Commands::Merge { .. }
+            | Commands::Doctor { fix: true }
+    )
+}
+
+/// How many changes `daily journal` lists per command.
+const JOURNAL_CHANGES_SHOWN: usize = 5;
+
+fn print_journal_entry(verb: &str, entry: &JournalEntry) {
+    println!("{} #{}: {}", verb, entry.seq, entry.command);
+    for change in entry.changes.iter().take(JOURNAL_CHANGES_SHOWN) {
+        println!("  {}", change);
+    }
+    if entry.changes.len() > JOURNAL_CHANGES_SHOWN {
+        println!("  ... and {} more", entry.changes.len() - JOURNAL_CHANGES_SHOWN);
+    }
+}
+
+/// The command as typed, without the global `--data-dir` option, for the journal.
+fn command_line() -> String {
+    let mut words = vec!["daily".to_string()];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--data-dir" {
+            args.next();
+            continue;
+        }
+        if arg.starts_with("--data-dir=") {
+            continue;
+        }
+        words.push(if arg.contains(' ') || arg.is_empty() { format!("\"{}\"", arg) } else { arg });
+    }
+    words.join(" ")
+}
+
+async fn run(command: Commands, storage: &dyn TaskStore, config: &Config, cipher: Option<&Cipher>) -> Result<()> {
+    match command {
         Commands::Add {
             title,
             priority,
@@ -37,15 +466,43 @@ async fn main() -> Result<()> {
             after,
             two_minute,
             days,
+            every,
+            from_completion,
+            starting,
+            until,
+            except,
             numeric,
             unit,
             target,
+            tags,
+            parent,
         } => {
-            let priority = Priority::from_str(&priority)
+            let priority = Priority::from_str(priority.as_deref().unwrap_or(&config.defaults.priority))
                 .context("Invalid priority. Use: low, medium, high, or critical")?;
+            // A rule with a due date is a recurring chore, not a habit.
+            let recurring_task = every.is_some() && due.is_some();
+            if recurring_task && daily {
+                anyhow::bail!("A daily habit has no due date; drop --due or --daily");
+            }
+            let parent = parent.map(|id| find_task_by_prefix(storage, &id)).transpose()?;
+            if let Some(parent) = &parent {
+                if parent.is_daily {
+                    anyhow::bail!("Task '{}' is a daily habit; only one-off tasks have subtasks", parent.title);
+                }
+                if recurring_task {
+                    anyhow::bail!("A recurring task can't be a subtask");
+                }
+                if daily || every.is_some() {
+                    anyhow::bail!("A daily habit can't be a subtask");
+                }
+            }
+            // Subtasks file under their parent's category unless told otherwise.
+            let category = category
+                .or_else(|| parent.as_ref().map(|p| p.category.clone()))
+                .unwrap_or_else(|| config.defaults.category.clone());
 
-            let task_id = storage.get_next_task_id()?;
-            let mut task = Task::new(task_id, title, priority, category);
+            let mut task = Task::new(Uuid::new_v4().to_string(), title, priority, category)
+                .with_handle(storage.next_handle()?);
 
             if let Some(desc) = description {
                 task = task.with_description(desc);
@@ -72,7 +529,7 @@ async fn main() -> Result<()> {
             }
 
             if let Some(after_id) = after {
-                task = task.with_habit_stack_after(after_id);
+                task = task.with_habit_stack_after(find_task_by_prefix(storage, &after_id)?.id);
             }
 
             if two_minute {
@@ -86,6 +543,28 @@ async fn main() -> Result<()> {
                 task = task.with_scheduled_days(nums);
             }
 
+            if let Some(every) = every {
+                let mut rule = Recurrence::parse(&every)
+                    .with_context(|| format!("Invalid recurrence '{}'", every))?;
+                if recurring_task && rule.times.is_some() {
+                    anyhow::bail!("A task with a due date needs fixed days, not '{}'", rule);
+                }
+                // A recurring task's series runs from its first due date.
+                let first_day = task.due_date.map_or_else(|| Local::now().date_naive(), |d| d.date_naive());
+                rule.start = match starting {
+                    Some(date) => Some(parse_date_arg(&date)?),
+                    None => rule.start.or(Some(first_day)),
+                };
+                rule.from_completion = from_completion;
+                if let Some(date) = until {
+                    rule.until = Some(parse_date_arg(&date)?);
+                }
+                for date in &except {
+                    rule.except.push(parse_date_arg(date)?);
+                }
+                task = task.with_daily(!recurring_task).with_recurrence(rule);
+            }
+
             if numeric {
                 task = task.with_numeric(true);
             }
@@ -98,15 +577,32 @@ async fn main() -> Result<()> {
                 task = task.with_target(t);
             }
 
+            if !tags.is_empty() {
+                task = task.with_tags(tags.iter().map(|t| parse_tag_arg(t)).collect::<Result<_>>()?);
+            }
+
+            if let Some(parent) = &parent {
+                task = task.with_parent(parent.id.clone());
+            }
+
             storage.save_task(&task)?;
             println!("Task added successfully!");
-            println!("ID: {}", task.id);
+            println!("ID: {}", task.short_id());
             println!("Title: {}", task.title);
-            println!("Priority: {}", task.priority.to_string());
+            println!("Priority: {}", task.priority);
             println!("Category: {}", task.category);
+            if !task.tags.is_empty() {
+                println!("Tags: {}", task.tags_display());
+            }
             if task.is_daily {
                 println!("Type: Daily recurring task");
             }
+            if let Some(rule) = &task.recurrence {
+                println!("Repeats: {}", rule);
+                if let Some(start) = rule.start {
+                    println!("Starting: {}", config.display.format_date(start));
+                }
+            }
             if task.numeric {
                 let unit_str = task.unit.as_deref().unwrap_or("units");
                 if let Some(t) = task.target {
@@ -122,19 +618,31 @@ async fn main() -> Result<()> {
                 println!("Where: {}", loc);
             }
             if let Some(ref after) = task.habit_stack_after {
-                println!("After: task {}", after);
+                let anchor = storage.load_task(after)?;
+                println!("After: task {} ({})", anchor.short_id(), anchor.title);
             }
             if task.two_minute {
                 println!("Two-minute rule: yes (starter version)");
             }
+            if let Some(parent) = &parent {
+                println!("Subtask of: task {} ({})", parent.short_id(), parent.title);
+                reopen_ancestors(storage, &task)?;
+            }
         }
 
-        Commands::List { category, priority, incomplete, completed, random } => {
-            let mut tasks = if let Some(cat) = category {
-                storage.list_tasks_by_category(&cat)?
+        Commands::List { category, priority, incomplete, completed, random, archived, tags, blocked, ready } => {
+            let tag_filter = TagFilter::parse(&tags)
+                .map_err(|tag| anyhow::anyhow!("Invalid tag '{}'. Use letters, digits, '-', '_', '/' and '.'", tag))?;
+            let mut tasks = if archived {
+                storage.list_archived()?.into_iter().map(|a| a.task).collect()
+            } else if let Some(cat) = &category {
+                storage.list_tasks_by_category(cat)?
             } else {
                 storage.list_all_tasks()?
             };
+            if let (true, Some(cat)) = (archived, &category) {
+                tasks.retain(|t| &t.category == cat);
+            }
 
             // Apply filters
             if let Some(priority_str) = priority {
@@ -149,6 +657,13 @@ async fn main() -> Result<()> {
                 tasks.retain(|t| t.completed);
             }
 
+            tasks.retain(|t| tag_filter.matches(t));
+
+            if blocked || ready {
+                let all = storage.list_all_tasks()?;
+                tasks.retain(|t| !t.completed && open_prerequisites(&all, t).is_empty() == ready);
+            }
+
             // If random flag is set, select one random task from each category
             if random {
                 use std::collections::HashMap;
@@ -158,13 +673,13 @@ async fn main() -> Result<()> {
                 let mut by_category: HashMap<String, Vec<Task>> = HashMap::new();
                 for task in tasks {
                     by_category.entry(task.category.clone())
-                        .or_insert_with(Vec::new)
+                        .or_default()
                         .push(task);
                 }
 
                 // Select one random task from each category
-                tasks = by_category.into_iter()
-                    .filter_map(|(_, mut cat_tasks)| {
+                tasks = by_category.into_values()
+                    .filter_map(|mut cat_tasks| {
                         cat_tasks.shuffle(&mut rng);
                         cat_tasks.into_iter().next()
                     })
@@ -184,40 +699,151 @@ async fn main() -> Result<()> {
             } else {
                 println!("\n{} task(s) found:\n", tasks.len());
 
+                // Progress counts every subtask, not just the ones shown.
+                let family = if archived { tasks.clone() } else { storage.list_all_tasks()? };
                 let mut current_category = String::new();
-                for task in tasks {
-                    // Print category header if changed
-                    if task.category != current_category {
-                        println!("\n=== {} ===", task.category.to_uppercase());
+                let color = config.display.use_color();
+                for (depth, task) in subtask_tree(tasks) {
+                    // Print category header if changed; subtasks stay under their parent
+                    if depth == 0 && task.category != current_category {
+                        println!("\n{}", paint(&format!("=== {} ===", task.category.to_uppercase()), BOLD, color));
                         current_category = task.category.clone();
                     }
 
-                    let status = if task.completed { "[✓]" } else { "[ ]" };
-                    let daily_indicator = if task.is_daily { " [Daily]" } else { "" };
+                    let indent = "    ".repeat(depth);
+                    let status = if task.completed { paint("[✓]", GREEN, color) } else { "[ ]".to_string() };
+                    let daily_indicator = recurrence_indicator(&task);
                     println!(
-                        "{} {} - {}{} (Priority: {})",
+                        "{}{} {} - {}{}{} (Priority: {}){}{}",
+                        indent,
                         status,
-                        task.id,
+                        task.short_id(),
                         task.title,
+                        progress_suffix(&family, &task),
                         daily_indicator,
-                        task.priority.to_string()
+                        task.priority,
+                        tag_suffix(&task),
+                        blocked_suffix(&family, &task),
                     );
 
                     if let Some(desc) = &task.description {
-                        println!("    {}", desc);
+                        println!("{}    {}", indent, desc);
                     }
 
                     if let Some(due) = &task.due_date {
-                        println!("    Due: {}", due.format("%Y-%m-%d"));
+                        println!("{}    Due: {}", indent, config.display.format_date(due.date_naive()));
                     }
                 }
                 println!();
             }
         }
 
+        Commands::Show { id } => {
+            let task = storage.load_task(&id)
+                .or_else(|_| find_task_by_prefix(storage, &id))?;
+            let all = storage.list_all_tasks()?;
+            let color = config.display.use_color();
+            let line = |t: &Task| {
+                let status = if t.completed { paint("[✓]", GREEN, color) } else { "[ ]".to_string() };
+                format!("  {} {} - {}", status, t.short_id(), t.title)
+            };
+
+            println!("{} {}", paint(&format!("Task {}:", task.short_id()), BOLD, color), task.title);
+            println!("UUID: {}", task.id);
+            let waiting_on = open_prerequisites(&all, &task);
+            let status = if task.completed {
+                "done"
+            } else if !waiting_on.is_empty() {
+                "blocked"
+            } else {
+                "open"
+            };
+            println!("Status: {}", status);
+            println!("Priority: {}", task.priority);
+            println!("Category: {}", task.category);
+            if let Some(desc) = &task.description {
+                println!("Description: {}", desc);
+            }
+            if let Some(due) = &task.due_date {
+                println!("Due: {}", config.display.format_date(due.date_naive()));
+            }
+            if !task.tags.is_empty() {
+                println!("Tags: {}", task.tags_display());
+            }
+            if task.is_daily {
+                println!("Type: Daily recurring task");
+                if let (None, Some(days)) = (&task.recurrence, task.scheduled_days_display()) {
+                    println!("Days: {}", days);
+                }
+            }
+            if let Some(rule) = &task.recurrence {
+                if rule.from_completion {
+                    println!("Repeats: {}, counted from completion", rule);
+                } else {
+                    println!("Repeats: {}", rule);
+                }
+                if let Some(start) = rule.start {
+                    println!("Starting: {}", config.display.format_date(start));
+                }
+                if !rule.except.is_empty() {
+                    let skipped: Vec<String> = rule.except.iter().map(|d| config.display.format_date(*d)).collect();
+                    println!("Except: {}", skipped.join(", "));
+                }
+            }
+            if let Some(ref t) = task.scheduled_time {
+                println!("When: {}", t);
+            }
+            if let Some(ref loc) = task.location {
+                println!("Where: {}", loc);
+            }
+            if let Some(anchor) = task.habit_stack_after.as_ref().and_then(|a| all.iter().find(|t| &t.id == a)) {
+                println!("After: task {} ({})", anchor.short_id(), anchor.title);
+            }
+            if let Some(parent) = task.parent.as_ref().and_then(|p| all.iter().find(|t| &t.id == p)) {
+                println!("Subtask of: task {} ({})", parent.short_id(), parent.title);
+            }
+
+            let subtasks: Vec<&Task> = all.iter().filter(|t| t.parent.as_deref() == Some(task.id.as_str())).collect();
+            if let Some((done, total)) = subtask_progress(&all, &task.id) {
+                println!("\nSubtasks ({}/{} done):", done, total);
+                for sub in subtasks {
+                    println!("{}", line(sub));
+                }
+            }
+
+            if !task.depends_on.is_empty() {
+                println!("\nDepends on:");
+                for prerequisite in &task.depends_on {
+                    match all.iter().find(|t| &t.id == prerequisite) {
+                        Some(t) => println!("{}", line(t)),
+                        None => println!("  [-] {} (deleted or archived)", prerequisite),
+                    }
+                }
+            }
+
+            let blocks: Vec<&Task> = all.iter().filter(|t| t.depends_on.contains(&task.id)).collect();
+            if !blocks.is_empty() {
+                println!("\nBlocks:");
+                for t in blocks {
+                    println!("{}", line(t));
+                }
+            }
+
+            // One line per completion, so a recurring chore keeps every occurrence.
+            let history: Vec<HistoryEntry> = storage.history_entries()?.into_iter()
+                .filter(|e| e.task_id == task.id)
+                .collect();
+            if !history.is_empty() {
+                println!("\nHistory:");
+                for entry in history {
+                    println!("  {}  {}", entry.timestamp, entry.title);
+                }
+            }
+        }
+
         Commands::Complete { id, amount } => {
             let mut task = storage.load_task(&id)
-                .or_else(|_| find_task_by_prefix(&storage, &id))?;
+                .or_else(|_| find_task_by_prefix(storage, &id))?;
 
             if task.is_daily {
                 let today = Local::now().date_naive();
@@ -227,7 +853,7 @@ async fn main() -> Result<()> {
                     if amt_str.is_empty() {
                         anyhow::bail!(
                             "Task '{}' tracks numeric values. Provide an amount, e.g.: daily complete {} 25",
-                            task.title, task.id
+                            task.title, task.short_id()
                         );
                     }
                     let stripped = amt_str.trim_start_matches('+');
@@ -256,11 +882,20 @@ async fn main() -> Result<()> {
                 }
 
                 // Atomic Habits: Make it Satisfying — show streak
-                let streak = storage.get_streak_for_task(&task.id, today)?;
-                if streak == 1 {
-                    println!("Day 1 — every streak starts here. Keep going!");
-                } else if streak > 1 {
-                    println!("Streak: {} days — don't break the chain!", streak);
+                let week_start = config.display.week_start();
+                if let (Some(rule), Some(progress)) = (&task.recurrence, period_progress(storage, &task, today, week_start)?) {
+                    println!("Progress: {}", progress);
+                    let streak = storage.get_period_streak(&task, today, week_start)?;
+                    if streak > 0 {
+                        println!("Streak: {} in a row — keep it up!", rule.periods(streak));
+                    }
+                } else {
+                    let streak = storage.get_habit_streak(&task, today)?;
+                    if streak == 1 {
+                        println!("Day 1 — every streak starts here. Keep going!");
+                    } else if streak > 1 {
+                        println!("Streak: {} days — don't break the chain!", streak);
+                    }
                 }
 
                 // Show identity reinforcement if category has one
@@ -271,21 +906,80 @@ async fn main() -> Result<()> {
                         }
                     }
                 }
+            } else if let (false, Some(rule)) = (task.completed, task.recurrence.clone()) {
+                // A recurring chore logs this occurrence and comes back with its next due date.
+                let today = Local::now().date_naive();
+                let due = task.due_date.map(|d| d.date_naive());
+                let occurrence = match due {
+                    Some(due) => format!("{} (due {})", task.title, due),
+                    None => task.title.clone(),
+                };
+                storage.log_task_completion(&task.id, &occurrence)?;
+                match task.roll_forward(today) {
+                    Some(next) => {
+                        storage.save_task(&task)?;
+                        match due {
+                            Some(due) => println!("Task '{}' done for {}.", task.title, config.display.format_date(due)),
+                            None => println!("Task '{}' done.", task.title),
+                        }
+                        println!("Next due {} ({}).", config.display.format_date(next), rule);
+                    }
+                    None => {
+                        task.mark_complete();
+                        storage.save_task(&task)?;
+                        println!("Task '{}' marked as complete! That was the last one ({}).", task.title, rule);
+                    }
+                }
             } else {
                 // For regular tasks, mark as complete and log to history
                 task.mark_complete();
                 storage.save_task(&task)?;
                 storage.log_task_completion(&task.id, &task.title)?;
                 println!("Task '{}' marked as complete!", task.title);
+
+                // A task is done when all of its steps are.
+                let mut all = storage.list_all_tasks()?;
+                let open: Vec<String> = descendants(&all, &task.id).into_iter()
+                    .filter(|t| !t.completed)
+                    .map(|t| t.id.clone())
+                    .collect();
+                for sub in all.iter_mut().filter(|t| open.contains(&t.id)) {
+                    sub.mark_complete();
+                    storage.save_task(sub)?;
+                    storage.log_task_completion(&sub.id, &sub.title)?;
+                }
+                if !open.is_empty() {
+                    println!("Also completed {} subtask(s).", open.len());
+                }
+
+                // Finishing the last step doesn't close the parent; say so instead.
+                if let Some(parent) = task.parent.as_deref().and_then(|p| all.iter().find(|t| t.id == p)) {
+                    if let (false, Some((done, total))) = (parent.completed, subtask_progress(&all, &parent.id)) {
+                        if done == total {
+                            println!("All {} subtasks of '{}' are done. Close it with `daily complete {}`.",
+                                total, parent.title, parent.short_id());
+                        } else {
+                            println!("'{}': {}/{} subtasks done.", parent.title, done, total);
+                        }
+                    }
+                }
             }
         }
 
         Commands::Uncomplete { id } => {
             let mut task = storage.load_task(&id)
-                .or_else(|_| find_task_by_prefix(&storage, &id))?;
-            task.mark_incomplete();
-            storage.save_task(&task)?;
-            println!("Task '{}' marked as incomplete!", task.title);
+                .or_else(|_| find_task_by_prefix(storage, &id))?;
+            if let (false, false, Some(due), Some(_)) = (task.completed, task.is_daily, task.due_date, &task.recurrence) {
+                // Completing a recurring chore only moved its due date; the old
+                // one isn't kept, so only the journal can take it back.
+                println!("Task '{}' isn't complete; it is next due {}.", task.title, config.display.format_date(due.date_naive()));
+                println!("To take back a completion, run `daily undo` right after it.");
+            } else {
+                task.mark_incomplete();
+                storage.save_task(&task)?;
+                println!("Task '{}' marked as incomplete!", task.title);
+                reopen_ancestors(storage, &task)?;
+            }
         }
 
         Commands::UncompleteAll => {
@@ -303,42 +997,218 @@ async fn main() -> Result<()> {
 
         Commands::Delete { id } => {
             let task = storage.load_task(&id)
-                .or_else(|_| find_task_by_prefix(&storage, &id))?;
-            let title = task.title.clone();
-            storage.delete_task(&task.id)?;
-            println!("Task '{}' deleted!", title);
+                .or_else(|_| find_task_by_prefix(storage, &id))?;
+            let subtasks: Vec<String> = descendants(&storage.list_all_tasks()?, &task.id).into_iter()
+                .map(|t| t.id.clone())
+                .collect();
+            storage.trash_task(&task.id)?;
+            for sub in &subtasks {
+                storage.trash_task(sub)?;
+            }
+            if subtasks.is_empty() {
+                println!("Task '{}' moved to the trash. Restore it with `daily trash restore {}`.", task.title, task.short_id());
+            } else {
+                println!("Task '{}' and {} subtask(s) moved to the trash. Restore them with `daily trash restore {}`.",
+                    task.title, subtasks.len(), task.short_id());
+            }
         }
 
-        Commands::DeleteAll => {
+        Commands::DeleteAll { yes } => {
             let tasks = storage.list_all_tasks()?;
             let count = tasks.len();
+            if count == 0 {
+                println!("No tasks to delete.");
+                return Ok(());
+            }
+            if !yes && !confirm(&format!("Move {} task(s) to the trash?", count))? {
+                println!("Nothing deleted.");
+                return Ok(());
+            }
             for task in tasks {
-                storage.delete_task(&task.id)?;
+                storage.trash_task(&task.id)?;
+            }
+            println!("{} task(s) moved to the trash.", count);
+        }
+
+        Commands::Archive { ids, older_than } => {
+            let tasks = if ids.is_empty() {
+                let cutoff = older_than.as_deref()
+                    .map(parse_age)
+                    .transpose()?
+                    .map(|age| Utc::now() - age);
+                storage.list_all_tasks()?
+                    .into_iter()
+                    .filter(|t| t.completed && !t.is_daily)
+                    .filter(|t| cutoff.is_none_or(|c| t.updated_at < c))
+                    .collect()
+            } else {
+                let mut tasks = Vec::new();
+                for id in &ids {
+                    let task = storage.load_task(id)
+                        .or_else(|_| find_task_by_prefix(storage, id))?;
+                    if !task.completed {
+                        anyhow::bail!("Task '{}' is not completed yet", task.title);
+                    }
+                    if task.is_daily {
+                        anyhow::bail!("Task '{}' is a daily habit; only one-off tasks are archived", task.title);
+                    }
+                    tasks.push(task);
+                }
+                tasks
+            };
+            for task in &tasks {
+                storage.archive_task(&task.id)?;
             }
-            println!("{} task(s) deleted.", count);
+            println!("Archived {} task(s). See them with `daily list --archived`.", tasks.len());
         }
 
+        Commands::Unarchive { id } => {
+            let archived = storage.list_archived()?;
+            let id = find_by_handle(archived.iter().map(|a| &a.task), &id, "archived task")?.id.clone();
+            let task = storage.unarchive_task(&id)?;
+            println!("Task '{}' is back in the task list (ID: {}).", task.title, task.short_id());
+        }
+
+        Commands::Trash { action } => match action {
+            TrashCommand::List => {
+                let mut trash = storage.list_trash()?;
+                if trash.is_empty() {
+                    println!("The trash is empty.");
+                    return Ok(());
+                }
+                trash.sort_by_key(|t| std::cmp::Reverse(t.deleted_at));
+                println!("Trash ({} task(s)):\n", trash.len());
+                for trashed in &trash {
+                    println!("  [{}] {} (deleted {})", trashed.task.short_id(), trashed.task.title,
+                        format_timestamp(&config.display, trashed.deleted_at));
+                }
+            }
+
+            TrashCommand::Restore { id } => {
+                let trash = storage.list_trash()?;
+                let id = find_by_handle(trash.iter().map(|t| &t.task), &id, "task in the trash")?.id.clone();
+                let task = storage.restore_task(&id)?;
+                println!("Task '{}' restored (ID: {}).", task.title, task.short_id());
+                let trashed: Vec<Task> = trash.into_iter().map(|t| t.task).collect();
+                let subtasks = descendants(&trashed, &id);
+                for sub in &subtasks {
+                    storage.restore_task(&sub.id)?;
+                }
+                if !subtasks.is_empty() {
+                    println!("Also restored {} subtask(s).", subtasks.len());
+                }
+            }
+
+            TrashCommand::Empty { older_than } => {
+                let cutoff = older_than.as_deref()
+                    .map(parse_age)
+                    .transpose()?
+                    .map(|age| Utc::now() - age);
+                let mut count = 0;
+                for trashed in storage.list_trash()? {
+                    if cutoff.is_none_or(|c| trashed.deleted_at < c) {
+                        storage.delete_trashed(&trashed.task.id)?;
+                        count += 1;
+                    }
+                }
+                println!("Permanently removed {} task(s) from the trash.", count);
+            }
+        },
+
         Commands::Priority { id, priority } => {
             let mut task = storage.load_task(&id)
-                .or_else(|_| find_task_by_prefix(&storage, &id))?;
+                .or_else(|_| find_task_by_prefix(storage, &id))?;
             let new_priority = Priority::from_str(&priority)
                 .context("Invalid priority. Use: low, medium, high, or critical")?;
             task.update_priority(new_priority);
             storage.save_task(&task)?;
-            println!("Task '{}' priority updated to {}!", task.title, task.priority.to_string());
+            println!("Task '{}' priority updated to {}!", task.title, task.priority);
         }
 
         Commands::Move { id, category } => {
             let mut task = storage.load_task(&id)
-                .or_else(|_| find_task_by_prefix(&storage, &id))?;
+                .or_else(|_| find_task_by_prefix(storage, &id))?;
             task.update_category(category.clone());
             storage.save_task(&task)?;
             println!("Task '{}' moved to category '{}'!", task.title, category);
         }
 
+        Commands::Tag { action } => match action {
+            TagCommand::Add { id, tag } => {
+                let mut task = storage.load_task(&id)
+                    .or_else(|_| find_task_by_prefix(storage, &id))?;
+                let tag = parse_tag_arg(&tag)?;
+                if task.add_tag(tag.clone()) {
+                    storage.save_task(&task)?;
+                    println!("Task '{}' tagged #{}.", task.title, tag);
+                } else {
+                    println!("Task '{}' is already tagged #{}.", task.title, tag);
+                }
+            }
+
+            TagCommand::Remove { id, tag } => {
+                let mut task = storage.load_task(&id)
+                    .or_else(|_| find_task_by_prefix(storage, &id))?;
+                let tag = parse_tag_arg(&tag)?;
+                if task.remove_tag(&tag) {
+                    storage.save_task(&task)?;
+                    println!("Removed #{} from task '{}'.", tag, task.title);
+                } else {
+                    println!("Task '{}' isn't tagged #{}.", task.title, tag);
+                }
+            }
+        },
+
+        Commands::Depend { id, on, remove } => {
+            let mut task = storage.load_task(&id)
+                .or_else(|_| find_task_by_prefix(storage, &id))?;
+            let mut all = storage.list_all_tasks()?;
+            for on in &on {
+                let prerequisite = find_by_handle(all.iter(), on, "task")?.clone();
+                if remove {
+                    if task.remove_dependency(&prerequisite.id) {
+                        println!("Task '{}' no longer depends on '{}'.", task.title, prerequisite.title);
+                    } else {
+                        println!("Task '{}' doesn't depend on '{}'.", task.title, prerequisite.title);
+                    }
+                    continue;
+                }
+
+                if prerequisite.id == task.id {
+                    anyhow::bail!("Task '{}' can't depend on itself", task.title);
+                }
+                if prerequisite.is_daily {
+                    anyhow::bail!("Task '{}' is a daily habit; only one-off tasks can be prerequisites", prerequisite.title);
+                }
+                if let Some(path) = dependency_path(&all, &prerequisite.id, &task.id) {
+                    let handles: Vec<&str> = path.iter()
+                        .map(|id| all.iter().find(|t| &t.id == id).map_or(id.as_str(), |t| t.short_id()))
+                        .collect();
+                    anyhow::bail!("Task {} can't depend on {}: that would make a cycle ({} -> {})",
+                        task.short_id(), prerequisite.short_id(), task.short_id(), handles.join(" -> "));
+                }
+                if task.add_dependency(prerequisite.id.clone()) {
+                    println!("Task '{}' now waits for '{}'.", task.title, prerequisite.title);
+                } else {
+                    println!("Task '{}' already depends on '{}'.", task.title, prerequisite.title);
+                }
+                // Later prerequisites are checked against this one too.
+                if let Some(stored) = all.iter_mut().find(|t| t.id == task.id) {
+                    *stored = task.clone();
+                }
+            }
+            storage.save_task(&task)?;
+        }
+
         Commands::Daily { id, daily } => {
             let mut task = storage.load_task(&id)
-                .or_else(|_| find_task_by_prefix(&storage, &id))?;
+                .or_else(|_| find_task_by_prefix(storage, &id))?;
+            if daily {
+                let all = storage.list_all_tasks()?;
+                if task.parent.is_some() || subtask_progress(&all, &task.id).is_some() {
+                    anyhow::bail!("Task '{}' is part of a task with subtasks; only one-off tasks have subtasks", task.title);
+                }
+            }
             task.update_daily(daily);
             storage.save_task(&task)?;
             if daily {
@@ -375,34 +1245,32 @@ async fn main() -> Result<()> {
             }
         }
 
-        Commands::Today { completed, all } => {
+        Commands::Today { completed, all, .. } => {
             let today = Local::now().date_naive();
             let filter = if all { DayFilter::All } else if completed { DayFilter::Completed } else { DayFilter::Incomplete };
-            show_day_tasks(&storage, today, filter)?;
+            show_day_tasks(storage, today, filter, &config.display)?;
         }
 
         Commands::Day { date } => {
             let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")?;
-            show_day_tasks(&storage, date, DayFilter::Incomplete)?;
+            show_day_tasks(storage, date, DayFilter::Incomplete, &config.display)?;
         }
 
         Commands::TodayPdf { output } => {
             let today = Local::now().date_naive();
-            let output_path = if let Some(path) = output {
-                path
-            } else {
-                let home = dirs::home_dir().context("Could not find home directory")?;
-                home.join(format!("daily-{}.pdf", today)).to_string_lossy().to_string()
+            let output_path = match output {
+                Some(path) => path,
+                None => config.pdf.path_for(today)?.to_string_lossy().to_string(),
             };
 
-            generate_daily_pdf(&storage, today, &output_path)?;
+            generate_daily_pdf(storage, today, &output_path, &config.display)?;
             println!("PDF generated: {}", output_path);
         }
 
         Commands::Schedule { task_id, date } => {
             let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")?;
             let task = storage.load_task(&task_id)
-                .or_else(|_| find_task_by_prefix(&storage, &task_id))?;
+                .or_else(|_| find_task_by_prefix(storage, &task_id))?;
 
             let mut day = storage.load_day(date)?;
             day.add_task(task.id.clone());
@@ -412,6 +1280,7 @@ async fn main() -> Result<()> {
         }
 
         Commands::Daemon { time } => {
+            let time = time.unwrap_or_else(|| config.daemon.time.clone());
             println!("Starting daily prompt daemon...");
             println!("Daily prompt will appear at {}", time);
             println!("Press Ctrl+C to stop.");
@@ -431,30 +1300,45 @@ async fn main() -> Result<()> {
                 println!("No daily habits found. Add one with: daily add \"habit\" --daily");
             } else {
                 let tasks_to_show: Vec<_> = if let Some(ref task_id) = id {
-                    daily_tasks.iter()
-                        .filter(|t| t.id.starts_with(task_id.as_str()))
-                        .collect()
+                    vec![find_by_handle(daily_tasks.iter(), task_id, "daily habit")?]
                 } else {
                     daily_tasks.iter().collect()
                 };
 
                 println!("\n=== HABIT STREAKS ===\n");
+                let week_start = config.display.week_start();
                 for task in tasks_to_show {
+                    let done_today = storage.is_daily_completed_on_date(&task.id, today)?;
+                    let due_today = task.is_scheduled_on(today);
+                    let status = match (done_today, due_today) {
+                        (true, _) => "[done]",
+                        (false, true) => "[todo]",
+                        (false, false) => "[off] ",
+                    };
+
+                    // A "N times per week" habit keeps its streak in weeks.
+                    if let (Some(rule), Some(progress)) = (&task.recurrence, period_progress(storage, task, today, week_start)?) {
+                        let streak = storage.get_period_streak(task, today, week_start)?;
+                        let streak_label = if streak > 0 { rule.periods(streak) } else { "no streak yet".to_string() };
+                        println!("{} {} — {} — Streak: {}", status, task.title, progress, streak_label);
+                        continue;
+                    }
+
                     // Streak as of yesterday (today may not be done yet)
                     let yesterday = today.pred_opt().unwrap_or(today);
-                    let streak = storage.get_streak_for_task(&task.id, yesterday)?;
-                    let done_today = storage.is_daily_completed_on_date(&task.id, today)?;
+                    let streak = storage.get_habit_streak(task, yesterday)?;
 
                     let streak_label = if done_today {
-                        let today_streak = storage.get_streak_for_task(&task.id, today)?;
+                        let today_streak = storage.get_habit_streak(task, today)?;
                         format!("{} days", today_streak)
+                    } else if streak > 0 && !due_today {
+                        format!("{} days (not due today)", streak)
                     } else if streak > 0 {
                         format!("{} days (not done today)", streak)
                     } else {
                         "no streak yet".to_string()
                     };
 
-                    let status = if done_today { "[done]" } else { "[todo]" };
                     println!("{} {} — Streak: {}", status, task.title, streak_label);
                 }
                 println!();
@@ -462,6 +1346,7 @@ async fn main() -> Result<()> {
         }
 
         Commands::Habits { days } => {
+            let days = days.unwrap_or(config.habits.grid_days);
             let today = Local::now().date_naive();
             let daily_tasks: Vec<_> = storage.list_all_tasks()?
                 .into_iter()
@@ -477,23 +1362,51 @@ async fn main() -> Result<()> {
                 // Find longest title for alignment
                 let max_len = daily_tasks.iter().map(|t| t.title.len()).max().unwrap_or(10);
 
+                let color = config.display.use_color();
+                let week_start = config.display.week_start();
+                let index = storage.completion_index()?;
                 for task in &daily_tasks {
                     let grid = storage.get_habit_grid(&task.id, today, days)?;
+                    let first = today - Duration::days(days as i64 - 1);
+                    // A space before each week start makes weeks easy to pick out.
                     let grid_str: String = grid.iter()
-                        .map(|&done| if done { "[+]" } else { "[ ]" })
-                        .collect::<Vec<_>>()
-                        .join("");
+                        .enumerate()
+                        .map(|(i, &done)| {
+                            let date = first + Duration::days(i as i64);
+                            let cell = if done {
+                                paint("[+]", GREEN, color)
+                            } else if let Some((target, start, end)) = task.target_period(date, week_start) {
+                                // Only a period that ended short of its target has misses.
+                                if index.days_completed(&task.id, start, end) >= target || end >= today {
+                                    " · ".to_string()
+                                } else {
+                                    "[ ]".to_string()
+                                }
+                            } else if !task.is_scheduled_on(date) {
+                                " · ".to_string()
+                            } else {
+                                "[ ]".to_string()
+                            };
+                            if i > 0 && date.weekday() == week_start { format!(" {}", cell) } else { cell }
+                        })
+                        .collect();
 
                     let yesterday = today.pred_opt().unwrap_or(today);
-                    let streak = storage.get_streak_for_task(&task.id, yesterday)?;
+                    let streak = storage.get_habit_streak(task, yesterday)?;
                     let done_today = storage.is_daily_completed_on_date(&task.id, today)?;
                     let current_streak = if done_today {
-                        storage.get_streak_for_task(&task.id, today)?
+                        storage.get_habit_streak(task, today)?
                     } else {
                         streak
                     };
 
-                    let streak_badge = if current_streak >= 7 {
+                    let period_rule = task.recurrence.as_ref().filter(|r| r.times.is_some());
+                    let streak_badge = if let Some(rule) = period_rule {
+                        match storage.get_period_streak(task, today, week_start)? {
+                            0 => "  —".to_string(),
+                            n => format!("  {}", rule.periods(n)),
+                        }
+                    } else if current_streak >= 7 {
                         format!("  {} days", current_streak)
                     } else if current_streak > 0 {
                         format!("  {} day{}", current_streak, if current_streak == 1 { "" } else { "s" })
@@ -510,14 +1423,20 @@ async fn main() -> Result<()> {
                     );
                 }
                 println!();
-                println!("[+] = completed  [ ] = missed");
+                if daily_tasks.iter().any(|t| t.recurrence.is_some() || t.scheduled_days.is_some()) {
+                    println!("[+] = completed  [ ] = missed   ·  = not due");
+                } else {
+                    println!("[+] = completed  [ ] = missed");
+                }
                 println!();
             }
         }
 
         Commands::Plan { prompt } => {
             let client = ClaudeClient::new()
-                .context("Failed to initialize Claude client. Make sure ANTHROPIC_API_KEY is set.")?;
+                .context("Failed to initialize Claude client. Make sure ANTHROPIC_API_KEY is set.")?
+                .with_model(config.ai.model.clone())
+                .with_max_tokens(config.ai.max_tokens);
 
             let tasks = storage.list_all_tasks()?;
             let today = Local::now().date_naive();
@@ -531,13 +1450,14 @@ async fn main() -> Result<()> {
             for action in plan.actions {
                 match action.action_type.as_str() {
                     "create_habit" => {
-                        let task_id = storage.get_next_task_id()?;
                         let priority = action.priority.as_deref()
                             .and_then(Priority::from_str)
+                            .or_else(|| Priority::from_str(&config.defaults.priority))
                             .unwrap_or(Priority::Medium);
-                        let category = action.category.clone().unwrap_or_else(|| "default".to_string());
+                        let category = action.category.clone().unwrap_or_else(|| config.defaults.category.clone());
 
-                        let mut task = Task::new(task_id, action.title.clone(), priority, category);
+                        let mut task = Task::new(Uuid::new_v4().to_string(), action.title.clone(), priority, category)
+                            .with_handle(storage.next_handle()?);
                         task = task.with_daily(true);
 
                         if let Some(days_nums) = action.scheduled_days_as_nums() {
@@ -555,13 +1475,13 @@ async fn main() -> Result<()> {
                         if let Some(desc) = action.description {
                             task = task.with_description(desc);
                         }
-                        if let Some(after_id) = action.habit_stack_after {
-                            task = task.with_habit_stack_after(after_id);
+                        if let Some(anchor) = action.habit_stack_after.and_then(|id| find_task_by_prefix(storage, &id).ok()) {
+                            task = task.with_habit_stack_after(anchor.id);
                         }
 
                         storage.save_task(&task)?;
 
-                        println!("Created habit: '{}' (ID: {})", task.title, task.id);
+                        println!("Created habit: '{}' (ID: {})", task.title, task.short_id());
                         if let Some(days_str) = task.scheduled_days_display() {
                             println!("  Days: {}", days_str);
                         } else {
@@ -584,7 +1504,9 @@ async fn main() -> Result<()> {
 
         Commands::Claude { prompt } => {
             let client = ClaudeClient::new()
-                .context("Failed to initialize Claude client. Make sure ANTHROPIC_API_KEY is set.")?;
+                .context("Failed to initialize Claude client. Make sure ANTHROPIC_API_KEY is set.")?
+                .with_model(config.ai.model.clone())
+                .with_max_tokens(config.ai.max_tokens);
 
             // Load all tasks to provide context
             let tasks = storage.list_all_tasks()?;
@@ -597,12 +1519,123 @@ async fn main() -> Result<()> {
             println!("\nClaude's response:\n");
             println!("{}", response);
         }
+
+        Commands::Backup { output } => {
+            let output_path = match output {
+                Some(path) => PathBuf::from(path),
+                None => {
+                    let home = dirs::home_dir().context("Could not find home directory")?;
+                    home.join(format!("daily-backup-{}.json", Local::now().format("%Y%m%d-%H%M%S")))
+                }
+            };
+            let skipped = storage.unreadable_records()?.len();
+            let backup = storage::backup::Backup::capture(storage)?;
+            backup.write_with(&output_path, cipher)?;
+            println!("Backed up {} task(s), {} day(s) and {} log entr{} to {}",
+                backup.tasks.len() + backup.trash.len() + backup.archive.len(),
+                backup.days.len(),
+                backup.daily_log.len() + backup.history_log.len(),
+                if backup.daily_log.len() + backup.history_log.len() == 1 { "y" } else { "ies" },
+                output_path.display());
+            if skipped > 0 {
+                println!("Skipped {} unreadable record(s); run `daily doctor` to see them.", skipped);
+            }
+        }
+
+        Commands::Restore { file, replace } => {
+            let mode = if replace { RestoreMode::Replace } else { RestoreMode::Merge };
+            // Everything is parsed before anything is written.
+            let backup = storage::backup::Backup::read_with(Path::new(&file), |params| {
+                Cipher::derive(&passphrase(config, "Backup passphrase: ")?, params)
+            })?;
+            let contents = backup.contents()?;
+            let report = storage::backup::restore(storage, &contents, mode)?;
+            println!("Restored from {}: {} task(s), {} day(s), {} categor{}, {} log entr{} written",
+                file, report.tasks, report.days, report.categories,
+                if report.categories == 1 { "y" } else { "ies" },
+                report.log_entries, if report.log_entries == 1 { "y" } else { "ies" });
+            if mode == RestoreMode::Replace {
+                println!("{} record(s) not in the backup removed.", report.removed);
+            }
+            println!("Run `daily undo` to reverse this.");
+        }
+
+        Commands::Merge { dir } => {
+            let other_dir = PathBuf::from(&dir);
+            if !other_dir.is_dir() {
+                anyhow::bail!("{} is not a directory", dir);
+            }
+            // Work from a copy so the other dir isn't upgraded or stamped.
+            let cipher = unlock(&other_dir, &Config::load(&other_dir)?)?;
+            let scratch = std::env::temp_dir().join(format!("daily-merge-{}", Uuid::new_v4()));
+            let merged = storage::open_snapshot(&other_dir, &scratch, cipher)
+                .and_then(|other| storage::merge::merge(storage, other.as_ref()));
+            let _ = std::fs::remove_dir_all(&scratch);
+            let report = merged?;
+
+            println!("Merged {}:", dir);
+            for task in &report.tasks {
+                println!("  {}", task);
+            }
+            println!("  {} day plan(s) and {} categor{} merged", report.days, report.categories,
+                if report.categories == 1 { "y" } else { "ies" });
+            println!("  {} daily log and {} history entr{} added, {} already here",
+                report.daily_entries, report.history_entries,
+                if report.history_entries == 1 { "y" } else { "ies" }, report.duplicates);
+            println!("Run `daily undo` to reverse this.");
+        }
+
+        Commands::Doctor { fix } => {
+            let report = storage::doctor::check(storage)?;
+            println!("Checked {} task(s), {} day(s), {} categor{}.", report.tasks, report.days, report.categories,
+                if report.categories == 1 { "y" } else { "ies" });
+
+            if report.issues.is_empty() {
+                println!("No problems found.");
+                return Ok(());
+            }
+
+            let fixed = if fix { storage::doctor::repair(storage, &report.issues)? } else { Vec::new() };
+            for issue in &report.issues {
+                let note = if fixed.contains(issue) {
+                    " [fixed]"
+                } else if issue.is_fixable() {
+                    " [fixable with --fix]"
+                } else {
+                    ""
+                };
+                println!("  - {}{}", issue, note);
+            }
+
+            let remaining = report.issues.len() - fixed.len();
+            if fix {
+                println!("\nFixed {} of {} problem(s).", fixed.len(), report.issues.len());
+            } else {
+                println!("\n{} problem(s) found.", remaining);
+            }
+            let unresolved = report.issues.iter()
+                .filter(|i| !fixed.contains(i) && !matches!(i, storage::doctor::Issue::OrphanLogEntries { .. }))
+                .count();
+            if unresolved > 0 {
+                anyhow::bail!("{} problem(s) need attention", unresolved);
+            }
+        }
+
+        Commands::Migrate { .. }
+        | Commands::Profile { .. }
+        | Commands::Config { .. }
+        | Commands::Sync { .. }
+        | Commands::Encrypt
+        | Commands::Decrypt => {
+            unreachable!("handled before the store is opened")
+        }
+        Commands::Undo | Commands::Redo | Commands::Journal { .. } => unreachable!("handled before recording starts"),
     }
 
     Ok(())
 }
 
-fn generate_daily_pdf(storage: &Storage, date: NaiveDate, output_path: &str) -> Result<()> {
+fn generate_daily_pdf(storage: &dyn TaskStore, date: NaiveDate, output_path: &str, display: &DisplayConfig) -> Result<()> {
     use printpdf::*;
     use std::fs::File;
     use std::io::BufWriter;
@@ -644,8 +1677,9 @@ fn generate_daily_pdf(storage: &Storage, date: NaiveDate, output_path: &str) ->
                 .then(b.priority.value().cmp(&a.priority.value()))
         });
 
+        let family = tasks.clone();
         let mut current_category = String::new();
-        for task in tasks {
+        for (depth, task) in subtask_tree(tasks) {
             // Check if we need a new page
             if y_position < 30.0 {
                 let (page, layer) = doc.add_page(Mm(210.0), Mm(297.0), "Layer 1");
@@ -653,8 +1687,8 @@ fn generate_daily_pdf(storage: &Storage, date: NaiveDate, output_path: &str) ->
                 y_position = 270.0;
             }
 
-            // Category header
-            if task.category != current_category {
+            // Category header; subtasks stay under their parent
+            if depth == 0 && task.category != current_category {
                 y_position -= 5.0;
                 current_layer.use_text(
                     format!("=== {} ===", task.category.to_uppercase()),
@@ -669,17 +1703,20 @@ fn generate_daily_pdf(storage: &Storage, date: NaiveDate, output_path: &str) ->
 
             // Task details
             let status = if task.completed { "[✓]" } else { "[ ]" };
-            let daily_indicator = if task.is_daily { " [Daily]" } else { "" };
+            let daily_indicator = recurrence_indicator(&task);
             let task_line = format!(
-                "{} {} - {}{} (Priority: {})",
+                "{} {} - {}{}{} (Priority: {}){}",
                 status,
-                task.id,
+                task.short_id(),
                 task.title,
+                progress_suffix(&family, &task),
                 daily_indicator,
-                task.priority.to_string()
+                task.priority,
+                tag_suffix(&task),
             );
+            let indent = 6.0 * depth as f32;
 
-            current_layer.use_text(task_line, 11.0, Mm(25.0), Mm(y_position), &font);
+            current_layer.use_text(task_line, 11.0, Mm(25.0 + indent), Mm(y_position), &font);
             y_position -= 6.0;
 
             // Description if present
@@ -687,7 +1724,7 @@ fn generate_daily_pdf(storage: &Storage, date: NaiveDate, output_path: &str) ->
                 current_layer.use_text(
                     format!("    {}", desc),
                     9.0,
-                    Mm(30.0),
+                    Mm(30.0 + indent),
                     Mm(y_position),
                     &font,
                 );
@@ -697,9 +1734,9 @@ fn generate_daily_pdf(storage: &Storage, date: NaiveDate, output_path: &str) ->
             // Due date if present
             if let Some(due) = &task.due_date {
                 current_layer.use_text(
-                    format!("    Due: {}", due.format("%Y-%m-%d")),
+                    format!("    Due: {}", display.format_date(due.date_naive())),
                     9.0,
-                    Mm(30.0),
+                    Mm(30.0 + indent),
                     Mm(y_position),
                     &font,
                 );
@@ -718,25 +1755,142 @@ fn generate_daily_pdf(storage: &Storage, date: NaiveDate, output_path: &str) ->
     Ok(())
 }
 
-fn find_task_by_prefix(storage: &Storage, prefix: &str) -> Result<Task> {
+/// Asks a yes/no question on stdin; anything but "y" or "yes" means no.
+fn confirm(question: &str) -> Result<bool> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Parses an age such as "30d", "2w" or "12h"; a bare number means days.
+fn parse_age(age: &str) -> Result<Duration> {
+    let age = age.trim();
+    let (number, unit) = match age.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => age.split_at(i),
+        None => (age, "d"),
+    };
+    let n: i64 = number.parse().with_context(|| format!("Invalid age '{}'. Use e.g. 30d or 2w", age))?;
+    match unit {
+        "h" => Ok(Duration::hours(n)),
+        "d" => Ok(Duration::days(n)),
+        "w" => Ok(Duration::weeks(n)),
+        _ => anyhow::bail!("Invalid age '{}'. Use e.g. 12h, 30d or 2w", age),
+    }
+}
+
+fn find_task_by_prefix(storage: &dyn TaskStore, prefix: &str) -> Result<Task> {
     let tasks = storage.list_all_tasks()?;
-    let matching: Vec<_> = tasks.into_iter()
-        .filter(|t| t.id.starts_with(prefix))
+    find_by_handle(tasks.iter(), prefix, "task").cloned()
+}
+
+/// The task `prefix` names on the command line: the one whose handle is
+/// exactly `prefix`, else the only one whose handle or UUID starts with it.
+/// `what` names the tasks searched in error messages.
+fn find_by_handle<'a>(tasks: impl Iterator<Item = &'a Task>, prefix: &str, what: &str) -> Result<&'a Task> {
+    let matching: Vec<&Task> = tasks
+        .filter(|t| t.handle.starts_with(prefix) || t.id.starts_with(prefix))
         .collect();
+    let exact: Vec<&Task> = matching.iter().copied().filter(|t| t.handle == prefix).collect();
+    let matching = if exact.is_empty() { matching } else { exact };
 
     match matching.len() {
-        0 => anyhow::bail!("No task found with ID starting with '{}'", prefix),
-        1 => Ok(matching.into_iter().next().unwrap()),
-        _ => anyhow::bail!("Multiple tasks found with ID starting with '{}'. Please be more specific.", prefix),
+        0 => anyhow::bail!("No {} found with ID starting with '{}'", what, prefix),
+        1 => Ok(matching[0]),
+        _ => anyhow::bail!("Multiple {}s found with ID starting with '{}'. Please be more specific.", what, prefix),
+    }
+}
+
+/// Reopens the completed tasks above `task`: a task with an open step isn't done.
+fn reopen_ancestors(storage: &dyn TaskStore, task: &Task) -> Result<()> {
+    let mut all = storage.list_all_tasks()?;
+    let done: Vec<String> = ancestors(&all, &task.id).into_iter()
+        .filter(|t| t.completed)
+        .map(|t| t.id.clone())
+        .collect();
+    for parent in all.iter_mut().filter(|t| done.contains(&t.id)) {
+        parent.mark_incomplete();
+        storage.save_task(parent)?;
+        println!("Task '{}' is open again.", parent.title);
+    }
+    Ok(())
+}
+
+/// ` [blocked by 3, 5]` after an open task waiting on others, or nothing.
+fn blocked_suffix(all: &[Task], task: &Task) -> String {
+    if task.completed {
+        return String::new();
+    }
+    let waiting_on: Vec<&str> = open_prerequisites(all, task).iter().map(|t| t.short_id()).collect();
+    if waiting_on.is_empty() { String::new() } else { format!(" [blocked by {}]", waiting_on.join(", ")) }
+}
+
+/// ` [3/6]` after a task with subtasks, counting the ones done.
+fn progress_suffix(all: &[Task], task: &Task) -> String {
+    subtask_progress(all, &task.id)
+        .map(|(done, total)| format!(" [{}/{}]", done, total))
+        .unwrap_or_default()
+}
+
+/// ` [every 2 weeks on Tue]` for a habit or chore on a rule, ` [Daily]` for
+/// other habits, nothing for one-off tasks.
+fn recurrence_indicator(task: &Task) -> String {
+    match (&task.recurrence, task.is_daily) {
+        (Some(rule), _) => format!(" [{}]", rule),
+        (None, true) => " [Daily]".to_string(),
+        (None, false) => String::new(),
     }
 }
 
+/// `2/3 this week` for a habit with a "N times per week/month" target.
+fn period_progress(storage: &dyn TaskStore, task: &Task, date: NaiveDate, week_start: Weekday) -> Result<Option<String>> {
+    let (Some(rule), Some((target, start, end))) = (&task.recurrence, task.target_period(date, week_start)) else {
+        return Ok(None);
+    };
+    let done = storage.count_completed_days(&task.id, start, end)?;
+    Ok(Some(format!("{}/{} {}", done, target, rule.period_label())))
+}
+
+/// A YYYY-MM-DD date from the command line.
+fn parse_date_arg(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .with_context(|| format!("Invalid date '{}'. Use YYYY-MM-DD", date))
+}
+
+/// A tag from the command line, normalized.
+fn parse_tag_arg(tag: &str) -> Result<String> {
+    parse_tag(tag).with_context(|| format!("Invalid tag '{}'. Use letters, digits, '-', '_', '/' and '.'", tag))
+}
+
+/// ` #a #b` after a task's title, or nothing when it has no tags.
+fn tag_suffix(task: &Task) -> String {
+    if task.tags.is_empty() { String::new() } else { format!(" {}", task.tags_display()) }
+}
+
+const BOLD: &str = "1";
+const GREEN: &str = "32";
+const DIM: &str = "2";
+
+/// Wraps `text` in an ANSI style when color output is on.
+fn paint(text: &str, style: &str, color: bool) -> String {
+    if color { format!("\x1b[{}m{}\x1b[0m", style, text) } else { text.to_string() }
+}
+
+/// A UTC timestamp in local time, using the configured date format.
+fn format_timestamp(display: &DisplayConfig, at: chrono::DateTime<Utc>) -> String {
+    let local = at.with_timezone(&Local);
+    format!("{} {}", display.format_date(local.date_naive()), local.format("%H:%M"))
+}
+
+#[derive(Clone, Copy)]
 enum DayFilter { Incomplete, Completed, All }
 
-fn show_day_tasks(storage: &Storage, date: NaiveDate, filter: DayFilter) -> Result<()> {
+fn show_day_tasks(storage: &dyn TaskStore, date: NaiveDate, filter: DayFilter, display: &DisplayConfig) -> Result<()> {
     let day = storage.load_day(date)?;
+    let color = display.use_color();
 
-    println!("\nTasks for {}:\n", date);
+    println!("\nTasks for {}:\n", display.format_date(date));
 
     // Collect tasks scheduled for this day
     let mut tasks: Vec<Task> = day.task_ids
@@ -746,22 +1900,15 @@ fn show_day_tasks(storage: &Storage, date: NaiveDate, filter: DayFilter) -> Resu
 
     // Also collect tasks with due_date matching this date
     let all_tasks = storage.list_all_tasks()?;
-    for task in all_tasks {
+    for task in all_tasks.iter().cloned() {
         // Skip if already in the list
         if tasks.iter().any(|t| t.id == task.id) {
             continue;
         }
 
-        // Include daily tasks that are scheduled for this day of the week
+        // Include daily tasks that are due on this day
         if task.is_daily {
-            let include = match &task.scheduled_days {
-                None => true, // every day
-                Some(days) => {
-                    let weekday = date.weekday().num_days_from_monday() as u8;
-                    days.contains(&weekday)
-                }
-            };
-            if include {
+            if task.is_scheduled_on(date) {
                 tasks.push(task);
             }
             continue;
@@ -775,6 +1922,16 @@ fn show_day_tasks(storage: &Storage, date: NaiveDate, filter: DayFilter) -> Resu
         }
     }
 
+    // Subtasks come along with their parent
+    let parents: Vec<String> = tasks.iter().filter(|t| !t.is_daily).map(|t| t.id.clone()).collect();
+    for id in &parents {
+        for sub in descendants(&all_tasks, id) {
+            if !tasks.iter().any(|t| t.id == sub.id) {
+                tasks.push(sub.clone());
+            }
+        }
+    }
+
     match filter {
         DayFilter::All => {}
         DayFilter::Completed => tasks.retain(|task| {
@@ -805,9 +1962,9 @@ fn show_day_tasks(storage: &Storage, date: NaiveDate, filter: DayFilter) -> Resu
         });
 
         let mut current_category = String::new();
-        for task in &tasks {
-            if task.category != current_category {
-                println!("\n=== {} ===", task.category.to_uppercase());
+        for (depth, task) in &subtask_tree(tasks) {
+            if *depth == 0 && task.category != current_category {
+                println!("\n{}", paint(&format!("=== {} ===", task.category.to_uppercase()), BOLD, color));
                 current_category = task.category.clone();
             }
 
@@ -816,7 +1973,7 @@ fn show_day_tasks(storage: &Storage, date: NaiveDate, filter: DayFilter) -> Resu
             } else {
                 task.completed
             };
-            let status = if done_today { "[+]" } else { "[ ]" };
+            let status = if done_today { paint("[+]", GREEN, color) } else { "[ ]".to_string() };
 
             // Build implementation intention hint
             let mut intention = String::new();
@@ -828,19 +1985,27 @@ fn show_day_tasks(storage: &Storage, date: NaiveDate, filter: DayFilter) -> Resu
             }
 
             let two_min_marker = if task.two_minute { " [2min]" } else { "" };
-            let days_marker = task.scheduled_days_display()
+            let days_marker = task.recurrence.as_ref()
+                .map(|rule| rule.to_string())
+                .or_else(|| task.scheduled_days_display())
                 .map(|d| format!(" ({})", d))
                 .unwrap_or_default();
 
-            println!(
-                "{} [{}] {}{}{}{}",
+            let line = format!(
+                "{} [{}] {}{}{}{}{}{}{}",
                 status,
-                task.id,
+                task.short_id(),
                 task.title,
+                progress_suffix(&all_tasks, task),
                 intention,
                 days_marker,
                 two_min_marker,
+                tag_suffix(task),
+                blocked_suffix(&all_tasks, task),
             );
+            // Tasks still waiting on a prerequisite are greyed out.
+            let line = if done_today || open_prerequisites(&all_tasks, task).is_empty() { line } else { paint(&line, DIM, color) };
+            println!("{}{}", "    ".repeat(*depth), line);
 
             // Habit stacking cue
             if let Some(ref after_id) = task.habit_stack_after {
@@ -862,12 +2027,18 @@ fn show_day_tasks(storage: &Storage, date: NaiveDate, filter: DayFilter) -> Resu
                 }
             }
 
-            // Streak for daily habits
-            if task.is_daily {
+            // Streak for daily habits, in periods for "N times per week" ones
+            if let (Some(rule), Some(progress)) = (&task.recurrence, period_progress(storage, task, date, display.week_start())?) {
+                println!("     Progress: {}", progress);
+                let streak = storage.get_period_streak(task, date, display.week_start())?;
+                if streak > 0 {
+                    println!("     Streak: {}", rule.periods(streak));
+                }
+            } else if task.is_daily {
                 let yesterday = date.pred_opt().unwrap_or(date);
-                let streak = storage.get_streak_for_task(&task.id, yesterday)?;
+                let streak = storage.get_habit_streak(task, yesterday)?;
                 if done_today {
-                    let today_streak = storage.get_streak_for_task(&task.id, date)?;
+                    let today_streak = storage.get_habit_streak(task, date)?;
                     if today_streak > 0 {
                         println!("     Streak: {} day{}", today_streak, if today_streak == 1 { "" } else { "s" });
                     }
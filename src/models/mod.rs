@@ -1,7 +1,9 @@
 pub mod task;
 pub mod category;
 pub mod day;
+pub mod recurrence;
 
-pub use task::{Task, Priority};
+pub use task::{Task, Priority, TagFilter};
 pub use category::Category;
 pub use day::Day;
+pub use recurrence::Recurrence;
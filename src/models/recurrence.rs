@@ -0,0 +1,784 @@
+use anyhow::{Context, Result};
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const DAY_CODES: [&str; 7] = ["MO", "TU", "WE", "TH", "FR", "SA", "SU"];
+const DAY_NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const MONTH_NAMES: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// How long one period of a rule is; `interval` counts these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    fn unit(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "day",
+            Frequency::Weekly => "week",
+            Frequency::Monthly => "month",
+            Frequency::Yearly => "year",
+        }
+    }
+}
+
+/// A weekday in a rule (0=Mon..6=Sun, like `scheduled_days`), optionally
+/// only its `nth` occurrence in the month: 1 is the first, -1 the last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleDay {
+    pub nth: Option<i8>,
+    pub weekday: u8,
+}
+
+/// When a recurring task is due. Mirrors the parts of an RFC 5545 RRULE that
+/// make sense for habits and chores, plus DTSTART and EXDATE:
+///
+/// - `interval`: every n-th day, week, month or year, counted from `start`.
+/// - `weekdays`, `month_days`, `months`: BYDAY, BYMONTHDAY, BYMONTH.
+/// - `times`: a flexible target instead of fixed days ("3 times per week");
+///   any day in the period counts. Written as the `X-TIMES` extension.
+/// - `from_completion`: for one-off tasks, the next due date counts from the
+///   day the task was done rather than from its due date.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub weekdays: Vec<RuleDay>,
+    pub month_days: Vec<i8>,
+    pub months: Vec<u8>,
+    pub times: Option<u32>,
+    pub start: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+    pub except: Vec<NaiveDate>,
+    #[serde(default)]
+    pub from_completion: bool,
+}
+
+impl Recurrence {
+    pub fn new(frequency: Frequency) -> Self {
+        Self {
+            frequency,
+            interval: 1,
+            weekdays: Vec::new(),
+            month_days: Vec::new(),
+            months: Vec::new(),
+            times: None,
+            start: None,
+            until: None,
+            except: Vec::new(),
+            from_completion: false,
+        }
+    }
+
+    /// Parses either an RRULE (anything containing `FREQ=`) or the friendly
+    /// syntax of `--every`.
+    pub fn parse(text: &str) -> Result<Self> {
+        if text.to_uppercase().contains("FREQ=") {
+            Self::parse_rrule(text)
+        } else {
+            Self::parse_friendly(text)
+        }
+    }
+
+    /// Parses an RRULE such as `FREQ=MONTHLY;BYDAY=1MO`. The `RRULE:` prefix
+    /// is optional, and `DTSTART` and `EXDATE` lines may come along as in an
+    /// iCalendar file. `COUNT` isn't supported; use `UNTIL`.
+    pub fn parse_rrule(text: &str) -> Result<Self> {
+        let mut rule: Option<Recurrence> = None;
+        let mut start = None;
+        let mut except = Vec::new();
+
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let upper = line.to_uppercase();
+            // Property parameters such as `;VALUE=DATE` sit before the colon.
+            let (name, value) = match upper.split_once(':') {
+                Some((name, value)) => (name.split(';').next().unwrap_or(name), value),
+                None => ("RRULE", upper.as_str()),
+            };
+            match name {
+                "RRULE" => rule = Some(parse_rule_parts(value)?),
+                "DTSTART" => start = Some(parse_ical_date(value)?),
+                "EXDATE" => {
+                    for date in value.split(',') {
+                        except.push(parse_ical_date(date)?);
+                    }
+                }
+                _ => anyhow::bail!("unsupported property '{}'", name),
+            }
+        }
+
+        let mut rule = rule.context("missing RRULE")?;
+        rule.start = start;
+        rule.except = except;
+        Ok(rule)
+    }
+
+    /// Parses the friendly syntax: `day`, `3 days`, `weekday`, `weekend`,
+    /// `2 weeks on tue`, `mon,wed,fri`, `month on the 15th`,
+    /// `month on the first monday`, `month on the last day`,
+    /// `year on mar 14` or `3 times per week`. A leading `every` and
+    /// `other` (for 2) are understood.
+    pub fn parse_friendly(text: &str) -> Result<Self> {
+        let text = text.trim().to_lowercase();
+        let text = text.strip_prefix("every ").unwrap_or(&text).trim();
+
+        if let Some((count, period)) = text.split_once(" times ").or_else(|| text.split_once(" time ")) {
+            let times: u32 = parse_count(count).context("expected a number of times, like '3 times per week'")?;
+            let period = period.trim_start_matches("per ").trim_start_matches("a ").trim();
+            let mut rule = Recurrence::new(match period {
+                "week" => Frequency::Weekly,
+                "month" => Frequency::Monthly,
+                _ => anyhow::bail!("'{}' isn't a period; use 'per week' or 'per month'", period),
+            });
+            if times == 0 {
+                anyhow::bail!("the number of times must be at least 1");
+            }
+            rule.times = Some(times);
+            return Ok(rule);
+        }
+
+        // A bare list of days: "mon,wed,fri" or "tue and thu".
+        if let Some(days) = parse_day_list(text) {
+            return Ok(Recurrence::new(Frequency::Weekly).with_weekdays(&days));
+        }
+
+        let (head, on) = match text.split_once(" on ") {
+            Some((head, on)) => (head.trim(), Some(on.trim())),
+            None => (text, None),
+        };
+        let words: Vec<&str> = head.split_whitespace().collect();
+        let (interval, unit) = match words.as_slice() {
+            [unit] => (1, *unit),
+            [count, unit] => (parse_count(count).with_context(|| format!("'{}' isn't a number", count))?, *unit),
+            _ => anyhow::bail!("expected something like '2 weeks on tue' or 'month on the 15th'"),
+        };
+        if interval == 0 {
+            anyhow::bail!("the interval must be at least 1");
+        }
+
+        let mut rule = match unit.trim_end_matches('s') {
+            "day" => Recurrence::new(Frequency::Daily),
+            "week" => Recurrence::new(Frequency::Weekly),
+            "month" => Recurrence::new(Frequency::Monthly),
+            "year" => Recurrence::new(Frequency::Yearly),
+            "weekday" => Recurrence::new(Frequency::Weekly).with_weekdays(&[0, 1, 2, 3, 4]),
+            "weekend" => Recurrence::new(Frequency::Weekly).with_weekdays(&[5, 6]),
+            _ => anyhow::bail!("'{}' isn't a period or a list of days; use days, weeks, months or years", unit),
+        };
+        rule.interval = interval;
+
+        if let Some(on) = on {
+            match rule.frequency {
+                Frequency::Daily => anyhow::bail!("'on' needs weeks, months or years"),
+                Frequency::Weekly => {
+                    let days = parse_day_list(on).with_context(|| format!("'{}' isn't a list of days", on))?;
+                    rule.weekdays = days.iter().map(|&weekday| RuleDay { nth: None, weekday }).collect();
+                }
+                Frequency::Monthly => parse_month_spec(&mut rule, on)?,
+                Frequency::Yearly => parse_year_spec(&mut rule, on)?,
+            }
+        }
+        Ok(rule)
+    }
+
+    fn with_weekdays(mut self, days: &[u8]) -> Self {
+        self.weekdays = days.iter().map(|&weekday| RuleDay { nth: None, weekday }).collect();
+        self
+    }
+
+    /// The rule as an RRULE value, without `DTSTART` and `EXDATE`.
+    pub fn to_rrule(&self) -> String {
+        let mut parts = vec![format!("FREQ={}", match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        })];
+        if self.interval != 1 {
+            parts.push(format!("INTERVAL={}", self.interval));
+        }
+        if !self.weekdays.is_empty() {
+            let days: Vec<String> = self.weekdays.iter()
+                .map(|d| format!("{}{}", d.nth.map(|n| n.to_string()).unwrap_or_default(), DAY_CODES[d.weekday as usize]))
+                .collect();
+            parts.push(format!("BYDAY={}", days.join(",")));
+        }
+        if !self.month_days.is_empty() {
+            parts.push(format!("BYMONTHDAY={}", join(&self.month_days)));
+        }
+        if !self.months.is_empty() {
+            parts.push(format!("BYMONTH={}", join(&self.months)));
+        }
+        if let Some(until) = self.until {
+            parts.push(format!("UNTIL={}", until.format("%Y%m%d")));
+        }
+        if let Some(times) = self.times {
+            parts.push(format!("X-TIMES={}", times));
+        }
+        parts.join(";")
+    }
+
+    /// Whether the task is due on `date`. Intervals count from `start`, or
+    /// from `anchor` (usually the day the task was created) when the rule
+    /// has none. Flexible rules are due every day of a period they cover.
+    pub fn occurs_on(&self, date: NaiveDate, anchor: NaiveDate) -> bool {
+        let anchor = self.start.unwrap_or(anchor);
+        if date < anchor || self.until.is_some_and(|u| date > u) || self.except.contains(&date) {
+            return false;
+        }
+
+        let periods = match self.frequency {
+            Frequency::Daily => (date - anchor).num_days(),
+            Frequency::Weekly => (week_of(date) - week_of(anchor)).num_weeks(),
+            Frequency::Monthly => month_number(date) - month_number(anchor),
+            Frequency::Yearly => (date.year() - anchor.year()) as i64,
+        };
+        if periods % self.interval.max(1) as i64 != 0 {
+            return false;
+        }
+        if !self.months.is_empty() && !self.months.contains(&(date.month() as u8)) {
+            return false;
+        }
+        if self.times.is_some() {
+            return true;
+        }
+
+        let weekday_matches = || self.weekdays.iter().any(|d| d.matches(date));
+        let month_day_matches = || self.month_days.iter().any(|&d| month_day(date, d));
+        match (self.weekdays.is_empty(), self.month_days.is_empty()) {
+            (false, false) => weekday_matches() && month_day_matches(),
+            (false, true) => weekday_matches(),
+            (true, false) => month_day_matches(),
+            (true, true) => match self.frequency {
+                Frequency::Daily => true,
+                Frequency::Weekly => date.weekday() == anchor.weekday(),
+                Frequency::Monthly => date.day() == anchor.day(),
+                Frequency::Yearly => {
+                    date.day() == anchor.day() && (!self.months.is_empty() || date.month() == anchor.month())
+                }
+            },
+        }
+    }
+}
+
+impl Recurrence {
+    /// The first day after `date` the rule falls on, or `None` when it has
+    /// ended (or finds nothing within ten of its periods, or a thousand years).
+    pub fn next_after(&self, date: NaiveDate, anchor: NaiveDate) -> Option<NaiveDate> {
+        let period_days: i64 = match self.frequency {
+            Frequency::Daily => 1,
+            Frequency::Weekly => 7,
+            Frequency::Monthly => 31,
+            // Leap days come round every four years at best.
+            Frequency::Yearly => 366 * 4,
+        };
+        let horizon = (period_days * 10 * self.interval.max(1) as i64).min(366 * 1000);
+        let limit = date.checked_add_signed(chrono::Duration::days(horizon)).unwrap_or(NaiveDate::MAX);
+        date.iter_days()
+            .skip(1)
+            .take_while(|d| *d <= limit && self.until.is_none_or(|u| *d <= u))
+            .find(|&d| self.occurs_on(d, anchor))
+    }
+}
+
+impl Recurrence {
+    /// For a flexible rule, the first and last day of the period containing
+    /// `date`. Weeks begin on `week_start` and months on the 1st; with an
+    /// interval, periods group that many of them counting from `start` (or
+    /// `anchor`). A period reaching past the calendar's range is cut off at
+    /// its end.
+    pub fn period_containing(&self, date: NaiveDate, anchor: NaiveDate, week_start: Weekday) -> (NaiveDate, NaiveDate) {
+        let anchor = self.start.unwrap_or(anchor);
+        let week_of = |d: NaiveDate| {
+            let offset = (7 + d.weekday().num_days_from_monday() - week_start.num_days_from_monday()) % 7;
+            d.checked_sub_signed(chrono::Duration::days(offset as i64)).unwrap_or(NaiveDate::MIN)
+        };
+        let unit_start = |k: i64| match self.frequency {
+            Frequency::Daily => chrono::Duration::try_days(k).and_then(|d| anchor.checked_add_signed(d)),
+            Frequency::Weekly => chrono::Duration::try_weeks(k).and_then(|d| week_of(anchor).checked_add_signed(d)),
+            Frequency::Monthly => {
+                let month = month_number(anchor).checked_add(k)?;
+                NaiveDate::from_ymd_opt(i32::try_from(month.div_euclid(12)).ok()?, month.rem_euclid(12) as u32 + 1, 1)
+            }
+            Frequency::Yearly => {
+                let year = i32::try_from(k).ok().and_then(|k| anchor.year().checked_add(k))?;
+                NaiveDate::from_ymd_opt(year, 1, 1)
+            }
+        };
+        let unit = match self.frequency {
+            Frequency::Daily => (date - anchor).num_days(),
+            Frequency::Weekly => (week_of(date) - week_of(anchor)).num_weeks(),
+            Frequency::Monthly => month_number(date) - month_number(anchor),
+            Frequency::Yearly => (date.year() - anchor.year()) as i64,
+        };
+        let n = self.interval.max(1) as i64;
+        let first = unit.div_euclid(n) * n;
+        let start = unit_start(first).unwrap_or(NaiveDate::MIN);
+        let end = unit_start(first + n).and_then(|d| d.pred_opt()).unwrap_or(NaiveDate::MAX);
+        (start, end)
+    }
+
+    /// `this week`, or `these 2 weeks` for a longer period.
+    pub fn period_label(&self) -> String {
+        let unit = self.frequency.unit();
+        if self.interval == 1 {
+            format!("this {}", unit)
+        } else {
+            format!("these {} {}s", self.interval, unit)
+        }
+    }
+
+    /// `n` periods: `1 week`, `3 months`, or `2 × 2 weeks`.
+    pub fn periods(&self, n: u32) -> String {
+        let unit = self.frequency.unit();
+        if self.interval == 1 {
+            format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" })
+        } else {
+            format!("{} × {} {}s", n, self.interval, unit)
+        }
+    }
+}
+
+impl RuleDay {
+    fn matches(&self, date: NaiveDate) -> bool {
+        if date.weekday().num_days_from_monday() as u8 != self.weekday {
+            return false;
+        }
+        match self.nth {
+            None => true,
+            Some(n) if n > 0 => (date.day() as i8 - 1) / 7 + 1 == n,
+            Some(n) => -((days_in_month(date) as i8 - date.day() as i8) / 7 + 1) == n,
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = self.frequency.unit();
+        if let Some(times) = self.times {
+            if self.interval == 1 {
+                return write!(f, "{} time{} per {}", times, if times == 1 { "" } else { "s" }, unit);
+            }
+            return write!(f, "{} time{} every {} {}s", times, if times == 1 { "" } else { "s" }, self.interval, unit);
+        }
+
+        if self.interval == 1 {
+            write!(f, "every {}", unit)?;
+        } else {
+            write!(f, "every {} {}s", self.interval, unit)?;
+        }
+
+        let mut on: Vec<String> = Vec::new();
+        if !self.months.is_empty() {
+            on.push(self.months.iter().map(|&m| MONTH_NAMES[m as usize - 1]).collect::<Vec<_>>().join(", "));
+        }
+        if !self.weekdays.is_empty() {
+            on.push(self.weekdays.iter().map(|d| match d.nth {
+                None => DAY_NAMES[d.weekday as usize].to_string(),
+                Some(n) => format!("the {} {}", ordinal(n), DAY_NAMES[d.weekday as usize]),
+            }).collect::<Vec<_>>().join(", "));
+        }
+        if !self.month_days.is_empty() {
+            on.push(format!("the {}", self.month_days.iter().map(|&d| day_ordinal(d)).collect::<Vec<_>>().join(", ")));
+        }
+        if !on.is_empty() {
+            write!(f, " on {}", on.join(" "))?;
+        }
+        if let Some(until) = self.until {
+            write!(f, " until {}", until)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_rule_parts(value: &str) -> Result<Recurrence> {
+    let mut frequency = None;
+    let mut rule = Recurrence::new(Frequency::Daily);
+
+    for part in value.split(';').filter(|p| !p.is_empty()) {
+        let (key, value) = part.split_once('=').with_context(|| format!("'{}' isn't KEY=VALUE", part))?;
+        match key {
+            "FREQ" => frequency = Some(match value {
+                "DAILY" => Frequency::Daily,
+                "WEEKLY" => Frequency::Weekly,
+                "MONTHLY" => Frequency::Monthly,
+                "YEARLY" => Frequency::Yearly,
+                _ => anyhow::bail!("FREQ={} isn't supported; use DAILY, WEEKLY, MONTHLY or YEARLY", value),
+            }),
+            "INTERVAL" => {
+                rule.interval = value.parse().ok().filter(|&n| n > 0).with_context(|| format!("bad INTERVAL '{}'", value))?;
+            }
+            "BYDAY" => {
+                for day in value.split(',') {
+                    let split = day.len().checked_sub(2).filter(|&i| day.is_char_boundary(i)).with_context(|| format!("bad BYDAY '{}'", day))?;
+                    let (nth, code) = day.split_at(split);
+                    let weekday = DAY_CODES.iter().position(|&c| c == code).with_context(|| format!("bad BYDAY '{}'", day))?;
+                    let nth = match nth {
+                        "" => None,
+                        n => Some(n.trim_start_matches('+').parse::<i8>().ok()
+                            .filter(|n| (1..=5).contains(&n.unsigned_abs()))
+                            .with_context(|| format!("bad BYDAY '{}'", day))?),
+                    };
+                    rule.weekdays.push(RuleDay { nth, weekday: weekday as u8 });
+                }
+            }
+            "BYMONTHDAY" => {
+                for day in value.split(',') {
+                    let day: i8 = day.parse().ok()
+                        .filter(|d: &i8| (1..=31).contains(&d.unsigned_abs()))
+                        .with_context(|| format!("bad BYMONTHDAY '{}'", day))?;
+                    rule.month_days.push(day);
+                }
+            }
+            "BYMONTH" => {
+                for month in value.split(',') {
+                    let month: u8 = month.parse().ok()
+                        .filter(|m| (1..=12).contains(m))
+                        .with_context(|| format!("bad BYMONTH '{}'", month))?;
+                    rule.months.push(month);
+                }
+            }
+            "UNTIL" => rule.until = Some(parse_ical_date(value)?),
+            "X-TIMES" => {
+                rule.times = Some(value.parse().ok().filter(|&n| n > 0).with_context(|| format!("bad X-TIMES '{}'", value))?);
+            }
+            // Weeks always start on Monday for intervals.
+            "WKST" => {}
+            "COUNT" => anyhow::bail!("COUNT isn't supported; use UNTIL"),
+            _ => anyhow::bail!("{} isn't supported", key),
+        }
+    }
+
+    rule.frequency = frequency.context("missing FREQ")?;
+    Ok(rule)
+}
+
+/// `20260314`, `20260314T090000Z` or `2026-03-14`; times are dropped.
+fn parse_ical_date(value: &str) -> Result<NaiveDate> {
+    let date = value.split('T').next().unwrap_or(value).trim();
+    NaiveDate::parse_from_str(date, "%Y%m%d")
+        .or_else(|_| NaiveDate::parse_from_str(date, "%Y-%m-%d"))
+        .with_context(|| format!("bad date '{}'", value))
+}
+
+fn parse_count(word: &str) -> Option<u32> {
+    match word {
+        "other" => Some(2),
+        "once" | "one" => Some(1),
+        "twice" | "two" => Some(2),
+        "three" => Some(3),
+        _ => word.parse().ok(),
+    }
+}
+
+fn parse_weekday(word: &str) -> Option<u8> {
+    let word = word.trim().trim_end_matches('s');
+    DAY_NAMES.iter().zip(["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"])
+        .position(|(short, long)| word == short.to_lowercase() || word == long)
+        .or(match word {
+            "tues" => Some(1),
+            "weds" => Some(2),
+            "thur" | "thurs" => Some(3),
+            _ => None,
+        })
+        .map(|d| d as u8)
+}
+
+/// `mon,wed,fri`, `mon wed` or `tue and thu`.
+fn parse_day_list(text: &str) -> Option<Vec<u8>> {
+    let days: Option<Vec<u8>> = text.replace(" and ", ",")
+        .split([',', ' '])
+        .filter(|w| !w.is_empty())
+        .map(parse_weekday)
+        .collect();
+    days.filter(|d| !d.is_empty())
+}
+
+/// `first`, `2nd`, `last`, ... as used in `the first monday`.
+fn parse_ordinal(word: &str) -> Option<i8> {
+    match word {
+        "first" | "1st" => Some(1),
+        "second" | "2nd" => Some(2),
+        "third" | "3rd" => Some(3),
+        "fourth" | "4th" => Some(4),
+        "fifth" | "5th" => Some(5),
+        "last" => Some(-1),
+        _ => None,
+    }
+}
+
+/// `15`, `15th` or `day 15`.
+fn parse_day_number(text: &str) -> Option<i8> {
+    let text = text.strip_prefix("day ").unwrap_or(text).trim();
+    let digits = text.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    digits.parse().ok().filter(|d| (1..=31).contains(d))
+}
+
+/// What follows `month on`: `the 15th`, `the last day`, `the first monday`,
+/// or several of one kind joined with commas.
+fn parse_month_spec(rule: &mut Recurrence, text: &str) -> Result<()> {
+    for item in text.replace(" and ", ",").split(',').map(str::trim).filter(|i| !i.is_empty()) {
+        let item = item.strip_prefix("the ").unwrap_or(item);
+        let words: Vec<&str> = item.split_whitespace().collect();
+        match words.as_slice() {
+            ["last", "day"] => rule.month_days.push(-1),
+            [nth, day] if parse_ordinal(nth).is_some() && parse_weekday(day).is_some() => {
+                rule.weekdays.push(RuleDay { nth: parse_ordinal(nth), weekday: parse_weekday(day).unwrap_or(0) });
+            }
+            _ => rule.month_days.push(parse_day_number(item)
+                .with_context(|| format!("'{}' isn't a day of the month, like 'the 15th' or 'the first monday'", item))?),
+        }
+    }
+    Ok(())
+}
+
+/// What follows `year on`: `mar 14`, `march 14`, `14 march` or `03-14`.
+fn parse_year_spec(rule: &mut Recurrence, text: &str) -> Result<()> {
+    let month_of = |word: &str| MONTH_NAMES.iter().position(|m| word.starts_with(&m.to_lowercase()) && word.len() >= 3);
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let (month, day) = match words.as_slice() {
+        [month, day] if month_of(month).is_some() => (month_of(month), parse_day_number(day)),
+        [day, month] if month_of(month).is_some() => (month_of(month), parse_day_number(day)),
+        [numeric] => match numeric.split_once('-') {
+            Some((m, d)) => (m.parse::<usize>().ok().filter(|m| (1..=12).contains(m)).map(|m| m - 1), parse_day_number(d)),
+            None => (None, None),
+        },
+        _ => (None, None),
+    };
+    match (month, day) {
+        (Some(month), Some(day)) => {
+            rule.months.push(month as u8 + 1);
+            rule.month_days.push(day);
+            Ok(())
+        }
+        _ => anyhow::bail!("'{}' isn't a date, like 'mar 14' or '03-14'", text),
+    }
+}
+
+/// Monday of `date`'s week.
+fn week_of(date: NaiveDate) -> NaiveDate {
+    date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+fn month_number(date: NaiveDate) -> i64 {
+    date.year() as i64 * 12 + date.month0() as i64
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 { (date.year() + 1, 1) } else { (date.year(), date.month() + 1) };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|d| d.pred_opt())
+        .map_or(31, |d| d.day())
+}
+
+/// Whether `date` is day `day` of its month; negative days count back from
+/// the end, -1 being the last.
+fn month_day(date: NaiveDate, day: i8) -> bool {
+    if day > 0 {
+        date.day() == day as u32
+    } else {
+        days_in_month(date) as i64 + day as i64 + 1 == date.day() as i64
+    }
+}
+
+fn join<T: ToString>(items: &[T]) -> String {
+    items.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn ordinal(n: i8) -> String {
+    match n {
+        -1 => "last".to_string(),
+        n if n < 0 => format!("{} last", day_ordinal(-n)),
+        n => day_ordinal(n),
+    }
+}
+
+fn day_ordinal(n: i8) -> String {
+    if n < 0 {
+        return ordinal(n) + " day";
+    }
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// The days in April 2026 (starting Wednesday the 1st) that `rule` is due,
+    /// anchored at `anchor`.
+    fn april(rule: &Recurrence, anchor: NaiveDate) -> Vec<u32> {
+        (1..=30).filter(|&d| rule.occurs_on(date(2026, 4, d), anchor)).collect()
+    }
+
+    #[test]
+    fn test_every_n_days_counts_from_start() {
+        let rule = Recurrence::parse("3 days").unwrap();
+        assert_eq!(april(&rule, date(2026, 4, 2)), vec![2, 5, 8, 11, 14, 17, 20, 23, 26, 29]);
+    }
+
+    #[test]
+    fn test_every_other_week_on_days() {
+        let rule = Recurrence::parse("every 2 weeks on tue, thu").unwrap();
+        assert_eq!(rule.to_rrule(), "FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,TH");
+        // The week of Wednesday April 1st counts as the first.
+        assert_eq!(april(&rule, date(2026, 4, 1)), vec![2, 14, 16, 28, 30]);
+    }
+
+    #[test]
+    fn test_bare_day_list_and_weekdays() {
+        assert_eq!(Recurrence::parse("mon,wed,fri").unwrap().to_rrule(), "FREQ=WEEKLY;BYDAY=MO,WE,FR");
+        assert_eq!(Recurrence::parse("every tuesday and thursday").unwrap().to_rrule(), "FREQ=WEEKLY;BYDAY=TU,TH");
+        assert_eq!(Recurrence::parse("weekday").unwrap().to_rrule(), "FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR");
+        assert_eq!(Recurrence::parse("weekends").unwrap().to_rrule(), "FREQ=WEEKLY;BYDAY=SA,SU");
+    }
+
+    #[test]
+    fn test_nth_weekday_of_month() {
+        let first_monday = Recurrence::parse("month on the first monday").unwrap();
+        assert_eq!(first_monday.to_rrule(), "FREQ=MONTHLY;BYDAY=1MO");
+        assert_eq!(april(&first_monday, date(2026, 1, 1)), vec![6]);
+
+        let last_friday = Recurrence::parse_rrule("RRULE:FREQ=MONTHLY;BYDAY=-1FR").unwrap();
+        assert_eq!(april(&last_friday, date(2026, 1, 1)), vec![24]);
+        assert_eq!(last_friday.to_string(), "every month on the last Fri");
+    }
+
+    #[test]
+    fn test_day_of_month() {
+        let rule = Recurrence::parse("month on the 15th").unwrap();
+        assert_eq!(april(&rule, date(2026, 1, 1)), vec![15]);
+        let last = Recurrence::parse("month on the last day").unwrap();
+        assert_eq!(last.to_rrule(), "FREQ=MONTHLY;BYMONTHDAY=-1");
+        assert_eq!(april(&last, date(2026, 1, 1)), vec![30]);
+        // Without a day, the start's day of the month is used.
+        assert_eq!(april(&Recurrence::parse("2 months").unwrap(), date(2026, 2, 10)), vec![10]);
+        assert!(april(&Recurrence::parse("2 months").unwrap(), date(2026, 3, 10)).is_empty());
+    }
+
+    #[test]
+    fn test_yearly() {
+        let rule = Recurrence::parse("year on mar 14").unwrap();
+        assert_eq!(rule.to_rrule(), "FREQ=YEARLY;BYMONTHDAY=14;BYMONTH=3");
+        let anchor = date(2026, 1, 1);
+        assert!(rule.occurs_on(date(2027, 3, 14), anchor));
+        assert!(!rule.occurs_on(date(2027, 4, 14), anchor));
+        assert_eq!(Recurrence::parse("year on 03-14").unwrap(), rule);
+        // Without a date, the start's anniversary.
+        let plain = Recurrence::parse("year").unwrap();
+        assert!(plain.occurs_on(date(2028, 1, 1), anchor) && !plain.occurs_on(date(2028, 2, 1), anchor));
+    }
+
+    #[test]
+    fn test_times_per_period_is_flexible() {
+        let rule = Recurrence::parse("3 times per week").unwrap();
+        assert_eq!(rule.times, Some(3));
+        assert_eq!(rule.to_rrule(), "FREQ=WEEKLY;X-TIMES=3");
+        assert_eq!(april(&rule, date(2026, 4, 1)).len(), 30);
+        assert_eq!(rule.to_string(), "3 times per week");
+        assert_eq!(Recurrence::parse_rrule(&rule.to_rrule()).unwrap(), rule);
+    }
+
+    #[test]
+    fn test_period_containing() {
+        let anchor = date(2026, 3, 18);
+        let weekly = Recurrence::parse("3 times per week").unwrap();
+        // Thursday 2026-04-16, in a Monday week and a Sunday week.
+        let thursday = date(2026, 4, 16);
+        assert_eq!(weekly.period_containing(thursday, anchor, Weekday::Mon), (date(2026, 4, 13), date(2026, 4, 19)));
+        assert_eq!(weekly.period_containing(thursday, anchor, Weekday::Sun), (date(2026, 4, 12), date(2026, 4, 18)));
+
+        let monthly = Recurrence::parse("2 times per month").unwrap();
+        assert_eq!(monthly.period_containing(date(2026, 12, 5), anchor, Weekday::Mon), (date(2026, 12, 1), date(2026, 12, 31)));
+
+        // Fortnights count from the anchor's week.
+        let mut fortnightly = weekly.clone();
+        fortnightly.interval = 2;
+        assert_eq!(fortnightly.period_containing(thursday, anchor, Weekday::Mon), (date(2026, 4, 13), date(2026, 4, 26)));
+        assert_eq!(fortnightly.period_containing(date(2026, 4, 12), anchor, Weekday::Mon), (date(2026, 3, 30), date(2026, 4, 12)));
+        assert_eq!(fortnightly.period_label(), "these 2 weeks");
+        assert_eq!(weekly.period_label(), "this week");
+        assert_eq!(weekly.periods(1), "1 week");
+        assert_eq!(monthly.periods(3), "3 months");
+    }
+
+    #[test]
+    fn test_huge_intervals_dont_overflow() {
+        let anchor = date(2026, 4, 1);
+        let centuries = Recurrence::parse_rrule("FREQ=YEARLY;INTERVAL=100000").unwrap();
+        assert_eq!(centuries.next_after(anchor, anchor), None);
+        let weeks = Recurrence::parse_rrule("FREQ=WEEKLY;INTERVAL=4000000000").unwrap();
+        assert_eq!(weeks.next_after(anchor, anchor), None);
+
+        let flexible = Recurrence::parse_rrule("FREQ=WEEKLY;INTERVAL=4000000000;X-TIMES=2").unwrap();
+        let (start, end) = flexible.period_containing(date(2026, 4, 16), anchor, Weekday::Mon);
+        assert_eq!((start, end), (date(2026, 3, 30), NaiveDate::MAX));
+        // Before the anchor, the period reaches back past the calendar's start.
+        assert_eq!(flexible.period_containing(date(2026, 3, 1), anchor, Weekday::Mon).0, NaiveDate::MIN);
+        for text in ["FREQ=YEARLY;INTERVAL=4000000000;X-TIMES=2", "FREQ=MONTHLY;INTERVAL=4000000000;X-TIMES=2",
+                     "FREQ=DAILY;INTERVAL=4000000000;X-TIMES=2"] {
+            let rule = Recurrence::parse_rrule(text).unwrap();
+            let (start, end) = rule.period_containing(date(2026, 4, 16), anchor, Weekday::Mon);
+            assert!(start <= date(2026, 4, 16) && end >= date(2026, 4, 16), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_start_until_and_exceptions() {
+        let rule = Recurrence::parse_rrule(
+            "DTSTART;VALUE=DATE:20260406\nRRULE:FREQ=DAILY;UNTIL=20260410T235959Z\nEXDATE:20260408",
+        ).unwrap();
+        assert_eq!(rule.start, Some(date(2026, 4, 6)));
+        assert_eq!(april(&rule, date(2026, 1, 1)), vec![6, 7, 9, 10]);
+    }
+
+    #[test]
+    fn test_next_after() {
+        let anchor = date(2026, 1, 1);
+        let rent = Recurrence::parse("month on the 1st").unwrap();
+        assert_eq!(rent.next_after(date(2026, 4, 1), anchor), Some(date(2026, 5, 1)));
+        assert_eq!(rent.next_after(date(2026, 4, 20), anchor), Some(date(2026, 5, 1)));
+
+        let leap = Recurrence::parse("year on feb 29").unwrap();
+        assert_eq!(leap.next_after(date(2026, 3, 1), anchor), Some(date(2028, 2, 29)));
+
+        let mut ending = Recurrence::parse("week").unwrap();
+        ending.until = Some(date(2026, 1, 10));
+        assert_eq!(ending.next_after(date(2026, 1, 1), anchor), Some(date(2026, 1, 8)));
+        assert_eq!(ending.next_after(date(2026, 1, 8), anchor), None);
+    }
+
+    #[test]
+    fn test_rrule_round_trips() {
+        for text in ["FREQ=DAILY", "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR", "FREQ=MONTHLY;BYDAY=2TU,-1FR",
+            "FREQ=YEARLY;BYMONTHDAY=1;BYMONTH=1,7;UNTIL=20301231"] {
+            assert_eq!(Recurrence::parse_rrule(text).unwrap().to_rrule(), text);
+        }
+    }
+
+    #[test]
+    fn test_rejects_nonsense() {
+        for text in ["fortnightly", "0 days", "month on the 40th", "week on funday", "3 times per decade"] {
+            assert!(Recurrence::parse(text).is_err(), "{}", text);
+        }
+        for text in ["FREQ=HOURLY", "FREQ=DAILY;COUNT=3", "INTERVAL=2", "FREQ=MONTHLY;BYDAY=9MO",
+                     "FREQ=MONTHLY;BYDAY=-128MO", "FREQ=MONTHLY;BYMONTHDAY=-128"] {
+            assert!(Recurrence::parse_rrule(text).is_err(), "{}", text);
+        }
+    }
+}
@@ -1,5 +1,8 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc, Weekday};
+use super::recurrence::Recurrence;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Priority {
@@ -10,6 +13,7 @@ pub enum Priority {
 }
 
 impl Priority {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "low" | "l" => Some(Priority::Low),
@@ -20,15 +24,6 @@ impl Priority {
         }
     }
 
-    pub fn to_string(&self) -> String {
-        match self {
-            Priority::Low => "Low".to_string(),
-            Priority::Medium => "Medium".to_string(),
-            Priority::High => "High".to_string(),
-            Priority::Critical => "Critical".to_string(),
-        }
-    }
-
     pub fn value(&self) -> u8 {
         match self {
             Priority::Low => 1,
@@ -39,9 +34,23 @@ impl Priority {
     }
 }
 
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Priority::Low => "Low",
+            Priority::Medium => "Medium",
+            Priority::High => "High",
+            Priority::Critical => "Critical",
+        };
+        f.write_str(s)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
-    pub id: String,
+    pub id: String,                         // UUID: permanent identity, used in file names, logs and references
+    #[serde(default)]
+    pub handle: String,                     // short number typed on the command line; unique per data dir
     pub title: String,
     pub description: Option<String>,
     pub priority: Priority,
@@ -59,10 +68,63 @@ pub struct Task {
     pub two_minute: bool,                   // two-minute rule: starter version of habit
     // Selective recurrence: which weekdays (0=Mon..6=Sun); None means every day
     pub scheduled_days: Option<Vec<u8>>,
+    // Full recurrence rule; takes precedence over `scheduled_days`
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
     // Numeric tracking (e.g. push ups, minutes read)
     pub numeric: bool,
     pub unit: Option<String>,
     pub target: Option<f64>,
+    // Free-form labels, kept normalized by `parse_tag`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Subtasks: ID of the one-off task this is a step of
+    #[serde(default)]
+    pub parent: Option<String>,
+    // Dependencies: IDs of one-off tasks that must be done first
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+/// Normalizes a tag as typed: trims it, drops a leading `#` and lowercases
+/// it. Tags are letters, digits, `-`, `_`, `/` and `.`, and can't start with
+/// `-` (that marks an excluded tag in filters). Returns `None` for anything
+/// else.
+pub fn parse_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim();
+    let tag = tag.strip_prefix('#').unwrap_or(tag).to_lowercase();
+    let valid = !tag.is_empty()
+        && !tag.starts_with('-')
+        && tag.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/' | '.'));
+    valid.then_some(tag)
+}
+
+/// Tags a task must have and tags it must not have, from `--tag work` and
+/// `--tag -someday`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl TagFilter {
+    /// Parses filter arguments. Returns the first invalid tag as the error.
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut filter = TagFilter::default();
+        for arg in args {
+            let (list, tag) = match arg.trim().strip_prefix('-') {
+                Some(tag) => (&mut filter.exclude, tag),
+                None => (&mut filter.include, arg.as_str()),
+            };
+            list.push(parse_tag(tag).ok_or_else(|| arg.clone())?);
+        }
+        Ok(filter)
+    }
+
+    /// Whether `task` has every included tag and none of the excluded ones.
+    pub fn matches(&self, task: &Task) -> bool {
+        self.include.iter().all(|t| task.has_tag(t)) && !self.exclude.iter().any(|t| task.has_tag(t))
+    }
 }
 
 impl Task {
@@ -70,6 +132,7 @@ impl Task {
         let now = Utc::now();
         Self {
             id,
+            handle: String::new(),
             title,
             description: None,
             priority,
@@ -84,12 +147,21 @@ impl Task {
             habit_stack_after: None,
             two_minute: false,
             scheduled_days: None,
+            recurrence: None,
             numeric: false,
             unit: None,
             target: None,
+            tags: Vec::new(),
+            parent: None,
+            depends_on: Vec::new(),
         }
     }
 
+    pub fn with_handle(mut self, handle: String) -> Self {
+        self.handle = handle;
+        self
+    }
+
     pub fn with_description(mut self, description: String) -> Self {
         self.description = Some(description);
         self
@@ -145,6 +217,16 @@ impl Task {
         self
     }
 
+    pub fn with_parent(mut self, task_id: String) -> Self {
+        self.parent = Some(task_id);
+        self
+    }
+
+    pub fn with_depends_on(mut self, task_ids: Vec<String>) -> Self {
+        self.depends_on = task_ids;
+        self
+    }
+
     pub fn with_two_minute(mut self, two_minute: bool) -> Self {
         self.two_minute = two_minute;
         self
@@ -155,6 +237,11 @@ impl Task {
         self
     }
 
+    pub fn with_recurrence(mut self, recurrence: Recurrence) -> Self {
+        self.recurrence = Some(recurrence);
+        self
+    }
+
     pub fn with_numeric(mut self, numeric: bool) -> Self {
         self.numeric = numeric;
         self
@@ -170,6 +257,117 @@ impl Task {
         self
     }
 
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = Vec::new();
+        for tag in tags {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+        self
+    }
+
+    /// Adds `tag` unless the task already has it. Returns whether it was added.
+    pub fn add_tag(&mut self, tag: String) -> bool {
+        if self.has_tag(&tag) {
+            return false;
+        }
+        self.tags.push(tag);
+        self.updated_at = Utc::now();
+        true
+    }
+
+    /// Removes `tag` if the task has it. Returns whether it was removed.
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let before = self.tags.len();
+        self.tags.retain(|t| t != tag);
+        if self.tags.len() == before {
+            return false;
+        }
+        self.updated_at = Utc::now();
+        true
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Adds a prerequisite; returns false if the task already depends on it.
+    pub fn add_dependency(&mut self, task_id: String) -> bool {
+        if self.depends_on.contains(&task_id) {
+            return false;
+        }
+        self.depends_on.push(task_id);
+        self.updated_at = Utc::now();
+        true
+    }
+
+    /// Drops a prerequisite; returns false if the task didn't depend on it.
+    pub fn remove_dependency(&mut self, task_id: &str) -> bool {
+        let before = self.depends_on.len();
+        self.depends_on.retain(|t| t != task_id);
+        if self.depends_on.len() == before {
+            return false;
+        }
+        self.updated_at = Utc::now();
+        true
+    }
+
+    /// The tags as `#a #b`, or an empty string.
+    pub fn tags_display(&self) -> String {
+        self.tags.iter().map(|t| format!("#{}", t)).collect::<Vec<_>>().join(" ")
+    }
+
+    /// What to show the user for this task: its handle, or the start of its
+    /// UUID when it has none.
+    pub fn short_id(&self) -> &str {
+        if !self.handle.is_empty() {
+            &self.handle
+        } else {
+            self.id.get(..8).unwrap_or(&self.id)
+        }
+    }
+
+    /// Moves a recurring one-off task's due date to its next occurrence: the
+    /// first after its current due date, or after `completed_on` if that is
+    /// later (an overdue task skips the occurrences it missed) or its rule
+    /// counts from completion. Returns the new due date, or `None` (leaving
+    /// the task alone) when the rule has run out.
+    pub fn roll_forward(&mut self, completed_on: NaiveDate) -> Option<NaiveDate> {
+        let rule = self.recurrence.as_ref()?;
+        let next = if rule.from_completion {
+            let mut rule = rule.clone();
+            rule.start = Some(completed_on);
+            let next = rule.next_after(completed_on, completed_on)?;
+            self.recurrence = Some(rule);
+            next
+        } else {
+            let due = self.due_date.map_or(completed_on, |d| d.date_naive());
+            rule.next_after(due.max(completed_on), self.created_at.date_naive())?
+        };
+        self.due_date = next.and_hms_opt(23, 59, 59).map(|d| d.and_utc());
+        self.updated_at = Utc::now();
+        Some(next)
+    }
+
+    /// Whether a recurring task is due on `date`: its rule decides, else its
+    /// scheduled weekdays, else it's due every day.
+    pub fn is_scheduled_on(&self, date: NaiveDate) -> bool {
+        match (&self.recurrence, &self.scheduled_days) {
+            (Some(rule), _) => rule.occurs_on(date, self.created_at.date_naive()),
+            (None, Some(days)) => days.contains(&(date.weekday().num_days_from_monday() as u8)),
+            (None, None) => true,
+        }
+    }
+
+    /// For a habit with a "N times per week/month" target, the target and the
+    /// first and last day of the period containing `date`.
+    pub fn target_period(&self, date: NaiveDate, week_start: Weekday) -> Option<(u32, NaiveDate, NaiveDate)> {
+        let rule = self.recurrence.as_ref()?;
+        let (start, end) = rule.period_containing(date, self.created_at.date_naive(), week_start);
+        rule.times.map(|times| (times, start, end))
+    }
+
     /// Returns the weekday numbers (0=Mon..6=Sun) as short names.
     pub fn scheduled_days_display(&self) -> Option<String> {
         self.scheduled_days.as_ref().map(|days| {
@@ -183,6 +381,109 @@ impl Task {
     }
 }
 
+/// Orders `tasks` so every subtask follows its parent, paired with its depth.
+/// Siblings keep their order from `tasks`. A task whose parent isn't in
+/// `tasks` is shown at the top level.
+pub fn subtask_tree(tasks: Vec<Task>) -> Vec<(usize, Task)> {
+    let ids: HashSet<String> = tasks.iter().map(|t| t.id.clone()).collect();
+    let (roots, mut children): (Vec<Task>, Vec<Task>) = tasks.into_iter()
+        .partition(|t| t.parent.as_ref().is_none_or(|p| !ids.contains(p)));
+
+    fn visit(task: Task, depth: usize, children: &mut Vec<Task>, out: &mut Vec<(usize, Task)>) {
+        let (mine, rest): (Vec<Task>, Vec<Task>) = std::mem::take(children)
+            .into_iter()
+            .partition(|t| t.parent.as_deref() == Some(task.id.as_str()));
+        *children = rest;
+        out.push((depth, task));
+        for child in mine {
+            visit(child, depth + 1, children, out);
+        }
+    }
+
+    let mut out = Vec::new();
+    for root in roots {
+        visit(root, 0, &mut children, &mut out);
+    }
+    // Whatever is left hangs off a loop of parents; show it rather than lose it.
+    out.extend(children.into_iter().map(|t| (0, t)));
+    out
+}
+
+/// How many of `id`'s direct subtasks are done, out of how many, or `None`
+/// when it has none.
+pub fn subtask_progress(tasks: &[Task], id: &str) -> Option<(usize, usize)> {
+    let subtasks: Vec<&Task> = tasks.iter().filter(|t| t.parent.as_deref() == Some(id)).collect();
+    if subtasks.is_empty() {
+        return None;
+    }
+    Some((subtasks.iter().filter(|t| t.completed).count(), subtasks.len()))
+}
+
+/// Every subtask of `id`, their subtasks and so on.
+pub fn descendants<'a>(tasks: &'a [Task], id: &str) -> Vec<&'a Task> {
+    let mut found: Vec<&Task> = Vec::new();
+    let mut seen: HashSet<&str> = HashSet::from([id]);
+    let mut queue = vec![id];
+    while let Some(current) = queue.pop() {
+        for task in tasks.iter().filter(|t| t.parent.as_deref() == Some(current)) {
+            if seen.insert(&task.id) {
+                found.push(task);
+                queue.push(&task.id);
+            }
+        }
+    }
+    found
+}
+
+/// `id`'s parent, its parent and so on, nearest first.
+pub fn ancestors<'a>(tasks: &'a [Task], id: &str) -> Vec<&'a Task> {
+    let mut found: Vec<&Task> = Vec::new();
+    let mut seen: HashSet<&str> = HashSet::from([id]);
+    let mut current = tasks.iter().find(|t| t.id == id).and_then(|t| t.parent.as_deref());
+    while let Some(parent_id) = current {
+        if !seen.insert(parent_id) {
+            break;
+        }
+        let Some(parent) = tasks.iter().find(|t| t.id == parent_id) else { break };
+        found.push(parent);
+        current = parent.parent.as_deref();
+    }
+    found
+}
+
+/// The prerequisites of `task` among `tasks` that aren't done yet. Ones that
+/// aren't in `tasks` (deleted or archived) don't hold it up.
+pub fn open_prerequisites<'a>(tasks: &'a [Task], task: &Task) -> Vec<&'a Task> {
+    tasks.iter().filter(|t| !t.completed && task.depends_on.contains(&t.id)).collect()
+}
+
+/// IDs along a chain of dependencies leading from `from` to `to`, both
+/// included, or `None` when `from` doesn't depend on `to` even indirectly.
+pub fn dependency_path(tasks: &[Task], from: &str, to: &str) -> Option<Vec<String>> {
+    let mut came_from: HashMap<&str, &str> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    while let Some(current) = queue.pop_front() {
+        if current == to {
+            let mut path = vec![to.to_string()];
+            let mut at = to;
+            while at != from {
+                at = came_from[at];
+                path.push(at.to_string());
+            }
+            path.reverse();
+            return Some(path);
+        }
+        let Some(task) = tasks.iter().find(|t| t.id == current) else { continue };
+        for next in &task.depends_on {
+            if next != from && !came_from.contains_key(next.as_str()) {
+                came_from.insert(next, current);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -268,6 +569,50 @@ mod tests {
         assert!(t.scheduled_days.is_none());
     }
 
+    #[test]
+    fn test_short_id_prefers_handle() {
+        let t = task("0b6f1c52-4d8e-4a8e-9d65-1c2f3e4a5b6c");
+        assert_eq!(t.short_id(), "0b6f1c52");
+        assert_eq!(t.with_handle("7".to_string()).short_id(), "7");
+        assert_eq!(task("3").short_id(), "3");
+    }
+
+    // --- Tags ---
+
+    #[test]
+    fn test_parse_tag_normalizes_and_rejects() {
+        assert_eq!(parse_tag(" #Work "), Some("work".to_string()));
+        assert_eq!(parse_tag("home/garden"), Some("home/garden".to_string()));
+        assert_eq!(parse_tag(""), None);
+        assert_eq!(parse_tag("-someday"), None);
+        assert_eq!(parse_tag("two words"), None);
+        assert_eq!(parse_tag("a,b"), None);
+    }
+
+    #[test]
+    fn test_add_and_remove_tags() {
+        let mut t = task("1").with_tags(vec!["work".to_string(), "work".to_string()]);
+        assert_eq!(t.tags, vec!["work"]);
+        assert!(!t.add_tag("work".to_string()));
+        assert!(t.add_tag("urgent".to_string()));
+        assert_eq!(t.tags_display(), "#work #urgent");
+        assert!(t.remove_tag("work"));
+        assert!(!t.remove_tag("work"));
+        assert_eq!(t.tags, vec!["urgent"]);
+    }
+
+    #[test]
+    fn test_tag_filter_includes_and_excludes() {
+        let filter = TagFilter::parse(&["work".to_string(), "-Someday".to_string()]).unwrap();
+        assert_eq!(filter.include, vec!["work"]);
+        assert_eq!(filter.exclude, vec!["someday"]);
+        assert!(filter.matches(&task("1").with_tags(vec!["work".to_string()])));
+        assert!(!filter.matches(&task("2").with_tags(vec!["work".to_string(), "someday".to_string()])));
+        assert!(!filter.matches(&task("3")));
+        assert!(TagFilter::default().matches(&task("4")));
+        assert_eq!(TagFilter::parse(&["-".to_string()]), Err("-".to_string()));
+    }
+
     // --- Builders ---
 
     #[test]
@@ -401,4 +746,146 @@ mod tests {
         t.update_daily(false);
         assert!(!t.is_daily);
     }
+
+    // --- Subtasks ---
+
+    fn ids(tree: &[(usize, Task)]) -> Vec<(usize, &str)> {
+        tree.iter().map(|(depth, t)| (*depth, t.id.as_str())).collect()
+    }
+
+    #[test]
+    fn test_subtask_tree_nests_children_under_parents() {
+        let tasks = vec![
+            task("a1").with_parent("a".into()),
+            task("b"),
+            task("a"),
+            task("a1x").with_parent("a1".into()),
+            task("a2").with_parent("a".into()),
+            task("orphan").with_parent("gone".into()),
+        ];
+        let tree = subtask_tree(tasks);
+        assert_eq!(ids(&tree), vec![(0, "b"), (0, "a"), (1, "a1"), (2, "a1x"), (1, "a2"), (0, "orphan")]);
+    }
+
+    #[test]
+    fn test_subtask_tree_keeps_tasks_in_parent_loops() {
+        let tasks = vec![task("x").with_parent("y".into()), task("y").with_parent("x".into())];
+        assert_eq!(subtask_tree(tasks).len(), 2);
+    }
+
+    #[test]
+    fn test_subtask_progress_counts_direct_subtasks() {
+        let mut done = task("c1").with_parent("p".into());
+        done.mark_complete();
+        let tasks = vec![task("p"), done, task("c2").with_parent("p".into()), task("g").with_parent("c2".into())];
+        assert_eq!(subtask_progress(&tasks, "p"), Some((1, 2)));
+        assert_eq!(subtask_progress(&tasks, "g"), None);
+    }
+
+    #[test]
+    fn test_descendants_and_ancestors() {
+        let tasks = vec![task("p"), task("c").with_parent("p".into()), task("g").with_parent("c".into()), task("other")];
+        let below: Vec<&str> = descendants(&tasks, "p").iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(below, vec!["c", "g"]);
+        let above: Vec<&str> = ancestors(&tasks, "g").iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(above, vec!["c", "p"]);
+    }
+
+    // --- Dependencies ---
+
+    #[test]
+    fn test_add_and_remove_dependency() {
+        let mut t = task("2");
+        assert!(t.add_dependency("1".into()));
+        assert!(!t.add_dependency("1".into()));
+        assert_eq!(t.depends_on, vec!["1".to_string()]);
+        assert!(t.remove_dependency("1"));
+        assert!(!t.remove_dependency("1"));
+    }
+
+    #[test]
+    fn test_open_prerequisites_skips_done_and_missing_tasks() {
+        let mut done = task("done");
+        done.mark_complete();
+        let tasks = vec![done, task("open")];
+        let t = task("t").with_depends_on(vec!["done".into(), "open".into(), "gone".into()]);
+        let open: Vec<&str> = open_prerequisites(&tasks, &t).iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(open, vec!["open"]);
+    }
+
+    #[test]
+    fn test_dependency_path_follows_chains() {
+        let tasks = vec![
+            task("a").with_depends_on(vec!["b".into()]),
+            task("b").with_depends_on(vec!["x".into(), "c".into()]),
+            task("c"),
+            task("x"),
+        ];
+        assert_eq!(dependency_path(&tasks, "a", "c"), Some(vec!["a".into(), "b".into(), "c".into()]));
+        assert_eq!(dependency_path(&tasks, "c", "a"), None);
+        assert_eq!(dependency_path(&tasks, "a", "a"), Some(vec!["a".into()]));
+    }
+
+    // --- Recurrence ---
+
+    #[test]
+    fn test_is_scheduled_on_prefers_rule_over_days() {
+        let monday = NaiveDate::from_ymd_opt(2026, 4, 13).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+        assert!(task("1").is_scheduled_on(tuesday));
+
+        let days = task("1").with_scheduled_days(vec![0]);
+        assert!(days.is_scheduled_on(monday) && !days.is_scheduled_on(tuesday));
+
+        let mut rule = Recurrence::parse("tue").unwrap();
+        rule.start = Some(monday);
+        let ruled = days.with_recurrence(rule);
+        assert!(!ruled.is_scheduled_on(monday) && ruled.is_scheduled_on(tuesday));
+    }
+
+    fn due(y: i32, m: u32, d: u32) -> DateTime<Utc> {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(23, 59, 59).unwrap().and_utc()
+    }
+
+    #[test]
+    fn test_roll_forward_from_due_date() {
+        let mut rule = Recurrence::parse("month on the 1st").unwrap();
+        rule.start = NaiveDate::from_ymd_opt(2026, 4, 1);
+        let mut t = task("1").with_due_date(due(2026, 4, 1)).with_recurrence(rule);
+        // Done late: the next one is still the one after the due date.
+        let next = t.roll_forward(NaiveDate::from_ymd_opt(2026, 4, 9).unwrap());
+        assert_eq!(next, NaiveDate::from_ymd_opt(2026, 5, 1));
+        assert_eq!(t.due_date, Some(due(2026, 5, 1)));
+        assert!(!t.completed);
+    }
+
+    #[test]
+    fn test_roll_forward_skips_missed_occurrences() {
+        let mut rule = Recurrence::parse("week on monday").unwrap();
+        rule.start = NaiveDate::from_ymd_opt(2026, 4, 6);
+        let mut t = task("1").with_due_date(due(2026, 4, 6)).with_recurrence(rule);
+        // Three Mondays overdue: the next one is the first after completion.
+        let next = t.roll_forward(NaiveDate::from_ymd_opt(2026, 4, 29).unwrap());
+        assert_eq!(next, NaiveDate::from_ymd_opt(2026, 5, 4));
+        // Done on the day it is due: the next one is a week later.
+        assert_eq!(t.roll_forward(NaiveDate::from_ymd_opt(2026, 5, 4).unwrap()), NaiveDate::from_ymd_opt(2026, 5, 11));
+    }
+
+    #[test]
+    fn test_roll_forward_from_completion() {
+        let mut rule = Recurrence::parse("3 months").unwrap();
+        rule.from_completion = true;
+        let mut t = task("1").with_due_date(due(2026, 4, 1)).with_recurrence(rule);
+        t.roll_forward(NaiveDate::from_ymd_opt(2026, 4, 20).unwrap());
+        assert_eq!(t.due_date, Some(due(2026, 7, 20)));
+    }
+
+    #[test]
+    fn test_roll_forward_stops_when_rule_ends() {
+        let mut rule = Recurrence::parse("week").unwrap();
+        rule.until = NaiveDate::from_ymd_opt(2026, 4, 5);
+        let mut t = task("1").with_due_date(due(2026, 4, 1)).with_recurrence(rule);
+        assert_eq!(t.roll_forward(NaiveDate::from_ymd_opt(2026, 4, 1).unwrap()), None);
+        assert_eq!(t.due_date, Some(due(2026, 4, 1)));
+    }
 }
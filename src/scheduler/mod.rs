@@ -1,6 +1,7 @@
 use anyhow::Result;
 use chrono::{Local, NaiveTime, Timelike};
 use tokio::time::{sleep, Duration};
+#[cfg(target_os = "macos")]
 use std::process::Command;
 
 pub struct Scheduler {
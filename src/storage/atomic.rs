@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Suffix for in-flight writes. Readers skip these files, so a crash between
+/// writing and renaming leaves the previous version of the file in place.
+pub const TMP_SUFFIX: &str = ".tmp";
+
+/// Replaces `path` with `contents` so that readers only ever see the old or
+/// the new file: write a sibling temp file, fsync it, rename it over `path`,
+/// then fsync the directory so the rename itself survives a crash.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let file_name = path.file_name()
+        .with_context(|| format!("Invalid path {}", path.display()))?;
+    let mut tmp_name = file_name.to_os_string();
+    tmp_name.push(TMP_SUFFIX);
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = (|| -> Result<()> {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        sync_parent_dir(path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result.with_context(|| format!("Could not write {}", path.display()))
+}
+
+/// Appends one line to `path` and fsyncs it. If a previous append was cut off
+/// mid-line, the new line starts on a fresh line instead of being glued on.
+pub fn append_line(path: &Path, line: &str) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Could not open {}", path.display()))?;
+
+    let mut entry = String::new();
+    if file.metadata()?.len() > 0 {
+        let mut last = [0u8; 1];
+        file.seek(SeekFrom::End(-1))?;
+        file.read_exact(&mut last)?;
+        if last[0] != b'\n' {
+            entry.push('\n');
+        }
+    }
+    entry.push_str(line);
+    entry.push('\n');
+
+    file.write_all(entry.as_bytes())?;
+    file.sync_data()?;
+    Ok(())
+}
+
+/// True for leftovers of an interrupted `write_atomic`.
+pub fn is_temp_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.ends_with(TMP_SUFFIX))
+}
+
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_atomic_creates_and_replaces() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("1.txt");
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_atomic_leaves_original_on_failure() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("1.txt");
+        write_atomic(&path, b"original").unwrap();
+        // A directory in the temp file's place makes the write fail.
+        fs::create_dir(dir.path().join("1.txt.tmp")).unwrap();
+        assert!(write_atomic(&path, b"replacement").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original");
+    }
+
+    #[test]
+    fn test_append_line_repairs_torn_last_line() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("daily.log");
+        fs::write(&path, "2026-04-13 | 1 | Ru").unwrap();
+        append_line(&path, "2026-04-13 | 2 | Read").unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "2026-04-13 | 1 | Ru\n2026-04-13 | 2 | Read\n"
+        );
+    }
+
+    #[test]
+    fn test_append_line_to_new_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("history.log");
+        append_line(&path, "a").unwrap();
+        append_line(&path, "b").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a\nb\n");
+    }
+
+    #[test]
+    fn test_is_temp_file() {
+        assert!(is_temp_file(Path::new("tasks/1.txt.tmp")));
+        assert!(!is_temp_file(Path::new("tasks/1.txt")));
+    }
+}
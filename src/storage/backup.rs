@@ -0,0 +1,597 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+use crate::models::{Task, Day, Category};
+use super::atomic::write_atomic;
+use super::crypto::{self, Cipher, KeyParams};
+use super::format::{
+    archived_to_text, category_to_text, daily_entry_to_line, day_to_text, history_entry_to_line,
+    line_to_daily_entry, line_to_history_entry, task_to_text, text_to_archived, text_to_category,
+    text_to_day, text_to_task, text_to_trashed, trashed_to_text,
+};
+use super::merge::Stored;
+use super::upgrade::DATA_VERSION;
+use super::{ArchivedTask, DailyLogEntry, HistoryEntry, TaskStore, TrashedTask};
+
+/// Version of the backup file layout written by this build.
+pub const BACKUP_VERSION: u32 = 1;
+
+/// Directory for automatic backups, relative to the data dir.
+pub const AUTO_BACKUP_DIR: &str = "backups";
+
+/// A whole data directory in one file. Records are kept in the same text
+/// form the text backend writes, so restoring runs them through the same
+/// parsers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backup {
+    pub backup_version: u32,
+    pub data_format: u32,
+    pub created_at: DateTime<Utc>,
+    pub tasks: Vec<String>,
+    #[serde(default)]
+    pub trash: Vec<String>,
+    #[serde(default)]
+    pub archive: Vec<String>,
+    pub days: Vec<String>,
+    pub categories: Vec<String>,
+    pub daily_log: Vec<String>,
+    pub history_log: Vec<String>,
+    pub id_counter: u64,
+}
+
+/// The parsed contents of a backup.
+#[derive(Debug, Default, Clone)]
+pub struct Contents {
+    pub data_format: u32,
+    pub tasks: Vec<Task>,
+    pub trash: Vec<TrashedTask>,
+    pub archive: Vec<ArchivedTask>,
+    pub days: Vec<Day>,
+    pub categories: Vec<Category>,
+    pub daily_log: Vec<DailyLogEntry>,
+    pub history_log: Vec<HistoryEntry>,
+    pub id_counter: u64,
+}
+
+impl Backup {
+    /// Snapshots everything `store` can read.
+    pub fn capture(store: &dyn TaskStore) -> Result<Self> {
+        Ok(Self {
+            backup_version: BACKUP_VERSION,
+            data_format: DATA_VERSION,
+            created_at: Utc::now(),
+            tasks: store.list_all_tasks()?.iter().map(task_to_text).collect(),
+            trash: store.list_trash()?.iter().map(trashed_to_text).collect(),
+            archive: store.list_archived()?.iter().map(archived_to_text).collect(),
+            days: store.list_days()?.iter().map(day_to_text).collect(),
+            categories: store.list_categories()?.iter().map(category_to_text).collect(),
+            daily_log: store.daily_log_entries()?.iter().map(daily_entry_to_line).collect(),
+            history_log: store.history_entries()?.iter().map(history_entry_to_line).collect(),
+            id_counter: store.id_counter()?,
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        self.write_with(path, None)
+    }
+
+    /// Writes the backup, encrypted with `cipher` if there is one. Encrypted
+    /// backups carry their key parameters, so the passphrase alone is enough
+    /// to restore them anywhere.
+    pub fn write_with(&self, path: &Path, cipher: Option<&Cipher>) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        let content = match cipher {
+            Some(cipher) => cipher.seal_portable(&json),
+            None => json,
+        };
+        write_atomic(path, content.as_bytes())
+            .with_context(|| format!("Could not write backup to {}", path.display()))
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        Self::read_with(path, |_| anyhow::bail!("{} is encrypted; a passphrase is needed to read it", path.display()))
+    }
+
+    /// Reads a backup, asking `unlock` for the cipher if it is encrypted.
+    pub fn read_with(path: &Path, unlock: impl FnOnce(&KeyParams) -> Result<Cipher>) -> Result<Self> {
+        let mut content = fs::read_to_string(path)
+            .with_context(|| format!("Could not read backup {}", path.display()))?;
+        if let Some(params) = crypto::portable_params(&content) {
+            content = crypto::unseal_portable(&unlock(&params)?, &content)
+                .with_context(|| format!("Could not decrypt backup {}", path.display()))?;
+        }
+        let backup: Backup = serde_json::from_str(&content)
+            .with_context(|| format!("{} is not a daily backup", path.display()))?;
+        if backup.backup_version > BACKUP_VERSION || backup.data_format > DATA_VERSION {
+            anyhow::bail!(
+                "{} was written by a newer version of daily (backup version {}, data format {}). \
+                 Upgrade daily to restore it.",
+                path.display(),
+                backup.backup_version,
+                backup.data_format
+            );
+        }
+        Ok(backup)
+    }
+
+    /// Parses every record and log line, failing on the first one that
+    /// doesn't parse so nothing is restored from a damaged backup.
+    pub fn contents(&self) -> Result<Contents> {
+        fn parse_all<T>(what: &str, records: &[String], parse: impl Fn(&str) -> Result<T>) -> Result<Vec<T>> {
+            records.iter().enumerate()
+                .map(|(i, r)| parse(r).with_context(|| format!("Backup {} #{} is invalid", what, i + 1)))
+                .collect()
+        }
+
+        Ok(Contents {
+            data_format: self.data_format,
+            tasks: parse_all("task", &self.tasks, text_to_task)?,
+            trash: parse_all("trashed task", &self.trash, text_to_trashed)?,
+            archive: parse_all("archived task", &self.archive, text_to_archived)?,
+            days: parse_all("day", &self.days, text_to_day)?,
+            categories: parse_all("category", &self.categories, text_to_category)?,
+            daily_log: parse_all("daily.log line", &self.daily_log, |l| {
+                line_to_daily_entry(l).context("Not a daily.log line")
+            })?,
+            history_log: parse_all("history.log line", &self.history_log, |l| {
+                line_to_history_entry(l).context("Not a history.log line")
+            })?,
+            id_counter: self.id_counter,
+        })
+    }
+}
+
+/// How `restore` treats data already in the store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreMode {
+    /// Keep existing data and add what the backup has on top. When a task
+    /// exists in both, the most recently updated copy wins.
+    Merge,
+    /// Make the store hold exactly what the backup holds.
+    Replace,
+}
+
+/// Counts of what `restore` changed.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RestoreReport {
+    pub tasks: usize,
+    pub days: usize,
+    pub categories: usize,
+    pub log_entries: usize,
+    pub removed: usize,
+}
+
+/// Writes `contents` into `store`. Only records that differ are written, so
+/// the journal of a restore holds just what it changed.
+pub fn restore(store: &dyn TaskStore, contents: &Contents, mode: RestoreMode) -> Result<RestoreReport> {
+    let upgraded;
+    let contents = if contents.data_format < 4 {
+        upgraded = upgrade_ids(store, contents)?;
+        &upgraded
+    } else {
+        contents
+    };
+    let mut report = RestoreReport::default();
+    let replace = mode == RestoreMode::Replace;
+
+    let existing: HashMap<String, Task> = store.list_all_tasks()?.into_iter().map(|t| (t.id.clone(), t)).collect();
+    for task in &contents.tasks {
+        let newer = match existing.get(&task.id) {
+            Some(current) if replace => task_to_text(current) != task_to_text(task),
+            Some(current) => task.updated_at > current.updated_at,
+            None => true,
+        };
+        if newer {
+            store.save_task(task)?;
+            report.tasks += 1;
+        }
+    }
+    if replace {
+        for id in existing.keys().filter(|id| !contents.tasks.iter().any(|t| &t.id == *id)) {
+            store.delete_task(id)?;
+            report.removed += 1;
+        }
+    }
+
+    let trashed: HashMap<String, TrashedTask> = store.list_trash()?.into_iter().map(|t| (t.task.id.clone(), t)).collect();
+    for t in &contents.trash {
+        let current = trashed.get(&t.task.id);
+        if (replace || current.is_none()) && current.map(trashed_to_text) != Some(trashed_to_text(t)) {
+            store.save_trashed(t)?;
+            report.tasks += 1;
+        }
+    }
+    let archived: HashMap<String, ArchivedTask> = store.list_archived()?.into_iter().map(|a| (a.task.id.clone(), a)).collect();
+    for a in &contents.archive {
+        let current = archived.get(&a.task.id);
+        if (replace || current.is_none()) && current.map(archived_to_text) != Some(archived_to_text(a)) {
+            store.save_archived(a)?;
+            report.tasks += 1;
+        }
+    }
+    if replace {
+        for id in trashed.keys().filter(|id| !contents.trash.iter().any(|t| &t.task.id == *id)) {
+            store.delete_trashed(id)?;
+            report.removed += 1;
+        }
+        for id in archived.keys().filter(|id| !contents.archive.iter().any(|a| &a.task.id == *id)) {
+            store.delete_archived(id)?;
+            report.removed += 1;
+        }
+    }
+
+    let days: HashMap<_, Day> = store.list_days()?.into_iter().map(|d| (d.date, d)).collect();
+    for day in &contents.days {
+        let merged = match days.get(&day.date) {
+            Some(current) if !replace => {
+                let mut merged = current.clone();
+                for id in &day.task_ids {
+                    merged.add_task(id.clone());
+                }
+                if merged.notes.is_none() {
+                    merged.notes = day.notes.clone();
+                }
+                merged
+            }
+            _ => day.clone(),
+        };
+        if days.get(&day.date).map(day_to_text) != Some(day_to_text(&merged)) {
+            store.save_day(&merged)?;
+            report.days += 1;
+        }
+    }
+    if replace {
+        for date in days.keys().filter(|d| !contents.days.iter().any(|day| &day.date == *d)) {
+            store.delete_day(*date)?;
+            report.removed += 1;
+        }
+    }
+
+    let categories: HashMap<String, Category> =
+        store.list_categories()?.into_iter().map(|c| (c.name.clone(), c)).collect();
+    for category in &contents.categories {
+        let differs = categories.get(&category.name).map(category_to_text) != Some(category_to_text(category));
+        if differs && (replace || !categories.contains_key(&category.name)) {
+            store.save_category(category)?;
+            report.categories += 1;
+        }
+    }
+    if replace {
+        for name in categories.keys().filter(|n| !contents.categories.iter().any(|c| &c.name == *n)) {
+            store.delete_category(name)?;
+            report.removed += 1;
+        }
+    }
+
+    // Logs are compared line by line, counting duplicates.
+    let (added, removed) = sync_lines(
+        &store.daily_log_entries()?,
+        &contents.daily_log,
+        daily_entry_to_line,
+        replace,
+        |e| store.append_daily_entry(e),
+        |e| store.remove_daily_entry(e),
+    )?;
+    report.log_entries += added;
+    report.removed += removed;
+    let (added, removed) = sync_lines(
+        &store.history_entries()?,
+        &contents.history_log,
+        history_entry_to_line,
+        replace,
+        |e| store.append_history_entry(e),
+        |e| store.remove_history_entry(e),
+    )?;
+    report.log_entries += added;
+    report.removed += removed;
+
+    let counter = store.id_counter()?;
+    let next = if replace { contents.id_counter } else { counter.max(contents.id_counter) };
+    if next != counter {
+        store.set_id_counter(next)?;
+    }
+
+    Ok(report)
+}
+
+/// Gives the tasks of a backup from before format 4 the UUIDs they got when
+/// the data dir was upgraded. A task `store` already holds (the same
+/// `created_at`, as `merge` matches them) takes its UUID and handle; the rest
+/// get new UUIDs and keep their number as their handle unless it is taken.
+/// Day plans, habit stacks and both logs follow the new IDs.
+fn upgrade_ids(store: &dyn TaskStore, contents: &Contents) -> Result<Contents> {
+    let local = Stored::load_all(store)?;
+    let by_created: HashMap<DateTime<Utc>, &Task> = local.iter().map(|s| (s.task().created_at, s.task())).collect();
+    let mut handles: HashSet<String> = local.iter().map(|s| s.task().handle.clone()).collect();
+    let mut next = store.id_counter()?.max(contents.id_counter);
+
+    let mut upgraded = Contents { data_format: DATA_VERSION, ..contents.clone() };
+    let mut ids: HashMap<String, String> = HashMap::new();
+    for task in upgraded.tasks.iter_mut()
+        .chain(upgraded.trash.iter_mut().map(|t| &mut t.task))
+        .chain(upgraded.archive.iter_mut().map(|a| &mut a.task))
+    {
+        let old = task.id.clone();
+        match by_created.get(&task.created_at) {
+            Some(existing) => {
+                task.id = existing.id.clone();
+                task.handle = existing.handle.clone();
+            }
+            None => {
+                task.id = Uuid::new_v4().to_string();
+                if handles.contains(&old) {
+                    while handles.contains(&next.to_string()) {
+                        next += 1;
+                    }
+                    task.handle = next.to_string();
+                } else {
+                    task.handle = old.clone();
+                }
+                handles.insert(task.handle.clone());
+            }
+        }
+        ids.entry(old).or_insert_with(|| task.id.clone());
+    }
+
+    let map = |id: &str| ids.get(id).cloned().unwrap_or_else(|| id.to_string());
+    for task in upgraded.tasks.iter_mut()
+        .chain(upgraded.trash.iter_mut().map(|t| &mut t.task))
+        .chain(upgraded.archive.iter_mut().map(|a| &mut a.task))
+    {
+        task.habit_stack_after = task.habit_stack_after.as_deref().map(map);
+        task.parent = task.parent.as_deref().map(map);
+        task.depends_on = task.depends_on.iter().map(|id| map(id)).collect();
+    }
+    for day in &mut upgraded.days {
+        day.task_ids = day.task_ids.iter().map(|id| map(id)).collect();
+    }
+    for entry in &mut upgraded.daily_log {
+        entry.task_id = map(&entry.task_id);
+    }
+    for entry in &mut upgraded.history_log {
+        entry.task_id = map(&entry.task_id);
+    }
+    while handles.contains(&next.to_string()) {
+        next += 1;
+    }
+    upgraded.id_counter = upgraded.id_counter.max(next);
+    Ok(upgraded)
+}
+
+/// Appends the lines of `wanted` missing from `current` and, when
+/// `remove_extra` is set, removes the lines of `current` missing from
+/// `wanted`. Returns how many were added and removed.
+fn sync_lines<T>(
+    current: &[T],
+    wanted: &[T],
+    line: fn(&T) -> String,
+    remove_extra: bool,
+    append: impl Fn(&T) -> Result<()>,
+    remove: impl Fn(&T) -> Result<()>,
+) -> Result<(usize, usize)> {
+    let mut have: HashMap<String, usize> = HashMap::new();
+    for entry in current {
+        *have.entry(line(entry)).or_default() += 1;
+    }
+
+    let mut added = 0;
+    for entry in wanted {
+        match have.get_mut(&line(entry)) {
+            Some(n) if *n > 0 => *n -= 1,
+            _ => {
+                append(entry)?;
+                added += 1;
+            }
+        }
+    }
+
+    let mut removed = 0;
+    if remove_extra {
+        for entry in current.iter().rev() {
+            if let Some(n) = have.get_mut(&line(entry)).filter(|n| **n > 0) {
+                *n -= 1;
+                remove(entry)?;
+                removed += 1;
+            }
+        }
+    }
+    Ok((added, removed))
+}
+
+/// Writes a backup of `store` into `<data_dir>/backups/` and deletes the
+/// oldest automatic backups beyond `keep`. In an encrypted data dir, pass its
+/// cipher so the backup is encrypted too.
+pub fn auto_backup(store: &dyn TaskStore, data_dir: &Path, keep: usize, cipher: Option<&Cipher>) -> Result<PathBuf> {
+    let dir = data_dir.join(AUTO_BACKUP_DIR);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("auto-{}.json", Local::now().format("%Y%m%d-%H%M%S%3f")));
+    Backup::capture(store)?.write_with(&path, cipher)?;
+
+    let mut existing: Vec<PathBuf> = fs::read_dir(&dir)?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            let name = p.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            name.starts_with("auto-") && name.ends_with(".json")
+        })
+        .collect();
+    // Timestamped names sort oldest first.
+    existing.sort();
+    let excess = existing.len().saturating_sub(keep.max(1));
+    for old in &existing[..excess] {
+        fs::remove_file(old)?;
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Priority;
+    use crate::storage::Storage;
+    use chrono::NaiveDate;
+    use tempfile::TempDir;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn task(id: &str, title: &str) -> Task {
+        Task::new(id.to_string(), title.to_string(), Priority::Medium, "default".to_string())
+    }
+
+    fn populated() -> (TempDir, Storage) {
+        let dir = TempDir::new().unwrap();
+        let store = Storage::new(dir.path().to_path_buf()).unwrap();
+        store.save_task(&task("1", "Read\nslowly").with_daily(true)).unwrap();
+        store.save_task(&task("2", "Write")).unwrap();
+        let mut day = Day::new(date(2026, 4, 13));
+        day.add_task("2".to_string());
+        store.save_day(&day).unwrap();
+        store.save_category(&Category::new("default".to_string())).unwrap();
+        store.log_daily_completion("1", "Read", date(2026, 4, 12), None).unwrap();
+        store.log_daily_completion("1", "Read", date(2026, 4, 13), None).unwrap();
+        store.log_task_completion("2", "Write").unwrap();
+        store.set_id_counter(3).unwrap();
+        (dir, store)
+    }
+
+    #[test]
+    fn test_backup_round_trips_through_a_file() {
+        let (dir, store) = populated();
+        let path = dir.path().join("backup.json");
+        Backup::capture(&store).unwrap().write(&path).unwrap();
+
+        let target_dir = TempDir::new().unwrap();
+        let target = Storage::new(target_dir.path().to_path_buf()).unwrap();
+        let contents = Backup::read(&path).unwrap().contents().unwrap();
+        restore(&target, &contents, RestoreMode::Replace).unwrap();
+
+        assert_eq!(target.load_task("1").unwrap().title, "Read\nslowly");
+        assert_eq!(target.load_day(date(2026, 4, 13)).unwrap().task_ids, vec!["2"]);
+        assert_eq!(target.get_streak_for_task("1", date(2026, 4, 13)).unwrap(), 2);
+        assert_eq!(target.history_entries().unwrap().len(), 1);
+        assert_eq!(target.id_counter().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_replace_drops_data_not_in_backup() {
+        let (_dir, store) = populated();
+        let backup = Backup::capture(&store).unwrap();
+        store.save_task(&task("9", "Later")).unwrap();
+        store.log_daily_completion("1", "Read", date(2026, 4, 14), None).unwrap();
+
+        let report = restore(&store, &backup.contents().unwrap(), RestoreMode::Replace).unwrap();
+        assert_eq!(report.removed, 2);
+        assert_eq!(report.tasks, 0);
+        assert!(store.load_task("9").is_err());
+        assert_eq!(store.daily_log_entries().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_merge_keeps_existing_data_and_newest_tasks() {
+        let (_dir, store) = populated();
+        let backup = Backup::capture(&store).unwrap();
+        let mut renamed = store.load_task("2").unwrap();
+        renamed.title = "Write more".to_string();
+        renamed.updated_at = Utc::now() + chrono::Duration::seconds(5);
+        store.save_task(&renamed).unwrap();
+        store.save_task(&task("9", "Later")).unwrap();
+
+        let report = restore(&store, &backup.contents().unwrap(), RestoreMode::Merge).unwrap();
+        assert_eq!(report, RestoreReport::default());
+        assert_eq!(store.load_task("2").unwrap().title, "Write more");
+        assert!(store.load_task("9").is_ok());
+    }
+
+    #[test]
+    fn test_format_3_backup_gets_uuids_on_restore() {
+        let (_dir, store) = populated();
+        let mut backup = Backup::capture(&store).unwrap();
+        backup.data_format = 3;
+
+        let target_dir = TempDir::new().unwrap();
+        let target = Storage::new(target_dir.path().to_path_buf()).unwrap();
+        restore(&target, &backup.contents().unwrap(), RestoreMode::Merge).unwrap();
+
+        let tasks = target.list_all_tasks().unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert!(tasks.iter().all(|t| Uuid::parse_str(&t.id).is_ok()));
+        let read = tasks.iter().find(|t| t.handle == "1").unwrap();
+        let write = tasks.iter().find(|t| t.handle == "2").unwrap();
+        assert_eq!(target.load_day(date(2026, 4, 13)).unwrap().task_ids, vec![write.id.clone()]);
+        assert_eq!(target.get_streak_for_task(&read.id, date(2026, 4, 13)).unwrap(), 2);
+        assert_eq!(target.history_entries().unwrap()[0].task_id, write.id);
+
+        // Restoring it again matches the upgraded tasks instead of adding copies.
+        let report = restore(&target, &backup.contents().unwrap(), RestoreMode::Merge).unwrap();
+        assert_eq!(report, RestoreReport::default());
+        assert_eq!(target.list_all_tasks().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_format_3_backup_renumbers_taken_handles() {
+        let (_dir, store) = populated();
+        let mut backup = Backup::capture(&store).unwrap();
+        backup.data_format = 3;
+
+        let target_dir = TempDir::new().unwrap();
+        let target = Storage::new(target_dir.path().to_path_buf()).unwrap();
+        let other = task(&Uuid::new_v4().to_string(), "Other").with_handle("1".to_string());
+        target.save_task(&other).unwrap();
+        target.set_id_counter(2).unwrap();
+        restore(&target, &backup.contents().unwrap(), RestoreMode::Merge).unwrap();
+
+        let mut handles: Vec<String> = target.list_all_tasks().unwrap().into_iter().map(|t| t.handle).collect();
+        handles.sort();
+        assert_eq!(handles, vec!["1", "2", "3"]);
+        assert_eq!(target.id_counter().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_damaged_backup_is_rejected_before_restoring() {
+        let (_dir, store) = populated();
+        let mut backup = Backup::capture(&store).unwrap();
+        backup.days.push("date: someday".to_string());
+        let err = backup.contents().unwrap_err().to_string();
+        assert!(err.contains("Backup day #2 is invalid"), "{}", err);
+    }
+
+    #[test]
+    fn test_refuses_newer_backup() {
+        let (dir, store) = populated();
+        let mut backup = Backup::capture(&store).unwrap();
+        backup.data_format = DATA_VERSION + 1;
+        let path = dir.path().join("new.json");
+        backup.write(&path).unwrap();
+        assert!(Backup::read(&path).unwrap_err().to_string().contains("newer version"));
+    }
+
+    #[test]
+    fn test_encrypted_backup_needs_the_passphrase() {
+        let (dir, store) = populated();
+        let path = dir.path().join("secret.json");
+        let cipher = Cipher::derive("pw", &KeyParams::generate()).unwrap();
+        Backup::capture(&store).unwrap().write_with(&path, Some(&cipher)).unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("Read"));
+
+        assert!(Backup::read(&path).unwrap_err().to_string().contains("encrypted"));
+        assert!(Backup::read_with(&path, |params| Cipher::derive("wrong", params)).is_err());
+        let backup = Backup::read_with(&path, |params| Cipher::derive("pw", params)).unwrap();
+        assert_eq!(backup.tasks.len(), 2);
+    }
+
+    #[test]
+    fn test_auto_backups_rotate() {
+        let (dir, store) = populated();
+        for _ in 0..4 {
+            auto_backup(&store, dir.path(), 2, None).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert_eq!(fs::read_dir(dir.path().join(AUTO_BACKUP_DIR)).unwrap().count(), 2);
+    }
+}
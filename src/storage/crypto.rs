@@ -0,0 +1,434 @@
+use anyhow::{Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use super::atomic::{self, write_atomic};
+use super::backup::AUTO_BACKUP_DIR;
+use super::journal::JOURNAL_DIR;
+use super::lock::DEFAULT_LOCK_TIMEOUT;
+use super::{Backend, DirLock, SQLITE_FILE};
+
+/// Marks an encrypted data dir and holds the key parameters, relative to the
+/// data dir. Its presence is what makes a directory encrypted.
+pub const ENCRYPTION_FILE: &str = "encryption.json";
+
+/// Version of the encryption scheme written by this build.
+pub const ENCRYPTION_VERSION: u32 = 1;
+
+/// Start of every encrypted record, log line and journal entry.
+const SEALED_PREFIX: &str = "enc1:";
+
+/// Encrypted into `encryption.json` so a wrong passphrase is caught before
+/// anything is read or written.
+const CHECK_TEXT: &str = "daily";
+
+/// Record directories whose files are encrypted whole.
+const RECORD_DIRS: &[&str] = &["tasks", "trash", "archive", "days", "categories", JOURNAL_DIR];
+
+/// Logs whose lines are encrypted one by one, so appending stays cheap.
+const LOG_FILES: &[&str] = &["daily.log", "history.log"];
+
+/// How the key is derived from the passphrase.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyParams {
+    pub kdf: String,
+    pub salt: String,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl KeyParams {
+    /// Argon2id with its recommended cost and a fresh random salt.
+    pub fn generate() -> Self {
+        let defaults = Params::default();
+        Self {
+            kdf: "argon2id".to_string(),
+            salt: to_hex(&rand::random::<[u8; 16]>()),
+            memory_kib: defaults.m_cost(),
+            iterations: defaults.t_cost(),
+            parallelism: defaults.p_cost(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    version: u32,
+    key: KeyParams,
+    check: String,
+}
+
+/// A file that carries its own key parameters, so it can be decrypted with
+/// the passphrase alone after the data dir it came from is gone.
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    encryption: KeyParams,
+    data: String,
+}
+
+/// XChaCha20-Poly1305 with a key derived from the passphrase.
+#[derive(Clone)]
+pub struct Cipher {
+    aead: Rc<XChaCha20Poly1305>,
+    params: KeyParams,
+}
+
+impl Cipher {
+    pub fn derive(passphrase: &str, params: &KeyParams) -> Result<Self> {
+        if params.kdf != "argon2id" {
+            anyhow::bail!("Unsupported key derivation '{}'", params.kdf);
+        }
+        let argon = Argon2::new(
+            Algorithm::Argon2id,
+            Version::V0x13,
+            Params::new(params.memory_kib, params.iterations, params.parallelism, Some(32))
+                .map_err(|e| anyhow::anyhow!("Invalid key parameters: {}", e))?,
+        );
+        let salt = from_hex(&params.salt).context("Invalid key salt")?;
+        let mut key = [0u8; 32];
+        argon.hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| anyhow::anyhow!("Could not derive the key: {}", e))?;
+        Ok(Self {
+            aead: Rc::new(XChaCha20Poly1305::new(Key::from_slice(&key))),
+            params: params.clone(),
+        })
+    }
+
+    /// Encrypts `plain` under a fresh nonce into one line of text.
+    pub fn seal(&self, plain: &str) -> String {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self.aead.encrypt(&nonce, plain.as_bytes())
+            .expect("encrypting in memory can't fail");
+        format!("{}{}{}", SEALED_PREFIX, to_hex(&nonce), to_hex(&ciphertext))
+    }
+
+    /// Decrypts text written by `seal`. Anything else is returned unchanged,
+    /// so a directory half way through `daily encrypt` stays readable.
+    pub fn unseal(&self, text: &str) -> Result<String> {
+        let Some(hex) = text.trim_end().strip_prefix(SEALED_PREFIX) else {
+            return Ok(text.to_string());
+        };
+        let bytes = from_hex(hex).context("Damaged encrypted data")?;
+        if bytes.len() < 24 {
+            anyhow::bail!("Damaged encrypted data");
+        }
+        let (nonce, ciphertext) = bytes.split_at(24);
+        let plain = self.aead.decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow::anyhow!("Could not decrypt: wrong passphrase or damaged data"))?;
+        String::from_utf8(plain).context("Decrypted data is not text")
+    }
+
+    /// Seals `plain` together with the key parameters.
+    pub fn seal_portable(&self, plain: &str) -> String {
+        let envelope = Envelope { encryption: self.params.clone(), data: self.seal(plain) };
+        serde_json::to_string_pretty(&envelope).expect("envelopes always serialize")
+    }
+}
+
+pub fn is_sealed(text: &str) -> bool {
+    text.starts_with(SEALED_PREFIX)
+}
+
+/// Decrypts `text` with `cipher`, or passes it through when there is no
+/// cipher and it isn't encrypted.
+pub fn decode(cipher: Option<&Cipher>, text: &str) -> Result<String> {
+    match cipher {
+        Some(cipher) => cipher.unseal(text),
+        None if is_sealed(text) => anyhow::bail!("Encrypted, and no passphrase was given"),
+        None => Ok(text.to_string()),
+    }
+}
+
+pub fn encode(cipher: Option<&Cipher>, text: &str) -> String {
+    match cipher {
+        Some(cipher) => cipher.seal(text),
+        None => text.to_string(),
+    }
+}
+
+/// The key parameters of a file written by `seal_portable`, or `None` if
+/// `content` is not one.
+pub fn portable_params(content: &str) -> Option<KeyParams> {
+    serde_json::from_str::<Envelope>(content).ok().map(|e| e.encryption)
+}
+
+/// Decrypts a file written by `seal_portable`.
+pub fn unseal_portable(cipher: &Cipher, content: &str) -> Result<String> {
+    let envelope: Envelope = serde_json::from_str(content).context("Not an encrypted file")?;
+    cipher.unseal(&envelope.data)
+}
+
+pub fn is_encrypted(data_dir: &Path) -> bool {
+    data_dir.join(ENCRYPTION_FILE).exists()
+}
+
+/// Derives the key of an encrypted data dir, checking the passphrase.
+pub fn unlock(data_dir: &Path, passphrase: &str) -> Result<Cipher> {
+    let path = data_dir.join(ENCRYPTION_FILE);
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+    let header: Header = serde_json::from_str(&content)
+        .with_context(|| format!("{} is damaged", path.display()))?;
+    if header.version > ENCRYPTION_VERSION {
+        anyhow::bail!("{} was encrypted by a newer version of daily", data_dir.display());
+    }
+    let cipher = Cipher::derive(passphrase, &header.key)?;
+    match cipher.unseal(&header.check) {
+        Ok(check) if check == CHECK_TEXT => Ok(cipher),
+        _ => anyhow::bail!("Wrong passphrase for {}", data_dir.display()),
+    }
+}
+
+/// How many files `encrypt_dir` or `decrypt_dir` rewrote, and the backed-up
+/// databases it couldn't encrypt.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ConvertReport {
+    pub files: usize,
+    pub plaintext: Vec<PathBuf>,
+}
+
+/// Encrypts every record, log and journal entry, the automatic backups and
+/// the `backup-*` copies left by upgrades and migrations of a text data dir.
+/// Those copies get the key header too, so each still opens on its own.
+/// Running it again after an interruption finishes the job.
+pub fn encrypt_dir(data_dir: &Path, passphrase: &str) -> Result<ConvertReport> {
+    if Backend::detect(data_dir) == Backend::Sqlite {
+        anyhow::bail!("Encryption needs the text backend. Run `daily migrate --to text` first.");
+    }
+    if passphrase.is_empty() {
+        anyhow::bail!("The passphrase can't be empty");
+    }
+    let _guard = DirLock::new(data_dir, DEFAULT_LOCK_TIMEOUT).acquire()?;
+    let cipher = if is_encrypted(data_dir) {
+        unlock(data_dir, passphrase)?
+    } else {
+        let key = KeyParams::generate();
+        let cipher = Cipher::derive(passphrase, &key)?;
+        let header = Header { version: ENCRYPTION_VERSION, key, check: cipher.seal(CHECK_TEXT) };
+        write_atomic(&data_dir.join(ENCRYPTION_FILE), serde_json::to_string_pretty(&header)?.as_bytes())?;
+        cipher
+    };
+
+    let report = convert(
+        data_dir,
+        |text| Ok(if is_sealed(text) { text.to_string() } else { cipher.seal(text) }),
+        |content| Ok(if portable_params(content).is_some() { content.to_string() } else { cipher.seal_portable(content) }),
+    )?;
+    for dir in copy_dirs(data_dir).iter().filter(|d| !d.join(SQLITE_FILE).exists()) {
+        fs::copy(data_dir.join(ENCRYPTION_FILE), dir.join(ENCRYPTION_FILE))?;
+    }
+    Ok(report)
+}
+
+/// Turns an encrypted data dir back into plain text.
+pub fn decrypt_dir(data_dir: &Path, passphrase: &str) -> Result<ConvertReport> {
+    if !is_encrypted(data_dir) {
+        anyhow::bail!("{} is not encrypted", data_dir.display());
+    }
+    let _guard = DirLock::new(data_dir, DEFAULT_LOCK_TIMEOUT).acquire()?;
+    let cipher = unlock(data_dir, passphrase)?;
+
+    let report = convert(
+        data_dir,
+        |text| if is_sealed(text) { cipher.unseal(text) } else { Ok(text.to_string()) },
+        |content| if portable_params(content).is_some() { unseal_portable(&cipher, content) } else { Ok(content.to_string()) },
+    )?;
+    for dir in copy_dirs(data_dir) {
+        let header = dir.join(ENCRYPTION_FILE);
+        if header.exists() {
+            fs::remove_file(header)?;
+        }
+    }
+    // Last, so an interrupted run can be finished with the same passphrase.
+    fs::remove_file(data_dir.join(ENCRYPTION_FILE))?;
+    Ok(report)
+}
+
+/// Rewrites every record file and log line with `record`, in the data dir
+/// and in its `backup-*` copies, and every automatic backup with `backup`,
+/// leaving files that come out unchanged alone.
+fn convert(
+    data_dir: &Path,
+    record: impl Fn(&str) -> Result<String>,
+    backup: impl Fn(&str) -> Result<String>,
+) -> Result<ConvertReport> {
+    let mut files = 0;
+    let mut rewrite = |path: &Path, transform: &dyn Fn(&str) -> Result<String>| -> Result<()> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Could not read {}", path.display()))?;
+        let converted = transform(&content).with_context(|| format!("Could not convert {}", path.display()))?;
+        if converted != content {
+            write_atomic(path, converted.as_bytes())?;
+            files += 1;
+        }
+        Ok(())
+    };
+
+    let copies = copy_dirs(data_dir);
+    for dir in std::iter::once(data_dir).chain(copies.iter().map(PathBuf::as_path)) {
+        for record_dir in RECORD_DIRS {
+            for path in files_in(&dir.join(record_dir)) {
+                rewrite(&path, &record)?;
+            }
+        }
+        for file_name in LOG_FILES {
+            let path = dir.join(file_name);
+            if path.exists() {
+                rewrite(&path, &|content: &str| {
+                    let mut out = String::new();
+                    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+                        out.push_str(&record(line)?);
+                        out.push('\n');
+                    }
+                    Ok(out)
+                })?;
+            }
+        }
+    }
+    for path in files_in(&data_dir.join(AUTO_BACKUP_DIR)) {
+        rewrite(&path, &backup)?;
+    }
+
+    // A database left by `daily migrate` can't be encrypted record by record.
+    let plaintext = copies.iter().map(|d| d.join(SQLITE_FILE)).filter(|p| p.exists()).collect();
+    Ok(ConvertReport { files, plaintext })
+}
+
+/// The `backup-*` directories upgrades and migrations leave in `data_dir`.
+fn copy_dirs(data_dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(data_dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    dirs.retain(|p| p.is_dir() && p.file_name().is_some_and(|n| n.to_string_lossy().starts_with("backup-")));
+    dirs.sort();
+    dirs
+}
+
+/// Regular files in `dir`, skipping in-flight temp files and lock files.
+fn files_in(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    files.retain(|p| p.is_file() && !atomic::is_temp_file(p) && p.file_name().is_some_and(|n| n != super::LOCK_FILE));
+    files.sort();
+    files
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|pair| u8::from_str_radix(pair, 16).ok()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Priority, Task};
+    use crate::storage::{Storage, TaskStore};
+    use crate::storage::journal::Journal;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_seal_round_trip_and_wrong_key() {
+        let params = KeyParams::generate();
+        let cipher = Cipher::derive("correct horse", &params).unwrap();
+        let sealed = cipher.seal("title: Call the client");
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.contains("client"));
+        assert_eq!(cipher.unseal(&sealed).unwrap(), "title: Call the client");
+        assert_ne!(cipher.seal("same"), cipher.seal("same"));
+
+        let other = Cipher::derive("battery staple", &params).unwrap();
+        assert!(other.unseal(&sealed).is_err());
+        assert!(decode(None, &sealed).is_err());
+        assert_eq!(decode(None, "plain").unwrap(), "plain");
+    }
+
+    #[test]
+    fn test_portable_files_carry_their_key_params() {
+        let cipher = Cipher::derive("pw", &KeyParams::generate()).unwrap();
+        let content = cipher.seal_portable("{\"tasks\": []}");
+        let params = portable_params(&content).unwrap();
+        let again = Cipher::derive("pw", &params).unwrap();
+        assert_eq!(unseal_portable(&again, &content).unwrap(), "{\"tasks\": []}");
+        assert!(portable_params("{\"tasks\": []}").is_none());
+    }
+
+    #[test]
+    fn test_encrypt_and_decrypt_dir() {
+        let dir = TempDir::new().unwrap();
+        let store = Storage::new(dir.path().to_path_buf()).unwrap();
+        let task = Task::new("1".to_string(), "Secret client call".to_string(), Priority::High, "work".to_string());
+        store.save_task(&task).unwrap();
+        store.log_task_completion("1", "Secret client call").unwrap();
+        Journal::new(dir.path()).record("daily add", Vec::new()).unwrap();
+
+        let report = encrypt_dir(dir.path(), "pw").unwrap();
+        assert_eq!(report.files, 3);
+        assert!(is_encrypted(dir.path()));
+        for file in ["tasks/1.txt", "history.log", "journal/000001.json"] {
+            let content = fs::read_to_string(dir.path().join(file)).unwrap();
+            assert!(is_sealed(&content), "{} is not encrypted", file);
+        }
+        assert_eq!(encrypt_dir(dir.path(), "pw").unwrap().files, 0);
+        assert!(unlock(dir.path(), "wrong").is_err());
+        assert!(decrypt_dir(dir.path(), "wrong").is_err());
+
+        let cipher = unlock(dir.path(), "pw").unwrap();
+        let store = Storage::new(dir.path().to_path_buf()).unwrap().with_cipher(cipher);
+        assert_eq!(store.load_task("1").unwrap().title, "Secret client call");
+        assert_eq!(store.history_entries().unwrap().len(), 1);
+
+        assert_eq!(decrypt_dir(dir.path(), "pw").unwrap().files, 3);
+        assert!(!is_encrypted(dir.path()));
+        let store = Storage::new(dir.path().to_path_buf()).unwrap();
+        assert_eq!(store.load_task("1").unwrap().title, "Secret client call");
+    }
+
+    #[test]
+    fn test_encrypt_covers_upgrade_and_migration_copies() {
+        let dir = TempDir::new().unwrap();
+        let copy = dir.path().join("backup-20260401000000-format1");
+        fs::create_dir_all(copy.join("tasks")).unwrap();
+        fs::write(copy.join("tasks").join("1.txt"), "id: 1\ntitle: Secret client call").unwrap();
+        fs::write(copy.join("daily.log"), "2026-04-12 | 1 | Run\n").unwrap();
+        let migrated = dir.path().join("backup-20260402000000");
+        fs::create_dir_all(&migrated).unwrap();
+        fs::write(migrated.join(SQLITE_FILE), "not really sqlite").unwrap();
+
+        let report = encrypt_dir(dir.path(), "pw").unwrap();
+        assert_eq!(report.files, 2);
+        assert_eq!(report.plaintext, vec![migrated.join(SQLITE_FILE)]);
+        for file in ["tasks/1.txt", "daily.log"] {
+            let content = fs::read_to_string(copy.join(file)).unwrap();
+            assert!(is_sealed(&content) && !content.contains("Secret"), "{} is not encrypted", file);
+        }
+        // The copy carries its key header, so it unlocks on its own.
+        assert!(unlock(&copy, "pw").is_ok());
+        assert!(!migrated.join(ENCRYPTION_FILE).exists());
+
+        assert_eq!(decrypt_dir(dir.path(), "pw").unwrap().files, 2);
+        assert_eq!(fs::read_to_string(copy.join("tasks").join("1.txt")).unwrap(), "id: 1\ntitle: Secret client call");
+        assert!(!copy.join(ENCRYPTION_FILE).exists());
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        assert_eq!(from_hex(&to_hex(&[0, 15, 255])).unwrap(), vec![0, 15, 255]);
+        assert!(from_hex("abc").is_none());
+        assert!(from_hex("zz").is_none());
+    }
+}
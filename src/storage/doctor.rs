@@ -0,0 +1,615 @@
+use anyhow::Result;
+use chrono::{NaiveDate, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use uuid::Uuid;
+use crate::models::Task;
+use super::{TaskStore, UnreadableRecord};
+
+/// One integrity problem found by `check`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    /// A record the store skips because it can't be read or parsed.
+    Unreadable(UnreadableRecord),
+    /// A task stored under `key` whose own ID is `id`. `duplicate` is set when
+    /// another record already claims `id`.
+    MisfiledTask { key: String, id: String, duplicate: bool },
+    /// A day plan that lists a task which no longer exists.
+    MissingDayTask { date: NaiveDate, task_id: String },
+    /// A habit stacked after a task which no longer exists.
+    MissingStackTarget { task_id: String, missing: String },
+    /// Habits stacked after each other in a loop, in stacking order.
+    StackCycle { task_ids: Vec<String> },
+    /// A subtask whose parent task no longer exists.
+    MissingParent { task_id: String, missing: String },
+    /// Tasks that are each other's subtasks, child first.
+    ParentCycle { task_ids: Vec<String> },
+    /// A task that depends on a task which no longer exists.
+    MissingDependency { task_id: String, missing: String },
+    /// Tasks that depend on each other in a loop, each on the next.
+    DependencyCycle { task_ids: Vec<String> },
+    /// Completion log entries for a task which no longer exists.
+    OrphanLogEntries { log: &'static str, task_id: String, count: usize },
+    /// The ID counter would hand out a handle that is already taken.
+    CounterBehind { counter: u64, highest: u64 },
+    /// Several tasks answer to the same handle, oldest first.
+    DuplicateHandle { handle: String, task_ids: Vec<String> },
+}
+
+impl Issue {
+    /// Whether `repair` can fix this without losing data. Unreadable records
+    /// need a human; orphaned log entries are kept as history.
+    pub fn is_fixable(&self) -> bool {
+        !matches!(self, Issue::Unreadable(_) | Issue::OrphanLogEntries { .. })
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::Unreadable(record) => write!(f, "{} can't be read: {}", record.location, record.error),
+            Issue::MisfiledTask { key, id, duplicate: true } => {
+                write!(f, "Task record '{}' holds task {}, which is a duplicate ID", key, id)
+            }
+            Issue::MisfiledTask { key, id, duplicate: false } => {
+                write!(f, "Task record '{}' holds task {}", key, id)
+            }
+            Issue::MissingDayTask { date, task_id } => {
+                write!(f, "Day {} lists missing task {}", date, task_id)
+            }
+            Issue::MissingStackTarget { task_id, missing } => {
+                write!(f, "Task {} is stacked after missing task {}", task_id, missing)
+            }
+            Issue::StackCycle { task_ids } => {
+                write!(f, "Habit stack cycle: {} -> {}", task_ids.join(" -> "), task_ids[0])
+            }
+            Issue::MissingParent { task_id, missing } => {
+                write!(f, "Task {} is a subtask of missing task {}", task_id, missing)
+            }
+            Issue::ParentCycle { task_ids } => {
+                write!(f, "Subtask cycle: {} -> {}", task_ids.join(" -> "), task_ids[0])
+            }
+            Issue::MissingDependency { task_id, missing } => {
+                write!(f, "Task {} depends on missing task {}", task_id, missing)
+            }
+            Issue::DependencyCycle { task_ids } => {
+                write!(f, "Dependency cycle: {} -> {}", task_ids.join(" -> "), task_ids[0])
+            }
+            Issue::OrphanLogEntries { log, task_id, count } => {
+                write!(f, "{} has {} entr{} for missing task {}", log, count,
+                    if *count == 1 { "y" } else { "ies" }, task_id)
+            }
+            Issue::CounterBehind { counter, highest } => {
+                write!(f, "ID counter is {} but task {} already exists", counter, highest)
+            }
+            Issue::DuplicateHandle { handle, task_ids } => {
+                write!(f, "{} tasks share the handle {}: {}", task_ids.len(), handle, task_ids.join(", "))
+            }
+        }
+    }
+}
+
+/// Result of scanning a data directory.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub tasks: usize,
+    pub days: usize,
+    pub categories: usize,
+    pub issues: Vec<Issue>,
+}
+
+/// Scans every record in `store` and reports what's wrong with it. Nothing is
+/// modified.
+pub fn check(store: &dyn TaskStore) -> Result<Report> {
+    let mut report = Report::default();
+
+    report.issues.extend(store.unreadable_records()?.into_iter().map(Issue::Unreadable));
+
+    let records = store.task_records()?;
+    let mut claims: HashMap<&str, usize> = HashMap::new();
+    for (_, task) in &records {
+        *claims.entry(task.id.as_str()).or_default() += 1;
+    }
+    let keys: HashSet<&str> = records.iter().map(|(key, _)| key.as_str()).collect();
+    for (key, task) in &records {
+        if *key != task.id {
+            let duplicate = keys.contains(task.id.as_str()) || claims[task.id.as_str()] > 1;
+            report.issues.push(Issue::MisfiledTask { key: key.clone(), id: task.id.clone(), duplicate });
+        }
+    }
+
+    let tasks = store.list_all_tasks()?;
+    report.tasks = tasks.len();
+    let ids: HashSet<&str> = tasks.iter().map(|t| t.id.as_str()).collect();
+    // References to trashed and archived tasks are kept so they come back on
+    // restore.
+    let trash = store.list_trash()?;
+    let archive = store.list_archived()?;
+    let set_aside: Vec<&Task> = trash.iter().map(|t| &t.task).chain(archive.iter().map(|a| &a.task)).collect();
+    let known: HashSet<&str> = ids.iter().copied().chain(set_aside.iter().map(|t| t.id.as_str())).collect();
+
+    let days = store.list_days()?;
+    report.days = days.len();
+    for day in &days {
+        for task_id in &day.task_ids {
+            if !known.contains(task_id.as_str()) {
+                report.issues.push(Issue::MissingDayTask { date: day.date, task_id: task_id.clone() });
+            }
+        }
+    }
+    report.categories = store.list_categories()?.len();
+
+    let mut stacks: BTreeMap<&str, &str> = BTreeMap::new();
+    for task in &tasks {
+        if let Some(after) = &task.habit_stack_after {
+            if ids.contains(after.as_str()) {
+                stacks.insert(&task.id, after);
+            } else if !known.contains(after.as_str()) {
+                report.issues.push(Issue::MissingStackTarget { task_id: task.id.clone(), missing: after.clone() });
+            }
+        }
+    }
+    report.issues.extend(link_cycles(&stacks).into_iter().map(|task_ids| Issue::StackCycle { task_ids }));
+
+    let mut parents: BTreeMap<&str, &str> = BTreeMap::new();
+    for task in &tasks {
+        if let Some(parent) = &task.parent {
+            if ids.contains(parent.as_str()) {
+                parents.insert(&task.id, parent);
+            } else if !known.contains(parent.as_str()) {
+                report.issues.push(Issue::MissingParent { task_id: task.id.clone(), missing: parent.clone() });
+            }
+        }
+    }
+    report.issues.extend(link_cycles(&parents).into_iter().map(|task_ids| Issue::ParentCycle { task_ids }));
+
+    let mut dependencies: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for task in &tasks {
+        for prerequisite in &task.depends_on {
+            if ids.contains(prerequisite.as_str()) {
+                dependencies.entry(&task.id).or_default().push(prerequisite);
+            } else if !known.contains(prerequisite.as_str()) {
+                report.issues.push(Issue::MissingDependency { task_id: task.id.clone(), missing: prerequisite.clone() });
+            }
+        }
+    }
+    report.issues.extend(dependency_cycles(&dependencies).into_iter().map(|task_ids| Issue::DependencyCycle { task_ids }));
+
+    let mut orphans: BTreeMap<(&'static str, String), usize> = BTreeMap::new();
+    for entry in store.daily_log_entries()? {
+        if !known.contains(entry.task_id.as_str()) {
+            *orphans.entry(("daily.log", entry.task_id)).or_default() += 1;
+        }
+    }
+    for entry in store.history_entries()? {
+        if !known.contains(entry.task_id.as_str()) {
+            *orphans.entry(("history.log", entry.task_id)).or_default() += 1;
+        }
+    }
+    report.issues.extend(orphans.into_iter().map(|((log, task_id), count)| Issue::OrphanLogEntries { log, task_id, count }));
+
+    let mut handles: BTreeMap<&str, Vec<&Task>> = BTreeMap::new();
+    for task in tasks.iter().chain(set_aside.iter().copied()).filter(|t| !t.handle.is_empty()) {
+        handles.entry(task.handle.as_str()).or_default().push(task);
+    }
+    for (handle, mut sharing) in handles.into_iter().filter(|(_, tasks)| tasks.len() > 1) {
+        sharing.sort_by_key(|t| t.created_at);
+        let task_ids = sharing.iter().map(|t| t.id.clone()).collect();
+        report.issues.push(Issue::DuplicateHandle { handle: handle.to_string(), task_ids });
+    }
+
+    // Tasks from before handles existed used their number as their ID.
+    let highest = records.iter()
+        .flat_map(|(key, task)| [key.as_str(), task.id.as_str(), task.handle.as_str()])
+        .chain(set_aside.iter().flat_map(|t| [t.id.as_str(), t.handle.as_str()]))
+        .filter_map(|n| n.parse::<u64>().ok())
+        .max();
+    let counter = store.id_counter()?;
+    if let Some(highest) = highest.filter(|&h| h >= counter) {
+        report.issues.push(Issue::CounterBehind { counter, highest });
+    }
+
+    Ok(report)
+}
+
+/// Every loop in a `task -> linked task` graph (stacked after, or subtask
+/// of), each rotated to start at its smallest ID so the same loop is always
+/// reported the same way.
+fn link_cycles(stacks: &BTreeMap<&str, &str>) -> Vec<Vec<String>> {
+    let mut cycles = Vec::new();
+    let mut done: HashSet<&str> = HashSet::new();
+
+    for &start in stacks.keys() {
+        let mut path: Vec<&str> = Vec::new();
+        let mut current = Some(start);
+        while let Some(id) = current {
+            if done.contains(id) {
+                break;
+            }
+            if let Some(pos) = path.iter().position(|&p| p == id) {
+                let mut cycle: Vec<String> = path[pos..].iter().map(|s| s.to_string()).collect();
+                let min = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap_or(0);
+                cycle.rotate_left(min);
+                cycles.push(cycle);
+                break;
+            }
+            path.push(id);
+            current = stacks.get(id).copied();
+        }
+        done.extend(path);
+    }
+
+    cycles
+}
+
+/// Loops in the `task -> prerequisites` graph, rotated like `link_cycles`.
+/// Loops sharing tasks may be reported once between them; fixing one and
+/// checking again finds the rest.
+fn dependency_cycles(dependencies: &BTreeMap<&str, Vec<&str>>) -> Vec<Vec<String>> {
+    fn visit<'a>(
+        id: &'a str,
+        dependencies: &BTreeMap<&'a str, Vec<&'a str>>,
+        path: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+        cycles: &mut Vec<Vec<String>>,
+    ) {
+        if let Some(pos) = path.iter().position(|&p| p == id) {
+            let mut cycle: Vec<String> = path[pos..].iter().map(|s| s.to_string()).collect();
+            let min = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap_or(0);
+            cycle.rotate_left(min);
+            if !cycles.contains(&cycle) {
+                cycles.push(cycle);
+            }
+            return;
+        }
+        if done.contains(id) {
+            return;
+        }
+        path.push(id);
+        for &next in dependencies.get(id).into_iter().flatten() {
+            visit(next, dependencies, path, done, cycles);
+        }
+        path.pop();
+        done.insert(id);
+    }
+
+    let mut cycles = Vec::new();
+    let mut done: HashSet<&str> = HashSet::new();
+    for &start in dependencies.keys() {
+        visit(start, dependencies, &mut Vec::new(), &mut done, &mut cycles);
+    }
+    cycles
+}
+
+/// Fixes every fixable issue and returns the ones it fixed. The counter is
+/// repaired first so that any handles handed out afterwards are fresh.
+pub fn repair(store: &dyn TaskStore, issues: &[Issue]) -> Result<Vec<Issue>> {
+    let mut ordered: Vec<&Issue> = issues.iter().filter(|i| i.is_fixable()).collect();
+    ordered.sort_by_key(|i| !matches!(i, Issue::CounterBehind { .. }));
+
+    let mut fixed = Vec::new();
+    for issue in ordered {
+        match issue {
+            Issue::CounterBehind { highest, .. } => {
+                let next = (*highest + 1).max(store.id_counter()?);
+                store.set_id_counter(next)?;
+            }
+            Issue::MisfiledTask { key, id, .. } => {
+                let mut task = store.load_task(key)?;
+                // Keep the ID that days, stacks and logs refer to unless
+                // another record already holds it; then the copy becomes a
+                // task of its own.
+                if store.load_task(id).is_ok() {
+                    task.id = Uuid::new_v4().to_string();
+                    task.handle = store.next_handle()?;
+                } else {
+                    task.id = id.clone();
+                }
+                task.updated_at = Utc::now();
+                store.save_task(&task)?;
+                store.delete_task(key)?;
+            }
+            Issue::MissingDayTask { date, task_id } => {
+                let mut day = store.load_day(*date)?;
+                day.task_ids.retain(|id| id != task_id);
+                store.save_day(&day)?;
+            }
+            Issue::MissingStackTarget { task_id, .. } => {
+                let mut task = store.load_task(task_id)?;
+                task.habit_stack_after = None;
+                task.updated_at = Utc::now();
+                store.save_task(&task)?;
+            }
+            Issue::StackCycle { task_ids } => {
+                // Cutting one link is enough to break the loop.
+                let mut task = store.load_task(&task_ids[0])?;
+                task.habit_stack_after = None;
+                task.updated_at = Utc::now();
+                store.save_task(&task)?;
+            }
+            Issue::MissingParent { task_id, .. } => {
+                let mut task = store.load_task(task_id)?;
+                task.parent = None;
+                task.updated_at = Utc::now();
+                store.save_task(&task)?;
+            }
+            Issue::ParentCycle { task_ids } => {
+                let mut task = store.load_task(&task_ids[0])?;
+                task.parent = None;
+                task.updated_at = Utc::now();
+                store.save_task(&task)?;
+            }
+            Issue::MissingDependency { task_id, missing } => {
+                let mut task = store.load_task(task_id)?;
+                task.remove_dependency(missing);
+                store.save_task(&task)?;
+            }
+            Issue::DependencyCycle { task_ids } => {
+                // Dropping one edge breaks the loop.
+                let mut task = store.load_task(&task_ids[0])?;
+                task.remove_dependency(&task_ids[1 % task_ids.len()]);
+                store.save_task(&task)?;
+            }
+            Issue::DuplicateHandle { task_ids, .. } => {
+                // The oldest task keeps the handle.
+                for id in &task_ids[1..] {
+                    let handle = store.next_handle()?;
+                    // Touched so the new handle wins when synced copies merge.
+                    let renumber = |task: &mut Task| {
+                        task.handle = handle.clone();
+                        task.updated_at = Utc::now();
+                    };
+                    if let Ok(mut task) = store.load_task(id) {
+                        renumber(&mut task);
+                        store.save_task(&task)?;
+                    } else if let Ok(mut trashed) = store.load_trashed(id) {
+                        renumber(&mut trashed.task);
+                        store.save_trashed(&trashed)?;
+                    } else {
+                        let mut archived = store.load_archived(id)?;
+                        renumber(&mut archived.task);
+                        store.save_archived(&archived)?;
+                    }
+                }
+            }
+            Issue::Unreadable(_) | Issue::OrphanLogEntries { .. } => continue,
+        }
+        fixed.push(issue.clone());
+    }
+
+    Ok(fixed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Day, Priority, Task};
+    use crate::storage::Storage;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn add(store: &Storage, title: &str) -> Task {
+        let id = store.next_handle().unwrap();
+        let t = Task::new(id, title.to_string(), Priority::Medium, "default".to_string());
+        store.save_task(&t).unwrap();
+        t
+    }
+
+    fn stack(store: &Storage, task: &Task, after: &str) {
+        let t = task.clone().with_habit_stack_after(after.to_string());
+        store.save_task(&t).unwrap();
+    }
+
+    #[test]
+    fn test_clean_store_has_no_issues() {
+        let dir = TempDir::new().unwrap();
+        let store = Storage::new(dir.path().to_path_buf()).unwrap();
+        let a = add(&store, "Coffee");
+        let b = add(&store, "Journal");
+        stack(&store, &b, &a.id);
+        let mut day = Day::new(date(2026, 4, 13));
+        day.add_task(a.id.clone());
+        store.save_day(&day).unwrap();
+        store.log_daily_completion(&a.id, "Coffee", date(2026, 4, 13), None).unwrap();
+
+        let report = check(&store).unwrap();
+        assert_eq!(report.tasks, 2);
+        assert_eq!(report.days, 1);
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+    }
+
+    #[test]
+    fn test_trashed_tasks_are_not_dangling() {
+        let dir = TempDir::new().unwrap();
+        let store = Storage::new(dir.path().to_path_buf()).unwrap();
+        let a = add(&store, "Coffee");
+        let b = add(&store, "Journal");
+        stack(&store, &b, &a.id);
+        let mut day = Day::new(date(2026, 4, 13));
+        day.add_task(a.id.clone());
+        store.save_day(&day).unwrap();
+        store.log_daily_completion(&a.id, "Coffee", date(2026, 4, 13), None).unwrap();
+        store.trash_task(&a.id).unwrap();
+
+        let report = check(&store).unwrap();
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+    }
+
+    #[test]
+    fn test_finds_and_repairs_dangling_references() {
+        let dir = TempDir::new().unwrap();
+        let store = Storage::new(dir.path().to_path_buf()).unwrap();
+        let a = add(&store, "Coffee");
+        let b = add(&store, "Journal");
+        stack(&store, &b, &a.id);
+        let mut day = Day::new(date(2026, 4, 13));
+        day.add_task(a.id.clone());
+        day.add_task(b.id.clone());
+        store.save_day(&day).unwrap();
+        store.log_task_completion(&a.id, "Coffee").unwrap();
+        store.delete_task(&a.id).unwrap();
+
+        let report = check(&store).unwrap();
+        assert!(report.issues.contains(&Issue::MissingDayTask { date: date(2026, 4, 13), task_id: a.id.clone() }));
+        assert!(report.issues.contains(&Issue::MissingStackTarget { task_id: b.id.clone(), missing: a.id.clone() }));
+        assert!(report.issues.contains(&Issue::OrphanLogEntries { log: "history.log", task_id: a.id.clone(), count: 1 }));
+
+        let fixed = repair(&store, &report.issues).unwrap();
+        assert_eq!(fixed.len(), 2);
+        assert_eq!(store.load_day(date(2026, 4, 13)).unwrap().task_ids, vec![b.id.clone()]);
+        assert!(store.load_task(&b.id).unwrap().habit_stack_after.is_none());
+        // History is kept.
+        let remaining = check(&store).unwrap().issues;
+        assert_eq!(remaining.len(), 1);
+        assert!(!remaining[0].is_fixable());
+    }
+
+    #[test]
+    fn test_finds_and_breaks_stack_cycles() {
+        let dir = TempDir::new().unwrap();
+        let store = Storage::new(dir.path().to_path_buf()).unwrap();
+        let a = add(&store, "A");
+        let b = add(&store, "B");
+        let c = add(&store, "C");
+        let solo = add(&store, "Solo");
+        stack(&store, &a, &c.id);
+        stack(&store, &b, &a.id);
+        stack(&store, &c, &b.id);
+        stack(&store, &solo, &solo.id);
+
+        let report = check(&store).unwrap();
+        let cycles: Vec<_> = report.issues.iter().filter(|i| matches!(i, Issue::StackCycle { .. })).collect();
+        assert_eq!(cycles.len(), 2);
+        assert!(report.issues.contains(&Issue::StackCycle { task_ids: vec!["1".into(), "3".into(), "2".into()] }));
+        assert!(report.issues.contains(&Issue::StackCycle { task_ids: vec![solo.id.clone()] }));
+
+        repair(&store, &report.issues).unwrap();
+        assert!(check(&store).unwrap().issues.is_empty());
+        assert_eq!(store.load_task(&b.id).unwrap().habit_stack_after.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn test_finds_and_clears_broken_subtask_links() {
+        let dir = TempDir::new().unwrap();
+        let store = Storage::new(dir.path().to_path_buf()).unwrap();
+        let a = add(&store, "A");
+        let b = add(&store, "B");
+        let step = add(&store, "Step");
+        store.save_task(&a.clone().with_parent(b.id.clone())).unwrap();
+        store.save_task(&b.clone().with_parent(a.id.clone())).unwrap();
+        store.save_task(&step.clone().with_parent("gone".into())).unwrap();
+
+        let report = check(&store).unwrap();
+        assert!(report.issues.contains(&Issue::ParentCycle { task_ids: vec![a.id.clone(), b.id.clone()] }));
+        assert!(report.issues.contains(&Issue::MissingParent { task_id: step.id.clone(), missing: "gone".into() }));
+
+        repair(&store, &report.issues).unwrap();
+        assert!(check(&store).unwrap().issues.is_empty());
+        assert!(store.load_task(&step.id).unwrap().parent.is_none());
+        assert_eq!(store.load_task(&b.id).unwrap().parent.as_deref(), Some(a.id.as_str()));
+    }
+
+    #[test]
+    fn test_finds_and_breaks_dependency_problems() {
+        let dir = TempDir::new().unwrap();
+        let store = Storage::new(dir.path().to_path_buf()).unwrap();
+        let a = add(&store, "A");
+        let b = add(&store, "B");
+        let c = add(&store, "C");
+        store.save_task(&a.clone().with_depends_on(vec![b.id.clone()])).unwrap();
+        store.save_task(&b.clone().with_depends_on(vec![c.id.clone(), "gone".into()])).unwrap();
+        store.save_task(&c.clone().with_depends_on(vec![a.id.clone()])).unwrap();
+
+        let report = check(&store).unwrap();
+        assert!(report.issues.contains(&Issue::DependencyCycle { task_ids: vec![a.id.clone(), b.id.clone(), c.id.clone()] }));
+        assert!(report.issues.contains(&Issue::MissingDependency { task_id: b.id.clone(), missing: "gone".into() }));
+
+        repair(&store, &report.issues).unwrap();
+        assert!(check(&store).unwrap().issues.is_empty());
+        assert!(store.load_task(&a.id).unwrap().depends_on.is_empty());
+        assert_eq!(store.load_task(&b.id).unwrap().depends_on, vec![c.id.clone()]);
+    }
+
+    #[test]
+    fn test_unreadable_files_are_reported_not_fixed() {
+        let dir = TempDir::new().unwrap();
+        let store = Storage::new(dir.path().to_path_buf()).unwrap();
+        add(&store, "Fine");
+        fs::write(dir.path().join("tasks").join("9.txt"), "title: no timestamps").unwrap();
+        fs::write(dir.path().join("daily.log"), "garbage\n2026-04-13 | 1 | Fine\n").unwrap();
+
+        let report = check(&store).unwrap();
+        let locations: Vec<_> = report.issues.iter().filter_map(|i| match i {
+            Issue::Unreadable(r) => Some(r.location.clone()),
+            _ => None,
+        }).collect();
+        assert!(locations.contains(&format!("tasks{}9.txt", std::path::MAIN_SEPARATOR)));
+        assert!(locations.contains(&"daily.log line 1".to_string()));
+        assert!(repair(&store, &report.issues).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_id_and_lagging_counter() {
+        let dir = TempDir::new().unwrap();
+        let store = Storage::new(dir.path().to_path_buf()).unwrap();
+        add(&store, "Original");
+        // A hand-copied file that still claims ID 1, and a counter reset by hand.
+        let copy = fs::read_to_string(dir.path().join("tasks").join("1.txt")).unwrap();
+        fs::write(dir.path().join("tasks").join("5.txt"), copy).unwrap();
+        store.set_id_counter(1).unwrap();
+
+        let report = check(&store).unwrap();
+        assert!(report.issues.contains(&Issue::MisfiledTask { key: "5".into(), id: "1".into(), duplicate: true }));
+        assert!(report.issues.contains(&Issue::CounterBehind { counter: 1, highest: 5 }));
+
+        repair(&store, &report.issues).unwrap();
+        assert!(check(&store).unwrap().issues.is_empty());
+        let copy = store.list_all_tasks().unwrap().into_iter().find(|t| t.id != "1").unwrap();
+        assert!(Uuid::parse_str(&copy.id).is_ok());
+        assert_eq!(copy.handle, "6");
+        assert_eq!(store.id_counter().unwrap(), 7);
+    }
+
+    #[test]
+    fn test_duplicate_handles_are_renumbered() {
+        let dir = TempDir::new().unwrap();
+        let store = Storage::new(dir.path().to_path_buf()).unwrap();
+        let first = Task::new(Uuid::new_v4().to_string(), "First".into(), Priority::Medium, "default".into())
+            .with_handle("1".into());
+        let mut second = first.clone();
+        second.id = Uuid::new_v4().to_string();
+        second.created_at += chrono::Duration::seconds(1);
+        store.save_task(&first).unwrap();
+        store.save_task(&second).unwrap();
+        store.trash_task(&second.id).unwrap();
+        store.set_id_counter(2).unwrap();
+
+        let report = check(&store).unwrap();
+        assert_eq!(report.issues, vec![Issue::DuplicateHandle {
+            handle: "1".into(),
+            task_ids: vec![first.id.clone(), second.id.clone()],
+        }]);
+        repair(&store, &report.issues).unwrap();
+        assert!(check(&store).unwrap().issues.is_empty());
+        assert_eq!(store.load_task(&first.id).unwrap().handle, "1");
+        assert_eq!(store.load_trashed(&second.id).unwrap().task.handle, "2");
+    }
+
+    #[test]
+    fn test_misfiled_task_moves_back_to_its_id() {
+        let dir = TempDir::new().unwrap();
+        let store = Storage::new(dir.path().to_path_buf()).unwrap();
+        add(&store, "Original");
+        fs::rename(dir.path().join("tasks").join("1.txt"), dir.path().join("tasks").join("1 copy.txt")).unwrap();
+
+        let report = check(&store).unwrap();
+        assert_eq!(report.issues, vec![Issue::MisfiledTask { key: "1 copy".into(), id: "1".into(), duplicate: false }]);
+        repair(&store, &report.issues).unwrap();
+        assert!(dir.path().join("tasks").join("1.txt").exists());
+        assert!(check(&store).unwrap().issues.is_empty());
+    }
+}
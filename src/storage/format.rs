@@ -0,0 +1,634 @@
+// The `key: value` text representation shared by every backend. The text
+// backend writes these records to files; the SQLite backend stores them in
+// `body` columns so both layouts stay in step as fields are added.
+//
+// Version 2 records start with a `format: 2` line and escape backslashes,
+// newlines and carriage returns in every value, so each field is exactly one
+// line and a description can never smuggle in another field. Records without
+// a header are version 1 and are read verbatim.
+
+use anyhow::{Context, Result};
+use crate::models::{Task, Day, Category, Priority, Recurrence};
+use crate::models::task::parse_tag;
+use chrono::{DateTime, NaiveDate, Utc};
+use super::{ArchivedTask, DailyLogEntry, HistoryEntry, TrashedTask};
+
+/// Record format written by this build.
+pub const FORMAT_VERSION: u32 = 2;
+
+const VERSION_KEY: &str = "format";
+
+/// Parses a record only to find out whether it is readable.
+pub type RecordCheck = fn(&str) -> Result<()>;
+
+/// Builds a versioned record one `key: value` line at a time.
+struct RecordWriter {
+    lines: Vec<String>,
+}
+
+impl RecordWriter {
+    fn new() -> Self {
+        Self { lines: vec![format!("{}: {}", VERSION_KEY, FORMAT_VERSION)] }
+    }
+
+    fn field(&mut self, key: &str, value: impl std::fmt::Display) {
+        self.lines.push(format!("{}: {}", key, escape(&value.to_string())));
+    }
+
+    fn finish(self) -> String {
+        self.lines.join("\n")
+    }
+}
+
+/// Splits a record into its fields, unescaping values of version 2+ records.
+/// Fails on records written by a newer, unknown format version.
+fn read_fields(text: &str) -> Result<Vec<(&str, String)>> {
+    let mut lines = text.lines().peekable();
+    let mut version = 1;
+    if let Some((VERSION_KEY, v)) = lines.peek().and_then(|l| l.split_once(": ")) {
+        version = v.trim().parse().with_context(|| format!("Invalid format version '{}'", v))?;
+        lines.next();
+    }
+    if version > FORMAT_VERSION {
+        anyhow::bail!(
+            "Record uses format version {}, but this build only understands up to {}. Upgrade daily to read it.",
+            version,
+            FORMAT_VERSION
+        );
+    }
+
+    Ok(lines
+        .filter_map(|line| line.split_once(": "))
+        .map(|(key, value)| {
+            let value = if version >= 2 { unescape(value) } else { value.to_string() };
+            (key, value)
+        })
+        .collect())
+}
+
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('\\') => out.push('\\'),
+            // Unknown escapes are kept as written.
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Log columns are separated by " | " and entries by newlines, so titles are
+/// flattened onto one line before they are appended.
+fn log_title(title: &str) -> String {
+    title.replace("\r\n", " ").replace(['\n', '\r'], " ")
+}
+
+pub fn task_to_text(task: &Task) -> String {
+    let mut record = RecordWriter::new();
+    record.field("id", &task.id);
+    if !task.handle.is_empty() {
+        record.field("handle", &task.handle);
+    }
+    record.field("title", &task.title);
+    record.field("priority", &task.priority);
+    record.field("category", &task.category);
+    record.field("completed", task.completed);
+    record.field("created_at", task.created_at.to_rfc3339());
+    record.field("updated_at", task.updated_at.to_rfc3339());
+    record.field("is_daily", task.is_daily);
+    record.field("two_minute", task.two_minute);
+
+    if let Some(desc) = &task.description {
+        record.field("description", desc);
+    }
+
+    if let Some(due) = &task.due_date {
+        record.field("due_date", due.to_rfc3339());
+    }
+
+    if let Some(time) = &task.scheduled_time {
+        record.field("scheduled_time", time);
+    }
+
+    if let Some(loc) = &task.location {
+        record.field("location", loc);
+    }
+
+    if let Some(after) = &task.habit_stack_after {
+        record.field("habit_stack_after", after);
+    }
+
+    if let Some(days) = &task.scheduled_days {
+        let s: Vec<String> = days.iter().map(|d| d.to_string()).collect();
+        record.field("scheduled_days", s.join(","));
+    }
+
+    if task.numeric {
+        record.field("numeric", true);
+    }
+
+    if let Some(unit) = &task.unit {
+        record.field("unit", unit);
+    }
+
+    if let Some(target) = &task.target {
+        record.field("target", target);
+    }
+
+    if !task.tags.is_empty() {
+        record.field("tags", task.tags.join(","));
+    }
+
+    if let Some(parent) = &task.parent {
+        record.field("parent", parent);
+    }
+
+    if let Some(rule) = &task.recurrence {
+        record.field("recurrence", rule.to_rrule());
+        if let Some(start) = rule.start {
+            record.field("recurrence_start", start);
+        }
+        if !rule.except.is_empty() {
+            let dates: Vec<String> = rule.except.iter().map(|d| d.to_string()).collect();
+            record.field("recurrence_except", dates.join(","));
+        }
+        if rule.from_completion {
+            record.field("recurrence_from", "completion");
+        }
+    }
+
+    if !task.depends_on.is_empty() {
+        record.field("depends_on", task.depends_on.join(","));
+    }
+
+    record.finish()
+}
+
+pub fn text_to_task(text: &str) -> Result<Task> {
+    let mut id = String::new();
+    let mut handle = String::new();
+    let mut title = String::new();
+    let mut description = None;
+    let mut priority = Priority::Medium;
+    let mut category = String::from("default");
+    let mut completed = false;
+    let mut created_at = None;
+    let mut updated_at = None;
+    let mut due_date = None;
+    let mut is_daily = false;
+    let mut scheduled_time = None;
+    let mut location = None;
+    let mut habit_stack_after = None;
+    let mut two_minute = false;
+    let mut scheduled_days: Option<Vec<u8>> = None;
+    let mut numeric = false;
+    let mut unit: Option<String> = None;
+    let mut target: Option<f64> = None;
+    let mut tags: Vec<String> = Vec::new();
+    let mut parent = None;
+    let mut depends_on: Vec<String> = Vec::new();
+    let mut recurrence: Option<Recurrence> = None;
+    let mut recurrence_start = None;
+    let mut recurrence_except: Vec<NaiveDate> = Vec::new();
+    let mut recurrence_from_completion = false;
+
+    for (key, value) in read_fields(text)? {
+        match key {
+            "id" => id = value,
+            "handle" => handle = value,
+            "title" => title = value,
+            "description" => description = Some(value),
+            "priority" => priority = Priority::from_str(&value).unwrap_or(Priority::Medium),
+            "category" => category = value,
+            "completed" => completed = value.parse().unwrap_or(false),
+            "created_at" => created_at = value.parse().ok(),
+            "updated_at" => updated_at = value.parse().ok(),
+            "due_date" => due_date = value.parse().ok(),
+            "is_daily" => is_daily = value.parse().unwrap_or(false),
+            "scheduled_time" => scheduled_time = Some(value),
+            "location" => location = Some(value),
+            "habit_stack_after" => habit_stack_after = Some(value),
+            "two_minute" => two_minute = value.parse().unwrap_or(false),
+            "scheduled_days" => {
+                let nums: Vec<u8> = value.split(',')
+                    .filter_map(|s| s.trim().parse().ok())
+                    .collect();
+                if !nums.is_empty() {
+                    scheduled_days = Some(nums);
+                }
+            }
+            "numeric" => numeric = value.parse().unwrap_or(false),
+            "unit" => unit = Some(value),
+            "target" => target = value.parse().ok(),
+            "tags" => tags = value.split(',').filter_map(parse_tag).collect(),
+            "parent" => parent = Some(value),
+            "recurrence" => {
+                recurrence = Some(Recurrence::parse_rrule(&value).with_context(|| format!("Invalid recurrence '{}'", value))?);
+            }
+            "recurrence_start" => recurrence_start = value.parse().ok(),
+            "recurrence_from" => recurrence_from_completion = value == "completion",
+            "recurrence_except" => recurrence_except = value.split(',').filter_map(|d| d.trim().parse().ok()).collect(),
+            "depends_on" => depends_on = value.split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect(),
+            _ => {}
+        }
+    }
+
+    if let Some(rule) = recurrence.as_mut() {
+        rule.start = recurrence_start;
+        rule.except = recurrence_except;
+        rule.from_completion = recurrence_from_completion;
+    }
+
+    Ok(Task {
+        id,
+        handle,
+        title,
+        description,
+        priority,
+        category,
+        completed,
+        created_at: created_at.context("Missing created_at")?,
+        updated_at: updated_at.context("Missing updated_at")?,
+        due_date,
+        is_daily,
+        scheduled_time,
+        location,
+        habit_stack_after,
+        two_minute,
+        scheduled_days,
+        recurrence,
+        numeric,
+        unit,
+        target,
+        tags,
+        parent,
+        depends_on,
+    })
+}
+
+// Trashed tasks are task records with one extra `deleted_at` field.
+pub fn trashed_to_text(trashed: &TrashedTask) -> String {
+    format!("{}\ndeleted_at: {}", task_to_text(&trashed.task), trashed.deleted_at.to_rfc3339())
+}
+
+pub fn text_to_trashed(text: &str) -> Result<TrashedTask> {
+    let task = text_to_task(text)?;
+    let deleted_at: DateTime<Utc> = read_fields(text)?
+        .into_iter()
+        .find(|(key, _)| *key == "deleted_at")
+        .and_then(|(_, value)| value.parse().ok())
+        .context("Missing deleted_at")?;
+    Ok(TrashedTask { task, deleted_at })
+}
+
+pub fn archived_to_text(archived: &ArchivedTask) -> String {
+    format!("{}\narchived_at: {}", task_to_text(&archived.task), archived.archived_at.to_rfc3339())
+}
+
+pub fn text_to_archived(text: &str) -> Result<ArchivedTask> {
+    let task = text_to_task(text)?;
+    let archived_at: DateTime<Utc> = read_fields(text)?
+        .into_iter()
+        .find(|(key, _)| *key == "archived_at")
+        .and_then(|(_, value)| value.parse().ok())
+        .context("Missing archived_at")?;
+    Ok(ArchivedTask { task, archived_at })
+}
+
+pub fn day_to_text(day: &Day) -> String {
+    let mut record = RecordWriter::new();
+    record.field("date", day.date);
+
+    if !day.task_ids.is_empty() {
+        record.field("tasks", day.task_ids.join(","));
+    }
+
+    if let Some(notes) = &day.notes {
+        record.field("notes", notes);
+    }
+
+    record.finish()
+}
+
+pub fn text_to_day(text: &str) -> Result<Day> {
+    let mut date = None;
+    let mut task_ids = Vec::new();
+    let mut notes = None;
+
+    for (key, value) in read_fields(text)? {
+        match key {
+            "date" => date = Some(value.parse()?),
+            "tasks" => task_ids = value.split(',').map(|s| s.to_string()).collect(),
+            "notes" => notes = Some(value),
+            _ => {}
+        }
+    }
+
+    Ok(Day {
+        date: date.context("Missing date")?,
+        task_ids,
+        notes,
+    })
+}
+
+pub fn category_to_text(category: &Category) -> String {
+    let mut record = RecordWriter::new();
+    record.field("name", &category.name);
+    if let Some(desc) = &category.description {
+        record.field("description", desc);
+    }
+    if let Some(identity) = &category.identity {
+        record.field("identity", identity);
+    }
+    record.finish()
+}
+
+pub fn text_to_category(text: &str) -> Result<Category> {
+    let mut name = String::new();
+    let mut description = None;
+    let mut identity = None;
+
+    for (key, value) in read_fields(text)? {
+        match key {
+            "name" => name = value,
+            "description" => description = Some(value),
+            "identity" => identity = Some(value),
+            _ => {}
+        }
+    }
+
+    Ok(Category { name, description, identity })
+}
+
+// `daily.log` lines: "date | task_id | title | value", with "-" as the value
+// of plain habits. Logs from before data format 3 leave the value column out
+// when there is no value, so a last column that is neither "-" nor a number
+// belongs to the title.
+pub fn daily_entry_to_line(entry: &DailyLogEntry) -> String {
+    let value = entry.value.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string());
+    format!("{} | {} | {} | {}", entry.date, entry.task_id, log_title(&entry.title), value)
+}
+
+pub fn line_to_daily_entry(line: &str) -> Option<DailyLogEntry> {
+    let mut parts = line.splitn(3, " | ");
+    let date = parts.next()?.parse().ok()?;
+    let task_id = parts.next()?.to_string();
+    let rest = parts.next()?;
+    let (title, value) = match rest.rsplit_once(" | ") {
+        Some((title, "-")) => (title, None),
+        Some((title, v)) => match v.trim().parse::<f64>() {
+            Ok(v) => (title, Some(v)),
+            Err(_) => (rest, None),
+        },
+        None => (rest, None),
+    };
+    Some(DailyLogEntry {
+        date,
+        task_id,
+        title: title.to_string(),
+        value,
+    })
+}
+
+// `history.log` lines: "YYYY-MM-DD HH:MM:SS | task_id | title"
+pub fn history_entry_to_line(entry: &HistoryEntry) -> String {
+    format!("{} | {} | {}", entry.timestamp, entry.task_id, log_title(&entry.title))
+}
+
+pub fn line_to_history_entry(line: &str) -> Option<HistoryEntry> {
+    let mut parts = line.splitn(3, " | ");
+    let timestamp = parts.next()?.to_string();
+    let task_id = parts.next()?.to_string();
+    let title = parts.next()?.to_string();
+    Some(HistoryEntry { timestamp, task_id, title })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 4, 13).unwrap()
+    }
+
+    #[test]
+    fn test_task_round_trips_multiline_and_special_characters() {
+        let t = Task::new("1".to_string(), "Café ☕ \\ \"quoted\"".to_string(), Priority::Low, "misc".to_string())
+            .with_description("first line\npriority: critical\r\nC:\\new\\table: 1".to_string());
+        let loaded = text_to_task(&task_to_text(&t)).unwrap();
+        assert_eq!(loaded.title, t.title);
+        assert_eq!(loaded.description, t.description);
+        assert_eq!(loaded.priority, Priority::Low);
+    }
+
+    #[test]
+    fn test_each_field_is_one_line() {
+        let t = Task::new("1".to_string(), "T".to_string(), Priority::Low, "misc".to_string())
+            .with_description("a\nb\nc".to_string());
+        let text = task_to_text(&t);
+        assert!(text.starts_with("format: 2\n"));
+        assert!(text.lines().any(|l| l == "description: a\\nb\\nc"));
+    }
+
+    #[test]
+    fn test_reads_v1_task_verbatim() {
+        let v1 = "id: 7\ntitle: Back\\slash\npriority: high\ncategory: work\ncompleted: false\n\
+                  created_at: 2026-04-13T09:00:00+00:00\nupdated_at: 2026-04-13T09:00:00+00:00\n\
+                  is_daily: false\ntwo_minute: false\ndescription: C:\\notes";
+        let t = text_to_task(v1).unwrap();
+        assert_eq!(t.id, "7");
+        assert_eq!(t.title, "Back\\slash");
+        assert_eq!(t.priority, Priority::High);
+        assert_eq!(t.description.as_deref(), Some("C:\\notes"));
+    }
+
+    #[test]
+    fn test_tags_round_trip() {
+        let t = Task::new("1".to_string(), "T".to_string(), Priority::Low, "misc".to_string())
+            .with_tags(vec!["work".to_string(), "home/garden".to_string()]);
+        let text = task_to_text(&t);
+        assert!(text.lines().any(|l| l == "tags: work,home/garden"));
+        assert_eq!(text_to_task(&text).unwrap().tags, t.tags);
+        assert!(!task_to_text(&Task::new("2".to_string(), "T".to_string(), Priority::Low, "misc".to_string())).contains("tags:"));
+    }
+
+    #[test]
+    fn test_parent_round_trip() {
+        let t = Task::new("2".to_string(), "Step".to_string(), Priority::Low, "misc".to_string())
+            .with_parent("1".to_string());
+        let text = task_to_text(&t);
+        assert!(text.lines().any(|l| l == "parent: 1"));
+        assert_eq!(text_to_task(&text).unwrap().parent.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn test_depends_on_round_trip() {
+        let t = Task::new("3".to_string(), "Ship".to_string(), Priority::Low, "misc".to_string())
+            .with_depends_on(vec!["1".to_string(), "2".to_string()]);
+        let text = task_to_text(&t);
+        assert!(text.lines().any(|l| l == "depends_on: 1,2"));
+        assert_eq!(text_to_task(&text).unwrap().depends_on, t.depends_on);
+    }
+
+    #[test]
+    fn test_recurrence_round_trip() {
+        let mut rule = Recurrence::parse("2 weeks on tue").unwrap();
+        rule.start = NaiveDate::from_ymd_opt(2026, 4, 14);
+        rule.except = vec![NaiveDate::from_ymd_opt(2026, 5, 12).unwrap()];
+        let t = Task::new("4".to_string(), "Yoga".to_string(), Priority::Low, "misc".to_string())
+            .with_daily(true)
+            .with_recurrence(rule);
+        let text = task_to_text(&t);
+        assert!(text.lines().any(|l| l == "recurrence: FREQ=WEEKLY;INTERVAL=2;BYDAY=TU"));
+        assert!(text.lines().any(|l| l == "recurrence_start: 2026-04-14"));
+        assert!(text.lines().any(|l| l == "recurrence_except: 2026-05-12"));
+        assert_eq!(text_to_task(&text).unwrap().recurrence, t.recurrence);
+        assert!(!text.contains("recurrence_from"));
+    }
+
+    #[test]
+    fn test_recurrence_from_completion_round_trip() {
+        let mut rule = Recurrence::parse("3 months").unwrap();
+        rule.from_completion = true;
+        let t = Task::new("5".to_string(), "Haircut".to_string(), Priority::Low, "misc".to_string())
+            .with_recurrence(rule);
+        let text = task_to_text(&t);
+        assert!(text.lines().any(|l| l == "recurrence_from: completion"));
+        assert!(text_to_task(&text).unwrap().recurrence.unwrap().from_completion);
+    }
+
+    #[test]
+    fn test_invalid_recurrence_is_an_error() {
+        let text = "id: 1\ntitle: T\ncreated_at: 2026-04-13T00:00:00Z\nupdated_at: 2026-04-13T00:00:00Z\nrecurrence: FREQ=HOURLY\n";
+        assert!(text_to_task(text).is_err());
+    }
+
+    #[test]
+    fn test_handle_round_trips_and_is_optional() {
+        let t = Task::new("0b6f1c52-4d8e-4a8e-9d65-1c2f3e4a5b6c".to_string(), "T".to_string(), Priority::Low, "misc".to_string())
+            .with_handle("12".to_string());
+        let text = task_to_text(&t);
+        assert!(text.lines().any(|l| l == "handle: 12"));
+        assert_eq!(text_to_task(&text).unwrap().handle, "12");
+        assert_eq!(text_to_task(&text.replace("handle: 12\n", "")).unwrap().handle, "");
+    }
+
+    #[test]
+    fn test_rejects_newer_format_version() {
+        let err = text_to_day("format: 99\ndate: 2026-04-13").unwrap_err().to_string();
+        assert!(err.contains("format version 99"));
+    }
+
+    #[test]
+    fn test_trashed_task_round_trip() {
+        let trashed = TrashedTask {
+            task: Task::new("4".to_string(), "Old".to_string(), Priority::High, "misc".to_string()),
+            deleted_at: "2026-04-13T09:00:00Z".parse().unwrap(),
+        };
+        let loaded = text_to_trashed(&trashed_to_text(&trashed)).unwrap();
+        assert_eq!(loaded.task.id, "4");
+        assert_eq!(loaded.deleted_at, trashed.deleted_at);
+        assert!(text_to_trashed(&task_to_text(&trashed.task)).is_err());
+    }
+
+    #[test]
+    fn test_archived_task_round_trip() {
+        let archived = ArchivedTask {
+            task: Task::new("5".to_string(), "Done".to_string(), Priority::Low, "misc".to_string()),
+            archived_at: "2026-04-13T09:00:00Z".parse().unwrap(),
+        };
+        let loaded = text_to_archived(&archived_to_text(&archived)).unwrap();
+        assert_eq!(loaded.task.title, "Done");
+        assert_eq!(loaded.archived_at, archived.archived_at);
+        assert!(text_to_archived(&task_to_text(&archived.task)).is_err());
+    }
+
+    #[test]
+    fn test_day_notes_round_trip() {
+        let mut day = Day::new(date());
+        day.add_task("1".to_string());
+        day.notes = Some("Line one\nLine two\ttabbed".to_string());
+        let loaded = text_to_day(&day_to_text(&day)).unwrap();
+        assert_eq!(loaded.notes, day.notes);
+        assert_eq!(loaded.task_ids, vec!["1"]);
+    }
+
+    #[test]
+    fn test_reads_v1_day_and_category() {
+        let day = text_to_day("date: 2026-04-13\ntasks: 1,2\nnotes: plain").unwrap();
+        assert_eq!(day.task_ids, vec!["1", "2"]);
+        assert_eq!(day.notes.as_deref(), Some("plain"));
+        let cat = text_to_category("name: fitness\nidentity: I am strong\n").unwrap();
+        assert_eq!(cat.identity.as_deref(), Some("I am strong"));
+    }
+
+    #[test]
+    fn test_category_round_trip() {
+        let c = Category::new("fitness".to_string()).with_identity("I am\nstrong".to_string());
+        let loaded = text_to_category(&category_to_text(&c)).unwrap();
+        assert_eq!(loaded.name, "fitness");
+        assert_eq!(loaded.identity, c.identity);
+    }
+
+    #[test]
+    fn test_log_lines_stay_on_one_line() {
+        let entry = DailyLogEntry { date: date(), task_id: "1".to_string(), title: "Run\nfast".to_string(), value: None };
+        let line = daily_entry_to_line(&entry);
+        assert!(!line.contains('\n'));
+        assert_eq!(line_to_daily_entry(&line).unwrap().title, "Run fast");
+    }
+
+    #[test]
+    fn test_daily_line_always_has_value_column() {
+        let plain = DailyLogEntry { date: date(), task_id: "1".to_string(), title: "Lap | 5".to_string(), value: None };
+        assert_eq!(daily_entry_to_line(&plain), "2026-04-13 | 1 | Lap | 5 | -");
+        assert_eq!(line_to_daily_entry(&daily_entry_to_line(&plain)).unwrap(), plain);
+
+        let numeric = DailyLogEntry { value: Some(2.5), ..plain };
+        assert_eq!(daily_entry_to_line(&numeric), "2026-04-13 | 1 | Lap | 5 | 2.5");
+        assert_eq!(line_to_daily_entry(&daily_entry_to_line(&numeric)).unwrap(), numeric);
+    }
+
+    #[test]
+    fn test_reads_pre_v3_daily_lines() {
+        let plain = line_to_daily_entry("2026-04-13 | 1 | Run").unwrap();
+        assert_eq!((plain.title.as_str(), plain.value), ("Run", None));
+        let numeric = line_to_daily_entry("2026-04-13 | 2 | Push ups | 25").unwrap();
+        assert_eq!((numeric.title.as_str(), numeric.value), ("Push ups", Some(25.0)));
+        let piped = line_to_daily_entry("2026-04-13 | 3 | Read | write").unwrap();
+        assert_eq!((piped.title.as_str(), piped.value), ("Read | write", None));
+        assert!(line_to_daily_entry("2026-04-13 | 1").is_none());
+    }
+
+    #[test]
+    fn test_unescape_keeps_unknown_escapes() {
+        assert_eq!(unescape("a\\tb\\"), "a\\tb\\");
+        assert_eq!(unescape(&escape("x\\n\ny")), "x\\n\ny");
+    }
+}
@@ -0,0 +1,239 @@
+use chrono::NaiveDate;
+use std::collections::{BTreeMap, HashMap};
+use super::DailyLogEntry;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct DayTotal {
+    count: u32,
+    total: f64,
+}
+
+/// Per-task, per-day view of `daily.log`, built in a single pass so that
+/// completion checks, numeric totals, streaks and grids don't have to rescan
+/// the log for every day they look at.
+#[derive(Debug, Default)]
+pub struct CompletionIndex {
+    by_task: HashMap<String, BTreeMap<NaiveDate, DayTotal>>,
+}
+
+impl CompletionIndex {
+    pub fn from_entries<'a>(entries: impl IntoIterator<Item = &'a DailyLogEntry>) -> Self {
+        let mut index = Self::default();
+        for entry in entries {
+            index.insert(entry);
+        }
+        index
+    }
+
+    pub fn insert(&mut self, entry: &DailyLogEntry) {
+        let day = self.by_task
+            .entry(entry.task_id.clone())
+            .or_default()
+            .entry(entry.date)
+            .or_default();
+        day.count += 1;
+        day.total += entry.value.unwrap_or(0.0);
+    }
+
+    fn day(&self, task_id: &str, date: NaiveDate) -> Option<&DayTotal> {
+        self.by_task.get(task_id).and_then(|days| days.get(&date))
+    }
+
+    pub fn is_completed(&self, task_id: &str, date: NaiveDate) -> bool {
+        self.day(task_id, date).is_some_and(|d| d.count > 0)
+    }
+
+    pub fn numeric_total(&self, task_id: &str, date: NaiveDate) -> f64 {
+        self.day(task_id, date).map(|d| d.total).unwrap_or(0.0)
+    }
+
+    /// Consecutive completed days ending at `as_of`.
+    pub fn streak(&self, task_id: &str, as_of: NaiveDate) -> u32 {
+        let days = match self.by_task.get(task_id) {
+            Some(days) => days,
+            None => return 0,
+        };
+        let mut streak = 0u32;
+        let mut expected = Some(as_of);
+        for (&date, _) in days.range(..=as_of).rev() {
+            if Some(date) != expected {
+                break;
+            }
+            streak += 1;
+            expected = date.pred_opt();
+        }
+        streak
+    }
+
+    /// Consecutive completed due days ending at `as_of`. Days `due` rejects
+    /// neither count nor break the streak.
+    pub fn streak_on(&self, task_id: &str, as_of: NaiveDate, due: impl Fn(NaiveDate) -> bool) -> u32 {
+        let Some(days) = self.by_task.get(task_id) else { return 0 };
+        let Some(&first) = days.keys().next() else { return 0 };
+        let mut streak = 0u32;
+        let mut date = as_of;
+        while date >= first {
+            if due(date) {
+                if !days.contains_key(&date) {
+                    break;
+                }
+                streak += 1;
+            }
+            match date.pred_opt() {
+                Some(prev) => date = prev,
+                None => break,
+            }
+        }
+        streak
+    }
+
+    /// Days with a completion from `from` to `to`, inclusive.
+    pub fn days_completed(&self, task_id: &str, from: NaiveDate, to: NaiveDate) -> u32 {
+        self.by_task.get(task_id).map_or(0, |days| days.range(from..=to).count() as u32)
+    }
+
+    /// Consecutive periods with at least `target` completed days, ending with
+    /// the one `period` puts `as_of` in. That period counts once it is met but
+    /// doesn't break the streak while it is still under way.
+    pub fn period_streak(
+        &self,
+        task_id: &str,
+        as_of: NaiveDate,
+        target: u32,
+        period: impl Fn(NaiveDate) -> (NaiveDate, NaiveDate),
+    ) -> u32 {
+        let Some(days) = self.by_task.get(task_id) else { return 0 };
+        let Some(&first) = days.keys().next() else { return 0 };
+        let met = |from: NaiveDate, to: NaiveDate| days.range(from..=to).count() as u32 >= target;
+        let (start, _) = period(as_of);
+        let mut streak = u32::from(met(start, as_of));
+        let mut before = start.pred_opt().filter(|&d| d >= first);
+        while let Some(date) = before {
+            let (start, end) = period(date);
+            if !met(start, end) {
+                break;
+            }
+            streak += 1;
+            before = start.pred_opt().filter(|&d| d >= first);
+        }
+        streak
+    }
+
+    /// Completion flags for the `days` days ending at `as_of` (oldest first).
+    pub fn grid(&self, task_id: &str, as_of: NaiveDate, days: u32) -> Vec<bool> {
+        (0..days)
+            .rev()
+            .map(|i| self.is_completed(task_id, as_of - chrono::Duration::days(i as i64)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Datelike;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn entry(task_id: &str, date: NaiveDate, value: Option<f64>) -> DailyLogEntry {
+        DailyLogEntry { date, task_id: task_id.to_string(), title: "T".to_string(), value }
+    }
+
+    #[test]
+    fn test_empty_index() {
+        let index = CompletionIndex::default();
+        let d = date(2026, 4, 13);
+        assert!(!index.is_completed("1", d));
+        assert_eq!(index.numeric_total("1", d), 0.0);
+        assert_eq!(index.streak("1", d), 0);
+        assert_eq!(index.grid("1", d, 3), vec![false, false, false]);
+    }
+
+    #[test]
+    fn test_numeric_totals_accumulate_per_day() {
+        let d = date(2026, 4, 13);
+        let entries = vec![
+            entry("1", d, Some(25.0)),
+            entry("1", d, Some(4.0)),
+            entry("1", date(2026, 4, 12), Some(10.0)),
+            entry("2", d, Some(50.0)),
+        ];
+        let index = CompletionIndex::from_entries(&entries);
+        assert_eq!(index.numeric_total("1", d), 29.0);
+        assert_eq!(index.numeric_total("2", d), 50.0);
+        assert_eq!(index.numeric_total("1", date(2026, 4, 11)), 0.0);
+    }
+
+    #[test]
+    fn test_streak_stops_at_gap() {
+        let today = date(2026, 4, 13);
+        let entries: Vec<_> = [0, 1, 2, 4]
+            .iter()
+            .map(|&i| entry("1", today - chrono::Duration::days(i), None))
+            .collect();
+        let index = CompletionIndex::from_entries(&entries);
+        assert_eq!(index.streak("1", today), 3);
+        assert_eq!(index.streak("1", today - chrono::Duration::days(3)), 0);
+        assert_eq!(index.streak("1", today - chrono::Duration::days(4)), 1);
+    }
+
+    #[test]
+    fn test_streak_ignores_future_entries() {
+        let today = date(2026, 4, 13);
+        let entries = vec![entry("1", today, None), entry("1", date(2026, 4, 14), None)];
+        let index = CompletionIndex::from_entries(&entries);
+        assert_eq!(index.streak("1", today), 1);
+    }
+
+    #[test]
+    fn test_streak_on_skips_days_not_due() {
+        // Due Mon/Wed/Fri; done on all of them for two weeks but one.
+        let monday = date(2026, 4, 13);
+        let due = |d: NaiveDate| matches!(d.weekday().num_days_from_monday(), 0 | 2 | 4);
+        let entries: Vec<_> = [0, 3, 5, 7, 12]
+            .iter()
+            .map(|&i| entry("1", monday - chrono::Duration::days(i), None))
+            .collect();
+        let index = CompletionIndex::from_entries(&entries);
+        assert_eq!(index.streak_on("1", monday, due), 4);
+        assert_eq!(index.streak_on("1", monday - chrono::Duration::days(5), due), 2);
+        assert_eq!(index.streak_on("1", monday - chrono::Duration::days(8), due), 0);
+        assert_eq!(index.streak_on("2", monday, due), 0);
+    }
+
+    #[test]
+    fn test_grid_positions() {
+        let today = date(2026, 4, 13);
+        let entries = vec![entry("1", today, None), entry("1", today - chrono::Duration::days(2), None)];
+        let index = CompletionIndex::from_entries(&entries);
+        assert_eq!(index.grid("1", today, 4), vec![false, true, false, true]);
+    }
+
+    #[test]
+    fn test_period_streak_counts_met_weeks() {
+        let week = |d: NaiveDate| {
+            let monday = d - chrono::Duration::days(d.weekday().num_days_from_monday() as i64);
+            (monday, monday + chrono::Duration::days(6))
+        };
+        // Three weeks of Mondays: 2026-03-30, 04-06, 04-13 (Mon).
+        let days = [
+            date(2026, 3, 30), date(2026, 4, 1), date(2026, 4, 3),
+            date(2026, 4, 6), date(2026, 4, 9), date(2026, 4, 11),
+            date(2026, 4, 13),
+        ];
+        let entries: Vec<_> = days.iter().map(|&d| entry("1", d, None)).collect();
+        let index = CompletionIndex::from_entries(&entries);
+        assert_eq!(index.days_completed("1", date(2026, 4, 6), date(2026, 4, 12)), 3);
+        // This week has one of three so far: it neither counts nor breaks.
+        assert_eq!(index.period_streak("1", date(2026, 4, 14), 3, week), 2);
+        assert_eq!(index.period_streak("1", date(2026, 4, 14), 1, week), 3);
+        // A week short of the target ends the streak.
+        assert_eq!(index.period_streak("1", date(2026, 4, 14), 3, |d| {
+            let (start, end) = week(d);
+            if start == date(2026, 3, 30) { (start, start) } else { (start, end) }
+        }), 1);
+        assert_eq!(index.period_streak("2", date(2026, 4, 14), 3, week), 0);
+    }
+}
@@ -0,0 +1,663 @@
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use crate::models::{Task, Day, Category};
+use super::atomic::{self, write_atomic};
+use super::crypto::{self, Cipher};
+use super::lock::{DirLock, DEFAULT_LOCK_TIMEOUT};
+use super::{ArchivedTask, CompletionIndex, DailyLogEntry, HistoryEntry, TaskStore, TrashedTask, UnreadableRecord};
+
+/// Directory holding the journal, relative to the data dir.
+pub const JOURNAL_DIR: &str = "journal";
+
+/// How many commands can be undone.
+pub const MAX_ENTRIES: usize = 100;
+
+/// One record or log line changed by a command. A missing `before` means the
+/// record was created; a missing `after` means it was deleted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    Task { before: Option<Box<Task>>, after: Option<Box<Task>> },
+    Trash { before: Option<Box<TrashedTask>>, after: Option<Box<TrashedTask>> },
+    Archive { before: Option<Box<ArchivedTask>>, after: Option<Box<ArchivedTask>> },
+    Day { before: Option<Day>, after: Option<Day> },
+    Category { before: Option<Category>, after: Option<Category> },
+    DailyLog { entry: DailyLogEntry, added: bool },
+    History { entry: HistoryEntry, added: bool },
+}
+
+impl Change {
+    /// Puts the store back the way it was before (`forward == false`) or
+    /// after (`forward == true`) this change.
+    fn apply(&self, store: &dyn TaskStore, forward: bool) -> Result<()> {
+        match self {
+            Change::Task { before, after } => match state(before, after, forward) {
+                Some(task) => store.save_task(task),
+                None => {
+                    let id = &before.as_ref().or(after.as_ref()).context("Empty task change")?.id;
+                    store.delete_task(id)
+                }
+            },
+            Change::Trash { before, after } => match state(before, after, forward) {
+                Some(trashed) => store.save_trashed(trashed),
+                None => {
+                    let id = &before.as_ref().or(after.as_ref()).context("Empty trash change")?.task.id;
+                    store.delete_trashed(id)
+                }
+            },
+            Change::Archive { before, after } => match state(before, after, forward) {
+                Some(archived) => store.save_archived(archived),
+                None => {
+                    let id = &before.as_ref().or(after.as_ref()).context("Empty archive change")?.task.id;
+                    store.delete_archived(id)
+                }
+            },
+            Change::Day { before, after } => match state(before, after, forward) {
+                Some(day) => store.save_day(day),
+                None => {
+                    let date = before.as_ref().or(after.as_ref()).context("Empty day change")?.date;
+                    store.delete_day(date)
+                }
+            },
+            Change::Category { before, after } => match state(before, after, forward) {
+                Some(category) => store.save_category(category),
+                None => {
+                    let name = &before.as_ref().or(after.as_ref()).context("Empty category change")?.name;
+                    store.delete_category(name)
+                }
+            },
+            Change::DailyLog { entry, added } => {
+                if *added == forward {
+                    store.append_daily_entry(entry)
+                } else {
+                    store.remove_daily_entry(entry)
+                }
+            }
+            Change::History { entry, added } => {
+                if *added == forward {
+                    store.append_history_entry(entry)
+                } else {
+                    store.remove_history_entry(entry)
+                }
+            }
+        }
+    }
+}
+
+/// The state a change leaves behind when applied in the given direction.
+fn state<'a, T>(before: &'a Option<T>, after: &'a Option<T>, forward: bool) -> Option<&'a T> {
+    if forward { after.as_ref() } else { before.as_ref() }
+}
+
+fn verb<T>(before: &Option<T>, after: &Option<T>) -> &'static str {
+    match (before, after) {
+        (None, _) => "added",
+        (_, None) => "deleted",
+        _ => "changed",
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Task { before, after } => {
+                let task = after.as_ref().or(before.as_ref());
+                let (id, title) = task.map(|t| (t.short_id(), t.title.as_str())).unwrap_or(("?", ""));
+                write!(f, "{} task {} '{}'", verb(before, after), id, title)
+            }
+            Change::Trash { before, after } => {
+                let task = after.as_ref().or(before.as_ref()).map(|t| &t.task);
+                let (id, title) = task.map(|t| (t.short_id(), t.title.as_str())).unwrap_or(("?", ""));
+                if after.is_some() {
+                    write!(f, "put task {} '{}' in the trash", id, title)
+                } else {
+                    write!(f, "took task {} '{}' out of the trash", id, title)
+                }
+            }
+            Change::Archive { before, after } => {
+                let task = after.as_ref().or(before.as_ref()).map(|a| &a.task);
+                let (id, title) = task.map(|t| (t.short_id(), t.title.as_str())).unwrap_or(("?", ""));
+                if after.is_some() {
+                    write!(f, "archived task {} '{}'", id, title)
+                } else {
+                    write!(f, "took task {} '{}' out of the archive", id, title)
+                }
+            }
+            Change::Day { before, after } => {
+                let date = after.as_ref().or(before.as_ref()).map(|d| d.date.to_string()).unwrap_or_default();
+                write!(f, "{} plan for {}", verb(before, after), date)
+            }
+            Change::Category { before, after } => {
+                let name = after.as_ref().or(before.as_ref()).map(|c| c.name.as_str()).unwrap_or("");
+                write!(f, "{} category '{}'", verb(before, after), name)
+            }
+            Change::DailyLog { entry, added } => {
+                let what = if *added { "logged" } else { "removed log of" };
+                write!(f, "{} '{}' on {}", what, entry.title, entry.date)
+            }
+            Change::History { entry, added } => {
+                let what = if *added { "recorded" } else { "removed" };
+                write!(f, "{} completion of '{}'", what, entry.title)
+            }
+        }
+    }
+}
+
+/// Everything one command changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub seq: u64,
+    pub timestamp: String,
+    pub command: String,
+    pub changes: Vec<Change>,
+    pub undone: bool,
+}
+
+/// Per-command undo history: one JSON file per command under `journal/`.
+/// Undone entries form the redo stack until the next command discards them.
+pub struct Journal {
+    dir: PathBuf,
+    cipher: Option<Cipher>,
+}
+
+impl Journal {
+    pub fn new(data_dir: &Path) -> Self {
+        Self { dir: data_dir.join(JOURNAL_DIR), cipher: None }
+    }
+
+    /// Encrypts entries with `cipher`, as in an encrypted data dir.
+    pub fn with_cipher(mut self, cipher: Cipher) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    fn lock(&self) -> Result<super::lock::LockGuard> {
+        fs::create_dir_all(&self.dir)?;
+        DirLock::new(&self.dir, DEFAULT_LOCK_TIMEOUT).acquire()
+    }
+
+    fn path(&self, seq: u64) -> PathBuf {
+        self.dir.join(format!("{:06}.json", seq))
+    }
+
+    fn save(&self, entry: &JournalEntry) -> Result<()> {
+        let json = serde_json::to_string_pretty(entry)?;
+        write_atomic(&self.path(entry.seq), crypto::encode(self.cipher.as_ref(), &json).as_bytes())
+    }
+
+    /// All entries, oldest first.
+    pub fn entries(&self) -> Result<Vec<JournalEntry>> {
+        let mut entries = Vec::new();
+        if let Ok(dir) = fs::read_dir(&self.dir) {
+            for file in dir.flatten() {
+                let path = file.path();
+                if atomic::is_temp_file(&path) || path.extension().is_none_or(|e| e != "json") {
+                    continue;
+                }
+                let content = crypto::decode(self.cipher.as_ref(), &fs::read_to_string(&path)?)
+                    .with_context(|| format!("Could not read journal entry {}", path.display()))?;
+                let entry: JournalEntry = serde_json::from_str(&content)
+                    .with_context(|| format!("Could not read journal entry {}", path.display()))?;
+                entries.push(entry);
+            }
+        }
+        entries.sort_by_key(|e| e.seq);
+        Ok(entries)
+    }
+
+    /// Adds an entry for `command`, dropping anything that could have been
+    /// redone and the oldest entries beyond `MAX_ENTRIES`.
+    pub fn record(&self, command: &str, changes: Vec<Change>) -> Result<u64> {
+        let _guard = self.lock()?;
+        let mut kept = Vec::new();
+        for entry in self.entries()? {
+            if entry.undone {
+                fs::remove_file(self.path(entry.seq))?;
+            } else {
+                kept.push(entry.seq);
+            }
+        }
+
+        let seq = kept.last().map_or(1, |s| s + 1);
+        self.save(&JournalEntry {
+            seq,
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            command: command.to_string(),
+            changes,
+            undone: false,
+        })?;
+
+        let excess = (kept.len() + 1).saturating_sub(MAX_ENTRIES);
+        for old in &kept[..excess] {
+            fs::remove_file(self.path(*old))?;
+        }
+        Ok(seq)
+    }
+
+    /// Reverses the most recent command that hasn't been undone.
+    pub fn undo(&self, store: &dyn TaskStore) -> Result<Option<JournalEntry>> {
+        let _guard = self.lock()?;
+        let Some(mut entry) = self.entries()?.into_iter().rev().find(|e| !e.undone) else {
+            return Ok(None);
+        };
+        for change in entry.changes.iter().rev() {
+            change.apply(store, false)?;
+        }
+        entry.undone = true;
+        self.save(&entry)?;
+        Ok(Some(entry))
+    }
+
+    /// Re-applies the most recently undone command.
+    pub fn redo(&self, store: &dyn TaskStore) -> Result<Option<JournalEntry>> {
+        let _guard = self.lock()?;
+        let Some(mut entry) = self.entries()?.into_iter().find(|e| e.undone) else {
+            return Ok(None);
+        };
+        for change in &entry.changes {
+            change.apply(store, true)?;
+        }
+        entry.undone = false;
+        self.save(&entry)?;
+        Ok(Some(entry))
+    }
+}
+
+/// Passes everything through to `inner`, noting the before and after state of
+/// every record and log line a command touches so it can go in the journal.
+pub struct Recorder<'a> {
+    inner: &'a dyn TaskStore,
+    changes: RefCell<Vec<Change>>,
+}
+
+impl<'a> Recorder<'a> {
+    pub fn new(inner: &'a dyn TaskStore) -> Self {
+        Self { inner, changes: RefCell::new(Vec::new()) }
+    }
+
+    pub fn into_changes(self) -> Vec<Change> {
+        self.changes.into_inner()
+    }
+
+    fn note(&self, change: Change) {
+        self.changes.borrow_mut().push(change);
+    }
+
+    // An empty plan is the same as no plan.
+    fn existing_day(&self, date: NaiveDate) -> Result<Option<Day>> {
+        let day = self.inner.load_day(date)?;
+        Ok(if day.task_ids.is_empty() && day.notes.is_none() { None } else { Some(day) })
+    }
+
+    fn existing_category(&self, name: &str) -> Result<Option<Category>> {
+        Ok(self.inner.list_categories()?.into_iter().find(|c| c.name == name))
+    }
+}
+
+impl TaskStore for Recorder<'_> {
+    fn save_task(&self, task: &Task) -> Result<()> {
+        let before = self.inner.load_task(&task.id).ok().map(Box::new);
+        self.inner.save_task(task)?;
+        self.note(Change::Task { before, after: Some(Box::new(task.clone())) });
+        Ok(())
+    }
+
+    fn load_task(&self, id: &str) -> Result<Task> {
+        self.inner.load_task(id)
+    }
+
+    fn delete_task(&self, id: &str) -> Result<()> {
+        let before = self.inner.load_task(id).ok().map(Box::new);
+        self.inner.delete_task(id)?;
+        if before.is_some() {
+            self.note(Change::Task { before, after: None });
+        }
+        Ok(())
+    }
+
+    fn list_all_tasks(&self) -> Result<Vec<Task>> {
+        self.inner.list_all_tasks()
+    }
+
+    fn list_tasks_by_category(&self, category: &str) -> Result<Vec<Task>> {
+        self.inner.list_tasks_by_category(category)
+    }
+
+    fn save_trashed(&self, trashed: &TrashedTask) -> Result<()> {
+        let before = self.inner.load_trashed(&trashed.task.id).ok().map(Box::new);
+        self.inner.save_trashed(trashed)?;
+        self.note(Change::Trash { before, after: Some(Box::new(trashed.clone())) });
+        Ok(())
+    }
+
+    fn load_trashed(&self, id: &str) -> Result<TrashedTask> {
+        self.inner.load_trashed(id)
+    }
+
+    fn list_trash(&self) -> Result<Vec<TrashedTask>> {
+        self.inner.list_trash()
+    }
+
+    fn delete_trashed(&self, id: &str) -> Result<()> {
+        let before = self.inner.load_trashed(id).ok().map(Box::new);
+        self.inner.delete_trashed(id)?;
+        if before.is_some() {
+            self.note(Change::Trash { before, after: None });
+        }
+        Ok(())
+    }
+
+    fn save_archived(&self, archived: &ArchivedTask) -> Result<()> {
+        let before = self.inner.load_archived(&archived.task.id).ok().map(Box::new);
+        self.inner.save_archived(archived)?;
+        self.note(Change::Archive { before, after: Some(Box::new(archived.clone())) });
+        Ok(())
+    }
+
+    fn load_archived(&self, id: &str) -> Result<ArchivedTask> {
+        self.inner.load_archived(id)
+    }
+
+    fn list_archived(&self) -> Result<Vec<ArchivedTask>> {
+        self.inner.list_archived()
+    }
+
+    fn delete_archived(&self, id: &str) -> Result<()> {
+        let before = self.inner.load_archived(id).ok().map(Box::new);
+        self.inner.delete_archived(id)?;
+        if before.is_some() {
+            self.note(Change::Archive { before, after: None });
+        }
+        Ok(())
+    }
+
+    fn save_day(&self, day: &Day) -> Result<()> {
+        let before = self.existing_day(day.date)?;
+        self.inner.save_day(day)?;
+        self.note(Change::Day { before, after: Some(day.clone()) });
+        Ok(())
+    }
+
+    fn load_day(&self, date: NaiveDate) -> Result<Day> {
+        self.inner.load_day(date)
+    }
+
+    fn list_days(&self) -> Result<Vec<Day>> {
+        self.inner.list_days()
+    }
+
+    fn delete_day(&self, date: NaiveDate) -> Result<()> {
+        let before = self.existing_day(date)?;
+        self.inner.delete_day(date)?;
+        if before.is_some() {
+            self.note(Change::Day { before, after: None });
+        }
+        Ok(())
+    }
+
+    fn save_category(&self, category: &Category) -> Result<()> {
+        let before = self.existing_category(&category.name)?;
+        self.inner.save_category(category)?;
+        self.note(Change::Category { before, after: Some(category.clone()) });
+        Ok(())
+    }
+
+    fn list_categories(&self) -> Result<Vec<Category>> {
+        self.inner.list_categories()
+    }
+
+    fn delete_category(&self, name: &str) -> Result<()> {
+        let before = self.existing_category(name)?;
+        self.inner.delete_category(name)?;
+        if before.is_some() {
+            self.note(Change::Category { before, after: None });
+        }
+        Ok(())
+    }
+
+    fn append_daily_entry(&self, entry: &DailyLogEntry) -> Result<()> {
+        self.inner.append_daily_entry(entry)?;
+        self.note(Change::DailyLog { entry: entry.clone(), added: true });
+        Ok(())
+    }
+
+    fn daily_log_entries(&self) -> Result<Vec<DailyLogEntry>> {
+        self.inner.daily_log_entries()
+    }
+
+    fn remove_daily_entry(&self, entry: &DailyLogEntry) -> Result<()> {
+        self.inner.remove_daily_entry(entry)?;
+        self.note(Change::DailyLog { entry: entry.clone(), added: false });
+        Ok(())
+    }
+
+    fn completion_index(&self) -> Result<Rc<CompletionIndex>> {
+        self.inner.completion_index()
+    }
+
+    fn get_daily_numeric_total(&self, task_id: &str, date: NaiveDate) -> Result<f64> {
+        self.inner.get_daily_numeric_total(task_id, date)
+    }
+
+    fn is_daily_completed_on_date(&self, task_id: &str, date: NaiveDate) -> Result<bool> {
+        self.inner.is_daily_completed_on_date(task_id, date)
+    }
+
+    fn append_history_entry(&self, entry: &HistoryEntry) -> Result<()> {
+        self.inner.append_history_entry(entry)?;
+        self.note(Change::History { entry: entry.clone(), added: true });
+        Ok(())
+    }
+
+    fn history_entries(&self) -> Result<Vec<HistoryEntry>> {
+        self.inner.history_entries()
+    }
+
+    fn remove_history_entry(&self, entry: &HistoryEntry) -> Result<()> {
+        self.inner.remove_history_entry(entry)?;
+        self.note(Change::History { entry: entry.clone(), added: false });
+        Ok(())
+    }
+
+    // IDs are never handed out twice, so the counter isn't journaled.
+    fn id_counter(&self) -> Result<u64> {
+        self.inner.id_counter()
+    }
+
+    fn set_id_counter(&self, next: u64) -> Result<()> {
+        self.inner.set_id_counter(next)
+    }
+
+    fn next_handle(&self) -> Result<String> {
+        self.inner.next_handle()
+    }
+
+    fn unreadable_records(&self) -> Result<Vec<UnreadableRecord>> {
+        self.inner.unreadable_records()
+    }
+
+    fn task_records(&self) -> Result<Vec<(String, Task)>> {
+        self.inner.task_records()
+    }
+
+    fn get_streak_for_task(&self, task_id: &str, as_of: NaiveDate) -> Result<u32> {
+        self.inner.get_streak_for_task(task_id, as_of)
+    }
+
+    fn get_habit_streak(&self, task: &Task, as_of: NaiveDate) -> Result<u32> {
+        self.inner.get_habit_streak(task, as_of)
+    }
+
+    fn get_period_streak(&self, task: &Task, as_of: NaiveDate, week_start: Weekday) -> Result<u32> {
+        self.inner.get_period_streak(task, as_of, week_start)
+    }
+
+    fn count_completed_days(&self, task_id: &str, from: NaiveDate, to: NaiveDate) -> Result<u32> {
+        self.inner.count_completed_days(task_id, from, to)
+    }
+
+    fn get_habit_grid(&self, task_id: &str, as_of: NaiveDate, days: u32) -> Result<Vec<bool>> {
+        self.inner.get_habit_grid(task_id, as_of, days)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Priority;
+    use crate::storage::Storage;
+    use tempfile::TempDir;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn setup() -> (TempDir, Storage, Journal) {
+        let dir = TempDir::new().unwrap();
+        let store = Storage::new(dir.path().to_path_buf()).unwrap();
+        let journal = Journal::new(dir.path());
+        (dir, store, journal)
+    }
+
+    fn task(id: &str, title: &str) -> Task {
+        Task::new(id.to_string(), title.to_string(), Priority::Medium, "default".to_string())
+    }
+
+    /// Runs `command` against a recorder and journals what it changed.
+    fn run(store: &Storage, journal: &Journal, name: &str, command: impl FnOnce(&dyn TaskStore)) {
+        let recorder = Recorder::new(store);
+        command(&recorder);
+        journal.record(name, recorder.into_changes()).unwrap();
+    }
+
+    #[test]
+    fn test_undo_and_redo_delete_all() {
+        let (_dir, store, journal) = setup();
+        run(&store, &journal, "add", |s| {
+            s.save_task(&task("1", "Read")).unwrap();
+            s.save_task(&task("2", "Write")).unwrap();
+        });
+        run(&store, &journal, "delete-all", |s| {
+            for t in s.list_all_tasks().unwrap() {
+                s.delete_task(&t.id).unwrap();
+            }
+        });
+        assert!(store.list_all_tasks().unwrap().is_empty());
+
+        let undone = journal.undo(&store).unwrap().unwrap();
+        assert_eq!(undone.command, "delete-all");
+        assert_eq!(store.list_all_tasks().unwrap().len(), 2);
+
+        let redone = journal.redo(&store).unwrap().unwrap();
+        assert_eq!(redone.command, "delete-all");
+        assert!(store.list_all_tasks().unwrap().is_empty());
+        assert!(journal.redo(&store).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_undo_restores_changed_fields_and_log_lines() {
+        let (_dir, store, journal) = setup();
+        store.save_task(&task("1", "Run").with_daily(true)).unwrap();
+        run(&store, &journal, "complete 1", |s| {
+            let mut t = s.load_task("1").unwrap();
+            t.mark_complete();
+            s.save_task(&t).unwrap();
+            s.log_daily_completion("1", "Run", date(2026, 4, 13), None).unwrap();
+            s.log_task_completion("1", "Run").unwrap();
+        });
+        assert!(store.is_daily_completed_on_date("1", date(2026, 4, 13)).unwrap());
+
+        journal.undo(&store).unwrap();
+        assert!(!store.load_task("1").unwrap().completed);
+        assert!(!store.is_daily_completed_on_date("1", date(2026, 4, 13)).unwrap());
+        assert!(store.history_entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_undo_removes_created_day_and_category() {
+        let (_dir, store, journal) = setup();
+        run(&store, &journal, "schedule", |s| {
+            let mut day = s.load_day(date(2026, 4, 13)).unwrap();
+            day.add_task("1".to_string());
+            s.save_day(&day).unwrap();
+            s.save_category(&Category::new("fitness".to_string())).unwrap();
+        });
+        journal.undo(&store).unwrap();
+        assert!(store.list_days().unwrap().is_empty());
+        assert!(store.list_categories().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_undo_trash_and_restore() {
+        let (_dir, store, journal) = setup();
+        store.save_task(&task("1", "Read")).unwrap();
+        run(&store, &journal, "delete 1", |s| { s.trash_task("1").unwrap(); });
+        assert!(store.load_task("1").is_err());
+
+        journal.undo(&store).unwrap();
+        assert_eq!(store.load_task("1").unwrap().title, "Read");
+        assert!(store.list_trash().unwrap().is_empty());
+
+        run(&store, &journal, "delete 1", |s| { s.trash_task("1").unwrap(); });
+        run(&store, &journal, "trash restore 1", |s| { s.restore_task("1").unwrap(); });
+        journal.undo(&store).unwrap();
+        assert!(store.load_task("1").is_err());
+        assert_eq!(store.list_trash().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_undo_archive() {
+        let (_dir, store, journal) = setup();
+        store.save_task(&task("1", "Taxes")).unwrap();
+        run(&store, &journal, "archive", |s| { s.archive_task("1").unwrap(); });
+        assert!(store.load_task("1").is_err());
+
+        journal.undo(&store).unwrap();
+        assert_eq!(store.load_task("1").unwrap().title, "Taxes");
+        assert!(store.list_archived().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_new_command_discards_redo() {
+        let (_dir, store, journal) = setup();
+        run(&store, &journal, "add 1", |s| s.save_task(&task("1", "A")).unwrap());
+        run(&store, &journal, "add 2", |s| s.save_task(&task("2", "B")).unwrap());
+        journal.undo(&store).unwrap();
+        run(&store, &journal, "add 3", |s| s.save_task(&task("3", "C")).unwrap());
+
+        assert!(journal.redo(&store).unwrap().is_none());
+        let commands: Vec<_> = journal.entries().unwrap().into_iter().map(|e| e.command).collect();
+        assert_eq!(commands, vec!["add 1", "add 3"]);
+    }
+
+    #[test]
+    fn test_journal_is_capped() {
+        let (_dir, store, journal) = setup();
+        for i in 0..MAX_ENTRIES + 5 {
+            run(&store, &journal, &format!("add {}", i), |s| s.save_task(&task("1", "A")).unwrap());
+        }
+        let entries = journal.entries().unwrap();
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].command, "add 5");
+    }
+
+    #[test]
+    fn test_undo_with_empty_journal() {
+        let (_dir, store, journal) = setup();
+        assert!(journal.undo(&store).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_change_descriptions() {
+        let added = Change::Task { before: None, after: Some(Box::new(task("1", "Read"))) };
+        assert_eq!(added.to_string(), "added task 1 'Read'");
+        let deleted = Change::Task { before: Some(Box::new(task("1", "Read"))), after: None };
+        assert_eq!(deleted.to_string(), "deleted task 1 'Read'");
+    }
+}
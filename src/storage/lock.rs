@@ -0,0 +1,419 @@
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, Weekday};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::models::{Task, Day, Category};
+use super::{ArchivedTask, CompletionIndex, DailyLogEntry, HistoryEntry, TaskStore, TrashedTask, UnreadableRecord};
+
+/// Lock file guarding a data directory, relative to the data dir.
+pub const LOCK_FILE: &str = ".lock";
+
+/// How long a mutating command waits for another process before giving up.
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+const RETRY_INTERVAL: Duration = Duration::from_millis(25);
+
+thread_local! {
+    /// Lock files this thread holds. A command holds its data dir's lock from
+    /// start to finish, and the store calls it makes meanwhile take it again.
+    static HELD: RefCell<HashSet<PathBuf>> = RefCell::new(HashSet::new());
+}
+
+/// Advisory, exclusive lock on a data directory. The OS drops it when the
+/// holding process exits, so a crashed command never leaves a stale lock.
+/// A thread that already holds the lock gets it again straight away.
+pub struct DirLock {
+    path: PathBuf,
+    timeout: Duration,
+}
+
+/// Held for the duration of one operation; unlocks on drop. A guard taken
+/// while the thread already held the lock leaves it to the outer guard.
+pub struct LockGuard {
+    held: Option<(File, PathBuf)>,
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        if let Some((file, path)) = self.held.take() {
+            let _ = file.unlock();
+            HELD.with(|held| held.borrow_mut().remove(&path));
+        }
+    }
+}
+
+impl DirLock {
+    pub fn new(data_dir: &Path, timeout: Duration) -> Self {
+        Self { path: data_dir.join(LOCK_FILE), timeout }
+    }
+
+    pub fn acquire(&self) -> Result<LockGuard> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(&self.path)
+            .with_context(|| format!("Could not open lock file {}", self.path.display()))?;
+        let key = fs::canonicalize(&self.path).unwrap_or_else(|_| self.path.clone());
+        if HELD.with(|held| held.borrow().contains(&key)) {
+            return Ok(LockGuard { held: None });
+        }
+
+        let started = Instant::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => break,
+                Err(TryLockError::WouldBlock) => {
+                    if started.elapsed() >= self.timeout {
+                        let holder = read_holder(&mut file)
+                            .map(|pid| format!(" (held by process {})", pid))
+                            .unwrap_or_default();
+                        anyhow::bail!(
+                            "Data directory is locked by another daily command{}; gave up after {:.1}s. \
+                             Try again once it has finished.",
+                            holder,
+                            self.timeout.as_secs_f64()
+                        );
+                    }
+                    thread::sleep(RETRY_INTERVAL);
+                }
+                Err(TryLockError::Error(e)) => {
+                    return Err(e).with_context(|| format!("Could not lock {}", self.path.display()));
+                }
+            }
+        }
+
+        // Record who holds the lock so a blocked command can say so.
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        write!(file, "{}", std::process::id())?;
+
+        HELD.with(|held| held.borrow_mut().insert(key.clone()));
+        Ok(LockGuard { held: Some((file, key)) })
+    }
+}
+
+fn read_holder(file: &mut File) -> Option<u32> {
+    let mut content = String::new();
+    file.seek(SeekFrom::Start(0)).ok()?;
+    file.read_to_string(&mut content).ok()?;
+    content.trim().parse().ok()
+}
+
+/// Wraps any backend so that every mutating call holds the data directory
+/// lock. Reads are not locked: writes are atomic, so readers always see
+/// complete files. This only keeps single writes apart; code that loads,
+/// changes and saves a record holds a `DirLock` around all of it, as every
+/// `daily` command does.
+pub struct Locked {
+    inner: Box<dyn TaskStore>,
+    lock: DirLock,
+}
+
+impl Locked {
+    pub fn new(inner: Box<dyn TaskStore>, lock: DirLock) -> Self {
+        Self { inner, lock }
+    }
+}
+
+impl TaskStore for Locked {
+    fn save_task(&self, task: &Task) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.save_task(task)
+    }
+
+    fn load_task(&self, id: &str) -> Result<Task> {
+        self.inner.load_task(id)
+    }
+
+    fn delete_task(&self, id: &str) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.delete_task(id)
+    }
+
+    fn list_all_tasks(&self) -> Result<Vec<Task>> {
+        self.inner.list_all_tasks()
+    }
+
+    fn list_tasks_by_category(&self, category: &str) -> Result<Vec<Task>> {
+        self.inner.list_tasks_by_category(category)
+    }
+
+    fn save_trashed(&self, trashed: &TrashedTask) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.save_trashed(trashed)
+    }
+
+    fn load_trashed(&self, id: &str) -> Result<TrashedTask> {
+        self.inner.load_trashed(id)
+    }
+
+    fn list_trash(&self) -> Result<Vec<TrashedTask>> {
+        self.inner.list_trash()
+    }
+
+    fn delete_trashed(&self, id: &str) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.delete_trashed(id)
+    }
+
+    fn save_archived(&self, archived: &ArchivedTask) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.save_archived(archived)
+    }
+
+    fn load_archived(&self, id: &str) -> Result<ArchivedTask> {
+        self.inner.load_archived(id)
+    }
+
+    fn list_archived(&self) -> Result<Vec<ArchivedTask>> {
+        self.inner.list_archived()
+    }
+
+    fn delete_archived(&self, id: &str) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.delete_archived(id)
+    }
+
+    fn save_day(&self, day: &Day) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.save_day(day)
+    }
+
+    fn load_day(&self, date: NaiveDate) -> Result<Day> {
+        self.inner.load_day(date)
+    }
+
+    fn list_days(&self) -> Result<Vec<Day>> {
+        self.inner.list_days()
+    }
+
+    fn delete_day(&self, date: NaiveDate) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.delete_day(date)
+    }
+
+    fn save_category(&self, category: &Category) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.save_category(category)
+    }
+
+    fn list_categories(&self) -> Result<Vec<Category>> {
+        self.inner.list_categories()
+    }
+
+    fn delete_category(&self, name: &str) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.delete_category(name)
+    }
+
+    fn append_daily_entry(&self, entry: &DailyLogEntry) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.append_daily_entry(entry)
+    }
+
+    fn daily_log_entries(&self) -> Result<Vec<DailyLogEntry>> {
+        self.inner.daily_log_entries()
+    }
+
+    fn remove_daily_entry(&self, entry: &DailyLogEntry) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.remove_daily_entry(entry)
+    }
+
+    fn log_daily_completion(&self, task_id: &str, task_title: &str, date: NaiveDate, value: Option<f64>) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.log_daily_completion(task_id, task_title, date, value)
+    }
+
+    fn completion_index(&self) -> Result<Rc<CompletionIndex>> {
+        self.inner.completion_index()
+    }
+
+    fn get_daily_numeric_total(&self, task_id: &str, date: NaiveDate) -> Result<f64> {
+        self.inner.get_daily_numeric_total(task_id, date)
+    }
+
+    fn is_daily_completed_on_date(&self, task_id: &str, date: NaiveDate) -> Result<bool> {
+        self.inner.is_daily_completed_on_date(task_id, date)
+    }
+
+    fn append_history_entry(&self, entry: &HistoryEntry) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.append_history_entry(entry)
+    }
+
+    fn history_entries(&self) -> Result<Vec<HistoryEntry>> {
+        self.inner.history_entries()
+    }
+
+    fn remove_history_entry(&self, entry: &HistoryEntry) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.remove_history_entry(entry)
+    }
+
+    fn log_task_completion(&self, task_id: &str, task_title: &str) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.log_task_completion(task_id, task_title)
+    }
+
+    fn rename_log_task_ids(&self, ids: &HashMap<String, String>) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.rename_log_task_ids(ids)
+    }
+
+    fn id_counter(&self) -> Result<u64> {
+        self.inner.id_counter()
+    }
+
+    fn set_id_counter(&self, next: u64) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        self.inner.set_id_counter(next)
+    }
+
+    // Read-then-write: the whole allocation must happen under one lock.
+    fn next_handle(&self) -> Result<String> {
+        let _guard = self.lock.acquire()?;
+        self.inner.next_handle()
+    }
+
+    fn unreadable_records(&self) -> Result<Vec<UnreadableRecord>> {
+        self.inner.unreadable_records()
+    }
+
+    fn task_records(&self) -> Result<Vec<(String, Task)>> {
+        self.inner.task_records()
+    }
+
+    fn get_streak_for_task(&self, task_id: &str, as_of: NaiveDate) -> Result<u32> {
+        self.inner.get_streak_for_task(task_id, as_of)
+    }
+
+    fn get_habit_streak(&self, task: &Task, as_of: NaiveDate) -> Result<u32> {
+        self.inner.get_habit_streak(task, as_of)
+    }
+
+    fn get_period_streak(&self, task: &Task, as_of: NaiveDate, week_start: Weekday) -> Result<u32> {
+        self.inner.get_period_streak(task, as_of, week_start)
+    }
+
+    fn count_completed_days(&self, task_id: &str, from: NaiveDate, to: NaiveDate) -> Result<u32> {
+        self.inner.count_completed_days(task_id, from, to)
+    }
+
+    fn get_habit_grid(&self, task_id: &str, as_of: NaiveDate, days: u32) -> Result<Vec<bool>> {
+        self.inner.get_habit_grid(task_id, as_of, days)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+    use std::sync::mpsc;
+    use tempfile::TempDir;
+
+    fn locked_store(dir: &Path) -> Locked {
+        Locked::new(
+            Box::new(Storage::new(dir.to_path_buf()).unwrap()),
+            DirLock::new(dir, DEFAULT_LOCK_TIMEOUT),
+        )
+    }
+
+    #[test]
+    fn test_acquire_and_release() {
+        let dir = TempDir::new().unwrap();
+        let lock = DirLock::new(dir.path(), Duration::from_millis(100));
+        drop(lock.acquire().unwrap());
+        lock.acquire().unwrap();
+    }
+
+    /// Holds the lock on `dir` from another thread until the returned sender
+    /// is dropped.
+    fn hold_elsewhere(dir: &Path) -> mpsc::Sender<()> {
+        let (held_tx, held_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let dir = dir.to_path_buf();
+        thread::spawn(move || {
+            let _guard = DirLock::new(&dir, DEFAULT_LOCK_TIMEOUT).acquire().unwrap();
+            held_tx.send(()).unwrap();
+            let _ = release_rx.recv();
+        });
+        held_rx.recv().unwrap();
+        release_tx
+    }
+
+    fn try_elsewhere(dir: &Path) -> bool {
+        let dir = dir.to_path_buf();
+        thread::spawn(move || DirLock::new(&dir, Duration::from_millis(50)).acquire().is_ok()).join().unwrap()
+    }
+
+    #[test]
+    fn test_acquire_times_out_when_held() {
+        let dir = TempDir::new().unwrap();
+        let _held = hold_elsewhere(dir.path());
+        let err = DirLock::new(dir.path(), Duration::from_millis(100))
+            .acquire()
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("locked by another daily command"));
+        assert!(err.contains(&std::process::id().to_string()));
+    }
+
+    #[test]
+    fn test_mutation_waits_for_lock_then_fails() {
+        let dir = TempDir::new().unwrap();
+        let store = Locked::new(
+            Box::new(Storage::new(dir.path().to_path_buf()).unwrap()),
+            DirLock::new(dir.path(), Duration::from_millis(50)),
+        );
+        let _held = hold_elsewhere(dir.path());
+        assert!(store.next_handle().is_err());
+        // Reads don't need the lock.
+        assert!(store.list_all_tasks().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_holder_can_take_the_lock_again() {
+        let dir = TempDir::new().unwrap();
+        let outer = DirLock::new(dir.path(), DEFAULT_LOCK_TIMEOUT).acquire().unwrap();
+        // A command holding the lock can still save through a locked store.
+        let store = locked_store(dir.path());
+        store.save_task(&Task::new("1".into(), "Read".into(), crate::models::Priority::Medium, "default".into())).unwrap();
+        drop(DirLock::new(dir.path(), Duration::from_millis(50)).acquire().unwrap());
+
+        // The inner guards didn't let go of it.
+        assert!(!try_elsewhere(dir.path()));
+        drop(outer);
+        assert!(try_elsewhere(dir.path()));
+    }
+
+    #[test]
+    fn test_concurrent_id_allocation_is_unique() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().to_path_buf();
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    let store = locked_store(&path);
+                    (0..25).map(|_| store.next_handle().unwrap()).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let ids: Vec<String> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+        let unique: HashSet<_> = ids.iter().collect();
+        assert_eq!(ids.len(), 200);
+        assert_eq!(unique.len(), 200);
+        assert_eq!(locked_store(&path).id_counter().unwrap(), 201);
+    }
+}
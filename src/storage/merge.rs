@@ -0,0 +1,379 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use crate::models::Task;
+use super::format::{daily_entry_to_line, history_entry_to_line, task_to_text};
+use super::{ArchivedTask, TaskStore, TrashedTask};
+
+/// What happened to one task from the other data dir, named by its handle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskMerge {
+    /// Copied over with its own handle.
+    Added { handle: String, title: String },
+    /// The same task existed here; the other copy was newer.
+    Updated { handle: String, title: String },
+    /// Copied over with a new handle because its handle was taken by a
+    /// different task.
+    Renumbered { from: String, to: String, title: String },
+}
+
+impl fmt::Display for TaskMerge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskMerge::Added { handle, title } => write!(f, "added task {} '{}'", handle, title),
+            TaskMerge::Updated { handle, title } => {
+                write!(f, "updated task {} '{}' from the newer copy", handle, title)
+            }
+            TaskMerge::Renumbered { from, to, title } => {
+                write!(f, "added task {} '{}' as {} (its ID was taken)", from, title, to)
+            }
+        }
+    }
+}
+
+/// Everything `merge` changed.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    pub tasks: Vec<TaskMerge>,
+    pub days: usize,
+    pub categories: usize,
+    pub daily_entries: usize,
+    pub history_entries: usize,
+    /// Log lines both dirs already had.
+    pub duplicates: usize,
+}
+
+/// A task wherever it is kept: active, in the trash or archived.
+#[derive(Clone)]
+pub(super) enum Stored {
+    Active(Task),
+    Trashed(TrashedTask),
+    Archived(ArchivedTask),
+}
+
+impl Stored {
+    pub(super) fn task(&self) -> &Task {
+        match self {
+            Stored::Active(task) => task,
+            Stored::Trashed(trashed) => &trashed.task,
+            Stored::Archived(archived) => &archived.task,
+        }
+    }
+
+    pub(super) fn task_mut(&mut self) -> &mut Task {
+        match self {
+            Stored::Active(task) => task,
+            Stored::Trashed(trashed) => &mut trashed.task,
+            Stored::Archived(archived) => &mut archived.task,
+        }
+    }
+
+    /// When this copy was last changed, including moving it to the trash or archive.
+    fn touched(&self) -> DateTime<Utc> {
+        match self {
+            Stored::Active(task) => task.updated_at,
+            Stored::Trashed(trashed) => trashed.deleted_at.max(trashed.task.updated_at),
+            Stored::Archived(archived) => archived.archived_at.max(archived.task.updated_at),
+        }
+    }
+
+    fn same_as(&self, other: &Stored) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+            && task_to_text(self.task()) == task_to_text(other.task())
+    }
+
+    pub(super) fn load_all(store: &dyn TaskStore) -> Result<Vec<Stored>> {
+        let mut all: Vec<Stored> = store.list_all_tasks()?.into_iter().map(Stored::Active).collect();
+        all.extend(store.list_trash()?.into_iter().map(Stored::Trashed));
+        all.extend(store.list_archived()?.into_iter().map(Stored::Archived));
+        all.sort_by_key(|s| s.task().created_at);
+        Ok(all)
+    }
+
+    pub(super) fn save(&self, store: &dyn TaskStore) -> Result<()> {
+        match self {
+            Stored::Active(task) => store.save_task(task),
+            Stored::Trashed(trashed) => store.save_trashed(trashed),
+            Stored::Archived(archived) => store.save_archived(archived),
+        }
+    }
+
+    pub(super) fn remove(&self, store: &dyn TaskStore) -> Result<()> {
+        let id = &self.task().id;
+        match self {
+            Stored::Active(_) => store.delete_task(id),
+            Stored::Trashed(_) => store.delete_trashed(id),
+            Stored::Archived(_) => store.delete_archived(id),
+        }
+    }
+}
+
+/// Brings everything from `other` into `store`. Tasks are the same task when
+/// their UUIDs match, or failing that their `created_at` (the same data
+/// upgraded separately on two machines), and the most recently changed copy
+/// wins. A different task whose handle is taken here gets a new handle. Day
+/// plans, habit stacks and log entries follow tasks matched by `created_at`.
+/// Log lines `store` already has are skipped, so merging twice changes
+/// nothing.
+pub fn merge(store: &dyn TaskStore, other: &dyn TaskStore) -> Result<MergeReport> {
+    let mut report = MergeReport::default();
+    let local = Stored::load_all(store)?;
+    let by_id: HashMap<&str, &Stored> = local.iter().map(|s| (s.task().id.as_str(), s)).collect();
+    let by_created: HashMap<DateTime<Utc>, &Stored> = local.iter().map(|s| (s.task().created_at, s)).collect();
+    let mut handles: HashSet<String> = local.iter().map(|s| s.task().handle.clone()).collect();
+    let mut next = store.id_counter()?.max(other.id_counter()?);
+
+    let incoming = Stored::load_all(other)?;
+    let matched: Vec<Option<&Stored>> = incoming.iter()
+        .map(|copy| by_id.get(copy.task().id.as_str()).or_else(|| by_created.get(&copy.task().created_at)).copied())
+        .collect();
+    let ids: HashMap<String, String> = incoming.iter().zip(&matched)
+        .filter_map(|(copy, existing)| existing.map(|e| (copy.task().id.clone(), e.task().id.clone())))
+        .collect();
+    let map = |id: &str| ids.get(id).cloned().unwrap_or_else(|| id.to_string());
+
+    for (mut copy, existing) in incoming.into_iter().zip(matched) {
+        let task = copy.task_mut();
+        task.id = map(&task.id);
+        task.habit_stack_after = task.habit_stack_after.as_deref().map(map);
+        task.parent = task.parent.as_deref().map(map);
+        task.depends_on = task.depends_on.iter().map(|id| map(id)).collect();
+        match existing {
+            Some(existing) => {
+                // The handle people know the task by here stays.
+                task.handle = existing.task().handle.clone();
+                let title = task.title.clone();
+                if copy.touched() > existing.touched() && !copy.same_as(existing) {
+                    existing.remove(store)?;
+                    copy.save(store)?;
+                    report.tasks.push(TaskMerge::Updated { handle: copy.task().handle.clone(), title });
+                }
+            }
+            None => {
+                let from = task.handle.clone();
+                if from.is_empty() || handles.contains(&from) {
+                    while handles.contains(&next.to_string()) {
+                        next += 1;
+                    }
+                    task.handle = next.to_string();
+                }
+                handles.insert(task.handle.clone());
+                let (handle, title) = (task.handle.clone(), task.title.clone());
+                copy.save(store)?;
+                report.tasks.push(if handle == from {
+                    TaskMerge::Added { handle, title }
+                } else {
+                    TaskMerge::Renumbered { from, to: handle, title }
+                });
+            }
+        }
+    }
+
+    for day in other.list_days()? {
+        let mut merged = store.load_day(day.date)?;
+        let (before_ids, before_notes) = (merged.task_ids.clone(), merged.notes.clone());
+        for id in day.task_ids.iter().map(|id| map(id)) {
+            if !merged.task_ids.contains(&id) {
+                merged.task_ids.push(id);
+            }
+        }
+        if merged.notes.is_none() {
+            merged.notes = day.notes.clone();
+        }
+        if merged.task_ids != before_ids || merged.notes != before_notes {
+            store.save_day(&merged)?;
+            report.days += 1;
+        }
+    }
+
+    let names: HashSet<String> = store.list_categories()?.into_iter().map(|c| c.name).collect();
+    for category in other.list_categories()? {
+        if !names.contains(&category.name) {
+            store.save_category(&category)?;
+            report.categories += 1;
+        }
+    }
+
+    // Counted rather than collected into a set, so a habit logged twice on
+    // the same day in one dir still counts twice.
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for entry in store.daily_log_entries()? {
+        *seen.entry(daily_entry_to_line(&entry)).or_default() += 1;
+    }
+    for mut entry in other.daily_log_entries()? {
+        entry.task_id = map(&entry.task_id);
+        match seen.get_mut(&daily_entry_to_line(&entry)) {
+            Some(n) if *n > 0 => {
+                *n -= 1;
+                report.duplicates += 1;
+            }
+            _ => {
+                store.append_daily_entry(&entry)?;
+                report.daily_entries += 1;
+            }
+        }
+    }
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for entry in store.history_entries()? {
+        *seen.entry(history_entry_to_line(&entry)).or_default() += 1;
+    }
+    for mut entry in other.history_entries()? {
+        entry.task_id = map(&entry.task_id);
+        match seen.get_mut(&history_entry_to_line(&entry)) {
+            Some(n) if *n > 0 => {
+                *n -= 1;
+                report.duplicates += 1;
+            }
+            _ => {
+                store.append_history_entry(&entry)?;
+                report.history_entries += 1;
+            }
+        }
+    }
+
+    while handles.contains(&next.to_string()) {
+        next += 1;
+    }
+    if next > store.id_counter()? {
+        store.set_id_counter(next)?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Category, Day, Priority};
+    use crate::storage::Storage;
+    use chrono::{Duration, NaiveDate};
+    use tempfile::TempDir;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn store() -> (TempDir, Storage) {
+        let dir = TempDir::new().unwrap();
+        let store = Storage::new(dir.path().to_path_buf()).unwrap();
+        (dir, store)
+    }
+
+    fn add(store: &Storage, title: &str) -> Task {
+        let id = uuid::Uuid::new_v4().to_string();
+        let task = Task::new(id, title.to_string(), Priority::Medium, "default".to_string())
+            .with_handle(store.next_handle().unwrap());
+        store.save_task(&task).unwrap();
+        task
+    }
+
+    fn by_title(store: &Storage, title: &str) -> Task {
+        store.list_all_tasks().unwrap().into_iter().find(|t| t.title == title).unwrap()
+    }
+
+    /// Two dirs that share task 1 and its log, then each added a task 2.
+    fn diverged() -> ((TempDir, Storage), (TempDir, Storage)) {
+        let (here_dir, here) = store();
+        let shared = add(&here, "Read").with_daily(true);
+        here.save_task(&shared).unwrap();
+        here.log_daily_completion(&shared.id, "Read", date(2026, 4, 12), None).unwrap();
+
+        let (there_dir, there) = store();
+        there.save_task(&shared).unwrap();
+        there.set_id_counter(2).unwrap();
+        there.log_daily_completion(&shared.id, "Read", date(2026, 4, 12), None).unwrap();
+
+        add(&here, "Write here");
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let stacked = add(&there, "Stretch there").with_habit_stack_after(shared.id.clone());
+        there.save_task(&stacked).unwrap();
+        let later = add(&there, "Walk there").with_habit_stack_after(stacked.id.clone());
+        there.save_task(&later).unwrap();
+        there.log_daily_completion(&stacked.id, "Stretch there", date(2026, 4, 13), None).unwrap();
+        there.log_task_completion(&later.id, "Walk there").unwrap();
+        let mut day = Day::new(date(2026, 4, 13));
+        day.add_task(stacked.id.clone());
+        there.save_day(&day).unwrap();
+        there.save_category(&Category::new("health".to_string())).unwrap();
+
+        ((here_dir, here), (there_dir, there))
+    }
+
+    #[test]
+    fn test_colliding_handles_are_renumbered() {
+        let ((_h, here), (_t, there)) = diverged();
+        let report = merge(&here, &there).unwrap();
+
+        assert_eq!(report.tasks, vec![
+            TaskMerge::Renumbered { from: "2".to_string(), to: "4".to_string(), title: "Stretch there".to_string() },
+            TaskMerge::Added { handle: "3".to_string(), title: "Walk there".to_string() },
+        ]);
+        let (read, stretch, walk) = (by_title(&here, "Read"), by_title(&here, "Stretch there"), by_title(&here, "Walk there"));
+        assert_eq!(by_title(&here, "Write here").handle, "2");
+        assert_eq!(stretch.handle, "4");
+        assert_eq!(stretch.id, by_title(&there, "Stretch there").id);
+        assert_eq!(stretch.habit_stack_after.as_deref(), Some(read.id.as_str()));
+        assert_eq!(walk.habit_stack_after.as_deref(), Some(stretch.id.as_str()));
+        assert_eq!(here.load_day(date(2026, 4, 13)).unwrap().task_ids, vec![stretch.id.clone()]);
+        assert!(here.is_daily_completed_on_date(&stretch.id, date(2026, 4, 13)).unwrap());
+        assert_eq!(here.history_entries().unwrap()[0].task_id, walk.id);
+        assert_eq!(report.daily_entries, 1);
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(report.categories, 1);
+        assert_eq!(here.id_counter().unwrap(), 5);
+    }
+
+    #[test]
+    fn test_same_task_with_different_ids_is_matched_by_created_at() {
+        let ((_h, here), (_t, there)) = diverged();
+        // Upgraded separately, the shared task got a different UUID there.
+        let mut read = by_title(&there, "Read");
+        let old_id = read.id.clone();
+        there.delete_task(&old_id).unwrap();
+        read.id = uuid::Uuid::new_v4().to_string();
+        read.title = "Read more".to_string();
+        read.updated_at += Duration::seconds(5);
+        there.save_task(&read).unwrap();
+        let mut stretch = by_title(&there, "Stretch there");
+        stretch.habit_stack_after = Some(read.id.clone());
+        there.save_task(&stretch).unwrap();
+
+        let report = merge(&here, &there).unwrap();
+        assert!(report.tasks.contains(&TaskMerge::Updated { handle: "1".to_string(), title: "Read more".to_string() }));
+        let merged = by_title(&here, "Read more");
+        assert_eq!(merged.id, old_id);
+        assert_eq!(by_title(&here, "Stretch there").habit_stack_after, Some(old_id));
+        assert_eq!(here.list_all_tasks().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_merging_twice_changes_nothing() {
+        let ((_h, here), (_t, there)) = diverged();
+        merge(&here, &there).unwrap();
+        let again = merge(&here, &there).unwrap();
+        assert!(again.tasks.is_empty());
+        assert_eq!((again.days, again.categories, again.daily_entries, again.history_entries), (0, 0, 0, 0));
+        assert_eq!(here.list_all_tasks().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_newest_copy_of_the_same_task_wins() {
+        let ((_h, here), (_t, there)) = diverged();
+        let mut renamed = by_title(&there, "Read");
+        renamed.title = "Read more".to_string();
+        renamed.updated_at += Duration::seconds(5);
+        there.save_task(&renamed).unwrap();
+        let walk = by_title(&there, "Walk there");
+        there.trash_task(&walk.id).unwrap();
+
+        let report = merge(&here, &there).unwrap();
+        assert!(report.tasks.contains(&TaskMerge::Updated { handle: "1".to_string(), title: "Read more".to_string() }));
+        assert_eq!(here.load_task(&renamed.id).unwrap().title, "Read more");
+        assert_eq!(here.load_trashed(&walk.id).unwrap().task.title, "Walk there");
+
+        // The older copy doesn't overwrite the newer one on the way back.
+        let back = merge(&there, &here).unwrap();
+        assert!(!back.tasks.iter().any(|t| matches!(t, TaskMerge::Updated { .. })));
+    }
+}
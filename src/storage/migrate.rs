@@ -0,0 +1,238 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use std::fs;
+use std::path::{Path, PathBuf};
+use super::text::TEXT_LAYOUT;
+use super::lock::DEFAULT_LOCK_TIMEOUT;
+use super::{open_sqlite, Backend, DirLock, Storage, TaskStore, SQLITE_FILE};
+
+/// Counts of everything copied between two stores.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub tasks: usize,
+    pub trashed: usize,
+    pub archived: usize,
+    pub days: usize,
+    pub categories: usize,
+    pub daily_entries: usize,
+    pub history_entries: usize,
+    pub id_counter: u64,
+}
+
+/// Copies every task, day, category, log entry and the ID counter from `from`
+/// into `to`. Log entries are appended in their original order, so `to` should
+/// start out empty.
+pub fn copy_store(from: &dyn TaskStore, to: &dyn TaskStore) -> Result<MigrationReport> {
+    let mut report = MigrationReport::default();
+
+    for task in from.list_all_tasks()? {
+        to.save_task(&task)?;
+        report.tasks += 1;
+    }
+    for trashed in from.list_trash()? {
+        to.save_trashed(&trashed)?;
+        report.trashed += 1;
+    }
+    for archived in from.list_archived()? {
+        to.save_archived(&archived)?;
+        report.archived += 1;
+    }
+    for day in from.list_days()? {
+        to.save_day(&day)?;
+        report.days += 1;
+    }
+    for category in from.list_categories()? {
+        to.save_category(&category)?;
+        report.categories += 1;
+    }
+    for entry in from.daily_log_entries()? {
+        to.append_daily_entry(&entry)?;
+        report.daily_entries += 1;
+    }
+    for entry in from.history_entries()? {
+        to.append_history_entry(&entry)?;
+        report.history_entries += 1;
+    }
+    report.id_counter = from.id_counter()?;
+    to.set_id_counter(report.id_counter)?;
+
+    Ok(report)
+}
+
+/// Converts the data directory to `target`. The previous layout is moved into
+/// a `backup-<timestamp>/` directory rather than deleted. The data directory
+/// stays locked for the whole conversion.
+pub fn migrate(data_dir: &Path, target: Backend) -> Result<(MigrationReport, PathBuf)> {
+    super::upgrade::ensure_current(data_dir, None)?;
+    let _guard = DirLock::new(data_dir, DEFAULT_LOCK_TIMEOUT).acquire()?;
+    if super::crypto::is_encrypted(data_dir) {
+        anyhow::bail!("{} is encrypted. Run `daily decrypt` before migrating it.", data_dir.display());
+    }
+    let current = Backend::detect(data_dir);
+    if current == target {
+        anyhow::bail!("Data directory already uses the {} backend", target);
+    }
+
+    let backup_dir = data_dir.join(format!("backup-{}", Local::now().format("%Y%m%d%H%M%S")));
+
+    let report = match target {
+        Backend::Sqlite => to_sqlite(data_dir, &backup_dir)?,
+        Backend::Text => to_text(data_dir, &backup_dir)?,
+    };
+
+    Ok((report, backup_dir))
+}
+
+fn to_sqlite(data_dir: &Path, backup_dir: &Path) -> Result<MigrationReport> {
+    let tmp_db = data_dir.join(format!("{}.tmp", SQLITE_FILE));
+    if tmp_db.exists() {
+        fs::remove_file(&tmp_db)?;
+    }
+
+    let report = {
+        let from = Storage::new(data_dir.to_path_buf())?;
+        let to = open_sqlite(&tmp_db)?;
+        copy_store(&from, to.as_ref())?
+    };
+
+    move_text_layout(data_dir, backup_dir)?;
+    fs::rename(&tmp_db, data_dir.join(SQLITE_FILE))?;
+    Ok(report)
+}
+
+fn to_text(data_dir: &Path, backup_dir: &Path) -> Result<MigrationReport> {
+    let tmp_dir = data_dir.join("text.tmp");
+    if tmp_dir.exists() {
+        fs::remove_dir_all(&tmp_dir)?;
+    }
+
+    let report = {
+        let from = open_sqlite(&data_dir.join(SQLITE_FILE))?;
+        let to = Storage::new(tmp_dir.clone())?;
+        copy_store(from.as_ref(), &to)?
+    };
+
+    move_text_layout(data_dir, backup_dir)?;
+    fs::rename(data_dir.join(SQLITE_FILE), backup_dir.join(SQLITE_FILE))?;
+    move_text_layout(&tmp_dir, data_dir)?;
+    fs::remove_dir_all(&tmp_dir)?;
+    Ok(report)
+}
+
+/// Moves every text-layout file or directory present in `from` into `to`.
+fn move_text_layout(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for name in TEXT_LAYOUT {
+        let src = from.join(name);
+        if src.exists() {
+            fs::rename(&src, to.join(name))
+                .with_context(|| format!("Could not move {}", src.display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Category, Day, Priority, Task};
+    use chrono::NaiveDate;
+    use tempfile::TempDir;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// Fills `store` and returns the ID of its numeric habit.
+    fn populate(store: &dyn TaskStore) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        let t = Task::new(id.clone(), "Push ups".to_string(), Priority::High, "fitness".to_string())
+            .with_handle(store.next_handle().unwrap())
+            .with_daily(true)
+            .with_numeric(true)
+            .with_unit("reps".to_string())
+            .with_target(100.0)
+            .with_scheduled_days(vec![0, 2, 4]);
+        store.save_task(&t).unwrap();
+        let other = uuid::Uuid::new_v4().to_string();
+        store.save_task(&Task::new(other.clone(), "Taxes".to_string(), Priority::Critical, "admin".to_string())
+            .with_handle(store.next_handle().unwrap())).unwrap();
+
+        let mut day = Day::new(date(2026, 4, 13));
+        day.add_task(other.clone());
+        store.save_day(&day).unwrap();
+        store.save_category(&Category::new("fitness".to_string()).with_identity("I am strong".to_string())).unwrap();
+
+        store.log_daily_completion(&id, "Push ups", date(2026, 4, 12), Some(25.0)).unwrap();
+        store.log_daily_completion(&id, "Push ups", date(2026, 4, 13), Some(4.5)).unwrap();
+        store.log_task_completion(&other, "Taxes").unwrap();
+        id
+    }
+
+    fn file_snapshot(dir: &Path) -> Vec<(String, String)> {
+        let mut files = Vec::new();
+        for name in TEXT_LAYOUT {
+            let path = dir.join(name);
+            if path.is_dir() {
+                for entry in fs::read_dir(&path).unwrap().flatten() {
+                    let rel = format!("{}/{}", name, entry.file_name().to_string_lossy());
+                    files.push((rel, fs::read_to_string(entry.path()).unwrap()));
+                }
+            } else if path.exists() {
+                files.push((name.to_string(), fs::read_to_string(&path).unwrap()));
+            }
+        }
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_copy_store_text_to_text() {
+        let a = TempDir::new().unwrap();
+        let b = TempDir::new().unwrap();
+        let from = Storage::new(a.path().to_path_buf()).unwrap();
+        let to = Storage::new(b.path().to_path_buf()).unwrap();
+        populate(&from);
+
+        let report = copy_store(&from, &to).unwrap();
+        assert_eq!(report.tasks, 2);
+        assert_eq!(report.days, 1);
+        assert_eq!(report.categories, 1);
+        assert_eq!(report.daily_entries, 2);
+        assert_eq!(report.history_entries, 1);
+        assert_eq!(report.id_counter, 3);
+        assert_eq!(file_snapshot(a.path()), file_snapshot(b.path()));
+    }
+
+    #[test]
+    fn test_migrate_to_same_backend_fails() {
+        let dir = TempDir::new().unwrap();
+        Storage::new(dir.path().to_path_buf()).unwrap();
+        assert!(migrate(dir.path(), Backend::Text).is_err());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_migrate_round_trip_is_lossless() {
+        let dir = TempDir::new().unwrap();
+        super::super::upgrade::ensure_current(dir.path(), None).unwrap();
+        let id = populate(&Storage::new(dir.path().to_path_buf()).unwrap());
+        let before = file_snapshot(dir.path());
+
+        let (report, _) = migrate(dir.path(), Backend::Sqlite).unwrap();
+        assert_eq!(report.tasks, 2);
+        assert_eq!(Backend::detect(dir.path()), Backend::Sqlite);
+        assert!(!dir.path().join("tasks").exists());
+
+        let store = super::super::open(dir.path().to_path_buf()).unwrap();
+        assert_eq!(store.list_all_tasks().unwrap().len(), 2);
+        assert_eq!(store.get_daily_numeric_total(&id, date(2026, 4, 13)).unwrap(), 4.5);
+        drop(store);
+
+        // Backups are timestamped to the second; make sure the second run gets its own.
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+        migrate(dir.path(), Backend::Text).unwrap();
+        assert_eq!(Backend::detect(dir.path()), Backend::Text);
+        assert_eq!(file_snapshot(dir.path()), before);
+    }
+}
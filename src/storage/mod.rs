@@ -1,907 +1,454 @@
 use anyhow::{Context, Result};
-use chrono::NaiveDate;
-use std::fs;
-use std::path::PathBuf;
-use crate::models::{Task, Day, Category, Priority};
+use chrono::{DateTime, Local, NaiveDate, Utc, Weekday};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use serde::{Deserialize, Serialize};
+use crate::models::{Task, Day, Category};
+
+mod atomic;
+pub mod backup;
+pub mod crypto;
+pub mod doctor;
+mod format;
+mod index;
+pub mod journal;
+mod lock;
+pub mod merge;
+pub mod migrate;
+pub mod profile;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+pub mod sync;
+mod text;
+pub mod upgrade;
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+pub(crate) use atomic::write_atomic;
+pub use crypto::Cipher;
+pub use index::CompletionIndex;
+pub use lock::{DirLock, DEFAULT_LOCK_TIMEOUT, LOCK_FILE};
+pub use text::Storage;
+
+/// One line of `daily.log`: a daily habit checked off on `date`, with the
+/// amount recorded for numeric habits.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyLogEntry {
+    pub date: NaiveDate,
+    pub task_id: String,
+    pub title: String,
+    pub value: Option<f64>,
+}
 
-pub struct Storage {
-    data_dir: PathBuf,
+/// One line of `history.log`: a one-off task completed at `timestamp`
+/// (local time, "YYYY-MM-DD HH:MM:SS").
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: String,
+    pub task_id: String,
+    pub title: String,
 }
 
-impl Storage {
-    pub fn new(data_dir: PathBuf) -> Result<Self> {
-        fs::create_dir_all(&data_dir)?;
-        fs::create_dir_all(data_dir.join("tasks"))?;
-        fs::create_dir_all(data_dir.join("days"))?;
-        fs::create_dir_all(data_dir.join("categories"))?;
+/// A deleted task kept in the trash until it is restored or purged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedTask {
+    pub task: Task,
+    pub deleted_at: DateTime<Utc>,
+}
 
-        Ok(Self { data_dir })
-    }
+/// A completed task moved out of the active task list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedTask {
+    pub task: Task,
+    pub archived_at: DateTime<Utc>,
+}
 
-    pub fn default_dir() -> Result<PathBuf> {
-        let home = dirs::home_dir().context("Could not find home directory")?;
-        Ok(home.join(".daily"))
-    }
+/// A stored record that the `list_*` methods skip because it can't be read or
+/// parsed. `location` is a path relative to the data dir, or `table/key`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnreadableRecord {
+    pub location: String,
+    pub error: String,
+}
 
+/// Everything the commands need from a data store. Backends only have to
+/// provide the primitive operations; completion checks, streaks and grids are
+/// answered from a `CompletionIndex` unless a backend can answer them faster.
+pub trait TaskStore {
     // Task operations
-    pub fn save_task(&self, task: &Task) -> Result<()> {
-        let path = self.data_dir.join("tasks").join(format!("{}.txt", task.id));
-        let content = self.task_to_text(task);
-        fs::write(path, content)?;
-        Ok(())
-    }
-
-    pub fn load_task(&self, id: &str) -> Result<Task> {
-        let path = self.data_dir.join("tasks").join(format!("{}.txt", id));
-        let content = fs::read_to_string(path)?;
-        self.text_to_task(&content)
-    }
-
-    pub fn delete_task(&self, id: &str) -> Result<()> {
-        let path = self.data_dir.join("tasks").join(format!("{}.txt", id));
-        fs::remove_file(path)?;
-        Ok(())
-    }
-
-    pub fn list_all_tasks(&self) -> Result<Vec<Task>> {
-        let tasks_dir = self.data_dir.join("tasks");
-        let mut tasks = Vec::new();
-
-        if let Ok(entries) = fs::read_dir(tasks_dir) {
-            for entry in entries.flatten() {
-                if let Ok(content) = fs::read_to_string(entry.path()) {
-                    if let Ok(task) = self.text_to_task(&content) {
-                        tasks.push(task);
-                    }
-                }
-            }
-        }
-
-        Ok(tasks)
-    }
+    fn save_task(&self, task: &Task) -> Result<()>;
+    fn load_task(&self, id: &str) -> Result<Task>;
+    fn delete_task(&self, id: &str) -> Result<()>;
+    fn list_all_tasks(&self) -> Result<Vec<Task>>;
 
-    pub fn list_tasks_by_category(&self, category: &str) -> Result<Vec<Task>> {
+    fn list_tasks_by_category(&self, category: &str) -> Result<Vec<Task>> {
         let all_tasks = self.list_all_tasks()?;
         Ok(all_tasks.into_iter().filter(|t| t.category == category).collect())
     }
 
-    // Day operations
-    pub fn save_day(&self, day: &Day) -> Result<()> {
-        let path = self.data_dir.join("days").join(format!("{}.txt", day.date));
-        let content = self.day_to_text(day);
-        fs::write(path, content)?;
-        Ok(())
-    }
+    // Trash operations
+    fn save_trashed(&self, trashed: &TrashedTask) -> Result<()>;
+    fn load_trashed(&self, id: &str) -> Result<TrashedTask>;
+    fn list_trash(&self) -> Result<Vec<TrashedTask>>;
+    fn delete_trashed(&self, id: &str) -> Result<()>;
 
-    pub fn load_day(&self, date: NaiveDate) -> Result<Day> {
-        let path = self.data_dir.join("days").join(format!("{}.txt", date));
-        if path.exists() {
-            let content = fs::read_to_string(path)?;
-            self.text_to_day(&content)
-        } else {
-            Ok(Day::new(date))
-        }
+    /// Moves a task to the trash. Its log entries stay where they are, so
+    /// restoring it brings its streaks back.
+    fn trash_task(&self, id: &str) -> Result<TrashedTask> {
+        let trashed = TrashedTask { task: self.load_task(id)?, deleted_at: Utc::now() };
+        self.save_trashed(&trashed)?;
+        self.delete_task(id)?;
+        Ok(trashed)
     }
 
-    // Category operations
-    pub fn save_category(&self, category: &Category) -> Result<()> {
-        let path = self.data_dir.join("categories").join(format!("{}.txt", category.name));
-        let mut content = format!("name: {}\n", category.name);
-        if let Some(desc) = &category.description {
-            content.push_str(&format!("description: {}\n", desc));
+    fn restore_task(&self, id: &str) -> Result<Task> {
+        let trashed = self.load_trashed(id)?;
+        if self.load_task(id).is_ok() {
+            anyhow::bail!("A task with ID {} already exists", id);
         }
-        if let Some(identity) = &category.identity {
-            content.push_str(&format!("identity: {}\n", identity));
+        self.save_task(&trashed.task)?;
+        self.delete_trashed(id)?;
+        Ok(trashed.task)
+    }
+
+    // Archive operations
+    fn save_archived(&self, archived: &ArchivedTask) -> Result<()>;
+    fn load_archived(&self, id: &str) -> Result<ArchivedTask>;
+    fn list_archived(&self) -> Result<Vec<ArchivedTask>>;
+    fn delete_archived(&self, id: &str) -> Result<()>;
+
+    /// Moves a task out of the active task list. Archived tasks are not
+    /// returned by `list_all_tasks`.
+    fn archive_task(&self, id: &str) -> Result<ArchivedTask> {
+        let archived = ArchivedTask { task: self.load_task(id)?, archived_at: Utc::now() };
+        self.save_archived(&archived)?;
+        self.delete_task(id)?;
+        Ok(archived)
+    }
+
+    fn unarchive_task(&self, id: &str) -> Result<Task> {
+        let archived = self.load_archived(id)?;
+        if self.load_task(id).is_ok() {
+            anyhow::bail!("A task with ID {} already exists", id);
         }
-        fs::write(path, content)?;
-        Ok(())
+        self.save_task(&archived.task)?;
+        self.delete_archived(id)?;
+        Ok(archived.task)
     }
 
-    pub fn list_categories(&self) -> Result<Vec<Category>> {
-        let cat_dir = self.data_dir.join("categories");
-        let mut categories = Vec::new();
-
-        if let Ok(entries) = fs::read_dir(cat_dir) {
-            for entry in entries.flatten() {
-                if let Ok(content) = fs::read_to_string(entry.path()) {
-                    if let Ok(category) = self.text_to_category(&content) {
-                        categories.push(category);
-                    }
-                }
-            }
-        }
+    // Day operations
+    fn save_day(&self, day: &Day) -> Result<()>;
+    fn load_day(&self, date: NaiveDate) -> Result<Day>;
+    fn list_days(&self) -> Result<Vec<Day>>;
+    fn delete_day(&self, date: NaiveDate) -> Result<()>;
 
-        Ok(categories)
-    }
+    // Category operations
+    fn save_category(&self, category: &Category) -> Result<()>;
+    fn list_categories(&self) -> Result<Vec<Category>>;
+    fn delete_category(&self, name: &str) -> Result<()>;
 
     // Daily task log operations
-    pub fn log_daily_completion(&self, task_id: &str, task_title: &str, date: NaiveDate, value: Option<f64>) -> Result<()> {
-        let log_path = self.data_dir.join("daily.log");
-        let log_entry = match value {
-            Some(v) => format!("{} | {} | {} | {}\n", date, task_id, task_title, v),
-            None => format!("{} | {} | {}\n", date, task_id, task_title),
-        };
-
-        use std::fs::OpenOptions;
-        use std::io::Write;
-
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(log_path)?;
-
-        file.write_all(log_entry.as_bytes())?;
-        Ok(())
-    }
-
-    pub fn get_daily_numeric_total(&self, task_id: &str, date: NaiveDate) -> Result<f64> {
-        let log_path = self.data_dir.join("daily.log");
-        if !log_path.exists() {
-            return Ok(0.0);
-        }
-        let content = fs::read_to_string(log_path)?;
-        let date_str = date.to_string();
-        let mut total = 0.0f64;
-        for line in content.lines() {
-            let parts: Vec<&str> = line.splitn(4, " | ").collect();
-            if parts.len() >= 4 && parts[0] == date_str && parts[1] == task_id {
-                if let Ok(v) = parts[3].trim().parse::<f64>() {
-                    total += v;
-                }
-            }
-        }
-        Ok(total)
-    }
-
-    pub fn is_daily_completed_on_date(&self, task_id: &str, date: NaiveDate) -> Result<bool> {
-        let log_path = self.data_dir.join("daily.log");
-
-        if !log_path.exists() {
-            return Ok(false);
-        }
-
-        let content = fs::read_to_string(log_path)?;
-        let date_str = date.to_string();
-
-        for line in content.lines() {
-            if let Some((log_date, rest)) = line.split_once(" | ") {
-                if log_date == date_str {
-                    if let Some((log_id, _)) = rest.split_once(" | ") {
-                        if log_id == task_id {
-                            return Ok(true);
-                        }
-                    }
-                }
-            }
-        }
-
-        Ok(false)
+    fn append_daily_entry(&self, entry: &DailyLogEntry) -> Result<()>;
+    fn daily_log_entries(&self) -> Result<Vec<DailyLogEntry>>;
+    /// Removes the most recent entry equal to `entry`, if there is one.
+    fn remove_daily_entry(&self, entry: &DailyLogEntry) -> Result<()>;
+
+    fn log_daily_completion(&self, task_id: &str, task_title: &str, date: NaiveDate, value: Option<f64>) -> Result<()> {
+        self.append_daily_entry(&DailyLogEntry {
+            date,
+            task_id: task_id.to_string(),
+            title: task_title.to_string(),
+            value,
+        })
     }
 
-    pub fn log_task_completion(&self, task_id: &str, task_title: &str) -> Result<()> {
-        use chrono::Local;
-        use std::fs::OpenOptions;
-        use std::io::Write;
-
-        let log_path = self.data_dir.join("history.log");
-        let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S");
-        let log_entry = format!("{} | {} | {}\n", timestamp, task_id, task_title);
-
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(log_path)?;
-
-        file.write_all(log_entry.as_bytes())?;
-        Ok(())
+    /// One-pass index over the daily log. Backends that can tell when the log
+    /// changed should cache it; the default rebuilds it on every call.
+    fn completion_index(&self) -> Result<Rc<CompletionIndex>> {
+        Ok(Rc::new(CompletionIndex::from_entries(&self.daily_log_entries()?)))
     }
 
-    pub fn get_next_task_id(&self) -> Result<String> {
-        use std::fs::OpenOptions;
-        use std::io::{Read, Write};
-
-        let counter_path = self.data_dir.join("id_counter.txt");
-
-        let next_id = if counter_path.exists() {
-            let mut content = String::new();
-            let mut file = OpenOptions::new().read(true).open(&counter_path)?;
-            file.read_to_string(&mut content)?;
-            content.trim().parse::<u64>().unwrap_or(1)
-        } else {
-            1
-        };
-
-        // Write the incremented counter back
-        let mut file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&counter_path)?;
-        file.write_all(format!("{}", next_id + 1).as_bytes())?;
-
-        Ok(next_id.to_string())
+    fn get_daily_numeric_total(&self, task_id: &str, date: NaiveDate) -> Result<f64> {
+        Ok(self.completion_index()?.numeric_total(task_id, date))
     }
 
-    // Conversion helpers
-    fn task_to_text(&self, task: &Task) -> String {
-        let mut lines = vec![
-            format!("id: {}", task.id),
-            format!("title: {}", task.title),
-            format!("priority: {}", task.priority.to_string()),
-            format!("category: {}", task.category),
-            format!("completed: {}", task.completed),
-            format!("created_at: {}", task.created_at.to_rfc3339()),
-            format!("updated_at: {}", task.updated_at.to_rfc3339()),
-            format!("is_daily: {}", task.is_daily),
-            format!("two_minute: {}", task.two_minute),
-        ];
-
-        if let Some(desc) = &task.description {
-            lines.push(format!("description: {}", desc));
-        }
-
-        if let Some(due) = &task.due_date {
-            lines.push(format!("due_date: {}", due.to_rfc3339()));
-        }
-
-        if let Some(time) = &task.scheduled_time {
-            lines.push(format!("scheduled_time: {}", time));
-        }
-
-        if let Some(loc) = &task.location {
-            lines.push(format!("location: {}", loc));
-        }
-
-        if let Some(after) = &task.habit_stack_after {
-            lines.push(format!("habit_stack_after: {}", after));
-        }
-
-        if let Some(days) = &task.scheduled_days {
-            let s: Vec<String> = days.iter().map(|d| d.to_string()).collect();
-            lines.push(format!("scheduled_days: {}", s.join(",")));
-        }
-
-        if task.numeric {
-            lines.push("numeric: true".to_string());
-        }
-
-        if let Some(unit) = &task.unit {
-            lines.push(format!("unit: {}", unit));
-        }
-
-        if let Some(target) = &task.target {
-            lines.push(format!("target: {}", target));
-        }
-
-        lines.join("\n")
+    fn is_daily_completed_on_date(&self, task_id: &str, date: NaiveDate) -> Result<bool> {
+        Ok(self.completion_index()?.is_completed(task_id, date))
     }
 
-    fn text_to_task(&self, text: &str) -> Result<Task> {
-        let mut id = String::new();
-        let mut title = String::new();
-        let mut description = None;
-        let mut priority = Priority::Medium;
-        let mut category = String::from("default");
-        let mut completed = false;
-        let mut created_at = None;
-        let mut updated_at = None;
-        let mut due_date = None;
-        let mut is_daily = false;
-        let mut scheduled_time = None;
-        let mut location = None;
-        let mut habit_stack_after = None;
-        let mut two_minute = false;
-        let mut scheduled_days: Option<Vec<u8>> = None;
-        let mut numeric = false;
-        let mut unit: Option<String> = None;
-        let mut target: Option<f64> = None;
-
-        for line in text.lines() {
-            if let Some((key, value)) = line.split_once(": ") {
-                match key {
-                    "id" => id = value.to_string(),
-                    "title" => title = value.to_string(),
-                    "description" => description = Some(value.to_string()),
-                    "priority" => priority = Priority::from_str(value).unwrap_or(Priority::Medium),
-                    "category" => category = value.to_string(),
-                    "completed" => completed = value.parse().unwrap_or(false),
-                    "created_at" => created_at = value.parse().ok(),
-                    "updated_at" => updated_at = value.parse().ok(),
-                    "due_date" => due_date = value.parse().ok(),
-                    "is_daily" => is_daily = value.parse().unwrap_or(false),
-                    "scheduled_time" => scheduled_time = Some(value.to_string()),
-                    "location" => location = Some(value.to_string()),
-                    "habit_stack_after" => habit_stack_after = Some(value.to_string()),
-                    "two_minute" => two_minute = value.parse().unwrap_or(false),
-                    "scheduled_days" => {
-                        let nums: Vec<u8> = value.split(',')
-                            .filter_map(|s| s.trim().parse().ok())
-                            .collect();
-                        if !nums.is_empty() {
-                            scheduled_days = Some(nums);
-                        }
-                    }
-                    "numeric" => numeric = value.parse().unwrap_or(false),
-                    "unit" => unit = Some(value.to_string()),
-                    "target" => target = value.parse().ok(),
-                    _ => {}
-                }
-            }
-        }
+    // Regular task history operations
+    fn append_history_entry(&self, entry: &HistoryEntry) -> Result<()>;
+    fn history_entries(&self) -> Result<Vec<HistoryEntry>>;
+    /// Removes the most recent entry equal to `entry`, if there is one.
+    fn remove_history_entry(&self, entry: &HistoryEntry) -> Result<()>;
 
-        Ok(Task {
-            id,
-            title,
-            description,
-            priority,
-            category,
-            completed,
-            created_at: created_at.context("Missing created_at")?,
-            updated_at: updated_at.context("Missing updated_at")?,
-            due_date,
-            is_daily,
-            scheduled_time,
-            location,
-            habit_stack_after,
-            two_minute,
-            scheduled_days,
-            numeric,
-            unit,
-            target,
+    fn log_task_completion(&self, task_id: &str, task_title: &str) -> Result<()> {
+        self.append_history_entry(&HistoryEntry {
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            task_id: task_id.to_string(),
+            title: task_title.to_string(),
         })
     }
 
-    fn day_to_text(&self, day: &Day) -> String {
-        let mut lines = vec![format!("date: {}", day.date)];
-
-        if !day.task_ids.is_empty() {
-            lines.push(format!("tasks: {}", day.task_ids.join(",")));
+    /// Points log entries for the tasks in `ids` (old ID to new ID) at their
+    /// new IDs. Backends that can rewrite a log in place should; the default
+    /// removes and re-appends each entry.
+    fn rename_log_task_ids(&self, ids: &HashMap<String, String>) -> Result<()> {
+        for mut entry in self.daily_log_entries()? {
+            if let Some(id) = ids.get(&entry.task_id) {
+                self.remove_daily_entry(&entry)?;
+                entry.task_id = id.clone();
+                self.append_daily_entry(&entry)?;
+            }
         }
-
-        if let Some(notes) = &day.notes {
-            lines.push(format!("notes: {}", notes));
+        for mut entry in self.history_entries()? {
+            if let Some(id) = ids.get(&entry.task_id) {
+                self.remove_history_entry(&entry)?;
+                entry.task_id = id.clone();
+                self.append_history_entry(&entry)?;
+            }
         }
-
-        lines.join("\n")
+        Ok(())
     }
 
-    fn text_to_day(&self, text: &str) -> Result<Day> {
-        let mut date = None;
-        let mut task_ids = Vec::new();
-        let mut notes = None;
-
-        for line in text.lines() {
-            if let Some((key, value)) = line.split_once(": ") {
-                match key {
-                    "date" => date = Some(value.parse()?),
-                    "tasks" => task_ids = value.split(',').map(|s| s.to_string()).collect(),
-                    "notes" => notes = Some(value.to_string()),
-                    _ => {}
-                }
-            }
-        }
+    // Handle allocation. Tasks are identified by UUID; `id_counter` is the
+    // next short numeric handle that will be handed out for the CLI.
+    fn id_counter(&self) -> Result<u64>;
+    fn set_id_counter(&self, next: u64) -> Result<()>;
 
-        Ok(Day {
-            date: date.context("Missing date")?,
-            task_ids,
-            notes,
-        })
+    fn next_handle(&self) -> Result<String> {
+        let next_id = self.id_counter()?;
+        self.set_id_counter(next_id + 1)?;
+        Ok(next_id.to_string())
     }
 
-    fn text_to_category(&self, text: &str) -> Result<Category> {
-        let mut name = String::new();
-        let mut description = None;
-        let mut identity = None;
-
-        for line in text.lines() {
-            if let Some((key, value)) = line.split_once(": ") {
-                match key {
-                    "name" => name = value.to_string(),
-                    "description" => description = Some(value.to_string()),
-                    "identity" => identity = Some(value.to_string()),
-                    _ => {}
-                }
-            }
-        }
+    // Integrity checks, used by `daily doctor`.
+    fn unreadable_records(&self) -> Result<Vec<UnreadableRecord>>;
 
-        Ok(Category { name, description, identity })
-    }
+    /// Every readable task with the key it is stored under (file stem or row
+    /// ID). The key only differs from `task.id` when a record was copied or
+    /// edited by hand.
+    fn task_records(&self) -> Result<Vec<(String, Task)>>;
 
     // Atomic Habits: streak tracking — "Make it Satisfying"
     // Returns the number of consecutive days (going backwards from `as_of`) the task was completed.
-    pub fn get_streak_for_task(&self, task_id: &str, as_of: NaiveDate) -> Result<u32> {
-        let mut streak = 0u32;
-        let mut check = as_of;
-        loop {
-            if self.is_daily_completed_on_date(task_id, check)? {
-                streak += 1;
-                match check.pred_opt() {
-                    Some(prev) => check = prev,
-                    None => break,
-                }
-            } else {
-                break;
-            }
-        }
-        Ok(streak)
+    fn get_streak_for_task(&self, task_id: &str, as_of: NaiveDate) -> Result<u32> {
+        Ok(self.completion_index()?.streak(task_id, as_of))
     }
 
-    // Returns a vec of booleans for the last `days` days (oldest first, newest last).
-    pub fn get_habit_grid(&self, task_id: &str, as_of: NaiveDate, days: u32) -> Result<Vec<bool>> {
-        let mut grid = Vec::with_capacity(days as usize);
-        for i in (0..days).rev() {
-            let date = as_of - chrono::Duration::days(i as i64);
-            grid.push(self.is_daily_completed_on_date(task_id, date)?);
+    // Streak for a habit in days. Days its rule or `scheduled_days` leave out
+    // neither count nor break it.
+    fn get_habit_streak(&self, task: &Task, as_of: NaiveDate) -> Result<u32> {
+        if task.recurrence.is_none() && task.scheduled_days.is_none() {
+            return self.get_streak_for_task(&task.id, as_of);
         }
-        Ok(grid)
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::models::{Category, Priority, Task};
-    use chrono::Utc;
-    use tempfile::TempDir;
-
-    // --- helpers ---
-
-    fn test_storage() -> (TempDir, Storage) {
-        let dir = TempDir::new().unwrap();
-        let storage = Storage::new(dir.path().to_path_buf()).unwrap();
-        (dir, storage)
-    }
-
-    fn task(id: &str, title: &str) -> Task {
-        Task::new(id.to_string(), title.to_string(), Priority::Medium, "default".to_string())
-    }
-
-    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
-        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+        Ok(self.completion_index()?.streak_on(&task.id, as_of, |d| task.is_scheduled_on(d)))
     }
 
-    // --- storage setup ---
-
-    #[test]
-    fn test_new_creates_subdirectories() {
-        let dir = TempDir::new().unwrap();
-        Storage::new(dir.path().to_path_buf()).unwrap();
-        assert!(dir.path().join("tasks").is_dir());
-        assert!(dir.path().join("days").is_dir());
-        assert!(dir.path().join("categories").is_dir());
-    }
-
-    // --- task CRUD ---
-
-    #[test]
-    fn test_save_and_load_task_basic() {
-        let (_dir, s) = test_storage();
-        let t = task("1", "Buy groceries");
-        s.save_task(&t).unwrap();
-        let loaded = s.load_task("1").unwrap();
-        assert_eq!(loaded.id, "1");
-        assert_eq!(loaded.title, "Buy groceries");
-        assert_eq!(loaded.priority, Priority::Medium);
-        assert_eq!(loaded.category, "default");
-        assert!(!loaded.completed);
-        assert!(!loaded.is_daily);
-        assert!(!loaded.two_minute);
-    }
-
-    #[test]
-    fn test_save_and_load_task_all_optional_fields() {
-        let (_dir, s) = test_storage();
-        let due = Utc::now();
-        let t = Task::new("2".to_string(), "Run".to_string(), Priority::High, "health".to_string())
-            .with_description("Morning run".to_string())
-            .with_due_date(due)
-            .with_daily(true)
-            .with_scheduled_time("06:30".to_string())
-            .with_location("front door".to_string())
-            .with_habit_stack_after("1".to_string())
-            .with_two_minute(true)
-            .with_scheduled_days(vec![0, 2, 4]);
-        s.save_task(&t).unwrap();
-        let loaded = s.load_task("2").unwrap();
-        assert_eq!(loaded.description, Some("Morning run".to_string()));
-        assert!(loaded.due_date.is_some());
-        assert!(loaded.is_daily);
-        assert_eq!(loaded.scheduled_time, Some("06:30".to_string()));
-        assert_eq!(loaded.location, Some("front door".to_string()));
-        assert_eq!(loaded.habit_stack_after, Some("1".to_string()));
-        assert!(loaded.two_minute);
-        assert_eq!(loaded.scheduled_days, Some(vec![0, 2, 4]));
-    }
-
-    #[test]
-    fn test_load_task_not_found() {
-        let (_dir, s) = test_storage();
-        assert!(s.load_task("ghost").is_err());
-    }
-
-    #[test]
-    fn test_delete_task() {
-        let (_dir, s) = test_storage();
-        s.save_task(&task("1", "Gone")).unwrap();
-        s.delete_task("1").unwrap();
-        assert!(s.load_task("1").is_err());
-    }
-
-    #[test]
-    fn test_delete_task_not_found() {
-        let (_dir, s) = test_storage();
-        assert!(s.delete_task("ghost").is_err());
-    }
-
-    #[test]
-    fn test_list_all_tasks_empty() {
-        let (_dir, s) = test_storage();
-        assert!(s.list_all_tasks().unwrap().is_empty());
-    }
-
-    #[test]
-    fn test_list_all_tasks_returns_all() {
-        let (_dir, s) = test_storage();
-        s.save_task(&task("1", "A")).unwrap();
-        s.save_task(&task("2", "B")).unwrap();
-        s.save_task(&task("3", "C")).unwrap();
-        assert_eq!(s.list_all_tasks().unwrap().len(), 3);
-    }
-
-    #[test]
-    fn test_list_tasks_by_category() {
-        let (_dir, s) = test_storage();
-        let work = Task::new("1".to_string(), "Work".to_string(), Priority::High, "work".to_string());
-        let personal = Task::new("2".to_string(), "Personal".to_string(), Priority::Low, "personal".to_string());
-        let work2 = Task::new("3".to_string(), "Work2".to_string(), Priority::Medium, "work".to_string());
-        s.save_task(&work).unwrap();
-        s.save_task(&personal).unwrap();
-        s.save_task(&work2).unwrap();
-
-        let work_tasks = s.list_tasks_by_category("work").unwrap();
-        assert_eq!(work_tasks.len(), 2);
-        assert!(work_tasks.iter().all(|t| t.category == "work"));
-
-        let personal_tasks = s.list_tasks_by_category("personal").unwrap();
-        assert_eq!(personal_tasks.len(), 1);
-
-        assert!(s.list_tasks_by_category("nonexistent").unwrap().is_empty());
-    }
-
-    #[test]
-    fn test_task_priority_roundtrip() {
-        let (_dir, s) = test_storage();
-        let t = Task::new("1".to_string(), "T".to_string(), Priority::Critical, "w".to_string());
-        s.save_task(&t).unwrap();
-        assert_eq!(s.load_task("1").unwrap().priority, Priority::Critical);
-    }
-
-    #[test]
-    fn test_task_completed_roundtrip() {
-        let (_dir, s) = test_storage();
-        let mut t = task("1", "Done");
-        t.mark_complete();
-        s.save_task(&t).unwrap();
-        assert!(s.load_task("1").unwrap().completed);
-    }
-
-    #[test]
-    fn test_task_scheduled_days_roundtrip() {
-        let (_dir, s) = test_storage();
-        let t = task("1", "Yoga").with_daily(true).with_scheduled_days(vec![0, 2, 4]);
-        s.save_task(&t).unwrap();
-        assert_eq!(s.load_task("1").unwrap().scheduled_days, Some(vec![0, 2, 4]));
-    }
-
-    #[test]
-    fn test_task_no_scheduled_days_roundtrip() {
-        let (_dir, s) = test_storage();
-        let t = task("1", "Daily").with_daily(true);
-        s.save_task(&t).unwrap();
-        assert!(s.load_task("1").unwrap().scheduled_days.is_none());
-    }
-
-    // --- day CRUD ---
-
-    #[test]
-    fn test_save_and_load_day() {
-        let (_dir, s) = test_storage();
-        let d = date(2026, 4, 13);
-        let mut day = Day::new(d);
-        day.add_task("1".to_string());
-        day.add_task("2".to_string());
-        s.save_day(&day).unwrap();
-        let loaded = s.load_day(d).unwrap();
-        assert_eq!(loaded.date, d);
-        assert!(loaded.task_ids.contains(&"1".to_string()));
-        assert!(loaded.task_ids.contains(&"2".to_string()));
-    }
-
-    #[test]
-    fn test_load_day_missing_returns_empty() {
-        let (_dir, s) = test_storage();
-        let d = date(2026, 1, 1);
-        let day = s.load_day(d).unwrap();
-        assert_eq!(day.date, d);
-        assert!(day.task_ids.is_empty());
-    }
-
-    // --- category CRUD ---
-
-    #[test]
-    fn test_save_and_list_category() {
-        let (_dir, s) = test_storage();
-        s.save_category(&Category::new("work".to_string())).unwrap();
-        let cats = s.list_categories().unwrap();
-        assert_eq!(cats.len(), 1);
-        assert_eq!(cats[0].name, "work");
-    }
-
-    #[test]
-    fn test_list_categories_empty() {
-        let (_dir, s) = test_storage();
-        assert!(s.list_categories().unwrap().is_empty());
-    }
-
-    #[test]
-    fn test_category_description_roundtrip() {
-        let (_dir, s) = test_storage();
-        let cat = Category::new("health".to_string()).with_description("Wellbeing".to_string());
-        s.save_category(&cat).unwrap();
-        let cats = s.list_categories().unwrap();
-        assert_eq!(cats[0].description, Some("Wellbeing".to_string()));
-    }
-
-    #[test]
-    fn test_category_identity_roundtrip() {
-        let (_dir, s) = test_storage();
-        let cat = Category::new("fitness".to_string())
-            .with_identity("I am someone who moves every day".to_string());
-        s.save_category(&cat).unwrap();
-        let cats = s.list_categories().unwrap();
-        assert_eq!(cats[0].identity, Some("I am someone who moves every day".to_string()));
-    }
-
-    #[test]
-    fn test_category_all_fields_roundtrip() {
-        let (_dir, s) = test_storage();
-        let cat = Category::new("x".to_string())
-            .with_description("desc".to_string())
-            .with_identity("identity".to_string());
-        s.save_category(&cat).unwrap();
-        let cats = s.list_categories().unwrap();
-        assert_eq!(cats[0].description, Some("desc".to_string()));
-        assert_eq!(cats[0].identity, Some("identity".to_string()));
+    // Streak for a habit with a "N times per week/month" target, counted in
+    // periods that met it. Other habits get their streak in days.
+    fn get_period_streak(&self, task: &Task, as_of: NaiveDate, week_start: Weekday) -> Result<u32> {
+        let Some((target, ..)) = task.target_period(as_of, week_start) else {
+            return self.get_habit_streak(task, as_of);
+        };
+        Ok(self.completion_index()?.period_streak(&task.id, as_of, target, |d| {
+            task.target_period(d, week_start).map_or((d, d), |(_, start, end)| (start, end))
+        }))
     }
 
-    // --- daily log ---
-
-    #[test]
-    fn test_daily_not_completed_no_log_file() {
-        let (_dir, s) = test_storage();
-        assert!(!s.is_daily_completed_on_date("t1", date(2026, 4, 13)).unwrap());
+    fn count_completed_days(&self, task_id: &str, from: NaiveDate, to: NaiveDate) -> Result<u32> {
+        Ok(self.completion_index()?.days_completed(task_id, from, to))
     }
 
-    #[test]
-    fn test_log_and_check_daily_completion() {
-        let (_dir, s) = test_storage();
-        let d = date(2026, 4, 13);
-        s.log_daily_completion("t1", "Run", d, None).unwrap();
-        assert!(s.is_daily_completed_on_date("t1", d).unwrap());
+    // Returns a vec of booleans for the last `days` days (oldest first, newest last).
+    fn get_habit_grid(&self, task_id: &str, as_of: NaiveDate, days: u32) -> Result<Vec<bool>> {
+        Ok(self.completion_index()?.grid(task_id, as_of, days))
     }
+}
 
-    #[test]
-    fn test_daily_completion_wrong_id_not_matched() {
-        let (_dir, s) = test_storage();
-        let d = date(2026, 4, 13);
-        s.log_daily_completion("t1", "Run", d, None).unwrap();
-        assert!(!s.is_daily_completed_on_date("t2", d).unwrap());
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Text,
+    Sqlite,
+}
 
-    #[test]
-    fn test_daily_completion_wrong_date_not_matched() {
-        let (_dir, s) = test_storage();
-        let d = date(2026, 4, 13);
-        s.log_daily_completion("t1", "Run", d, None).unwrap();
-        assert!(!s.is_daily_completed_on_date("t1", date(2026, 4, 14)).unwrap());
+impl Backend {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "text" | "txt" => Some(Backend::Text),
+            "sqlite" | "db" => Some(Backend::Sqlite),
+            _ => None,
+        }
     }
 
-    #[test]
-    fn test_daily_completion_multiple_tasks_same_day() {
-        let (_dir, s) = test_storage();
-        let d = date(2026, 4, 13);
-        s.log_daily_completion("t1", "Run", d, None).unwrap();
-        s.log_daily_completion("t2", "Read", d, None).unwrap();
-        assert!(s.is_daily_completed_on_date("t1", d).unwrap());
-        assert!(s.is_daily_completed_on_date("t2", d).unwrap());
+    /// The backend a data directory is currently using. A `daily.db` file
+    /// marks the SQLite layout; anything else is the text layout.
+    pub fn detect(data_dir: &Path) -> Self {
+        if data_dir.join(SQLITE_FILE).exists() {
+            Backend::Sqlite
+        } else {
+            Backend::Text
+        }
     }
+}
 
-    #[test]
-    fn test_log_task_completion_creates_history_log() {
-        let (_dir, s) = test_storage();
-        s.log_task_completion("t1", "Finish report").unwrap();
-        let content = std::fs::read_to_string(s.data_dir.join("history.log")).unwrap();
-        assert!(content.contains("t1"));
-        assert!(content.contains("Finish report"));
+impl std::fmt::Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Backend::Text => f.write_str("text"),
+            Backend::Sqlite => f.write_str("sqlite"),
+        }
     }
+}
 
-    // --- ID counter ---
+/// Database file used by the SQLite backend, relative to the data directory.
+pub const SQLITE_FILE: &str = "daily.db";
 
-    #[test]
-    fn test_get_next_task_id_starts_at_one() {
-        let (_dir, s) = test_storage();
-        assert_eq!(s.get_next_task_id().unwrap(), "1");
-    }
+/// Opens the store rooted at `data_dir` using whichever backend it holds,
+/// upgrading data written by an older build first.
+/// Every mutating call takes the data directory lock, so concurrent `daily`
+/// processes (the daemon, cron jobs, a shell) never interleave their writes;
+/// hold a `DirLock` to keep a whole read-modify-write sequence together.
+pub fn open(data_dir: PathBuf) -> Result<Box<dyn TaskStore>> {
+    open_with(data_dir, None)
+}
 
-    #[test]
-    fn test_get_next_task_id_sequential() {
-        let (_dir, s) = test_storage();
-        assert_eq!(s.get_next_task_id().unwrap(), "1");
-        assert_eq!(s.get_next_task_id().unwrap(), "2");
-        assert_eq!(s.get_next_task_id().unwrap(), "3");
+/// Like `open`, but an encrypted data dir is read and written with `cipher`.
+pub fn open_with(data_dir: PathBuf, cipher: Option<Cipher>) -> Result<Box<dyn TaskStore>> {
+    upgrade::ensure_current(&data_dir, cipher.as_ref())?;
+    if crypto::is_encrypted(&data_dir) && cipher.is_none() {
+        anyhow::bail!("{} is encrypted; a passphrase is needed to open it", data_dir.display());
     }
+    let backend = open_backend(&data_dir, cipher)?;
+    let lock = DirLock::new(&data_dir, lock::DEFAULT_LOCK_TIMEOUT);
+    Ok(Box::new(lock::Locked::new(backend, lock)))
+}
 
-    // --- streak ---
-
-    #[test]
-    fn test_streak_zero_with_no_completions() {
-        let (_dir, s) = test_storage();
-        assert_eq!(s.get_streak_for_task("t1", date(2026, 4, 13)).unwrap(), 0);
+/// Opens a copy of `data_dir` made in `scratch`, which must not exist yet,
+/// so reading it changes nothing: data in an older format is upgraded in the
+/// copy, leaving the original for whichever build it belongs to. The caller
+/// removes `scratch` when done.
+pub fn open_snapshot(data_dir: &Path, scratch: &Path, cipher: Option<Cipher>) -> Result<Box<dyn TaskStore>> {
+    if upgrade::data_version(data_dir)?.is_some_and(|v| v > upgrade::DATA_VERSION) {
+        anyhow::bail!("{} was written by a newer version of daily. Upgrade daily to read it.", data_dir.display());
     }
-
-    #[test]
-    fn test_streak_one_day() {
-        let (_dir, s) = test_storage();
-        let d = date(2026, 4, 13);
-        s.log_daily_completion("t1", "Run", d, None).unwrap();
-        assert_eq!(s.get_streak_for_task("t1", d).unwrap(), 1);
+    if scratch.exists() {
+        anyhow::bail!("{} already exists", scratch.display());
     }
+    upgrade::copy_data(data_dir, scratch)
+        .with_context(|| format!("Could not copy {}", data_dir.display()))?;
+    open_with(scratch.to_path_buf(), cipher)
+}
 
-    #[test]
-    fn test_streak_consecutive_days() {
-        let (_dir, s) = test_storage();
-        let today = date(2026, 4, 13);
-        for i in 0..5 {
-            s.log_daily_completion("t1", "Run", today - chrono::Duration::days(i), None).unwrap();
+/// The bare backend, without locking or version checks. Only the text
+/// backend can be encrypted.
+fn open_backend(data_dir: &Path, cipher: Option<Cipher>) -> Result<Box<dyn TaskStore>> {
+    match Backend::detect(data_dir) {
+        Backend::Text => {
+            let storage = Storage::new(data_dir.to_path_buf())?;
+            Ok(match cipher {
+                Some(cipher) => Box::new(storage.with_cipher(cipher)),
+                None => Box::new(storage),
+            })
         }
-        assert_eq!(s.get_streak_for_task("t1", today).unwrap(), 5);
-    }
-
-    #[test]
-    fn test_streak_broken_by_gap() {
-        let (_dir, s) = test_storage();
-        let today = date(2026, 4, 13);
-        // Complete today and yesterday, gap on -2, then -3
-        s.log_daily_completion("t1", "Run", today, None).unwrap();
-        s.log_daily_completion("t1", "Run", today - chrono::Duration::days(1), None).unwrap();
-        s.log_daily_completion("t1", "Run", today - chrono::Duration::days(3), None).unwrap();
-        assert_eq!(s.get_streak_for_task("t1", today).unwrap(), 2);
-    }
-
-    #[test]
-    fn test_streak_ignores_other_task() {
-        let (_dir, s) = test_storage();
-        let d = date(2026, 4, 13);
-        s.log_daily_completion("t2", "Read", d, None).unwrap();
-        assert_eq!(s.get_streak_for_task("t1", d).unwrap(), 0);
-    }
-
-    // --- habit grid ---
-
-    #[test]
-    fn test_habit_grid_all_false_no_completions() {
-        let (_dir, s) = test_storage();
-        let grid = s.get_habit_grid("t1", date(2026, 4, 13), 7).unwrap();
-        assert_eq!(grid.len(), 7);
-        assert!(grid.iter().all(|&b| !b));
+        Backend::Sqlite => open_sqlite(&data_dir.join(SQLITE_FILE)),
     }
+}
 
-    #[test]
-    fn test_habit_grid_single_day_true() {
-        let (_dir, s) = test_storage();
-        let d = date(2026, 4, 13);
-        s.log_daily_completion("t1", "Run", d, None).unwrap();
-        let grid = s.get_habit_grid("t1", d, 1).unwrap();
-        assert_eq!(grid, vec![true]);
-    }
+#[cfg(feature = "sqlite")]
+fn open_sqlite(path: &Path) -> Result<Box<dyn TaskStore>> {
+    Ok(Box::new(SqliteStorage::open(path)?))
+}
 
-    #[test]
-    fn test_habit_grid_correct_positions() {
-        let (_dir, s) = test_storage();
-        let today = date(2026, 4, 13);
-        // Complete today (index 6) and 2 days ago (index 4) in a 7-day window
-        s.log_daily_completion("t1", "Run", today, None).unwrap();
-        s.log_daily_completion("t1", "Run", today - chrono::Duration::days(2), None).unwrap();
-        let grid = s.get_habit_grid("t1", today, 7).unwrap();
-        assert_eq!(grid.len(), 7);
-        assert!(grid[6]);  // today
-        assert!(!grid[5]); // yesterday
-        assert!(grid[4]);  // 2 days ago
-        assert!(!grid[3]); // 3 days ago
-    }
+#[cfg(not(feature = "sqlite"))]
+fn open_sqlite(path: &Path) -> Result<Box<dyn TaskStore>> {
+    anyhow::bail!(
+        "{} uses the SQLite backend, but this build was compiled without it. Rebuild with `--features sqlite`.",
+        path.display()
+    )
+}
 
-    #[test]
-    fn test_habit_grid_length_matches_days_param() {
-        let (_dir, s) = test_storage();
-        let d = date(2026, 4, 13);
-        assert_eq!(s.get_habit_grid("t1", d, 14).unwrap().len(), 14);
-        assert_eq!(s.get_habit_grid("t1", d, 21).unwrap().len(), 21);
-        assert_eq!(s.get_habit_grid("t1", d, 1).unwrap().len(), 1);
-    }
+pub fn default_dir() -> Result<PathBuf> {
+    let home = dirs::home_dir().context("Could not find home directory")?;
+    Ok(home.join(".daily"))
+}
 
-    // --- numeric total ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Priority;
+    use tempfile::TempDir;
 
-    #[test]
-    fn test_numeric_total_no_log_returns_zero() {
-        let (_dir, s) = test_storage();
-        assert_eq!(s.get_daily_numeric_total("t1", date(2026, 4, 13)).unwrap(), 0.0);
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
     }
 
     #[test]
-    fn test_numeric_total_single_entry() {
-        let (_dir, s) = test_storage();
-        let d = date(2026, 4, 13);
-        s.log_daily_completion("t1", "Push ups", d, Some(25.0)).unwrap();
-        assert_eq!(s.get_daily_numeric_total("t1", d).unwrap(), 25.0);
+    fn test_open_returns_working_store() {
+        let dir = TempDir::new().unwrap();
+        let store = open(dir.path().to_path_buf()).unwrap();
+        let id = store.next_handle().unwrap();
+        let t = Task::new(id.clone(), "Read".to_string(), Priority::Low, "learning".to_string());
+        store.save_task(&t).unwrap();
+        assert_eq!(store.load_task(&id).unwrap().title, "Read");
+        assert_eq!(store.list_tasks_by_category("learning").unwrap().len(), 1);
     }
 
     #[test]
-    fn test_numeric_total_accumulates() {
-        let (_dir, s) = test_storage();
-        let d = date(2026, 4, 13);
-        s.log_daily_completion("t1", "Push ups", d, Some(25.0)).unwrap();
-        s.log_daily_completion("t1", "Push ups", d, Some(4.0)).unwrap();
-        assert_eq!(s.get_daily_numeric_total("t1", d).unwrap(), 29.0);
-    }
+    fn test_open_snapshot_leaves_older_data_alone() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("tasks")).unwrap();
+        let v1 = "id: 1\ntitle: Legacy\npriority: High\ncategory: default\ncompleted: false\n\
+            created_at: 2026-04-01T06:00:00+00:00\nupdated_at: 2026-04-01T06:00:00+00:00";
+        std::fs::write(dir.path().join("tasks").join("1.txt"), v1).unwrap();
+        let scratch = TempDir::new().unwrap();
 
-    #[test]
-    fn test_numeric_total_ignores_other_task() {
-        let (_dir, s) = test_storage();
-        let d = date(2026, 4, 13);
-        s.log_daily_completion("t2", "Squats", d, Some(50.0)).unwrap();
-        assert_eq!(s.get_daily_numeric_total("t1", d).unwrap(), 0.0);
+        let store = open_snapshot(dir.path(), &scratch.path().join("copy"), None).unwrap();
+        let tasks = store.list_all_tasks().unwrap();
+        assert_eq!((tasks[0].handle.as_str(), tasks[0].title.as_str()), ("1", "Legacy"));
+        let names: Vec<_> = std::fs::read_dir(dir.path()).unwrap().flatten().map(|e| e.file_name()).collect();
+        assert_eq!(names, vec!["tasks"]);
+        assert_eq!(std::fs::read_to_string(dir.path().join("tasks").join("1.txt")).unwrap(), v1);
     }
 
     #[test]
-    fn test_numeric_total_ignores_other_date() {
-        let (_dir, s) = test_storage();
-        let d = date(2026, 4, 13);
-        s.log_daily_completion("t1", "Push ups", d, Some(25.0)).unwrap();
-        assert_eq!(s.get_daily_numeric_total("t1", date(2026, 4, 14)).unwrap(), 0.0);
+    fn test_backend_from_str() {
+        assert_eq!(Backend::from_str("text"), Some(Backend::Text));
+        assert_eq!(Backend::from_str("SQLite"), Some(Backend::Sqlite));
+        assert_eq!(Backend::from_str("postgres"), None);
     }
 
     #[test]
-    fn test_numeric_total_boolean_entry_contributes_zero() {
-        let (_dir, s) = test_storage();
-        let d = date(2026, 4, 13);
-        s.log_daily_completion("t1", "Run", d, None).unwrap();
-        assert_eq!(s.get_daily_numeric_total("t1", d).unwrap(), 0.0);
+    fn test_backend_detect() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(Backend::detect(dir.path()), Backend::Text);
+        std::fs::write(dir.path().join(SQLITE_FILE), "").unwrap();
+        assert_eq!(Backend::detect(dir.path()), Backend::Sqlite);
     }
 
-    // --- numeric task roundtrip ---
-
     #[test]
-    fn test_numeric_task_fields_roundtrip() {
-        let (_dir, s) = test_storage();
-        let t = task("1", "Push ups")
-            .with_numeric(true)
-            .with_unit("reps".to_string())
-            .with_target(100.0);
-        s.save_task(&t).unwrap();
-        let loaded = s.load_task("1").unwrap();
-        assert!(loaded.numeric);
-        assert_eq!(loaded.unit, Some("reps".to_string()));
-        assert_eq!(loaded.target, Some(100.0));
+    fn test_log_task_completion_records_timestamp() {
+        let dir = TempDir::new().unwrap();
+        let store = open(dir.path().to_path_buf()).unwrap();
+        store.log_task_completion("1", "Report").unwrap();
+        let entries = store.history_entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].timestamp.len(), "2026-04-13 09:00:00".len());
+        assert_eq!(entries[0].title, "Report");
     }
 
     #[test]
-    fn test_non_numeric_task_defaults() {
-        let (_dir, s) = test_storage();
-        let t = task("1", "Read book");
-        s.save_task(&t).unwrap();
-        let loaded = s.load_task("1").unwrap();
-        assert!(!loaded.numeric);
-        assert!(loaded.unit.is_none());
-        assert!(loaded.target.is_none());
+    fn test_daily_log_entries_keep_order_and_values() {
+        let dir = TempDir::new().unwrap();
+        let store = open(dir.path().to_path_buf()).unwrap();
+        store.log_daily_completion("1", "Push ups", date(2026, 4, 13), Some(25.0)).unwrap();
+        store.log_daily_completion("2", "Run", date(2026, 4, 12), None).unwrap();
+        let entries = store.daily_log_entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].value, Some(25.0));
+        assert_eq!(entries[1].task_id, "2");
+        assert!(entries[1].value.is_none());
     }
 }
@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use super::atomic::write_atomic;
+
+/// The profile living directly in the root data dir.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Directory holding the other profiles, relative to the root data dir.
+pub const PROFILES_DIR: &str = "profiles";
+
+/// File in the root data dir naming the profile `daily profile switch` chose.
+pub const ACTIVE_PROFILE_FILE: &str = "active_profile";
+
+/// Named data directories under one root. The default profile is the root
+/// itself, so data from before profiles existed stays where it is; every
+/// other profile is a complete data dir under `profiles/<name>/`.
+pub struct Profiles {
+    root: PathBuf,
+}
+
+impl Profiles {
+    pub fn new(root: &Path) -> Self {
+        Self { root: root.to_path_buf() }
+    }
+
+    /// The data dir of profile `name`, whether or not it exists yet.
+    pub fn dir(&self, name: &str) -> Result<PathBuf> {
+        validate_name(name)?;
+        if name == DEFAULT_PROFILE {
+            Ok(self.root.clone())
+        } else {
+            Ok(self.root.join(PROFILES_DIR).join(name))
+        }
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        name == DEFAULT_PROFILE || self.dir(name).is_ok_and(|dir| dir.is_dir())
+    }
+
+    /// Every profile, the default one first and the rest by name.
+    pub fn list(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        if let Ok(entries) = fs::read_dir(self.root.join(PROFILES_DIR)) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if entry.path().is_dir() && validate_name(&name).is_ok() {
+                    names.push(name);
+                }
+            }
+        }
+        names.sort();
+        names.insert(0, DEFAULT_PROFILE.to_string());
+        Ok(names)
+    }
+
+    /// The profile chosen with `switch`, or the default one.
+    pub fn active(&self) -> Result<String> {
+        let path = self.root.join(ACTIVE_PROFILE_FILE);
+        if !path.exists() {
+            return Ok(DEFAULT_PROFILE.to_string());
+        }
+        let name = fs::read_to_string(&path)?.trim().to_string();
+        Ok(if name.is_empty() { DEFAULT_PROFILE.to_string() } else { name })
+    }
+
+    /// Picks `requested` (from `--profile` or `DAILY_PROFILE`) or else the
+    /// active profile, and returns its name and data dir.
+    pub fn resolve(&self, requested: Option<&str>) -> Result<(String, PathBuf)> {
+        let name = match requested {
+            Some(name) => name.to_string(),
+            None => self.active()?,
+        };
+        if !self.exists(&name) {
+            anyhow::bail!("No profile named '{}'. Create it with `daily profile create {}`", name, name);
+        }
+        let dir = self.dir(&name)?;
+        Ok((name, dir))
+    }
+
+    pub fn create(&self, name: &str) -> Result<PathBuf> {
+        if self.exists(name) {
+            anyhow::bail!("Profile '{}' already exists", name);
+        }
+        let dir = self.dir(name)?;
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Could not create profile directory {}", dir.display()))?;
+        Ok(dir)
+    }
+
+    pub fn switch(&self, name: &str) -> Result<()> {
+        if !self.exists(name) {
+            anyhow::bail!("No profile named '{}'", name);
+        }
+        fs::create_dir_all(&self.root)?;
+        write_atomic(&self.root.join(ACTIVE_PROFILE_FILE), format!("{}\n", name).as_bytes())
+    }
+
+    /// Removes a profile and all of its data. The default and the active
+    /// profile can't be deleted.
+    pub fn delete(&self, name: &str) -> Result<()> {
+        if name == DEFAULT_PROFILE {
+            anyhow::bail!("The default profile can't be deleted");
+        }
+        if !self.exists(name) {
+            anyhow::bail!("No profile named '{}'", name);
+        }
+        if self.active()? == name {
+            anyhow::bail!("Profile '{}' is active; switch to another profile first", name);
+        }
+        fs::remove_dir_all(self.dir(name)?)?;
+        Ok(())
+    }
+}
+
+/// Profile names become directory names, so keep them to letters, digits,
+/// `-` and `_`.
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        anyhow::bail!("Invalid profile name '{}'. Use letters, digits, '-' and '_'", name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_default_profile_is_the_root() {
+        let dir = TempDir::new().unwrap();
+        let profiles = Profiles::new(dir.path());
+        assert_eq!(profiles.list().unwrap(), vec!["default"]);
+        let (name, path) = profiles.resolve(None).unwrap();
+        assert_eq!(name, "default");
+        assert_eq!(path, dir.path());
+    }
+
+    #[test]
+    fn test_create_switch_and_delete() {
+        let dir = TempDir::new().unwrap();
+        let profiles = Profiles::new(dir.path());
+        let work = profiles.create("work").unwrap();
+        assert_eq!(work, dir.path().join("profiles").join("work"));
+        assert!(profiles.create("work").is_err());
+        assert_eq!(profiles.list().unwrap(), vec!["default", "work"]);
+
+        profiles.switch("work").unwrap();
+        assert_eq!(profiles.resolve(None).unwrap().0, "work");
+        assert_eq!(profiles.resolve(Some("default")).unwrap().0, "default");
+        assert!(profiles.delete("work").is_err());
+
+        profiles.switch("default").unwrap();
+        profiles.delete("work").unwrap();
+        assert!(!work.exists());
+        assert!(profiles.delete("default").is_err());
+    }
+
+    #[test]
+    fn test_rejects_unknown_and_invalid_names() {
+        let dir = TempDir::new().unwrap();
+        let profiles = Profiles::new(dir.path());
+        assert!(profiles.resolve(Some("nope")).is_err());
+        assert!(profiles.switch("nope").is_err());
+        assert!(profiles.create("../escape").is_err());
+        assert!(profiles.create("").is_err());
+    }
+}
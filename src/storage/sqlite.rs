@@ -0,0 +1,637 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use crate::models::{Task, Day, Category};
+use super::format::{
+    category_to_text, day_to_text, task_to_text, text_to_category, text_to_day, text_to_task,
+    text_to_trashed, trashed_to_text, archived_to_text, text_to_archived, RecordCheck,
+};
+use super::{ArchivedTask, DailyLogEntry, HistoryEntry, TaskStore, TrashedTask, UnreadableRecord};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS tasks (
+        id       TEXT PRIMARY KEY,
+        category TEXT NOT NULL,
+        body     TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS tasks_category ON tasks (category);
+
+    CREATE TABLE IF NOT EXISTS trash (
+        id         TEXT PRIMARY KEY,
+        deleted_at TEXT NOT NULL,
+        body       TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS archive (
+        id          TEXT PRIMARY KEY,
+        archived_at TEXT NOT NULL,
+        body        TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS days (
+        date TEXT PRIMARY KEY,
+        body TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS categories (
+        name TEXT PRIMARY KEY,
+        body TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS daily_log (
+        seq     INTEGER PRIMARY KEY AUTOINCREMENT,
+        date    TEXT NOT NULL,
+        task_id TEXT NOT NULL,
+        title   TEXT NOT NULL,
+        value   REAL
+    );
+    CREATE INDEX IF NOT EXISTS daily_log_task_date ON daily_log (task_id, date);
+
+    CREATE TABLE IF NOT EXISTS history_log (
+        seq       INTEGER PRIMARY KEY AUTOINCREMENT,
+        timestamp TEXT NOT NULL,
+        task_id   TEXT NOT NULL,
+        title     TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS meta (
+        key   TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+";
+
+/// SQLite backend: task, day and category records are kept in their text form
+/// (see `format`), while both completion logs live in indexed tables so streak
+/// and grid lookups don't have to scan the whole history.
+pub struct SqliteStorage {
+    conn: Connection,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Could not open database {}", path.display()))?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    fn completed_dates(&self, task_id: &str, from: NaiveDate, to: NaiveDate) -> Result<HashSet<NaiveDate>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT DISTINCT date FROM daily_log WHERE task_id = ?1 AND date >= ?2 AND date <= ?3",
+        )?;
+        let rows = stmt.query_map(params![task_id, from.to_string(), to.to_string()], |row| {
+            row.get::<_, String>(0)
+        })?;
+        let mut dates = HashSet::new();
+        for row in rows {
+            if let Ok(date) = row?.parse() {
+                dates.insert(date);
+            }
+        }
+        Ok(dates)
+    }
+}
+
+impl TaskStore for SqliteStorage {
+    // Task operations
+    fn save_task(&self, task: &Task) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO tasks (id, category, body) VALUES (?1, ?2, ?3)",
+            params![task.id, task.category, task_to_text(task)],
+        )?;
+        Ok(())
+    }
+
+    fn load_task(&self, id: &str) -> Result<Task> {
+        let body: Option<String> = self.conn
+            .query_row("SELECT body FROM tasks WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?;
+        text_to_task(&body.with_context(|| format!("Task '{}' not found", id))?)
+    }
+
+    fn delete_task(&self, id: &str) -> Result<()> {
+        let deleted = self.conn.execute("DELETE FROM tasks WHERE id = ?1", params![id])?;
+        if deleted == 0 {
+            anyhow::bail!("Task '{}' not found", id);
+        }
+        Ok(())
+    }
+
+    fn list_all_tasks(&self) -> Result<Vec<Task>> {
+        let mut stmt = self.conn.prepare("SELECT body FROM tasks")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut tasks = Vec::new();
+        for body in rows {
+            if let Ok(task) = text_to_task(&body?) {
+                tasks.push(task);
+            }
+        }
+        Ok(tasks)
+    }
+
+    fn list_tasks_by_category(&self, category: &str) -> Result<Vec<Task>> {
+        let mut stmt = self.conn.prepare("SELECT body FROM tasks WHERE category = ?1")?;
+        let rows = stmt.query_map(params![category], |row| row.get::<_, String>(0))?;
+        let mut tasks = Vec::new();
+        for body in rows {
+            if let Ok(task) = text_to_task(&body?) {
+                tasks.push(task);
+            }
+        }
+        Ok(tasks)
+    }
+
+    // Trash operations
+    fn save_trashed(&self, trashed: &TrashedTask) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO trash (id, deleted_at, body) VALUES (?1, ?2, ?3)",
+            params![trashed.task.id, trashed.deleted_at.to_rfc3339(), trashed_to_text(trashed)],
+        )?;
+        Ok(())
+    }
+
+    fn load_trashed(&self, id: &str) -> Result<TrashedTask> {
+        let body: Option<String> = self.conn
+            .query_row("SELECT body FROM trash WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?;
+        text_to_trashed(&body.with_context(|| format!("No task with ID {} in the trash", id))?)
+    }
+
+    fn list_trash(&self) -> Result<Vec<TrashedTask>> {
+        let mut stmt = self.conn.prepare("SELECT body FROM trash ORDER BY deleted_at")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut trash = Vec::new();
+        for body in rows {
+            if let Ok(trashed) = text_to_trashed(&body?) {
+                trash.push(trashed);
+            }
+        }
+        Ok(trash)
+    }
+
+    fn delete_trashed(&self, id: &str) -> Result<()> {
+        let deleted = self.conn.execute("DELETE FROM trash WHERE id = ?1", params![id])?;
+        if deleted == 0 {
+            anyhow::bail!("No task with ID {} in the trash", id);
+        }
+        Ok(())
+    }
+
+    // Archive operations
+    fn save_archived(&self, archived: &ArchivedTask) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO archive (id, archived_at, body) VALUES (?1, ?2, ?3)",
+            params![archived.task.id, archived.archived_at.to_rfc3339(), archived_to_text(archived)],
+        )?;
+        Ok(())
+    }
+
+    fn load_archived(&self, id: &str) -> Result<ArchivedTask> {
+        let body: Option<String> = self.conn
+            .query_row("SELECT body FROM archive WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?;
+        text_to_archived(&body.with_context(|| format!("No archived task with ID {}", id))?)
+    }
+
+    fn list_archived(&self) -> Result<Vec<ArchivedTask>> {
+        let mut stmt = self.conn.prepare("SELECT body FROM archive ORDER BY archived_at")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut archive = Vec::new();
+        for body in rows {
+            if let Ok(archived) = text_to_archived(&body?) {
+                archive.push(archived);
+            }
+        }
+        Ok(archive)
+    }
+
+    fn delete_archived(&self, id: &str) -> Result<()> {
+        let deleted = self.conn.execute("DELETE FROM archive WHERE id = ?1", params![id])?;
+        if deleted == 0 {
+            anyhow::bail!("No archived task with ID {}", id);
+        }
+        Ok(())
+    }
+
+    // Day operations
+    fn save_day(&self, day: &Day) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO days (date, body) VALUES (?1, ?2)",
+            params![day.date.to_string(), day_to_text(day)],
+        )?;
+        Ok(())
+    }
+
+    fn load_day(&self, date: NaiveDate) -> Result<Day> {
+        let body: Option<String> = self.conn
+            .query_row("SELECT body FROM days WHERE date = ?1", params![date.to_string()], |row| row.get(0))
+            .optional()?;
+        match body {
+            Some(body) => text_to_day(&body),
+            None => Ok(Day::new(date)),
+        }
+    }
+
+    fn list_days(&self) -> Result<Vec<Day>> {
+        let mut stmt = self.conn.prepare("SELECT body FROM days ORDER BY date")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut days = Vec::new();
+        for body in rows {
+            if let Ok(day) = text_to_day(&body?) {
+                days.push(day);
+            }
+        }
+        Ok(days)
+    }
+
+    fn delete_day(&self, date: NaiveDate) -> Result<()> {
+        self.conn.execute("DELETE FROM days WHERE date = ?1", params![date.to_string()])?;
+        Ok(())
+    }
+
+    // Category operations
+    fn save_category(&self, category: &Category) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO categories (name, body) VALUES (?1, ?2)",
+            params![category.name, category_to_text(category)],
+        )?;
+        Ok(())
+    }
+
+    fn list_categories(&self) -> Result<Vec<Category>> {
+        let mut stmt = self.conn.prepare("SELECT body FROM categories")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut categories = Vec::new();
+        for body in rows {
+            if let Ok(category) = text_to_category(&body?) {
+                categories.push(category);
+            }
+        }
+        Ok(categories)
+    }
+
+    fn delete_category(&self, name: &str) -> Result<()> {
+        self.conn.execute("DELETE FROM categories WHERE name = ?1", params![name])?;
+        Ok(())
+    }
+
+    // Daily task log operations
+    fn append_daily_entry(&self, entry: &DailyLogEntry) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO daily_log (date, task_id, title, value) VALUES (?1, ?2, ?3, ?4)",
+            params![entry.date.to_string(), entry.task_id, entry.title, entry.value],
+        )?;
+        Ok(())
+    }
+
+    fn daily_log_entries(&self) -> Result<Vec<DailyLogEntry>> {
+        let mut stmt = self.conn.prepare("SELECT date, task_id, title, value FROM daily_log ORDER BY seq")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<f64>>(3)?,
+            ))
+        })?;
+        let mut entries = Vec::new();
+        for row in rows {
+            let (date, task_id, title, value) = row?;
+            if let Ok(date) = date.parse() {
+                entries.push(DailyLogEntry { date, task_id, title, value });
+            }
+        }
+        Ok(entries)
+    }
+
+    fn remove_daily_entry(&self, entry: &DailyLogEntry) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM daily_log WHERE seq = (
+                SELECT MAX(seq) FROM daily_log
+                WHERE date = ?1 AND task_id = ?2 AND title = ?3 AND value IS ?4
+            )",
+            params![entry.date.to_string(), entry.task_id, entry.title, entry.value],
+        )?;
+        Ok(())
+    }
+
+    fn get_daily_numeric_total(&self, task_id: &str, date: NaiveDate) -> Result<f64> {
+        let total: Option<f64> = self.conn.query_row(
+            "SELECT SUM(value) FROM daily_log WHERE task_id = ?1 AND date = ?2",
+            params![task_id, date.to_string()],
+            |row| row.get(0),
+        )?;
+        Ok(total.unwrap_or(0.0))
+    }
+
+    fn is_daily_completed_on_date(&self, task_id: &str, date: NaiveDate) -> Result<bool> {
+        let found: Option<i64> = self.conn
+            .query_row(
+                "SELECT 1 FROM daily_log WHERE task_id = ?1 AND date = ?2 LIMIT 1",
+                params![task_id, date.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    // Regular task history operations
+    fn append_history_entry(&self, entry: &HistoryEntry) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO history_log (timestamp, task_id, title) VALUES (?1, ?2, ?3)",
+            params![entry.timestamp, entry.task_id, entry.title],
+        )?;
+        Ok(())
+    }
+
+    fn remove_history_entry(&self, entry: &HistoryEntry) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM history_log WHERE seq = (
+                SELECT MAX(seq) FROM history_log
+                WHERE timestamp = ?1 AND task_id = ?2 AND title = ?3
+            )",
+            params![entry.timestamp, entry.task_id, entry.title],
+        )?;
+        Ok(())
+    }
+
+    fn history_entries(&self) -> Result<Vec<HistoryEntry>> {
+        let mut stmt = self.conn.prepare("SELECT timestamp, task_id, title FROM history_log ORDER BY seq")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(HistoryEntry {
+                timestamp: row.get(0)?,
+                task_id: row.get(1)?,
+                title: row.get(2)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    fn rename_log_task_ids(&self, ids: &HashMap<String, String>) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for (from, to) in ids {
+            self.conn.execute("UPDATE daily_log SET task_id = ?2 WHERE task_id = ?1", params![from, to])?;
+            self.conn.execute("UPDATE history_log SET task_id = ?2 WHERE task_id = ?1", params![from, to])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    // Handle allocation
+    fn id_counter(&self) -> Result<u64> {
+        let value: Option<String> = self.conn
+            .query_row("SELECT value FROM meta WHERE key = 'id_counter'", [], |row| row.get(0))
+            .optional()?;
+        Ok(value.and_then(|v| v.trim().parse().ok()).unwrap_or(1))
+    }
+
+    fn set_id_counter(&self, next: u64) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES ('id_counter', ?1)",
+            params![next.to_string()],
+        )?;
+        Ok(())
+    }
+
+    // Integrity checks
+    fn unreadable_records(&self) -> Result<Vec<UnreadableRecord>> {
+        let mut unreadable = Vec::new();
+
+        let tables: [(&str, &str, RecordCheck); 5] = [
+            ("tasks", "SELECT id, body FROM tasks ORDER BY id", |t| text_to_task(t).map(|_| ())),
+            ("trash", "SELECT id, body FROM trash ORDER BY id", |t| text_to_trashed(t).map(|_| ())),
+            ("archive", "SELECT id, body FROM archive ORDER BY id", |t| text_to_archived(t).map(|_| ())),
+            ("days", "SELECT date, body FROM days ORDER BY date", |t| text_to_day(t).map(|_| ())),
+            ("categories", "SELECT name, body FROM categories ORDER BY name", |t| text_to_category(t).map(|_| ())),
+        ];
+        for (table, sql, parse) in tables {
+            let mut stmt = self.conn.prepare(sql)?;
+            let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+            for row in rows {
+                let (key, body) = row?;
+                if let Err(e) = parse(&body) {
+                    unreadable.push(UnreadableRecord { location: format!("{}/{}", table, key), error: format!("{:#}", e) });
+                }
+            }
+        }
+
+        let counter: Option<String> = self.conn
+            .query_row("SELECT value FROM meta WHERE key = 'id_counter'", [], |row| row.get(0))
+            .optional()?;
+        if counter.is_some_and(|v| v.trim().parse::<u64>().is_err()) {
+            unreadable.push(UnreadableRecord {
+                location: "meta/id_counter".to_string(),
+                error: "Not a number".to_string(),
+            });
+        }
+
+        Ok(unreadable)
+    }
+
+    fn task_records(&self) -> Result<Vec<(String, Task)>> {
+        let mut stmt = self.conn.prepare("SELECT id, body FROM tasks ORDER BY id")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        let mut records = Vec::new();
+        for row in rows {
+            let (key, body) = row?;
+            if let Ok(task) = text_to_task(&body) {
+                records.push((key, task));
+            }
+        }
+        Ok(records)
+    }
+
+    fn next_handle(&self) -> Result<String> {
+        let tx = self.conn.unchecked_transaction()?;
+        let next_id = self.id_counter()?;
+        self.set_id_counter(next_id + 1)?;
+        tx.commit()?;
+        Ok(next_id.to_string())
+    }
+
+    // Streaks and grids answered from the (task_id, date) index with one query each.
+    fn get_streak_for_task(&self, task_id: &str, as_of: NaiveDate) -> Result<u32> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT DISTINCT date FROM daily_log WHERE task_id = ?1 AND date <= ?2 ORDER BY date DESC",
+        )?;
+        let rows = stmt.query_map(params![task_id, as_of.to_string()], |row| row.get::<_, String>(0))?;
+
+        let mut streak = 0u32;
+        let mut expected = Some(as_of);
+        for row in rows {
+            let date: NaiveDate = match row?.parse() {
+                Ok(d) => d,
+                Err(_) => continue,
+            };
+            if Some(date) != expected {
+                break;
+            }
+            streak += 1;
+            expected = date.pred_opt();
+        }
+        Ok(streak)
+    }
+
+    fn get_habit_grid(&self, task_id: &str, as_of: NaiveDate, days: u32) -> Result<Vec<bool>> {
+        if days == 0 {
+            return Ok(Vec::new());
+        }
+        let start = as_of - chrono::Duration::days(days as i64 - 1);
+        let done = self.completed_dates(task_id, start, as_of)?;
+        Ok((0..days)
+            .rev()
+            .map(|i| done.contains(&(as_of - chrono::Duration::days(i as i64))))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Priority;
+    use tempfile::TempDir;
+
+    fn test_storage() -> (TempDir, SqliteStorage) {
+        let dir = TempDir::new().unwrap();
+        let storage = SqliteStorage::open(&dir.path().join("daily.db")).unwrap();
+        (dir, storage)
+    }
+
+    fn task(id: &str, title: &str) -> Task {
+        Task::new(id.to_string(), title.to_string(), Priority::Medium, "default".to_string())
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_save_load_delete_task() {
+        let (_dir, s) = test_storage();
+        s.save_task(&task("1", "Read")).unwrap();
+        assert_eq!(s.load_task("1").unwrap().title, "Read");
+        s.delete_task("1").unwrap();
+        assert!(s.load_task("1").is_err());
+        assert!(s.delete_task("1").is_err());
+    }
+
+    #[test]
+    fn test_list_tasks_by_category() {
+        let (_dir, s) = test_storage();
+        s.save_task(&task("1", "A")).unwrap();
+        s.save_task(&Task::new("2".to_string(), "B".to_string(), Priority::Low, "work".to_string())).unwrap();
+        assert_eq!(s.list_all_tasks().unwrap().len(), 2);
+        assert_eq!(s.list_tasks_by_category("work").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_days_and_categories() {
+        let (_dir, s) = test_storage();
+        let mut day = Day::new(date(2026, 4, 13));
+        day.add_task("1".to_string());
+        s.save_day(&day).unwrap();
+        assert_eq!(s.load_day(date(2026, 4, 13)).unwrap().task_ids, vec!["1"]);
+        assert!(s.load_day(date(2026, 4, 14)).unwrap().task_ids.is_empty());
+        assert_eq!(s.list_days().unwrap().len(), 1);
+
+        s.save_category(&Category::new("health".to_string()).with_identity("I move".to_string())).unwrap();
+        assert_eq!(s.list_categories().unwrap()[0].identity, Some("I move".to_string()));
+    }
+
+    #[test]
+    fn test_numeric_total_and_completion() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        s.log_daily_completion("1", "Push ups", d, Some(25.0)).unwrap();
+        s.log_daily_completion("1", "Push ups", d, Some(4.0)).unwrap();
+        assert_eq!(s.get_daily_numeric_total("1", d).unwrap(), 29.0);
+        assert_eq!(s.get_daily_numeric_total("2", d).unwrap(), 0.0);
+        assert!(s.is_daily_completed_on_date("1", d).unwrap());
+        assert!(!s.is_daily_completed_on_date("1", date(2026, 4, 14)).unwrap());
+    }
+
+    #[test]
+    fn test_streak_and_grid() {
+        let (_dir, s) = test_storage();
+        let today = date(2026, 4, 13);
+        s.log_daily_completion("1", "Run", today, None).unwrap();
+        s.log_daily_completion("1", "Run", today - chrono::Duration::days(1), None).unwrap();
+        s.log_daily_completion("1", "Run", today - chrono::Duration::days(3), None).unwrap();
+        assert_eq!(s.get_streak_for_task("1", today).unwrap(), 2);
+        assert_eq!(s.get_streak_for_task("1", today - chrono::Duration::days(2)).unwrap(), 0);
+        let grid = s.get_habit_grid("1", today, 5).unwrap();
+        assert_eq!(grid, vec![false, true, false, true, true]);
+    }
+
+    #[test]
+    fn test_id_counter() {
+        let (_dir, s) = test_storage();
+        assert_eq!(s.next_handle().unwrap(), "1");
+        assert_eq!(s.next_handle().unwrap(), "2");
+        s.set_id_counter(10).unwrap();
+        assert_eq!(s.next_handle().unwrap(), "10");
+    }
+
+    #[test]
+    fn test_unreadable_records_and_task_records() {
+        let (_dir, s) = test_storage();
+        s.save_task(&task("1", "Read")).unwrap();
+        s.conn.execute("INSERT INTO tasks (id, category, body) VALUES ('2', 'default', 'title: broken')", []).unwrap();
+
+        let unreadable = s.unreadable_records().unwrap();
+        assert_eq!(unreadable.len(), 1);
+        assert_eq!(unreadable[0].location, "tasks/2");
+        let records = s.task_records().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].0, "1");
+    }
+
+    #[test]
+    fn test_remove_log_entries_and_delete_day_category() {
+        let (_dir, s) = test_storage();
+        s.log_daily_completion("1", "Push ups", date(2026, 4, 12), Some(10.0)).unwrap();
+        s.log_daily_completion("1", "Run", date(2026, 4, 12), None).unwrap();
+        s.remove_daily_entry(&s.daily_log_entries().unwrap()[1]).unwrap();
+        s.remove_daily_entry(&s.daily_log_entries().unwrap()[0]).unwrap();
+        assert!(s.daily_log_entries().unwrap().is_empty());
+
+        s.log_task_completion("1", "Run").unwrap();
+        s.remove_history_entry(&s.history_entries().unwrap()[0]).unwrap();
+        assert!(s.history_entries().unwrap().is_empty());
+
+        let mut day = Day::new(date(2026, 4, 13));
+        day.add_task("1".to_string());
+        s.save_day(&day).unwrap();
+        s.save_category(&Category::new("fitness".to_string())).unwrap();
+        s.delete_day(date(2026, 4, 13)).unwrap();
+        s.delete_category("fitness").unwrap();
+        assert!(s.list_days().unwrap().is_empty());
+        assert!(s.list_categories().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_trash_and_restore_task() {
+        let (_dir, s) = test_storage();
+        s.save_task(&task("1", "Read")).unwrap();
+        s.trash_task("1").unwrap();
+        assert!(s.load_task("1").is_err());
+        assert_eq!(s.list_trash().unwrap()[0].task.title, "Read");
+        s.restore_task("1").unwrap();
+        assert!(s.list_trash().unwrap().is_empty());
+        assert!(s.delete_trashed("1").is_err());
+    }
+
+    #[test]
+    fn test_archive_and_unarchive_task() {
+        let (_dir, s) = test_storage();
+        s.save_task(&task("1", "Done")).unwrap();
+        s.archive_task("1").unwrap();
+        assert!(s.list_all_tasks().unwrap().is_empty());
+        assert_eq!(s.load_archived("1").unwrap().task.title, "Done");
+        s.unarchive_task("1").unwrap();
+        assert!(s.list_archived().unwrap().is_empty());
+        assert!(s.delete_archived("1").is_err());
+    }
+}
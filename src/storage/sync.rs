@@ -0,0 +1,483 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use crate::models::Day;
+use super::atomic::write_atomic;
+use super::crypto::{self, Cipher};
+use super::format::{day_to_text, text_to_archived, text_to_day, text_to_task, text_to_trashed};
+use super::lock::DEFAULT_LOCK_TIMEOUT;
+use super::{Backend, DirLock};
+
+/// Logs merged line by line rather than as one record.
+const LOG_FILES: &[&str] = &["daily.log", "history.log"];
+
+/// Name of the git remote `daily sync` pulls from and pushes to.
+pub const SYNC_REMOTE: &str = "origin";
+
+/// Branch holding the synced data.
+pub const SYNC_BRANCH: &str = "main";
+
+/// Local state that stays on each machine.
+const GITIGNORE: &str = "\
+# Written by `daily sync init`. Machine-local state that is not synced.
+.lock
+*.tmp
+journal/
+backups/
+backup-*/
+profiles/
+active_profile
+";
+
+/// Logs and records go through `daily sync merge-driver`, which understands
+/// their format.
+const GITATTRIBUTES: &str = "\
+# Written by `daily sync init`.
+daily.log merge=daily
+history.log merge=daily
+tasks/*.txt merge=daily
+trash/*.txt merge=daily
+archive/*.txt merge=daily
+days/*.txt merge=daily
+categories/*.txt merge=daily
+id_counter.txt merge=daily
+";
+
+/// What one `daily sync` did.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Local changes that had not been committed yet.
+    pub committed: bool,
+    /// Commits pulled from the remote.
+    pub pulled: usize,
+    /// Files changed on one side and deleted on the other, kept as changed.
+    pub kept: Vec<String>,
+    pub pushed: bool,
+}
+
+pub fn is_enabled(data_dir: &Path) -> bool {
+    data_dir.join(".git").is_dir()
+}
+
+/// Turns `data_dir` into a git repository syncing with `remote`, a path to a
+/// bare repository that is created if missing, then syncs once.
+/// `exe` is the `daily` binary git runs to merge record files.
+pub fn init(data_dir: &Path, remote: &Path, exe: &Path, passphrase: Option<&str>) -> Result<SyncReport> {
+    if Backend::detect(data_dir) == Backend::Sqlite {
+        anyhow::bail!("Sync needs the text backend. Run `daily migrate --to text` first.");
+    }
+    fs::create_dir_all(data_dir)?;
+    if !remote.exists() {
+        fs::create_dir_all(remote)
+            .with_context(|| format!("Could not create {}", remote.display()))?;
+        git(remote, &["init", "-q", "--bare", "-b", SYNC_BRANCH])?;
+    }
+    let remote = fs::canonicalize(remote)?;
+    let remote = remote.to_str().context("The remote path is not valid UTF-8")?;
+
+    if !is_enabled(data_dir) {
+        git(data_dir, &["init", "-q", "-b", SYNC_BRANCH])?;
+    }
+    write_atomic(&data_dir.join(".gitignore"), GITIGNORE.as_bytes())?;
+    if git(data_dir, &["remote", "get-url", SYNC_REMOTE]).is_ok() {
+        git(data_dir, &["remote", "set-url", SYNC_REMOTE, remote])?;
+    } else {
+        git(data_dir, &["remote", "add", SYNC_REMOTE, remote])?;
+    }
+    sync(data_dir, exe, passphrase)
+}
+
+/// Commits local changes, merges what the remote has and pushes the result.
+/// `passphrase` unlocks an encrypted data dir for the merge driver.
+pub fn sync(data_dir: &Path, exe: &Path, passphrase: Option<&str>) -> Result<SyncReport> {
+    if !is_enabled(data_dir) {
+        anyhow::bail!("{} is not set up for syncing. Run `daily sync init <remote>` first.", data_dir.display());
+    }
+    let _guard = DirLock::new(data_dir, DEFAULT_LOCK_TIMEOUT).acquire()?;
+    configure(data_dir, exe)?;
+    let mut report = SyncReport { committed: commit(data_dir, "daily sync")?, ..Default::default() };
+
+    git(data_dir, &["fetch", "-q", SYNC_REMOTE])?;
+    let remote_branch = format!("{}/{}", SYNC_REMOTE, SYNC_BRANCH);
+    let has_remote = git(data_dir, &["rev-parse", "-q", "--verify", &remote_branch]).is_ok();
+    if has_remote {
+        report.pulled = count(data_dir, &format!("HEAD..{}", remote_branch))?;
+        if report.pulled > 0 {
+            let args = ["merge", "-q", "--no-edit", "--allow-unrelated-histories", &remote_branch];
+            let merged = git_with(data_dir, &args, passphrase);
+            if merged.is_err() {
+                report.kept = resolve_deletions(data_dir)?;
+                git(data_dir, &["commit", "-q", "--no-edit"])?;
+            }
+        }
+    }
+
+    if !has_remote || count(data_dir, &format!("{}..HEAD", remote_branch))? > 0 {
+        git(data_dir, &["push", "-q", SYNC_REMOTE, &format!("HEAD:{}", SYNC_BRANCH)])?;
+        report.pushed = true;
+    }
+    Ok(report)
+}
+
+/// Commits everything that changed in `data_dir`. Returns false if nothing did.
+pub fn commit(data_dir: &Path, message: &str) -> Result<bool> {
+    git(data_dir, &["add", "-A"])?;
+    if git(data_dir, &["diff", "--cached", "--quiet"]).is_ok() {
+        return Ok(false);
+    }
+    git(data_dir, &["commit", "-q", "-m", message])?;
+    Ok(true)
+}
+
+/// Points the repository at the merge driver in `exe` (the binary may have
+/// moved since the last sync), routes files to it as this build expects, and
+/// gives the repository a committer identity if git has none.
+fn configure(data_dir: &Path, exe: &Path) -> Result<()> {
+    let exe = exe.to_str().context("The path to daily is not valid UTF-8")?;
+    git(data_dir, &["config", "merge.daily.name", "daily record merge"])?;
+    git(data_dir, &["config", "merge.daily.driver", &format!("{} sync merge-driver %O %A %B %P", shell_quote(exe))])?;
+    let attributes = data_dir.join(".gitattributes");
+    if fs::read_to_string(&attributes).ok().as_deref() != Some(GITATTRIBUTES) {
+        write_atomic(&attributes, GITATTRIBUTES.as_bytes())?;
+    }
+    git(data_dir, &["config", "commit.gpgsign", "false"])?;
+    if git(data_dir, &["config", "user.email"]).is_err() {
+        git(data_dir, &["config", "user.name", "daily"])?;
+        git(data_dir, &["config", "user.email", "daily@localhost"])?;
+    }
+    Ok(())
+}
+
+/// After a merge stopped on conflicts: keeps the surviving copy of every file
+/// one side changed and the other deleted. Any other conflict aborts the merge.
+fn resolve_deletions(data_dir: &Path) -> Result<Vec<String>> {
+    let unmerged = git(data_dir, &["diff", "--name-only", "--diff-filter=U"])?;
+    let paths: Vec<String> = unmerged.lines().map(|l| l.to_string()).collect();
+    let conflicts: Vec<&String> = paths.iter().filter(|p| !data_dir.join(p).exists() || both_sides_kept(data_dir, p)).collect();
+    if !conflicts.is_empty() {
+        let list = conflicts.iter().map(|p| p.as_str()).collect::<Vec<_>>().join(", ");
+        git(data_dir, &["merge", "--abort"])?;
+        anyhow::bail!(
+            "Could not merge {} automatically; nothing was changed. \
+             Resolve it in {} with git, then run `daily sync` again.",
+            list,
+            data_dir.display()
+        );
+    }
+    for path in &paths {
+        git(data_dir, &["add", "--", path])?;
+    }
+    Ok(paths)
+}
+
+/// Whether both sides still have `path`, i.e. a real content conflict rather
+/// than a change against a deletion.
+fn both_sides_kept(data_dir: &Path, path: &str) -> bool {
+    let stages = git(data_dir, &["ls-files", "-u", "--", path]).unwrap_or_default();
+    let has = |stage: &str| stages.lines().any(|l| l.split_whitespace().nth(2) == Some(stage));
+    has("2") && has("3")
+}
+
+/// Quotes `word` for the shell git runs merge drivers with.
+fn shell_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
+
+fn count(data_dir: &Path, range: &str) -> Result<usize> {
+    Ok(git(data_dir, &["rev-list", "--count", range])?.trim().parse()?)
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    git_with(dir, args, None)
+}
+
+/// Runs git, handing `passphrase` to any merge driver it starts through
+/// `DAILY_PASSPHRASE`.
+fn git_with(dir: &Path, args: &[&str], passphrase: Option<&str>) -> Result<String> {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(dir).args(args);
+    if let Some(passphrase) = passphrase {
+        command.env("DAILY_PASSPHRASE", passphrase);
+    }
+    let output = command.output().context("Could not run git. Is it installed?")?;
+    if !output.status.success() {
+        anyhow::bail!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Git merge driver: merges `theirs` into `ours` for the record at `path`
+/// (relative to the data dir), given their common `base`. Returns false,
+/// leaving `ours` alone, when the two can't be reconciled. Records of an
+/// encrypted data dir are unsealed with `cipher` and the result sealed again.
+pub fn merge_driver(base: &Path, ours: &Path, theirs: &Path, path: &str, cipher: Option<&Cipher>) -> Result<bool> {
+    let read = |p: &Path| fs::read_to_string(p).with_context(|| format!("Could not read {}", p.display()));
+    let (base, ours_text, theirs) = (read(base)?, read(ours)?, read(theirs)?);
+    let sealed = crypto::is_sealed(&ours_text) || crypto::is_sealed(&theirs);
+    let open = |text: &str| crypto::decode(cipher, text).with_context(|| format!("Could not merge {}", path));
+    if LOG_FILES.contains(&path) {
+        // Each line is sealed on its own with a fresh nonce, so lines are
+        // compared unsealed and written back as they were.
+        let lines = |text: &str| -> Result<Vec<(String, String)>> {
+            text.lines().filter(|l| !l.trim().is_empty()).map(|l| Ok((open(l)?, l.to_string()))).collect()
+        };
+        fs::write(ours, merge_lines(&lines(&base)?, &lines(&ours_text)?, &lines(&theirs)?))?;
+        return Ok(true);
+    }
+    let plain_ours = open(&ours_text)?;
+    match merge_record(path, &open(&base)?, &plain_ours, &open(&theirs)?) {
+        // Sealing again would change the file for nothing.
+        Some(merged) if merged == plain_ours => Ok(true),
+        Some(merged) => {
+            let merged = if sealed { crypto::encode(cipher, &merged) } else { merged };
+            fs::write(ours, merged)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Three-way merge of one record file. Task files keep the copy with the
+/// newest `updated_at`, day plans merge their task lists, the ID counter
+/// takes the higher value and logs merge line by line. `None` means a
+/// conflict, e.g. two machines creating different tasks with the same ID.
+pub fn merge_record(path: &str, base: &str, ours: &str, theirs: &str) -> Option<String> {
+    if ours == theirs || theirs == base {
+        return Some(ours.to_string());
+    }
+    if ours == base {
+        return Some(theirs.to_string());
+    }
+
+    if LOG_FILES.contains(&path) {
+        let lines = |text: &str| text.lines().filter(|l| !l.trim().is_empty()).map(|l| (l.to_string(), l.to_string())).collect::<Vec<_>>();
+        return Some(merge_lines(&lines(base), &lines(ours), &lines(theirs)));
+    }
+
+    let newest = |ours_key, theirs_key| Some(if theirs_key > ours_key { theirs } else { ours }.to_string());
+    match path.split('/').next()? {
+        "tasks" => {
+            let (o, t) = (text_to_task(ours).ok()?, text_to_task(theirs).ok()?);
+            if base.is_empty() && o.created_at != t.created_at {
+                return None;
+            }
+            newest(o.updated_at, t.updated_at)
+        }
+        "trash" => {
+            let (o, t) = (text_to_trashed(ours).ok()?, text_to_trashed(theirs).ok()?);
+            newest(o.deleted_at, t.deleted_at)
+        }
+        "archive" => {
+            let (o, t) = (text_to_archived(ours).ok()?, text_to_archived(theirs).ok()?);
+            newest(o.archived_at, t.archived_at)
+        }
+        "days" => {
+            let (o, t) = (text_to_day(ours).ok()?, text_to_day(theirs).ok()?);
+            let b = text_to_day(base).ok().unwrap_or_else(|| Day::new(o.date));
+            Some(day_to_text(&merge_days(&b, &o, &t)))
+        }
+        // Categories carry no timestamps, so this machine's copy wins.
+        "categories" => Some(ours.to_string()),
+        "id_counter.txt" => {
+            let (o, t) = (ours.trim().parse::<u64>().ok()?, theirs.trim().parse::<u64>().ok()?);
+            Some(o.max(t).to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Three-way merge of a log given as (what the line says, how it is written)
+/// pairs. Counted like `daily merge` does, so a line both sides added, such as
+/// the same habit completed on both machines, is kept once, and a line either
+/// side removed (as `daily undo` does) is dropped.
+fn merge_lines(base: &[(String, String)], ours: &[(String, String)], theirs: &[(String, String)]) -> String {
+    let count = |lines: &[(String, String)]| {
+        let mut counts: HashMap<String, i64> = HashMap::new();
+        for (key, _) in lines {
+            *counts.entry(key.clone()).or_default() += 1;
+        }
+        counts
+    };
+    let (b, o, t) = (count(base), count(ours), count(theirs));
+    let get = |counts: &HashMap<String, i64>, key: &str| counts.get(key).copied().unwrap_or(0);
+    let mut left: HashMap<&str, i64> = HashMap::new();
+    for key in o.keys().chain(t.keys()) {
+        let base = get(&b, key);
+        let (ours, theirs) = (get(&o, key) - base, get(&t, key) - base);
+        let change = match (ours >= 0, theirs >= 0) {
+            (true, true) => ours.max(theirs),
+            (false, false) => ours.min(theirs),
+            _ => ours + theirs,
+        };
+        left.insert(key, (base + change).max(0));
+    }
+
+    let mut merged = String::new();
+    for (key, line) in ours.iter().chain(theirs) {
+        if let Some(n) = left.get_mut(key.as_str()).filter(|n| **n > 0) {
+            *n -= 1;
+            merged.push_str(line);
+            merged.push('\n');
+        }
+    }
+    merged
+}
+
+/// A task stays planned unless one side removed it; additions from both
+/// sides are kept. Notes changed on both sides keep this machine's.
+fn merge_days(base: &Day, ours: &Day, theirs: &Day) -> Day {
+    let mut merged = Day::new(ours.date);
+    for id in ours.task_ids.iter().chain(&theirs.task_ids) {
+        let in_ours = ours.task_ids.contains(id);
+        let in_theirs = theirs.task_ids.contains(id);
+        let removed = base.task_ids.contains(id) && !(in_ours && in_theirs);
+        if !removed && !merged.task_ids.contains(id) {
+            merged.task_ids.push(id.clone());
+        }
+    }
+    merged.notes = if ours.notes == base.notes { theirs.notes.clone() } else { ours.notes.clone() };
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Priority, Task};
+    use super::super::format::task_to_text;
+    use chrono::{Duration, NaiveDate, Utc};
+
+    fn task(title: &str) -> Task {
+        Task::new("1".to_string(), title.to_string(), Priority::Medium, "default".to_string())
+    }
+
+    fn day(ids: &[&str], notes: Option<&str>) -> Day {
+        let mut day = Day::new(NaiveDate::from_ymd_opt(2026, 4, 13).unwrap());
+        day.task_ids = ids.iter().map(|id| id.to_string()).collect();
+        day.notes = notes.map(|n| n.to_string());
+        day
+    }
+
+    #[test]
+    fn test_one_sided_changes_are_taken() {
+        assert_eq!(merge_record("categories/a.txt", "x", "x", "y").unwrap(), "y");
+        assert_eq!(merge_record("categories/a.txt", "x", "y", "x").unwrap(), "y");
+        assert_eq!(merge_record("unknown", "x", "y", "y").unwrap(), "y");
+        assert!(merge_record("unknown", "x", "y", "z").is_none());
+    }
+
+    #[test]
+    fn test_newest_task_wins() {
+        let base = task("Read");
+        let mut ours = base.clone();
+        ours.title = "Read a book".to_string();
+        ours.updated_at = base.updated_at + Duration::seconds(10);
+        let mut theirs = base.clone();
+        theirs.title = "Read an article".to_string();
+        theirs.updated_at = base.updated_at + Duration::seconds(20);
+
+        let (b, o, t) = (task_to_text(&base), task_to_text(&ours), task_to_text(&theirs));
+        assert_eq!(merge_record("tasks/1.txt", &b, &o, &t).unwrap(), t);
+        assert_eq!(merge_record("tasks/1.txt", &b, &t, &o).unwrap(), t);
+    }
+
+    #[test]
+    fn test_same_id_created_twice_conflicts() {
+        let ours = task("Mine");
+        let mut theirs = task("Theirs");
+        theirs.created_at = Utc::now() + Duration::seconds(1);
+        assert!(merge_record("tasks/1.txt", "", &task_to_text(&ours), &task_to_text(&theirs)).is_none());
+    }
+
+    #[test]
+    fn test_day_plans_merge_additions_and_removals() {
+        let base = day(&["1", "2"], None);
+        let ours = day(&["1", "2", "3"], Some("busy"));
+        let theirs = day(&["1", "4"], None);
+        let merged = text_to_day(
+            &merge_record("days/2026-04-13.txt", &day_to_text(&base), &day_to_text(&ours), &day_to_text(&theirs)).unwrap(),
+        ).unwrap();
+        assert_eq!(merged.task_ids, vec!["1", "3", "4"]);
+        assert_eq!(merged.notes.as_deref(), Some("busy"));
+    }
+
+    #[test]
+    fn test_id_counter_takes_the_higher_value() {
+        assert_eq!(merge_record("id_counter.txt", "3", "5", "7").unwrap(), "7");
+    }
+
+    #[test]
+    fn test_logs_keep_lines_added_on_both_sides_once() {
+        let base = "2026-04-12 | a | Run | -\n";
+        let ours = "2026-04-12 | a | Run | -\n2026-04-13 | a | Run | -\n2026-04-13 | b | Read | -\n";
+        let theirs = "2026-04-12 | a | Run | -\n2026-04-13 | a | Run | -\n2026-04-13 | c | Swim | -\n";
+        assert_eq!(
+            merge_record("daily.log", base, ours, theirs).unwrap(),
+            "2026-04-12 | a | Run | -\n2026-04-13 | a | Run | -\n2026-04-13 | b | Read | -\n2026-04-13 | c | Swim | -\n"
+        );
+    }
+
+    #[test]
+    fn test_logs_drop_lines_removed_on_either_side() {
+        let base = "2026-04-12 | a | Run | -\n2026-04-13 | a | Run | -\n";
+        let ours = "2026-04-12 | a | Run | -\n";
+        let theirs = "2026-04-12 | a | Run | -\n2026-04-13 | a | Run | -\n2026-04-14 | a | Run | -\n";
+        assert_eq!(
+            merge_record("daily.log", base, ours, theirs).unwrap(),
+            "2026-04-12 | a | Run | -\n2026-04-14 | a | Run | -\n"
+        );
+    }
+
+    #[test]
+    fn test_driver_command_survives_quotes_in_the_path() {
+        assert_eq!(shell_quote("/opt/daily"), "'/opt/daily'");
+        let quoted = shell_quote("/home/o'brien/bin/daily");
+        assert_eq!(quoted, "'/home/o'\\''brien/bin/daily'");
+        let echoed = Command::new("sh").arg("-c").arg(format!("printf %s {}", quoted)).output().unwrap();
+        assert_eq!(String::from_utf8(echoed.stdout).unwrap(), "/home/o'brien/bin/daily");
+    }
+
+    #[test]
+    fn test_driver_merges_encrypted_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let cipher = Cipher::derive("pw", &crypto::KeyParams::generate()).unwrap();
+        let write = |name: &str, day: &Day| {
+            let path = dir.path().join(name);
+            fs::write(&path, cipher.seal(&day_to_text(day))).unwrap();
+            path
+        };
+        let base = write("base", &day(&["1"], None));
+        let ours = write("ours", &day(&["1", "2"], None));
+        let theirs = write("theirs", &day(&["1", "3"], None));
+
+        assert!(merge_driver(&base, &ours, &theirs, "days/2026-04-13.txt", None).is_err());
+        assert!(merge_driver(&base, &ours, &theirs, "days/2026-04-13.txt", Some(&cipher)).unwrap());
+        let merged = fs::read_to_string(&ours).unwrap();
+        assert!(crypto::is_sealed(&merged));
+        assert_eq!(text_to_day(&cipher.unseal(&merged).unwrap()).unwrap().task_ids, vec!["1", "2", "3"]);
+    }
+
+    #[test]
+    fn test_driver_merges_encrypted_logs_by_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let cipher = Cipher::derive("pw", &crypto::KeyParams::generate()).unwrap();
+        let write = |name: &str, lines: &[&str]| {
+            let path = dir.path().join(name);
+            fs::write(&path, lines.iter().map(|l| cipher.seal(l) + "\n").collect::<String>()).unwrap();
+            path
+        };
+        let (run, read) = ("2026-04-13 | a | Run | -", "2026-04-13 | b | Read | -");
+        let base = write("base", &[]);
+        let ours = write("ours", &[run]);
+        let theirs = write("theirs", &[run, read]);
+        let sealed_ours = fs::read_to_string(&ours).unwrap();
+
+        assert!(merge_driver(&base, &ours, &theirs, "daily.log", Some(&cipher)).unwrap());
+        let merged = fs::read_to_string(&ours).unwrap();
+        let lines: Vec<String> = merged.lines().map(|l| cipher.unseal(l).unwrap()).collect();
+        assert_eq!(lines, vec![run, read]);
+        // Lines kept from this side are written back unchanged.
+        assert!(merged.starts_with(&sealed_ours));
+    }
+}
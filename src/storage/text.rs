@@ -0,0 +1,1094 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::SystemTime;
+use crate::models::{Task, Day, Category};
+use super::format::{
+    category_to_text, daily_entry_to_line, day_to_text, history_entry_to_line,
+    line_to_daily_entry, line_to_history_entry, task_to_text, text_to_category,
+    text_to_day, text_to_task, text_to_trashed, trashed_to_text, archived_to_text, text_to_archived,
+    RecordCheck,
+};
+use super::atomic::{self, write_atomic};
+use super::crypto::{self, Cipher};
+use super::{ArchivedTask, CompletionIndex, DailyLogEntry, HistoryEntry, TaskStore, TrashedTask, UnreadableRecord};
+
+/// Plain-text backend: one `key: value` file per task, day and category under
+/// `tasks/`, `days/` and `categories/`, deleted tasks under `trash/` and
+/// archived ones under `archive/`, plus the append-only `daily.log` and
+/// `history.log`. With a cipher, record files and log lines are encrypted.
+pub struct Storage {
+    data_dir: PathBuf,
+    cipher: Option<Cipher>,
+    // Parsed `daily.log`, reused until the file's size or mtime changes.
+    index_cache: RefCell<Option<(LogStamp, Rc<CompletionIndex>)>>,
+}
+
+type LogStamp = Option<(u64, Option<SystemTime>)>;
+
+type LineCheck = fn(&str) -> bool;
+
+/// Files and directories that make up the text layout, relative to the data dir.
+pub const TEXT_LAYOUT: &[&str] = &[
+    "tasks",
+    "trash",
+    "archive",
+    "days",
+    "categories",
+    "daily.log",
+    "history.log",
+    "id_counter.txt",
+];
+
+impl Storage {
+    pub fn new(data_dir: PathBuf) -> Result<Self> {
+        fs::create_dir_all(&data_dir)?;
+        fs::create_dir_all(data_dir.join("tasks"))?;
+        fs::create_dir_all(data_dir.join("trash"))?;
+        fs::create_dir_all(data_dir.join("archive"))?;
+        fs::create_dir_all(data_dir.join("days"))?;
+        fs::create_dir_all(data_dir.join("categories"))?;
+
+        Ok(Self { data_dir, cipher: None, index_cache: RefCell::new(None) })
+    }
+
+    /// Encrypts everything written and decrypts everything read with `cipher`.
+    pub fn with_cipher(mut self, cipher: Cipher) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    fn read_record(&self, path: &Path) -> Result<String> {
+        let content = fs::read_to_string(path)?;
+        crypto::decode(self.cipher.as_ref(), &content)
+    }
+
+    fn write_record(&self, path: &Path, content: &str) -> Result<()> {
+        write_atomic(path, crypto::encode(self.cipher.as_ref(), content).as_bytes())
+    }
+
+    fn daily_log_stamp(&self) -> LogStamp {
+        fs::metadata(self.data_dir.join("daily.log"))
+            .ok()
+            .map(|m| (m.len(), m.modified().ok()))
+    }
+
+    fn append_line(&self, file_name: &str, line: &str) -> Result<()> {
+        atomic::append_line(&self.data_dir.join(file_name), &crypto::encode(self.cipher.as_ref(), line))
+    }
+
+    /// The lines of a log as stored, still encrypted if the log is.
+    fn raw_lines(&self, file_name: &str) -> Result<Vec<String>> {
+        let path = self.data_dir.join(file_name);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(content.lines().map(|l| l.to_string()).collect())
+    }
+
+    /// The lines of a log. A line that can't be decrypted is returned as
+    /// stored, so it fails to parse like any other damaged line.
+    fn read_lines(&self, file_name: &str) -> Result<Vec<String>> {
+        Ok(self.raw_lines(file_name)?
+            .into_iter()
+            .map(|l| crypto::decode(self.cipher.as_ref(), &l).unwrap_or(l))
+            .collect())
+    }
+
+    /// Rewrites a log without the last line matching `matches`.
+    fn remove_last_line(&self, file_name: &str, matches: impl Fn(&str) -> bool) -> Result<()> {
+        let mut lines = self.raw_lines(file_name)?;
+        let cipher = self.cipher.as_ref();
+        if let Some(pos) = lines.iter().rposition(|l| crypto::decode(cipher, l).is_ok_and(|l| matches(l.as_str()))) {
+            lines.remove(pos);
+            self.write_raw_lines(file_name, &lines)?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites every line `rewrite` returns a replacement for, leaving lines
+    /// it declines (or can't decrypt) exactly as stored.
+    fn rewrite_lines(&self, file_name: &str, rewrite: impl Fn(&str) -> Option<String>) -> Result<()> {
+        let cipher = self.cipher.as_ref();
+        let mut changed = false;
+        let lines: Vec<String> = self.raw_lines(file_name)?
+            .into_iter()
+            .map(|raw| match crypto::decode(cipher, &raw).ok().and_then(|l| rewrite(&l)) {
+                Some(line) => {
+                    changed = true;
+                    crypto::encode(cipher, &line)
+                }
+                None => raw,
+            })
+            .collect();
+        if changed {
+            self.write_raw_lines(file_name, &lines)?;
+        }
+        Ok(())
+    }
+
+    fn write_raw_lines(&self, file_name: &str, lines: &[String]) -> Result<()> {
+        let mut content = lines.join("\n");
+        if !lines.is_empty() {
+            content.push('\n');
+        }
+        write_atomic(&self.data_dir.join(file_name), content.as_bytes())
+    }
+
+    /// Record files in `dir`, skipping in-flight temp files.
+    fn record_files(&self, dir: &str) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = fs::read_dir(self.data_dir.join(dir))
+            .map(|entries| entries.flatten().map(|e| e.path()).collect())
+            .unwrap_or_default();
+        files.retain(|p| !atomic::is_temp_file(p));
+        files.sort();
+        files
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.data_dir).unwrap_or(path).display().to_string()
+    }
+}
+
+impl TaskStore for Storage {
+    // Task operations
+    fn save_task(&self, task: &Task) -> Result<()> {
+        let path = self.data_dir.join("tasks").join(format!("{}.txt", task.id));
+        self.write_record(&path, &task_to_text(task))
+    }
+
+    fn load_task(&self, id: &str) -> Result<Task> {
+        let path = self.data_dir.join("tasks").join(format!("{}.txt", id));
+        let content = self.read_record(&path)?;
+        text_to_task(&content)
+    }
+
+    fn delete_task(&self, id: &str) -> Result<()> {
+        let path = self.data_dir.join("tasks").join(format!("{}.txt", id));
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    fn list_all_tasks(&self) -> Result<Vec<Task>> {
+        let tasks_dir = self.data_dir.join("tasks");
+        let mut tasks = Vec::new();
+
+        if let Ok(entries) = fs::read_dir(tasks_dir) {
+            for entry in entries.flatten() {
+                if atomic::is_temp_file(&entry.path()) {
+                    continue;
+                }
+                if let Ok(content) = self.read_record(&entry.path()) {
+                    if let Ok(task) = text_to_task(&content) {
+                        tasks.push(task);
+                    }
+                }
+            }
+        }
+
+        Ok(tasks)
+    }
+
+    // Trash operations
+    fn save_trashed(&self, trashed: &TrashedTask) -> Result<()> {
+        let path = self.data_dir.join("trash").join(format!("{}.txt", trashed.task.id));
+        self.write_record(&path, &trashed_to_text(trashed))
+    }
+
+    fn load_trashed(&self, id: &str) -> Result<TrashedTask> {
+        let path = self.data_dir.join("trash").join(format!("{}.txt", id));
+        let content = self.read_record(&path)
+            .with_context(|| format!("No task with ID {} in the trash", id))?;
+        text_to_trashed(&content)
+    }
+
+    fn list_trash(&self) -> Result<Vec<TrashedTask>> {
+        let mut trash: Vec<TrashedTask> = self.record_files("trash")
+            .iter()
+            .filter_map(|path| self.read_record(path).ok())
+            .filter_map(|content| text_to_trashed(&content).ok())
+            .collect();
+        trash.sort_by_key(|t| t.deleted_at);
+        Ok(trash)
+    }
+
+    fn delete_trashed(&self, id: &str) -> Result<()> {
+        let path = self.data_dir.join("trash").join(format!("{}.txt", id));
+        fs::remove_file(path).with_context(|| format!("No task with ID {} in the trash", id))
+    }
+
+    // Archive operations
+    fn save_archived(&self, archived: &ArchivedTask) -> Result<()> {
+        let path = self.data_dir.join("archive").join(format!("{}.txt", archived.task.id));
+        self.write_record(&path, &archived_to_text(archived))
+    }
+
+    fn load_archived(&self, id: &str) -> Result<ArchivedTask> {
+        let path = self.data_dir.join("archive").join(format!("{}.txt", id));
+        let content = self.read_record(&path)
+            .with_context(|| format!("No archived task with ID {}", id))?;
+        text_to_archived(&content)
+    }
+
+    fn list_archived(&self) -> Result<Vec<ArchivedTask>> {
+        let mut archive: Vec<ArchivedTask> = self.record_files("archive")
+            .iter()
+            .filter_map(|path| self.read_record(path).ok())
+            .filter_map(|content| text_to_archived(&content).ok())
+            .collect();
+        archive.sort_by_key(|a| a.archived_at);
+        Ok(archive)
+    }
+
+    fn delete_archived(&self, id: &str) -> Result<()> {
+        let path = self.data_dir.join("archive").join(format!("{}.txt", id));
+        fs::remove_file(path).with_context(|| format!("No archived task with ID {}", id))
+    }
+
+    // Day operations
+    fn save_day(&self, day: &Day) -> Result<()> {
+        let path = self.data_dir.join("days").join(format!("{}.txt", day.date));
+        self.write_record(&path, &day_to_text(day))
+    }
+
+    fn load_day(&self, date: NaiveDate) -> Result<Day> {
+        let path = self.data_dir.join("days").join(format!("{}.txt", date));
+        if path.exists() {
+            let content = self.read_record(&path)?;
+            text_to_day(&content)
+        } else {
+            Ok(Day::new(date))
+        }
+    }
+
+    fn list_days(&self) -> Result<Vec<Day>> {
+        let days_dir = self.data_dir.join("days");
+        let mut days = Vec::new();
+
+        if let Ok(entries) = fs::read_dir(days_dir) {
+            for entry in entries.flatten() {
+                if atomic::is_temp_file(&entry.path()) {
+                    continue;
+                }
+                if let Ok(content) = self.read_record(&entry.path()) {
+                    if let Ok(day) = text_to_day(&content) {
+                        days.push(day);
+                    }
+                }
+            }
+        }
+
+        days.sort_by_key(|d| d.date);
+        Ok(days)
+    }
+
+    fn delete_day(&self, date: NaiveDate) -> Result<()> {
+        let path = self.data_dir.join("days").join(format!("{}.txt", date));
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    // Category operations
+    fn save_category(&self, category: &Category) -> Result<()> {
+        let path = self.data_dir.join("categories").join(format!("{}.txt", category.name));
+        self.write_record(&path, &category_to_text(category))
+    }
+
+    fn list_categories(&self) -> Result<Vec<Category>> {
+        let cat_dir = self.data_dir.join("categories");
+        let mut categories = Vec::new();
+
+        if let Ok(entries) = fs::read_dir(cat_dir) {
+            for entry in entries.flatten() {
+                if atomic::is_temp_file(&entry.path()) {
+                    continue;
+                }
+                if let Ok(content) = self.read_record(&entry.path()) {
+                    if let Ok(category) = text_to_category(&content) {
+                        categories.push(category);
+                    }
+                }
+            }
+        }
+
+        Ok(categories)
+    }
+
+    fn delete_category(&self, name: &str) -> Result<()> {
+        let path = self.data_dir.join("categories").join(format!("{}.txt", name));
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    // Daily task log operations
+    fn append_daily_entry(&self, entry: &DailyLogEntry) -> Result<()> {
+        self.append_line("daily.log", &daily_entry_to_line(entry))?;
+        self.index_cache.borrow_mut().take();
+        Ok(())
+    }
+
+    fn daily_log_entries(&self) -> Result<Vec<DailyLogEntry>> {
+        Ok(self.read_lines("daily.log")?
+            .iter()
+            .filter_map(|l| line_to_daily_entry(l))
+            .collect())
+    }
+
+    fn remove_daily_entry(&self, entry: &DailyLogEntry) -> Result<()> {
+        let target = daily_entry_to_line(entry);
+        self.remove_last_line("daily.log", |l| line_to_daily_entry(l).map(|e| daily_entry_to_line(&e)) == Some(target.clone()))?;
+        self.index_cache.borrow_mut().take();
+        Ok(())
+    }
+
+    fn completion_index(&self) -> Result<Rc<CompletionIndex>> {
+        let stamp = self.daily_log_stamp();
+        if let Some((cached_stamp, index)) = self.index_cache.borrow().as_ref() {
+            if *cached_stamp == stamp {
+                return Ok(Rc::clone(index));
+            }
+        }
+
+        let index = Rc::new(CompletionIndex::from_entries(&self.daily_log_entries()?));
+        *self.index_cache.borrow_mut() = Some((stamp, Rc::clone(&index)));
+        Ok(index)
+    }
+
+    // Regular task history operations
+    fn append_history_entry(&self, entry: &HistoryEntry) -> Result<()> {
+        self.append_line("history.log", &history_entry_to_line(entry))
+    }
+
+    fn history_entries(&self) -> Result<Vec<HistoryEntry>> {
+        Ok(self.read_lines("history.log")?
+            .iter()
+            .filter_map(|l| line_to_history_entry(l))
+            .collect())
+    }
+
+    fn remove_history_entry(&self, entry: &HistoryEntry) -> Result<()> {
+        let target = history_entry_to_line(entry);
+        self.remove_last_line("history.log", |l| line_to_history_entry(l).map(|e| history_entry_to_line(&e)) == Some(target.clone()))
+    }
+
+    fn rename_log_task_ids(&self, ids: &HashMap<String, String>) -> Result<()> {
+        self.rewrite_lines("daily.log", |l| {
+            let mut entry = line_to_daily_entry(l)?;
+            entry.task_id = ids.get(&entry.task_id)?.clone();
+            Some(daily_entry_to_line(&entry))
+        })?;
+        self.index_cache.borrow_mut().take();
+        self.rewrite_lines("history.log", |l| {
+            let mut entry = line_to_history_entry(l)?;
+            entry.task_id = ids.get(&entry.task_id)?.clone();
+            Some(history_entry_to_line(&entry))
+        })
+    }
+
+    // Handle allocation
+    fn id_counter(&self) -> Result<u64> {
+        let counter_path = self.data_dir.join("id_counter.txt");
+        if counter_path.exists() {
+            let content = fs::read_to_string(&counter_path)?;
+            Ok(content.trim().parse::<u64>().unwrap_or(1))
+        } else {
+            Ok(1)
+        }
+    }
+
+    fn set_id_counter(&self, next: u64) -> Result<()> {
+        write_atomic(&self.data_dir.join("id_counter.txt"), next.to_string().as_bytes())?;
+        Ok(())
+    }
+
+    // Integrity checks
+    fn unreadable_records(&self) -> Result<Vec<UnreadableRecord>> {
+        let mut unreadable = Vec::new();
+
+        let parsers: [(&str, RecordCheck); 5] = [
+            ("tasks", |t| text_to_task(t).map(|_| ())),
+            ("trash", |t| text_to_trashed(t).map(|_| ())),
+            ("archive", |t| text_to_archived(t).map(|_| ())),
+            ("days", |t| text_to_day(t).map(|_| ())),
+            ("categories", |t| text_to_category(t).map(|_| ())),
+        ];
+        for (dir, parse) in parsers {
+            for path in self.record_files(dir) {
+                let result = self.read_record(&path).and_then(|content| parse(&content));
+                if let Err(e) = result {
+                    unreadable.push(UnreadableRecord { location: self.relative(&path), error: format!("{:#}", e) });
+                }
+            }
+        }
+
+        let logs: [(&str, LineCheck); 2] = [
+            ("daily.log", |l| line_to_daily_entry(l).is_some()),
+            ("history.log", |l| line_to_history_entry(l).is_some()),
+        ];
+        for (file_name, parses) in logs {
+            for (i, line) in self.read_lines(file_name)?.iter().enumerate() {
+                if !line.trim().is_empty() && !parses(line) {
+                    unreadable.push(UnreadableRecord {
+                        location: format!("{} line {}", file_name, i + 1),
+                        error: format!("Malformed entry '{}'", line),
+                    });
+                }
+            }
+        }
+
+        let counter_path = self.data_dir.join("id_counter.txt");
+        if counter_path.exists() && fs::read_to_string(&counter_path)?.trim().parse::<u64>().is_err() {
+            unreadable.push(UnreadableRecord {
+                location: "id_counter.txt".to_string(),
+                error: "Not a number".to_string(),
+            });
+        }
+
+        Ok(unreadable)
+    }
+
+    fn task_records(&self) -> Result<Vec<(String, Task)>> {
+        let mut records = Vec::new();
+        for path in self.record_files("tasks") {
+            let key = match path.file_stem().and_then(|s| s.to_str()) {
+                Some(key) => key.to_string(),
+                None => continue,
+            };
+            if let Ok(task) = self.read_record(&path).and_then(|c| text_to_task(&c)) {
+                records.push((key, task));
+            }
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Category, Priority, Task};
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    // --- helpers ---
+
+    fn test_storage() -> (TempDir, Storage) {
+        let dir = TempDir::new().unwrap();
+        let storage = Storage::new(dir.path().to_path_buf()).unwrap();
+        (dir, storage)
+    }
+
+    fn task(id: &str, title: &str) -> Task {
+        Task::new(id.to_string(), title.to_string(), Priority::Medium, "default".to_string())
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    // --- storage setup ---
+
+    #[test]
+    fn test_new_creates_subdirectories() {
+        let dir = TempDir::new().unwrap();
+        Storage::new(dir.path().to_path_buf()).unwrap();
+        assert!(dir.path().join("tasks").is_dir());
+        assert!(dir.path().join("trash").is_dir());
+        assert!(dir.path().join("archive").is_dir());
+        assert!(dir.path().join("days").is_dir());
+        assert!(dir.path().join("categories").is_dir());
+    }
+
+    // --- task CRUD ---
+
+    #[test]
+    fn test_save_and_load_task_basic() {
+        let (_dir, s) = test_storage();
+        let t = task("1", "Buy groceries");
+        s.save_task(&t).unwrap();
+        let loaded = s.load_task("1").unwrap();
+        assert_eq!(loaded.id, "1");
+        assert_eq!(loaded.title, "Buy groceries");
+        assert_eq!(loaded.priority, Priority::Medium);
+        assert_eq!(loaded.category, "default");
+        assert!(!loaded.completed);
+        assert!(!loaded.is_daily);
+        assert!(!loaded.two_minute);
+    }
+
+    #[test]
+    fn test_save_and_load_task_all_optional_fields() {
+        let (_dir, s) = test_storage();
+        let due = Utc::now();
+        let t = Task::new("2".to_string(), "Run".to_string(), Priority::High, "health".to_string())
+            .with_description("Morning run".to_string())
+            .with_due_date(due)
+            .with_daily(true)
+            .with_scheduled_time("06:30".to_string())
+            .with_location("front door".to_string())
+            .with_habit_stack_after("1".to_string())
+            .with_two_minute(true)
+            .with_scheduled_days(vec![0, 2, 4]);
+        s.save_task(&t).unwrap();
+        let loaded = s.load_task("2").unwrap();
+        assert_eq!(loaded.description, Some("Morning run".to_string()));
+        assert!(loaded.due_date.is_some());
+        assert!(loaded.is_daily);
+        assert_eq!(loaded.scheduled_time, Some("06:30".to_string()));
+        assert_eq!(loaded.location, Some("front door".to_string()));
+        assert_eq!(loaded.habit_stack_after, Some("1".to_string()));
+        assert!(loaded.two_minute);
+        assert_eq!(loaded.scheduled_days, Some(vec![0, 2, 4]));
+    }
+
+    #[test]
+    fn test_load_task_not_found() {
+        let (_dir, s) = test_storage();
+        assert!(s.load_task("ghost").is_err());
+    }
+
+    #[test]
+    fn test_delete_task() {
+        let (_dir, s) = test_storage();
+        s.save_task(&task("1", "Gone")).unwrap();
+        s.delete_task("1").unwrap();
+        assert!(s.load_task("1").is_err());
+    }
+
+    #[test]
+    fn test_delete_task_not_found() {
+        let (_dir, s) = test_storage();
+        assert!(s.delete_task("ghost").is_err());
+    }
+
+    #[test]
+    fn test_list_all_tasks_empty() {
+        let (_dir, s) = test_storage();
+        assert!(s.list_all_tasks().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_list_all_tasks_returns_all() {
+        let (_dir, s) = test_storage();
+        s.save_task(&task("1", "A")).unwrap();
+        s.save_task(&task("2", "B")).unwrap();
+        s.save_task(&task("3", "C")).unwrap();
+        assert_eq!(s.list_all_tasks().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_list_all_tasks_skips_interrupted_writes() {
+        let (dir, s) = test_storage();
+        s.save_task(&task("1", "A")).unwrap();
+        std::fs::write(dir.path().join("tasks").join("2.txt.tmp"), "id: 2\ntitle: Half").unwrap();
+        let tasks = s.list_all_tasks().unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, "1");
+    }
+
+    #[test]
+    fn test_save_task_leaves_no_temp_files() {
+        let (dir, s) = test_storage();
+        s.save_task(&task("1", "A")).unwrap();
+        s.save_task(&task("1", "B")).unwrap();
+        let names: Vec<_> = std::fs::read_dir(dir.path().join("tasks")).unwrap()
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["1.txt"]);
+    }
+
+    #[test]
+    fn test_list_tasks_by_category() {
+        let (_dir, s) = test_storage();
+        let work = Task::new("1".to_string(), "Work".to_string(), Priority::High, "work".to_string());
+        let personal = Task::new("2".to_string(), "Personal".to_string(), Priority::Low, "personal".to_string());
+        let work2 = Task::new("3".to_string(), "Work2".to_string(), Priority::Medium, "work".to_string());
+        s.save_task(&work).unwrap();
+        s.save_task(&personal).unwrap();
+        s.save_task(&work2).unwrap();
+
+        let work_tasks = s.list_tasks_by_category("work").unwrap();
+        assert_eq!(work_tasks.len(), 2);
+        assert!(work_tasks.iter().all(|t| t.category == "work"));
+
+        let personal_tasks = s.list_tasks_by_category("personal").unwrap();
+        assert_eq!(personal_tasks.len(), 1);
+
+        assert!(s.list_tasks_by_category("nonexistent").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_task_priority_roundtrip() {
+        let (_dir, s) = test_storage();
+        let t = Task::new("1".to_string(), "T".to_string(), Priority::Critical, "w".to_string());
+        s.save_task(&t).unwrap();
+        assert_eq!(s.load_task("1").unwrap().priority, Priority::Critical);
+    }
+
+    #[test]
+    fn test_task_completed_roundtrip() {
+        let (_dir, s) = test_storage();
+        let mut t = task("1", "Done");
+        t.mark_complete();
+        s.save_task(&t).unwrap();
+        assert!(s.load_task("1").unwrap().completed);
+    }
+
+    #[test]
+    fn test_task_scheduled_days_roundtrip() {
+        let (_dir, s) = test_storage();
+        let t = task("1", "Yoga").with_daily(true).with_scheduled_days(vec![0, 2, 4]);
+        s.save_task(&t).unwrap();
+        assert_eq!(s.load_task("1").unwrap().scheduled_days, Some(vec![0, 2, 4]));
+    }
+
+    #[test]
+    fn test_task_no_scheduled_days_roundtrip() {
+        let (_dir, s) = test_storage();
+        let t = task("1", "Daily").with_daily(true);
+        s.save_task(&t).unwrap();
+        assert!(s.load_task("1").unwrap().scheduled_days.is_none());
+    }
+
+    // --- day CRUD ---
+
+    #[test]
+    fn test_save_and_load_day() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        let mut day = Day::new(d);
+        day.add_task("1".to_string());
+        day.add_task("2".to_string());
+        s.save_day(&day).unwrap();
+        let loaded = s.load_day(d).unwrap();
+        assert_eq!(loaded.date, d);
+        assert!(loaded.task_ids.contains(&"1".to_string()));
+        assert!(loaded.task_ids.contains(&"2".to_string()));
+    }
+
+    #[test]
+    fn test_load_day_missing_returns_empty() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 1, 1);
+        let day = s.load_day(d).unwrap();
+        assert_eq!(day.date, d);
+        assert!(day.task_ids.is_empty());
+    }
+
+    // --- category CRUD ---
+
+    #[test]
+    fn test_save_and_list_category() {
+        let (_dir, s) = test_storage();
+        s.save_category(&Category::new("work".to_string())).unwrap();
+        let cats = s.list_categories().unwrap();
+        assert_eq!(cats.len(), 1);
+        assert_eq!(cats[0].name, "work");
+    }
+
+    #[test]
+    fn test_list_categories_empty() {
+        let (_dir, s) = test_storage();
+        assert!(s.list_categories().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_category_description_roundtrip() {
+        let (_dir, s) = test_storage();
+        let cat = Category::new("health".to_string()).with_description("Wellbeing".to_string());
+        s.save_category(&cat).unwrap();
+        let cats = s.list_categories().unwrap();
+        assert_eq!(cats[0].description, Some("Wellbeing".to_string()));
+    }
+
+    #[test]
+    fn test_category_identity_roundtrip() {
+        let (_dir, s) = test_storage();
+        let cat = Category::new("fitness".to_string())
+            .with_identity("I am someone who moves every day".to_string());
+        s.save_category(&cat).unwrap();
+        let cats = s.list_categories().unwrap();
+        assert_eq!(cats[0].identity, Some("I am someone who moves every day".to_string()));
+    }
+
+    #[test]
+    fn test_category_all_fields_roundtrip() {
+        let (_dir, s) = test_storage();
+        let cat = Category::new("x".to_string())
+            .with_description("desc".to_string())
+            .with_identity("identity".to_string());
+        s.save_category(&cat).unwrap();
+        let cats = s.list_categories().unwrap();
+        assert_eq!(cats[0].description, Some("desc".to_string()));
+        assert_eq!(cats[0].identity, Some("identity".to_string()));
+    }
+
+    // --- daily log ---
+
+    #[test]
+    fn test_daily_not_completed_no_log_file() {
+        let (_dir, s) = test_storage();
+        assert!(!s.is_daily_completed_on_date("t1", date(2026, 4, 13)).unwrap());
+    }
+
+    #[test]
+    fn test_log_and_check_daily_completion() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        s.log_daily_completion("t1", "Run", d, None).unwrap();
+        assert!(s.is_daily_completed_on_date("t1", d).unwrap());
+    }
+
+    #[test]
+    fn test_daily_completion_wrong_id_not_matched() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        s.log_daily_completion("t1", "Run", d, None).unwrap();
+        assert!(!s.is_daily_completed_on_date("t2", d).unwrap());
+    }
+
+    #[test]
+    fn test_daily_completion_wrong_date_not_matched() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        s.log_daily_completion("t1", "Run", d, None).unwrap();
+        assert!(!s.is_daily_completed_on_date("t1", date(2026, 4, 14)).unwrap());
+    }
+
+    #[test]
+    fn test_daily_completion_multiple_tasks_same_day() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        s.log_daily_completion("t1", "Run", d, None).unwrap();
+        s.log_daily_completion("t2", "Read", d, None).unwrap();
+        assert!(s.is_daily_completed_on_date("t1", d).unwrap());
+        assert!(s.is_daily_completed_on_date("t2", d).unwrap());
+    }
+
+    #[test]
+    fn test_log_task_completion_creates_history_log() {
+        let (_dir, s) = test_storage();
+        s.log_task_completion("t1", "Finish report").unwrap();
+        let content = std::fs::read_to_string(s.data_dir.join("history.log")).unwrap();
+        assert!(content.contains("t1"));
+        assert!(content.contains("Finish report"));
+    }
+
+    // --- ID counter ---
+
+    #[test]
+    fn test_next_handle_starts_at_one() {
+        let (_dir, s) = test_storage();
+        assert_eq!(s.next_handle().unwrap(), "1");
+    }
+
+    #[test]
+    fn test_next_handle_sequential() {
+        let (_dir, s) = test_storage();
+        assert_eq!(s.next_handle().unwrap(), "1");
+        assert_eq!(s.next_handle().unwrap(), "2");
+        assert_eq!(s.next_handle().unwrap(), "3");
+    }
+
+    // --- streak ---
+
+    #[test]
+    fn test_streak_zero_with_no_completions() {
+        let (_dir, s) = test_storage();
+        assert_eq!(s.get_streak_for_task("t1", date(2026, 4, 13)).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_streak_one_day() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        s.log_daily_completion("t1", "Run", d, None).unwrap();
+        assert_eq!(s.get_streak_for_task("t1", d).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_streak_consecutive_days() {
+        let (_dir, s) = test_storage();
+        let today = date(2026, 4, 13);
+        for i in 0..5 {
+            s.log_daily_completion("t1", "Run", today - chrono::Duration::days(i), None).unwrap();
+        }
+        assert_eq!(s.get_streak_for_task("t1", today).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_streak_broken_by_gap() {
+        let (_dir, s) = test_storage();
+        let today = date(2026, 4, 13);
+        // Complete today and yesterday, gap on -2, then -3
+        s.log_daily_completion("t1", "Run", today, None).unwrap();
+        s.log_daily_completion("t1", "Run", today - chrono::Duration::days(1), None).unwrap();
+        s.log_daily_completion("t1", "Run", today - chrono::Duration::days(3), None).unwrap();
+        assert_eq!(s.get_streak_for_task("t1", today).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_habit_streak_skips_unscheduled_days() {
+        let (_dir, s) = test_storage();
+        // Mon/Wed/Fri: 2026-04-06 to 04-13, a Monday.
+        let t = task("t1", "Gym").with_daily(true).with_scheduled_days(vec![0, 2, 4]);
+        for d in [6, 8, 10, 13] {
+            s.log_daily_completion("t1", "Gym", date(2026, 4, d), None).unwrap();
+        }
+        assert_eq!(s.get_streak_for_task("t1", date(2026, 4, 13)).unwrap(), 1);
+        assert_eq!(s.get_habit_streak(&t, date(2026, 4, 13)).unwrap(), 4);
+        // Sunday is a rest day, so Friday's streak carries over it.
+        assert_eq!(s.get_habit_streak(&t, date(2026, 4, 12)).unwrap(), 3);
+        // A missed Wednesday still breaks it.
+        assert_eq!(s.get_habit_streak(&t, date(2026, 4, 16)).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_streak_ignores_other_task() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        s.log_daily_completion("t2", "Read", d, None).unwrap();
+        assert_eq!(s.get_streak_for_task("t1", d).unwrap(), 0);
+    }
+
+    // --- habit grid ---
+
+    #[test]
+    fn test_habit_grid_all_false_no_completions() {
+        let (_dir, s) = test_storage();
+        let grid = s.get_habit_grid("t1", date(2026, 4, 13), 7).unwrap();
+        assert_eq!(grid.len(), 7);
+        assert!(grid.iter().all(|&b| !b));
+    }
+
+    #[test]
+    fn test_habit_grid_single_day_true() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        s.log_daily_completion("t1", "Run", d, None).unwrap();
+        let grid = s.get_habit_grid("t1", d, 1).unwrap();
+        assert_eq!(grid, vec![true]);
+    }
+
+    #[test]
+    fn test_habit_grid_correct_positions() {
+        let (_dir, s) = test_storage();
+        let today = date(2026, 4, 13);
+        // Complete today (index 6) and 2 days ago (index 4) in a 7-day window
+        s.log_daily_completion("t1", "Run", today, None).unwrap();
+        s.log_daily_completion("t1", "Run", today - chrono::Duration::days(2), None).unwrap();
+        let grid = s.get_habit_grid("t1", today, 7).unwrap();
+        assert_eq!(grid.len(), 7);
+        assert!(grid[6]);  // today
+        assert!(!grid[5]); // yesterday
+        assert!(grid[4]);  // 2 days ago
+        assert!(!grid[3]); // 3 days ago
+    }
+
+    #[test]
+    fn test_habit_grid_length_matches_days_param() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        assert_eq!(s.get_habit_grid("t1", d, 14).unwrap().len(), 14);
+        assert_eq!(s.get_habit_grid("t1", d, 21).unwrap().len(), 21);
+        assert_eq!(s.get_habit_grid("t1", d, 1).unwrap().len(), 1);
+    }
+
+    // --- numeric total ---
+
+    #[test]
+    fn test_numeric_total_no_log_returns_zero() {
+        let (_dir, s) = test_storage();
+        assert_eq!(s.get_daily_numeric_total("t1", date(2026, 4, 13)).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_numeric_total_single_entry() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        s.log_daily_completion("t1", "Push ups", d, Some(25.0)).unwrap();
+        assert_eq!(s.get_daily_numeric_total("t1", d).unwrap(), 25.0);
+    }
+
+    #[test]
+    fn test_numeric_total_accumulates() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        s.log_daily_completion("t1", "Push ups", d, Some(25.0)).unwrap();
+        s.log_daily_completion("t1", "Push ups", d, Some(4.0)).unwrap();
+        assert_eq!(s.get_daily_numeric_total("t1", d).unwrap(), 29.0);
+    }
+
+    #[test]
+    fn test_numeric_total_ignores_other_task() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        s.log_daily_completion("t2", "Squats", d, Some(50.0)).unwrap();
+        assert_eq!(s.get_daily_numeric_total("t1", d).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_numeric_total_ignores_other_date() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        s.log_daily_completion("t1", "Push ups", d, Some(25.0)).unwrap();
+        assert_eq!(s.get_daily_numeric_total("t1", date(2026, 4, 14)).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_numeric_total_boolean_entry_contributes_zero() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        s.log_daily_completion("t1", "Run", d, None).unwrap();
+        assert_eq!(s.get_daily_numeric_total("t1", d).unwrap(), 0.0);
+    }
+
+    // --- completion index cache ---
+
+    #[test]
+    fn test_completion_index_reused_until_log_changes() {
+        let (_dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        s.log_daily_completion("t1", "Run", d, None).unwrap();
+        let first = s.completion_index().unwrap();
+        let second = s.completion_index().unwrap();
+        assert!(std::rc::Rc::ptr_eq(&first, &second));
+
+        s.log_daily_completion("t1", "Run", date(2026, 4, 12), None).unwrap();
+        assert!(!std::rc::Rc::ptr_eq(&first, &s.completion_index().unwrap()));
+        assert_eq!(s.get_streak_for_task("t1", d).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_completion_index_sees_writes_from_other_process() {
+        let (dir, s) = test_storage();
+        let d = date(2026, 4, 13);
+        assert!(!s.is_daily_completed_on_date("t1", d).unwrap());
+
+        let other = Storage::new(dir.path().to_path_buf()).unwrap();
+        other.log_daily_completion("t1", "Run", d, Some(3.0)).unwrap();
+        assert!(s.is_daily_completed_on_date("t1", d).unwrap());
+        assert_eq!(s.get_daily_numeric_total("t1", d).unwrap(), 3.0);
+    }
+
+    // --- numeric task roundtrip ---
+
+    #[test]
+    fn test_numeric_task_fields_roundtrip() {
+        let (_dir, s) = test_storage();
+        let t = task("1", "Push ups")
+            .with_numeric(true)
+            .with_unit("reps".to_string())
+            .with_target(100.0);
+        s.save_task(&t).unwrap();
+        let loaded = s.load_task("1").unwrap();
+        assert!(loaded.numeric);
+        assert_eq!(loaded.unit, Some("reps".to_string()));
+        assert_eq!(loaded.target, Some(100.0));
+    }
+
+    #[test]
+    fn test_non_numeric_task_defaults() {
+        let (_dir, s) = test_storage();
+        let t = task("1", "Read book");
+        s.save_task(&t).unwrap();
+        let loaded = s.load_task("1").unwrap();
+        assert!(!loaded.numeric);
+        assert!(loaded.unit.is_none());
+        assert!(loaded.target.is_none());
+    }
+
+    #[test]
+    fn test_remove_log_entries_takes_the_latest_match() {
+        let (dir, s) = test_storage();
+        s.log_daily_completion("1", "Run", date(2026, 4, 12), None).unwrap();
+        s.log_daily_completion("2", "Read", date(2026, 4, 12), None).unwrap();
+        s.log_daily_completion("1", "Run", date(2026, 4, 12), None).unwrap();
+        assert!(s.is_daily_completed_on_date("2", date(2026, 4, 12)).unwrap());
+
+        s.remove_daily_entry(&s.daily_log_entries().unwrap()[1]).unwrap();
+        let ids: Vec<_> = s.daily_log_entries().unwrap().into_iter().map(|e| e.task_id).collect();
+        assert_eq!(ids, vec!["1", "1"]);
+        // The cached index sees the removal.
+        assert!(!s.is_daily_completed_on_date("2", date(2026, 4, 12)).unwrap());
+
+        s.log_task_completion("1", "Run").unwrap();
+        s.remove_history_entry(&s.history_entries().unwrap()[0]).unwrap();
+        assert!(s.history_entries().unwrap().is_empty());
+        assert_eq!(fs::read_to_string(dir.path().join("history.log")).unwrap(), "");
+    }
+
+    #[test]
+    fn test_delete_day_and_category() {
+        let (_dir, s) = test_storage();
+        let mut day = Day::new(date(2026, 4, 13));
+        day.add_task("1".to_string());
+        s.save_day(&day).unwrap();
+        s.save_category(&Category::new("fitness".to_string())).unwrap();
+
+        s.delete_day(date(2026, 4, 13)).unwrap();
+        s.delete_category("fitness").unwrap();
+        assert!(s.list_days().unwrap().is_empty());
+        assert!(s.list_categories().unwrap().is_empty());
+        // Deleting what isn't there is fine.
+        s.delete_day(date(2026, 4, 13)).unwrap();
+    }
+
+    #[test]
+    fn test_trash_and_restore_task() {
+        let (_dir, s) = test_storage();
+        s.save_task(&task("1", "Read")).unwrap();
+        s.log_daily_completion("1", "Read", date(2026, 4, 13), None).unwrap();
+
+        let trashed = s.trash_task("1").unwrap();
+        assert_eq!(trashed.task.title, "Read");
+        assert!(s.load_task("1").is_err());
+        assert_eq!(s.list_trash().unwrap().len(), 1);
+        // History stays in place.
+        assert_eq!(s.daily_log_entries().unwrap().len(), 1);
+
+        let restored = s.restore_task("1").unwrap();
+        assert_eq!(restored.title, "Read");
+        assert!(s.list_trash().unwrap().is_empty());
+        assert_eq!(s.get_streak_for_task("1", date(2026, 4, 13)).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_restore_refuses_to_overwrite() {
+        let (_dir, s) = test_storage();
+        s.save_task(&task("1", "Old")).unwrap();
+        s.trash_task("1").unwrap();
+        s.save_task(&task("1", "New")).unwrap();
+        assert!(s.restore_task("1").is_err());
+        assert_eq!(s.load_task("1").unwrap().title, "New");
+        assert!(s.load_trashed("2").is_err());
+    }
+
+    #[test]
+    fn test_archive_and_unarchive_task() {
+        let (_dir, s) = test_storage();
+        let mut t = task("1", "File taxes");
+        t.mark_complete();
+        s.save_task(&t).unwrap();
+        s.save_task(&task("2", "Open")).unwrap();
+
+        s.archive_task("1").unwrap();
+        let active: Vec<_> = s.list_all_tasks().unwrap().into_iter().map(|t| t.id).collect();
+        assert_eq!(active, vec!["2"]);
+        assert_eq!(s.list_archived().unwrap()[0].task.title, "File taxes");
+
+        let restored = s.unarchive_task("1").unwrap();
+        assert!(restored.completed);
+        assert!(s.list_archived().unwrap().is_empty());
+        assert!(s.unarchive_task("1").is_err());
+    }
+}
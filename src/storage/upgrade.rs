@@ -0,0 +1,532 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+use super::atomic::write_atomic;
+use super::crypto::{self, Cipher, ENCRYPTION_FILE};
+use super::format::{daily_entry_to_line, line_to_daily_entry};
+use super::journal::JOURNAL_DIR;
+use super::lock::DEFAULT_LOCK_TIMEOUT;
+use super::merge::Stored;
+use super::text::TEXT_LAYOUT;
+use super::{open_backend, DirLock, SQLITE_FILE};
+
+/// File in the data dir recording which data format it holds.
+pub const VERSION_FILE: &str = "format_version";
+
+/// Data format written by this build. Version 1 is the original layout,
+/// which had no version file.
+pub const DATA_VERSION: u32 = 8;
+
+/// One in-place upgrade from `to - 1` to `to`. Steps from before encryption
+/// existed ignore the cipher.
+struct Step {
+    to: u32,
+    description: &'static str,
+    run: fn(&Path, Option<&Cipher>) -> Result<()>,
+}
+
+const STEPS: &[Step] = &[
+    Step {
+        to: 2,
+        description: "rewrite tasks, days and categories as escaped records",
+        run: rewrite_records,
+    },
+    Step {
+        to: 3,
+        description: "give every daily.log line a value column",
+        run: rewrite_daily_log,
+    },
+    Step {
+        to: 4,
+        description: "give every task a UUID, keeping its number as its handle; undo history starts over",
+        run: assign_uuids,
+    },
+    Step {
+        to: 5,
+        description: "allow tags on tasks",
+        run: stamp_only,
+    },
+    Step {
+        to: 6,
+        description: "allow subtasks",
+        run: stamp_only,
+    },
+    Step {
+        to: 7,
+        description: "allow task dependencies",
+        run: stamp_only,
+    },
+    Step {
+        to: 8,
+        description: "allow recurrence rules",
+        run: stamp_only,
+    },
+];
+
+/// What `ensure_current` did to bring a data dir up to date.
+#[derive(Debug)]
+pub struct UpgradeReport {
+    pub from: u32,
+    pub to: u32,
+    pub steps: Vec<&'static str>,
+    pub backup_dir: PathBuf,
+}
+
+/// The data format `data_dir` holds, or `None` if it holds no data yet.
+pub fn data_version(data_dir: &Path) -> Result<Option<u32>> {
+    let path = data_dir.join(VERSION_FILE);
+    if path.exists() {
+        let content = fs::read_to_string(&path)?;
+        let version = content.trim().parse()
+            .with_context(|| format!("Invalid data format version '{}' in {}", content.trim(), path.display()))?;
+        return Ok(Some(version));
+    }
+    Ok(if has_data(data_dir) { Some(1) } else { None })
+}
+
+/// Whether `data_dir` holds data in an older format that `ensure_current`
+/// would upgrade.
+pub fn is_pending(data_dir: &Path) -> Result<bool> {
+    Ok(data_version(data_dir)?.is_some_and(|v| v < DATA_VERSION))
+}
+
+/// Makes sure `data_dir` can be opened by this build: stamps new data dirs
+/// with the current version, upgrades older ones in place after backing them
+/// up, and refuses data written by a newer build. An encrypted data dir can
+/// only be upgraded with its `cipher`. Returns a report when an upgrade ran.
+pub fn ensure_current(data_dir: &Path, cipher: Option<&Cipher>) -> Result<Option<UpgradeReport>> {
+    ensure_version(data_dir, cipher, DATA_VERSION)
+}
+
+/// `ensure_current` for a build that supports data formats up to `supported`.
+fn ensure_version(data_dir: &Path, cipher: Option<&Cipher>, supported: u32) -> Result<Option<UpgradeReport>> {
+    if data_version(data_dir)? == Some(supported) {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(data_dir)?;
+    let _guard = DirLock::new(data_dir, DEFAULT_LOCK_TIMEOUT).acquire()?;
+
+    // Another process may have upgraded while we waited for the lock.
+    let from = match data_version(data_dir)? {
+        Some(v) if v == supported => return Ok(None),
+        Some(v) if v > supported => anyhow::bail!(
+            "{} was written by a newer version of daily (data format {}, this build supports up to {}). \
+             Upgrade daily to use it.",
+            data_dir.display(),
+            v,
+            supported
+        ),
+        Some(v) => v,
+        None => {
+            write_version(data_dir, supported)?;
+            return Ok(None);
+        }
+    };
+    if crypto::is_encrypted(data_dir) && cipher.is_none() {
+        anyhow::bail!("{} is encrypted; a passphrase is needed to upgrade it", data_dir.display());
+    }
+
+    let backup_dir = data_dir.join(format!("backup-{}-format{}", Local::now().format("%Y%m%d%H%M%S"), from));
+    copy_data(data_dir, &backup_dir)
+        .with_context(|| format!("Could not back up {} before upgrading it", data_dir.display()))?;
+
+    let mut steps = Vec::new();
+    for step in STEPS.iter().filter(|s| s.to > from && s.to <= supported) {
+        (step.run)(data_dir, cipher)
+            .with_context(|| format!("Upgrade to data format {} failed ({}); a backup is in {}",
+                step.to, step.description, backup_dir.display()))?;
+        // Record progress after every step so an interrupted upgrade resumes.
+        write_version(data_dir, step.to)?;
+        steps.push(step.description);
+    }
+
+    Ok(Some(UpgradeReport { from, to: supported, steps, backup_dir }))
+}
+
+fn write_version(data_dir: &Path, version: u32) -> Result<()> {
+    write_atomic(&data_dir.join(VERSION_FILE), format!("{}\n", version).as_bytes())
+}
+
+fn has_data(data_dir: &Path) -> bool {
+    if data_dir.join(SQLITE_FILE).exists() {
+        return true;
+    }
+    TEXT_LAYOUT.iter().any(|name| {
+        let path = data_dir.join(name);
+        if path.is_dir() {
+            fs::read_dir(&path).map(|mut entries| entries.next().is_some()).unwrap_or(false)
+        } else {
+            path.exists()
+        }
+    })
+}
+
+/// Copies the text layout, database and undo journal (not earlier backups)
+/// into `backup_dir`.
+pub(super) fn copy_data(data_dir: &Path, backup_dir: &Path) -> Result<()> {
+    fs::create_dir_all(backup_dir)?;
+    for name in TEXT_LAYOUT.iter().copied().chain([SQLITE_FILE, VERSION_FILE, ENCRYPTION_FILE, JOURNAL_DIR]) {
+        let src = data_dir.join(name);
+        if src.is_dir() {
+            fs::create_dir_all(backup_dir.join(name))?;
+            for entry in fs::read_dir(&src)?.flatten() {
+                if entry.path().is_file() {
+                    fs::copy(entry.path(), backup_dir.join(name).join(entry.file_name()))?;
+                }
+            }
+        } else if src.exists() {
+            fs::copy(&src, backup_dir.join(name))?;
+        }
+    }
+    Ok(())
+}
+
+// Format 1 -> 2: load and save every record so it is written with the
+// `format: 2` header and escaped values. Works for either backend.
+fn rewrite_records(data_dir: &Path, _cipher: Option<&Cipher>) -> Result<()> {
+    let store = open_backend(data_dir, None)?;
+    for (key, task) in store.task_records()? {
+        // Misfiled records are left for `daily doctor`; saving them here could
+        // overwrite the task they collide with.
+        if key == task.id {
+            store.save_task(&task)?;
+        }
+    }
+    for day in store.list_days()? {
+        store.save_day(&day)?;
+    }
+    for category in store.list_categories()? {
+        store.save_category(&category)?;
+    }
+    Ok(())
+}
+
+// Format 2 -> 3: rewrite `daily.log` so every line carries a value column.
+// The SQLite backend keeps values in their own column and has nothing to do.
+fn rewrite_daily_log(data_dir: &Path, _cipher: Option<&Cipher>) -> Result<()> {
+    let path = data_dir.join("daily.log");
+    if !path.exists() {
+        return Ok(());
+    }
+    let mut content = String::new();
+    for line in fs::read_to_string(&path)?.lines() {
+        // Lines that don't parse are kept as they are for `daily doctor`.
+        match line_to_daily_entry(line) {
+            Some(entry) => content.push_str(&daily_entry_to_line(&entry)),
+            None if line.trim().is_empty() => continue,
+            None => content.push_str(line),
+        }
+        content.push('\n');
+    }
+    write_atomic(&path, content.as_bytes())
+}
+
+// Format 3 -> 4: every task gets a UUID as its ID and keeps its old number
+// as its handle, so tasks created on different machines never collide. Day
+// plans, habit stacks and both logs follow the new IDs. The undo journal
+// still names the old ones, so it is cleared; `ensure_current` has copied it
+// into the backup first.
+fn assign_uuids(data_dir: &Path, cipher: Option<&Cipher>) -> Result<()> {
+    let store = open_backend(data_dir, cipher.cloned())?;
+    // Misfiled records are left for `daily doctor`, as in format 2.
+    let misfiled: HashSet<String> = store.task_records()?
+        .into_iter()
+        .filter(|(key, task)| *key != task.id)
+        .map(|(_, task)| task.id)
+        .collect();
+
+    // An interrupted run left some tasks converted already; their handles
+    // are their old IDs.
+    let (done, pending): (Vec<Stored>, Vec<Stored>) = Stored::load_all(store.as_ref())?
+        .into_iter()
+        .partition(|s| Uuid::parse_str(&s.task().id).is_ok());
+    let mut ids: HashMap<String, String> = HashMap::new();
+    let mut converted: HashMap<String, DateTime<Utc>> = HashMap::new();
+    for stored in &done {
+        ids.insert(stored.task().handle.clone(), stored.task().id.clone());
+        converted.insert(stored.task().handle.clone(), stored.task().created_at);
+    }
+
+    for old in pending {
+        let (from, created_at) = (old.task().id.clone(), old.task().created_at);
+        if matches!(old, Stored::Active(_)) && misfiled.contains(&from) {
+            continue;
+        }
+        if converted.get(&from) == Some(&created_at) {
+            // Saved under its UUID before the run stopped; drop the old copy.
+            old.remove(store.as_ref())?;
+            continue;
+        }
+        // Copies sharing one ID each become their own task with their own handle.
+        let handle = if ids.contains_key(&from) { store.next_handle()? } else { from.clone() };
+        let id = Uuid::new_v4().to_string();
+        ids.entry(from.clone()).or_insert_with(|| id.clone());
+        converted.insert(handle.clone(), created_at);
+
+        let mut new = old.clone();
+        new.task_mut().id = id;
+        new.task_mut().handle = handle;
+        new.save(store.as_ref())?;
+        old.remove(store.as_ref())?;
+    }
+
+    let map = |id: &String| ids.get(id).cloned().unwrap_or_else(|| id.clone());
+    for mut stored in Stored::load_all(store.as_ref())? {
+        let task = stored.task_mut();
+        let after = task.habit_stack_after.as_ref().map(map);
+        if after != task.habit_stack_after {
+            task.habit_stack_after = after;
+            stored.save(store.as_ref())?;
+        }
+    }
+    for mut day in store.list_days()? {
+        let task_ids: Vec<String> = day.task_ids.iter().map(map).collect();
+        if task_ids != day.task_ids {
+            day.task_ids = task_ids;
+            store.save_day(&day)?;
+        }
+    }
+    store.rename_log_task_ids(&ids)?;
+
+    let journal = data_dir.join(JOURNAL_DIR);
+    if journal.exists() {
+        fs::remove_dir_all(journal)?;
+    }
+    Ok(())
+}
+
+// Formats that only add record keys change no files. The new version keeps
+// older builds, which would drop those keys when saving, from opening the data.
+fn stamp_only(_data_dir: &Path, _cipher: Option<&Cipher>) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Priority, Task};
+    use crate::storage::{open, Storage, TaskStore};
+    use chrono::NaiveDate;
+    use tempfile::TempDir;
+
+    const V1_TASK: &str = "id: 1\ntitle: C:\\notes\npriority: High\ncategory: fitness\ncompleted: false\n\
+        created_at: 2026-04-01T06:00:00+00:00\nupdated_at: 2026-04-01T06:00:00+00:00\n\
+        is_daily: true\ntwo_minute: false";
+
+    fn write_v1(dir: &Path) {
+        fs::create_dir_all(dir.join("tasks")).unwrap();
+        fs::create_dir_all(dir.join("days")).unwrap();
+        fs::create_dir_all(dir.join("categories")).unwrap();
+        fs::write(dir.join("tasks").join("1.txt"), V1_TASK).unwrap();
+        fs::write(dir.join("days").join("2026-04-13.txt"), "date: 2026-04-13\ntasks: 1").unwrap();
+        fs::write(dir.join("categories").join("fitness.txt"), "name: fitness\nidentity: I am strong\n").unwrap();
+        fs::write(dir.join("daily.log"), "2026-04-12 | 1 | Run\n2026-04-13 | 1 | Run | 5\n").unwrap();
+        fs::write(dir.join("id_counter.txt"), "2").unwrap();
+    }
+
+    #[test]
+    fn test_fresh_dir_is_stamped_current() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(data_version(dir.path()).unwrap(), None);
+        assert!(ensure_current(dir.path(), None).unwrap().is_none());
+        assert_eq!(data_version(dir.path()).unwrap(), Some(DATA_VERSION));
+    }
+
+    #[test]
+    fn test_unversioned_data_is_format_1() {
+        let dir = TempDir::new().unwrap();
+        write_v1(dir.path());
+        assert_eq!(data_version(dir.path()).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_upgrades_v1_in_place_with_backup() {
+        let dir = TempDir::new().unwrap();
+        write_v1(dir.path());
+
+        let report = ensure_current(dir.path(), None).unwrap().unwrap();
+        assert_eq!((report.from, report.to), (1, DATA_VERSION));
+        assert_eq!(report.steps.len(), STEPS.len());
+        assert_eq!(data_version(dir.path()).unwrap(), Some(DATA_VERSION));
+
+        let store = open(dir.path().to_path_buf()).unwrap();
+        let task = store.list_all_tasks().unwrap().remove(0);
+        assert_eq!((task.handle.as_str(), task.title.as_str()), ("1", "C:\\notes"));
+        let record = fs::read_to_string(dir.path().join("tasks").join(format!("{}.txt", task.id))).unwrap();
+        assert!(record.starts_with("format: 2\n"));
+        assert!(record.contains("title: C:\\\\notes"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("daily.log")).unwrap(),
+            format!("2026-04-12 | {0} | Run | -\n2026-04-13 | {0} | Run | 5\n", task.id)
+        );
+        assert_eq!(fs::read_to_string(report.backup_dir.join("tasks").join("1.txt")).unwrap(), V1_TASK);
+
+        assert_eq!(store.get_daily_numeric_total(&task.id, NaiveDate::from_ymd_opt(2026, 4, 13).unwrap()).unwrap(), 5.0);
+        assert_eq!(store.load_day(NaiveDate::from_ymd_opt(2026, 4, 13).unwrap()).unwrap().task_ids, vec![task.id]);
+        assert_eq!(store.id_counter().unwrap(), 2);
+
+        // Already current: nothing more to do.
+        assert!(ensure_current(dir.path(), None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_resumes_after_interrupted_upgrade() {
+        let dir = TempDir::new().unwrap();
+        write_v1(dir.path());
+        write_version(dir.path(), 2).unwrap();
+        let report = ensure_current(dir.path(), None).unwrap().unwrap();
+        assert_eq!(report.from, 2);
+        assert_eq!(report.steps[..2], [
+            "give every daily.log line a value column",
+            "give every task a UUID, keeping its number as its handle; undo history starts over",
+        ]);
+    }
+
+    /// A format 3 dir with a habit stack, a trashed task and a journal.
+    fn write_v3(dir: &Path) -> Storage {
+        let store = Storage::new(dir.to_path_buf()).unwrap();
+        let task = |id: &str, title: &str| Task::new(id.into(), title.into(), Priority::Medium, "default".into());
+        store.save_task(&task("1", "Coffee")).unwrap();
+        store.save_task(&task("2", "Journal").with_habit_stack_after("1".into())).unwrap();
+        store.save_task(&task("3", "Old")).unwrap();
+        store.trash_task("3").unwrap();
+        store.set_id_counter(4).unwrap();
+        store.log_daily_completion("1", "Coffee", NaiveDate::from_ymd_opt(2026, 4, 13).unwrap(), None).unwrap();
+        store.log_task_completion("3", "Old").unwrap();
+        fs::create_dir_all(dir.join(JOURNAL_DIR)).unwrap();
+        fs::write(dir.join(JOURNAL_DIR).join("1.json"), "{}").unwrap();
+        write_version(dir, 3).unwrap();
+        store
+    }
+
+    #[test]
+    fn test_tasks_get_uuids_and_keep_their_numbers_as_handles() {
+        let dir = TempDir::new().unwrap();
+        let store = write_v3(dir.path());
+        let report = ensure_current(dir.path(), None).unwrap().unwrap();
+
+        let tasks = store.list_all_tasks().unwrap();
+        let by_handle = |h: &str| tasks.iter().find(|t| t.handle == h).unwrap().clone();
+        let (coffee, journal) = (by_handle("1"), by_handle("2"));
+        assert!(Uuid::parse_str(&coffee.id).is_ok());
+        assert_eq!(journal.habit_stack_after, Some(coffee.id.clone()));
+        let old = store.list_trash().unwrap().remove(0).task;
+        assert_eq!(old.handle, "3");
+        assert!(store.is_daily_completed_on_date(&coffee.id, NaiveDate::from_ymd_opt(2026, 4, 13).unwrap()).unwrap());
+        assert_eq!(store.history_entries().unwrap()[0].task_id, old.id);
+        assert!(!dir.path().join(JOURNAL_DIR).exists());
+        // The cleared journal is kept with the rest of the old data.
+        assert!(report.backup_dir.join(JOURNAL_DIR).join("1.json").exists());
+        assert_eq!(store.id_counter().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_interrupted_uuid_step_resumes_without_duplicates() {
+        let dir = TempDir::new().unwrap();
+        let store = write_v3(dir.path());
+        // Task 1 was saved under its UUID but its old record is still there.
+        let converted = store.load_task("1").unwrap();
+        let mut copy = converted.clone().with_handle("1".into());
+        copy.id = Uuid::new_v4().to_string();
+        store.save_task(&copy).unwrap();
+
+        ensure_current(dir.path(), None).unwrap().unwrap();
+        let tasks = store.list_all_tasks().unwrap();
+        assert_eq!(tasks.len(), 2);
+        let journal = tasks.iter().find(|t| t.handle == "2").unwrap();
+        assert_eq!(journal.habit_stack_after, Some(copy.id.clone()));
+        assert!(store.load_task("1").is_err());
+    }
+
+    #[test]
+    fn test_encrypted_dir_needs_the_cipher_to_upgrade() {
+        let dir = TempDir::new().unwrap();
+        write_v3(dir.path());
+        crypto::encrypt_dir(dir.path(), "pw").unwrap();
+        write_version(dir.path(), 3).unwrap();
+
+        let err = ensure_current(dir.path(), None).unwrap_err().to_string();
+        assert!(err.contains("passphrase"), "{}", err);
+        let cipher = crypto::unlock(dir.path(), "pw").unwrap();
+        ensure_current(dir.path(), Some(&cipher)).unwrap().unwrap();
+        let store = Storage::new(dir.path().to_path_buf()).unwrap().with_cipher(cipher);
+        assert_eq!(store.list_all_tasks().unwrap().len(), 2);
+        assert!(store.list_all_tasks().unwrap().iter().all(|t| Uuid::parse_str(&t.id).is_ok()));
+    }
+
+    #[test]
+    fn test_refuses_newer_data() {
+        let dir = TempDir::new().unwrap();
+        write_version(dir.path(), DATA_VERSION + 1).unwrap();
+        let err = ensure_current(dir.path(), None).unwrap_err().to_string();
+        assert!(err.contains("newer version of daily"));
+        assert!(open(dir.path().to_path_buf()).is_err());
+    }
+
+    /// Saves `task` with this build and checks that a build supporting only
+    /// `older` formats refuses the dir rather than dropping `key`.
+    fn assert_refused_by(older: u32, task: Task, key: &str) {
+        let dir = TempDir::new().unwrap();
+        open(dir.path().to_path_buf()).unwrap().save_task(&task).unwrap();
+        let record = fs::read_dir(dir.path().join("tasks")).unwrap().flatten().next().unwrap().path();
+        assert!(fs::read_to_string(record).unwrap().contains(&format!("\n{}: ", key)));
+
+        let err = ensure_version(dir.path(), None, older).unwrap_err().to_string();
+        assert!(err.contains("newer version of daily"), "{}", err);
+    }
+
+    fn task(title: &str) -> Task {
+        Task::new(Uuid::new_v4().to_string(), title.into(), Priority::Medium, "default".into())
+    }
+
+    #[test]
+    fn test_tags_are_refused_by_format_4_builds() {
+        assert_refused_by(4, task("Read").with_tags(vec!["books".into()]), "tags");
+    }
+
+    #[test]
+    fn test_subtasks_are_refused_by_format_5_builds() {
+        assert_refused_by(5, task("Pack").with_parent(Uuid::new_v4().to_string()), "parent");
+    }
+
+    #[test]
+    fn test_dependencies_are_refused_by_format_6_builds() {
+        assert_refused_by(6, task("Paint").with_depends_on(vec![Uuid::new_v4().to_string()]), "depends_on");
+    }
+
+    #[test]
+    fn test_recurrence_rules_are_refused_by_format_7_builds() {
+        let rule = crate::models::Recurrence::parse("FREQ=WEEKLY;BYDAY=MO").unwrap();
+        assert_refused_by(7, task("Bins").with_daily(true).with_recurrence(rule), "recurrence");
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_upgrades_sqlite_records() {
+        let dir = TempDir::new().unwrap();
+        // Create the schema, then plant a record as an older build wrote it.
+        crate::storage::SqliteStorage::open(&dir.path().join(SQLITE_FILE)).unwrap();
+        {
+            let conn = rusqlite::Connection::open(dir.path().join(SQLITE_FILE)).unwrap();
+            conn.execute("INSERT INTO tasks (id, category, body) VALUES ('1', 'fitness', ?1)", [V1_TASK]).unwrap();
+        }
+        assert_eq!(data_version(dir.path()).unwrap(), Some(1));
+
+        ensure_current(dir.path(), None).unwrap().unwrap();
+        assert!(report_backup_has_db(dir.path()));
+        let conn = rusqlite::Connection::open(dir.path().join(SQLITE_FILE)).unwrap();
+        let body: String = conn.query_row("SELECT body FROM tasks", [], |r| r.get(0)).unwrap();
+        assert!(body.starts_with("format: 2\n"));
+        assert!(body.contains("handle: 1\n"));
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn report_backup_has_db(dir: &Path) -> bool {
+        fs::read_dir(dir).unwrap().flatten()
+            .any(|e| e.file_name().to_string_lossy().starts_with("backup-") && e.path().join(SQLITE_FILE).exists())
+    }
+}
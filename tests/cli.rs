@@ -2,7 +2,7 @@
 ///
 /// Each test uses a fresh TempDir via `--data-dir` so nothing touches ~/.daily.
 use assert_cmd::Command;
-use chrono::Local;
+use chrono::{Datelike, Local};
 use predicates::prelude::*;
 use tempfile::TempDir;
 
@@ -32,6 +32,14 @@ fn add_task_get_id(dir: &TempDir, title: &str) -> String {
     panic!("could not find ID in output:\n{}", stdout);
 }
 
+/// The record file of the only task in `dir`. Records are named by UUID, not
+/// by the handle the CLI prints.
+fn only_task_file(dir: &TempDir) -> std::path::PathBuf {
+    let files: Vec<_> = std::fs::read_dir(dir.path().join("tasks")).unwrap().flatten().map(|e| e.path()).collect();
+    assert_eq!(files.len(), 1, "{:?}", files);
+    files[0].clone()
+}
+
 // ---------------------------------------------------------------------------
 // add
 // ---------------------------------------------------------------------------
@@ -248,479 +256,2421 @@ fn test_list_random_returns_one_per_category() {
 }
 
 // ---------------------------------------------------------------------------
-// complete / uncomplete
+// tags
 // ---------------------------------------------------------------------------
 
 #[test]
-fn test_complete_regular_task() {
+fn test_add_with_tags() {
     let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Finish report");
     daily(&dir)
-        .args(["complete", &id])
+        .args(["add", "Write report", "--tag", "Work", "--tag", "#q3"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("marked as complete"));
+        .stdout(predicate::str::contains("Tags: #work #q3"));
+    let content = std::fs::read_to_string(only_task_file(&dir)).unwrap();
+    assert!(content.contains("tags: work,q3"));
 }
 
 #[test]
-fn test_complete_daily_task_shows_streak() {
+fn test_add_with_invalid_tag_fails() {
     let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Morning run");
-    daily(&dir).args(["daily", &id, "true"]).assert().success();
     daily(&dir)
-        .args(["complete", &id])
+        .args(["add", "Write report", "--tag", "two words"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("completed for"));
-}
-
-#[test]
-fn test_complete_task_not_found() {
-    let dir = TempDir::new().unwrap();
-    daily(&dir).args(["complete", "999"]).assert().failure();
+        .failure()
+        .stderr(predicate::str::contains("Invalid tag 'two words'"));
 }
 
 #[test]
-fn test_complete_by_id_prefix() {
+fn test_list_filters_by_tags() {
     let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Prefix task");
-    // Use just first character of id as prefix
-    let prefix = &id[..1];
-    daily(&dir)
-        .args(["complete", prefix])
-        .assert()
-        .success();
-}
+    daily(&dir).args(["add", "Report", "--tag", "work"]).assert().success();
+    daily(&dir).args(["add", "Reorganize desk", "--tag", "work", "--tag", "someday"]).assert().success();
+    daily(&dir).args(["add", "Paint", "--tag", "home"]).assert().success();
 
-#[test]
-fn test_uncomplete_task() {
-    let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Task to undo");
-    daily(&dir).args(["complete", &id]).assert().success();
     daily(&dir)
-        .args(["uncomplete", &id])
+        .args(["list", "--tag", "work", "--tag", "-someday"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("marked as incomplete"));
+        .stdout(predicate::str::contains("Report (Priority: Medium) #work"))
+        .stdout(predicate::str::contains("Reorganize").not())
+        .stdout(predicate::str::contains("Paint").not());
 }
 
 #[test]
-fn test_uncomplete_task_not_found() {
+fn test_tag_add_and_remove() {
     let dir = TempDir::new().unwrap();
-    daily(&dir).args(["uncomplete", "999"]).assert().failure();
-}
+    let id = add_task_get_id(&dir, "Report");
 
-#[test]
-fn test_uncomplete_all() {
-    let dir = TempDir::new().unwrap();
-    let id1 = add_task_get_id(&dir, "Task 1");
-    let id2 = add_task_get_id(&dir, "Task 2");
-    daily(&dir).args(["complete", &id1]).assert().success();
-    daily(&dir).args(["complete", &id2]).assert().success();
     daily(&dir)
-        .args(["uncomplete-all"])
+        .args(["tag", "add", &id, "work"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("2 task(s) marked as incomplete"));
-}
-
-#[test]
-fn test_uncomplete_all_when_none_completed() {
-    let dir = TempDir::new().unwrap();
-    add_task(&dir, "Task 1").success();
+        .stdout(predicate::str::contains("Task 'Report' tagged #work."));
     daily(&dir)
-        .args(["uncomplete-all"])
+        .args(["tag", "add", &id, "#WORK"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("0 task(s)"));
-}
-
-// ---------------------------------------------------------------------------
-// delete
-// ---------------------------------------------------------------------------
+        .stdout(predicate::str::contains("already tagged #work"));
+    daily(&dir).args(["list", "--tag", "work"]).assert().success().stdout(predicate::str::contains("Report"));
 
-#[test]
-fn test_delete_task() {
-    let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Delete me");
     daily(&dir)
-        .args(["delete", &id])
+        .args(["tag", "remove", &id, "work"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("deleted"));
-    // Confirm it's gone
+        .stdout(predicate::str::contains("Removed #work from task 'Report'."));
     daily(&dir)
-        .args(["list"])
+        .args(["tag", "remove", &id, "work"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("No tasks found."));
+        .stdout(predicate::str::contains("isn't tagged #work"));
+    daily(&dir).args(["list", "--tag", "work"]).assert().success().stdout(predicate::str::contains("Report").not());
 }
 
 #[test]
-fn test_delete_task_not_found() {
+fn test_today_shows_tags() {
     let dir = TempDir::new().unwrap();
-    daily(&dir).args(["delete", "999"]).assert().failure();
+    daily(&dir).args(["add", "Stretch", "--daily", "--tag", "health"]).assert().success();
+    daily(&dir).args(["today"]).assert().success().stdout(predicate::str::contains("Stretch #health"));
 }
 
 // ---------------------------------------------------------------------------
-// priority
+// subtasks
 // ---------------------------------------------------------------------------
 
+fn add_subtask_get_id(dir: &TempDir, title: &str, parent: &str) -> String {
+    let output = daily(dir).args(["add", title, "--parent", parent]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    stdout.lines()
+        .find_map(|l| l.strip_prefix("ID: "))
+        .unwrap()
+        .trim()
+        .to_string()
+}
+
 #[test]
-fn test_priority_update() {
+fn test_add_subtask_inherits_category() {
     let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Needs upgrade");
+    let parent = daily(&dir).args(["add", "Prepare release", "-c", "work"]).output().unwrap();
+    assert!(parent.status.success());
     daily(&dir)
-        .args(["priority", &id, "critical"])
+        .args(["add", "Write changelog", "--parent", "1"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Critical"));
+        .stdout(predicate::str::contains("Category: work"))
+        .stdout(predicate::str::contains("Subtask of: task 1 (Prepare release)"));
 }
 
 #[test]
-fn test_priority_invalid() {
+fn test_add_subtask_of_daily_habit_fails() {
     let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Task");
+    daily(&dir).args(["add", "Stretch", "--daily"]).assert().success();
     daily(&dir)
-        .args(["priority", &id, "extreme"])
+        .args(["add", "Hamstrings", "--parent", "1"])
         .assert()
-        .failure();
+        .failure()
+        .stderr(predicate::str::contains("only one-off tasks have subtasks"));
 }
 
-// ---------------------------------------------------------------------------
-// move
-// ---------------------------------------------------------------------------
-
 #[test]
-fn test_move_task_category() {
+fn test_list_shows_subtask_tree_with_progress() {
     let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Moveable");
-    daily(&dir)
-        .args(["move", &id, "personal"])
-        .assert()
-        .success()
-        .stdout(predicate::str::contains("personal"));
+    let parent = add_task_get_id(&dir, "Prepare release");
+    let first = add_subtask_get_id(&dir, "Write changelog", &parent);
+    add_subtask_get_id(&dir, "Tag version", &parent);
+    add_task(&dir, "Unrelated").success();
+    daily(&dir).args(["complete", &first]).assert().success()
+        .stdout(predicate::str::contains("'Prepare release': 1/2 subtasks done."));
+
+    let output = daily(&dir).args(["list"]).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    let at = lines.iter().position(|l| l.contains("Prepare release [1/2]")).unwrap();
+    assert!(lines[at + 1].starts_with("    [ ]") && lines[at + 1].contains("Tag version"));
+    assert!(lines[at + 2].starts_with("    [✓]") && lines[at + 2].contains("Write changelog"));
 }
 
-// ---------------------------------------------------------------------------
-// daily (toggle)
-// ---------------------------------------------------------------------------
-
 #[test]
-fn test_set_task_daily() {
+fn test_completing_parent_completes_subtasks() {
     let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Habit candidate");
+    let parent = add_task_get_id(&dir, "Prepare release");
+    add_subtask_get_id(&dir, "Write changelog", &parent);
+    add_subtask_get_id(&dir, "Tag version", &parent);
+
     daily(&dir)
-        .args(["daily", &id, "true"])
+        .args(["complete", &parent])
         .assert()
         .success()
-        .stdout(predicate::str::contains("daily recurring task"));
+        .stdout(predicate::str::contains("Also completed 2 subtask(s)."));
+    daily(&dir).args(["list", "-i"]).assert().success().stdout(predicate::str::contains("No tasks found."));
 }
 
 #[test]
-fn test_unset_task_daily() {
+fn test_completing_last_subtask_leaves_parent_open() {
     let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "No longer daily");
-    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    let parent = add_task_get_id(&dir, "Prepare release");
+    let only = add_subtask_get_id(&dir, "Tag version", &parent);
+
     daily(&dir)
-        .args(["daily", &id, "false"])
+        .args(["complete", &only])
         .assert()
         .success()
-        .stdout(predicate::str::contains("no longer a daily"));
+        .stdout(predicate::str::contains(format!("All 1 subtasks of 'Prepare release' are done. Close it with `daily complete {}`.", parent)));
+    daily(&dir).args(["list", "-i"]).assert().success().stdout(predicate::str::contains("Prepare release [1/1]"));
 }
 
-// ---------------------------------------------------------------------------
-// category / categories
-// ---------------------------------------------------------------------------
-
 #[test]
-fn test_create_category() {
+fn test_reopening_subtask_reopens_parent() {
     let dir = TempDir::new().unwrap();
+    let parent = add_task_get_id(&dir, "Prepare release");
+    let sub = add_subtask_get_id(&dir, "Tag version", &parent);
+    daily(&dir).args(["complete", &parent]).assert().success();
+
     daily(&dir)
-        .args(["category", "work"])
+        .args(["uncomplete", &sub])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Category 'work' created!"));
+        .stdout(predicate::str::contains("Task 'Prepare release' is open again."));
+    daily(&dir).args(["list", "-i"]).assert().success().stdout(predicate::str::contains("Prepare release [0/1]"));
 }
 
 #[test]
-fn test_create_category_with_description() {
+fn test_delete_and_restore_take_subtasks_along() {
     let dir = TempDir::new().unwrap();
-    daily(&dir)
-        .args(["category", "health", "-d", "Physical wellbeing"])
-        .assert()
-        .success();
-}
+    let parent = add_task_get_id(&dir, "Prepare release");
+    add_subtask_get_id(&dir, "Tag version", &parent);
 
-#[test]
-fn test_create_category_with_identity() {
-    let dir = TempDir::new().unwrap();
     daily(&dir)
-        .args(["category", "fitness", "--identity", "I am someone who moves every day"])
+        .args(["delete", &parent])
         .assert()
-        .success();
-}
+        .success()
+        .stdout(predicate::str::contains("and 1 subtask(s) moved to the trash"));
+    daily(&dir).args(["list"]).assert().success().stdout(predicate::str::contains("No tasks found."));
 
-#[test]
-fn test_list_categories_empty() {
-    let dir = TempDir::new().unwrap();
     daily(&dir)
-        .args(["categories"])
+        .args(["trash", "restore", &parent])
         .assert()
         .success()
-        .stdout(predicate::str::contains("No categories found."));
+        .stdout(predicate::str::contains("Also restored 1 subtask(s)."));
+    daily(&dir).args(["list"]).assert().success().stdout(predicate::str::contains("Prepare release [0/1]"));
 }
 
 #[test]
-fn test_list_categories_shows_created() {
+fn test_today_shows_subtasks_under_due_parent() {
     let dir = TempDir::new().unwrap();
-    daily(&dir).args(["category", "work"]).assert().success();
-    daily(&dir).args(["category", "health"]).assert().success();
-    daily(&dir)
-        .args(["categories"])
-        .assert()
-        .success()
-        .stdout(predicate::str::contains("work"))
-        .stdout(predicate::str::contains("health"));
+    let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+    let output = daily(&dir).args(["add", "Prepare release", "--due", &today]).output().unwrap();
+    assert!(output.status.success());
+    add_subtask_get_id(&dir, "Tag version", "1");
+
+    let output = daily(&dir).args(["today"]).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    let at = lines.iter().position(|l| l.contains("Prepare release [0/1]")).unwrap();
+    assert!(lines[at + 1].starts_with("    [ ]") && lines[at + 1].contains("Tag version"));
 }
 
 // ---------------------------------------------------------------------------
-// today / day
+// dependencies
 // ---------------------------------------------------------------------------
 
 #[test]
-fn test_today_empty() {
+fn test_depend_blocks_task_until_prerequisite_is_done() {
     let dir = TempDir::new().unwrap();
+    let shoes = add_task_get_id(&dir, "Buy shoes");
+    let run = add_task_get_id(&dir, "First run");
+
     daily(&dir)
-        .args(["today"])
+        .args(["depend", &run, "--on", &shoes])
         .assert()
         .success()
-        .stdout(predicate::str::contains("No tasks scheduled"));
-}
+        .stdout(predicate::str::contains("Task 'First run' now waits for 'Buy shoes'."));
+    daily(&dir)
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("First run (Priority: Medium) [blocked by {}]", shoes)));
+    daily(&dir)
+        .args(["list", "--blocked"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("First run"))
+        .stdout(predicate::str::contains("Buy shoes").not());
+    daily(&dir)
+        .args(["list", "--ready"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Buy shoes"))
+        .stdout(predicate::str::contains("First run").not());
 
-#[test]
-fn test_today_shows_daily_tasks() {
-    let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Daily habit");
-    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    daily(&dir).args(["complete", &shoes]).assert().success();
     daily(&dir)
-        .args(["today"])
+        .args(["list", "--ready"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Daily habit"));
+        .stdout(predicate::str::contains("First run"))
+        .stdout(predicate::str::contains("blocked by").not());
 }
 
 #[test]
-fn test_today_shows_due_today() {
+fn test_depend_rejects_cycles() {
     let dir = TempDir::new().unwrap();
-    let today = Local::now().date_naive().to_string();
+    let a = add_task_get_id(&dir, "A");
+    let b = add_task_get_id(&dir, "B");
+    let c = add_task_get_id(&dir, "C");
+    daily(&dir).args(["depend", &b, "--on", &a]).assert().success();
+    daily(&dir).args(["depend", &c, "--on", &b]).assert().success();
+
     daily(&dir)
-        .args(["add", "Due today", "-D", &today])
+        .args(["depend", &a, "--on", &c])
         .assert()
-        .success();
+        .failure()
+        .stderr(predicate::str::contains(format!("that would make a cycle ({} -> {} -> {} -> {})", a, c, b, a)));
     daily(&dir)
-        .args(["today"])
+        .args(["depend", &a, "--on", &a])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("Due today"));
+        .failure()
+        .stderr(predicate::str::contains("can't depend on itself"));
 }
 
 #[test]
-fn test_today_completed_daily_shows_done() {
+fn test_depend_remove() {
     let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Done daily");
-    daily(&dir).args(["daily", &id, "true"]).assert().success();
-    daily(&dir).args(["complete", &id]).assert().success();
+    let a = add_task_get_id(&dir, "A");
+    let b = add_task_get_id(&dir, "B");
+    daily(&dir).args(["depend", &b, "--on", &a]).assert().success();
     daily(&dir)
-        .args(["today"])
+        .args(["depend", &b, "--on", &a, "--remove"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("[+]"));
+        .stdout(predicate::str::contains("Task 'B' no longer depends on 'A'."));
+    daily(&dir).args(["list", "--blocked"]).assert().success().stdout(predicate::str::contains("No tasks found."));
 }
 
 #[test]
-fn test_day_empty() {
+fn test_habit_cannot_be_a_prerequisite() {
     let dir = TempDir::new().unwrap();
+    daily(&dir).args(["add", "Stretch", "--daily"]).assert().success();
+    let b = add_task_get_id(&dir, "B");
     daily(&dir)
-        .args(["day", "2026-01-01"])
+        .args(["depend", &b, "--on", "1"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("No tasks scheduled"));
+        .failure()
+        .stderr(predicate::str::contains("only one-off tasks can be prerequisites"));
 }
 
 #[test]
-fn test_day_invalid_date_fails() {
+fn test_today_marks_blocked_tasks() {
     let dir = TempDir::new().unwrap();
-    daily(&dir).args(["day", "not-a-date"]).assert().failure();
-}
-
-// ---------------------------------------------------------------------------
-// schedule
-// ---------------------------------------------------------------------------
+    let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+    let shoes = add_task_get_id(&dir, "Buy shoes");
+    daily(&dir).args(["add", "First run", "--due", &today]).assert().success();
+    daily(&dir).args(["depend", "2", "--on", &shoes]).assert().success();
 
-#[test]
-fn test_schedule_task_for_date() {
-    let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Scheduled task");
-    daily(&dir)
-        .args(["schedule", &id, "2026-06-15"])
-        .assert()
-        .success()
-        .stdout(predicate::str::contains("scheduled for 2026-06-15"));
     daily(&dir)
-        .args(["day", "2026-06-15"])
+        .args(["today"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Scheduled task"));
+        .stdout(predicate::str::contains(format!("First run [blocked by {}]", shoes)));
 }
 
 #[test]
-fn test_schedule_task_not_found() {
+fn test_show_lists_dependency_edges() {
     let dir = TempDir::new().unwrap();
-    daily(&dir).args(["schedule", "999", "2026-06-15"]).assert().failure();
+    let shoes = add_task_get_id(&dir, "Buy shoes");
+    let run = add_task_get_id(&dir, "First run");
+    let race = add_task_get_id(&dir, "Sign up for race");
+    daily(&dir).args(["depend", &run, "--on", &shoes]).assert().success();
+    daily(&dir).args(["depend", &race, "--on", &run]).assert().success();
+
+    let output = daily(&dir).args(["show", &run]).output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Status: blocked"));
+    let depends = stdout.find("Depends on:").unwrap();
+    let blocks = stdout.find("Blocks:").unwrap();
+    assert!(stdout[depends..blocks].contains(&format!("[ ] {} - Buy shoes", shoes)));
+    assert!(stdout[blocks..].contains(&format!("[ ] {} - Sign up for race", race)));
 }
 
 // ---------------------------------------------------------------------------
-// streak
+// recurrence rules
 // ---------------------------------------------------------------------------
 
+fn weekday_name(date: chrono::NaiveDate) -> String {
+    date.format("%a").to_string().to_lowercase()
+}
+
 #[test]
-fn test_streak_no_daily_tasks() {
+fn test_add_every_stores_rule_and_starts_today() {
     let dir = TempDir::new().unwrap();
+    let today = Local::now().date_naive();
     daily(&dir)
-        .args(["streak"])
+        .args(["add", "Yoga", "--every", &format!("2 weeks on {}", weekday_name(today))])
         .assert()
         .success()
-        .stdout(predicate::str::contains("No daily habits found"));
+        .stdout(predicate::str::contains("Type: Daily recurring task"))
+        .stdout(predicate::str::contains("Repeats: every 2 weeks on"))
+        .stdout(predicate::str::contains(format!("Starting: {}", today.format("%Y-%m-%d"))));
+    let content = std::fs::read_to_string(only_task_file(&dir)).unwrap();
+    assert!(content.contains("recurrence: FREQ=WEEKLY;INTERVAL=2;BYDAY="));
+    assert!(content.contains(&format!("recurrence_start: {}", today.format("%Y-%m-%d"))));
+    daily(&dir).args(["today"]).assert().success().stdout(predicate::str::contains("Yoga (every 2 weeks on"));
 }
 
 #[test]
-fn test_streak_shows_daily_task() {
+fn test_today_skips_habits_not_due() {
     let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Morning run");
-    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    let today = Local::now().date_naive();
+    let tomorrow = today.succ_opt().unwrap();
+    daily(&dir).args(["add", "Swim", "--every", &weekday_name(tomorrow)]).assert().success();
+    daily(&dir).args(["add", "Later", "--every", "day", "--starting", &tomorrow.format("%Y-%m-%d").to_string()]).assert().success();
+    daily(&dir).args(["add", "Skipped", "--every", "day", "--except", &today.format("%Y-%m-%d").to_string()]).assert().success();
     daily(&dir)
-        .args(["streak"])
+        .args(["today"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Morning run"));
-}
-
-#[test]
-fn test_streak_after_completion() {
-    let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Meditate");
-    daily(&dir).args(["daily", &id, "true"]).assert().success();
-    daily(&dir).args(["complete", &id]).assert().success();
+        .stdout(predicate::str::contains("Swim").not())
+        .stdout(predicate::str::contains("Later").not())
+        .stdout(predicate::str::contains("Skipped").not());
     daily(&dir)
-        .args(["streak"])
+        .args(["day", &tomorrow.format("%Y-%m-%d").to_string()])
         .assert()
         .success()
-        .stdout(predicate::str::contains("[done]"));
+        .stdout(predicate::str::contains("Swim"))
+        .stdout(predicate::str::contains("Later"))
+        .stdout(predicate::str::contains("Skipped"));
 }
 
 #[test]
-fn test_streak_specific_task_id() {
+fn test_add_every_accepts_rrule() {
     let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Read");
-    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    let today = Local::now().date_naive();
     daily(&dir)
-        .args(["streak", &id])
+        .args(["add", "Pay rent", "--every", &format!("RRULE:FREQ=MONTHLY;BYMONTHDAY={}", today.format("%-d"))])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Read"));
+        .stdout(predicate::str::contains("Repeats: every month on the"));
+    daily(&dir).args(["today"]).assert().success().stdout(predicate::str::contains("Pay rent"));
 }
 
-// ---------------------------------------------------------------------------
-// habits
-// ---------------------------------------------------------------------------
-
 #[test]
-fn test_habits_no_daily_tasks() {
+fn test_add_every_rejects_bad_rules() {
     let dir = TempDir::new().unwrap();
     daily(&dir)
-        .args(["habits"])
+        .args(["add", "Yoga", "--every", "fortnightly"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("No daily habits found"));
+        .failure()
+        .stderr(predicate::str::contains("Invalid recurrence 'fortnightly'"));
+    daily(&dir)
+        .args(["add", "Yoga", "--daily", "--every", "day", "--due", "2026-05-01"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("A daily habit has no due date"));
+    daily(&dir)
+        .args(["add", "Yoga", "--until", "2026-05-01"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_huge_rrule_intervals_dont_crash() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir).args(["add", "Swim", "--every", "FREQ=WEEKLY;INTERVAL=4000000000;X-TIMES=2"]).assert().success();
+    daily(&dir)
+        .args(["add", "Launch", "--every", "FREQ=YEARLY;INTERVAL=100000", "--due", "2026-05-01"])
+        .assert()
+        .success();
+    for args in [&["today"][..], &["streak"], &["habits"], &["complete", "1"]] {
+        daily(&dir).args(args).assert().success();
+    }
+    daily(&dir)
+        .args(["complete", "2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("That was the last one"));
+}
+
+#[test]
+fn test_streak_skips_days_the_rule_skips() {
+    let dir = TempDir::new().unwrap();
+    let today = Local::now().date_naive();
+    let start = today - chrono::Duration::days(4);
+    daily(&dir)
+        .args(["add", "Run", "--every", "2 days", "--starting", &start.format("%Y-%m-%d").to_string()])
+        .assert()
+        .success();
+    let content = std::fs::read_to_string(only_task_file(&dir)).unwrap();
+    let uuid = content.lines().find_map(|l| l.strip_prefix("id: ")).unwrap().to_string();
+    let log: String = [4, 2]
+        .iter()
+        .map(|&d| format!("{} | {} | Run | -\n", today - chrono::Duration::days(d), uuid))
+        .collect();
+    std::fs::write(dir.path().join("daily.log"), log).unwrap();
+
+    daily(&dir)
+        .args(["complete", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Streak: 3 days"));
+    let output = daily(&dir).args(["habits", "--days", "5"]).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    // Week breaks add spaces between cells; ignore them.
+    let row: String = stdout.lines().find(|l| l.starts_with("Run")).unwrap().split_whitespace().collect();
+    assert!(row.contains("[+]·[+]·[+]"), "{}", row);
+    assert!(stdout.contains("·  = not due"));
+}
+
+#[test]
+fn test_frequency_habit_streak_counts_weeks() {
+    let dir = TempDir::new().unwrap();
+    let today = Local::now().date_naive();
+    let monday = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+    daily(&dir)
+        .args(["add", "Swim", "--every", "3 times per week", "--starting", &(monday - chrono::Duration::weeks(3)).format("%Y-%m-%d").to_string()])
+        .assert()
+        .success();
+    let content = std::fs::read_to_string(only_task_file(&dir)).unwrap();
+    let uuid = content.lines().find_map(|l| l.strip_prefix("id: ")).unwrap().to_string();
+    // Two weeks ago: once (missed). Last week: three times (met).
+    let log: String = [10, 7, 5, 3]
+        .iter()
+        .map(|&d| format!("{} | {} | Swim | -\n", monday - chrono::Duration::days(d), uuid))
+        .collect();
+    std::fs::write(dir.path().join("daily.log"), log).unwrap();
+
+    daily(&dir)
+        .args(["streak"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Swim — 0/3 this week — Streak: 1 week"));
+    daily(&dir)
+        .args(["complete", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Progress: 1/3 this week"))
+        .stdout(predicate::str::contains("Streak: 1 week in a row"));
+
+    let output = daily(&dir).args(["habits", "--days", "14"]).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let row = stdout.lines().find(|l| l.starts_with("Swim")).unwrap();
+    // Last week was met, so its other days weren't needed.
+    let last_week: String = row.split_whitespace().collect();
+    assert!(last_week.contains("[+]·[+]·[+]··"), "{}", row);
+    assert!(row.ends_with("1 week"), "{}", row);
+}
+
+#[test]
+fn test_frequency_habit_grid_marks_missed_weeks() {
+    let dir = TempDir::new().unwrap();
+    let today = Local::now().date_naive();
+    let monday = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+    daily(&dir)
+        .args(["add", "Swim", "--every", "2 times per week", "--starting", &(monday - chrono::Duration::weeks(2)).format("%Y-%m-%d").to_string()])
+        .assert()
+        .success();
+    let output = daily(&dir).args(["habits", "--days", "21"]).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let row = stdout.lines().find(|l| l.starts_with("Swim")).unwrap();
+    // Last week ended short: all seven days are misses.
+    assert!(row.contains("[ ][ ][ ][ ][ ][ ][ ]"), "{}", row);
+    assert!(row.ends_with("—"), "{}", row);
+}
+
+#[test]
+fn test_scheduled_days_skip_rest_days_in_streaks_and_grid() {
+    let dir = TempDir::new().unwrap();
+    let today = Local::now().date_naive();
+    let days: Vec<String> = [0, 2, 4].iter().map(|&d| weekday_name(today - chrono::Duration::days(d))).collect();
+    daily(&dir).args(["add", "Gym", "--daily", "--days", &days.join(",")]).assert().success();
+    let content = std::fs::read_to_string(only_task_file(&dir)).unwrap();
+    let uuid = content.lines().find_map(|l| l.strip_prefix("id: ")).unwrap().to_string();
+    let log: String = [4, 2]
+        .iter()
+        .map(|&d| format!("{} | {} | Gym | -\n", today - chrono::Duration::days(d), uuid))
+        .collect();
+    std::fs::write(dir.path().join("daily.log"), log).unwrap();
+
+    daily(&dir)
+        .args(["streak"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[todo] Gym — Streak: 2 days (not done today)"));
+    daily(&dir)
+        .args(["complete", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Streak: 3 days"));
+    let output = daily(&dir).args(["habits", "--days", "5"]).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let row: String = stdout.lines().find(|l| l.starts_with("Gym")).unwrap().split_whitespace().collect();
+    assert!(row.contains("[+]·[+]·[+]"), "{}", row);
+    assert!(stdout.contains("·  = not due"));
+}
+
+#[test]
+fn test_streak_on_a_rest_day() {
+    let dir = TempDir::new().unwrap();
+    let today = Local::now().date_naive();
+    let yesterday = today - chrono::Duration::days(1);
+    daily(&dir).args(["add", "Gym", "--daily", "--days", &weekday_name(yesterday)]).assert().success();
+    let content = std::fs::read_to_string(only_task_file(&dir)).unwrap();
+    let uuid = content.lines().find_map(|l| l.strip_prefix("id: ")).unwrap().to_string();
+    std::fs::write(dir.path().join("daily.log"), format!("{} | {} | Gym | -\n", yesterday, uuid)).unwrap();
+    daily(&dir)
+        .args(["streak"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[off]  Gym — Streak: 1 days (not due today)"));
+}
+
+// ---------------------------------------------------------------------------
+// recurring tasks
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_add_every_with_due_is_a_one_off_task() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir)
+        .args(["add", "Pay rent", "--every", "month on the 1st", "--due", "2026-05-01"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Repeats: every month on the 1st"))
+        .stdout(predicate::str::contains("Starting: 2026-05-01"))
+        .stdout(predicate::str::contains("Type: Daily").not());
+    let content = std::fs::read_to_string(only_task_file(&dir)).unwrap();
+    assert!(content.contains("daily: false"));
+    assert!(content.contains("recurrence: FREQ=MONTHLY;BYMONTHDAY=1"));
+    daily(&dir).args(["list"]).assert().success().stdout(predicate::str::contains("[every month on the 1st]"));
+}
+
+#[test]
+fn test_complete_recurring_task_rolls_due_date_forward() {
+    let dir = TempDir::new().unwrap();
+    let year = Local::now().year() + 1;
+    daily(&dir)
+        .args(["add", "Pay rent", "--every", "month on the 1st", "--due", &format!("{}-05-01", year)])
+        .assert()
+        .success();
+    daily(&dir)
+        .args(["complete", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Task 'Pay rent' done for {}-05-01.", year)))
+        .stdout(predicate::str::contains(format!("Next due {}-06-01", year)));
+    daily(&dir).args(["complete", "1"]).assert().success().stdout(predicate::str::contains(format!("Next due {}-07-01", year)));
+
+    let content = std::fs::read_to_string(only_task_file(&dir)).unwrap();
+    assert!(content.contains("completed: false"));
+    let history = std::fs::read_to_string(dir.path().join("history.log")).unwrap();
+    assert!(history.contains(&format!("Pay rent (due {}-05-01)", year)));
+    assert!(history.contains(&format!("Pay rent (due {}-06-01)", year)));
+    daily(&dir)
+        .args(["show", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Due: {}-07-01", year)))
+        .stdout(predicate::str::contains("History:"))
+        .stdout(predicate::str::contains(format!("Pay rent (due {}-06-01)", year)));
+}
+
+#[test]
+fn test_complete_overdue_recurring_task_skips_missed_occurrences() {
+    let dir = TempDir::new().unwrap();
+    let today = Local::now().date_naive();
+    let due = today - chrono::Duration::weeks(3);
+    daily(&dir)
+        .args(["add", "Water plants", "--every", "week", "--due", &due.to_string()])
+        .assert()
+        .success();
+    let next = today + chrono::Duration::weeks(1);
+    daily(&dir)
+        .args(["complete", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Next due {}", next.format("%Y-%m-%d"))));
+}
+
+#[test]
+fn test_uncomplete_recurring_task_keeps_next_due_date() {
+    let dir = TempDir::new().unwrap();
+    let year = Local::now().year() + 1;
+    daily(&dir)
+        .args(["add", "Pay rent", "--every", "month on the 1st", "--due", &format!("{}-05-01", year)])
+        .assert()
+        .success();
+    daily(&dir).args(["complete", "1"]).assert().success();
+    daily(&dir)
+        .args(["uncomplete", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Task 'Pay rent' isn't complete; it is next due {}-06-01.", year)))
+        .stdout(predicate::str::contains("daily undo"));
+    daily(&dir).args(["show", "1"]).assert().success().stdout(predicate::str::contains(format!("Due: {}-06-01", year)));
+
+    daily(&dir).args(["undo"]).assert().success();
+    daily(&dir).args(["show", "1"]).assert().success().stdout(predicate::str::contains(format!("Due: {}-05-01", year)));
+}
+
+#[test]
+fn test_complete_recurring_task_from_completion_date() {
+    let dir = TempDir::new().unwrap();
+    let today = Local::now().date_naive();
+    daily(&dir)
+        .args(["add", "Haircut", "--every", "6 weeks", "--due", "2026-01-10", "--from-completion"])
+        .assert()
+        .success();
+    let next = today + chrono::Duration::weeks(6);
+    daily(&dir)
+        .args(["complete", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Next due {}", next.format("%Y-%m-%d"))));
+    daily(&dir)
+        .args(["show", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Repeats: every 6 weeks, counted from completion"));
+    let content = std::fs::read_to_string(only_task_file(&dir)).unwrap();
+    assert!(content.contains("recurrence_from: completion"));
+}
+
+#[test]
+fn test_complete_recurring_task_after_its_last_occurrence() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir)
+        .args(["add", "Renew passport", "--every", "year", "--due", "2026-03-01", "--until", "2026-12-31"])
+        .assert()
+        .success();
+    daily(&dir)
+        .args(["complete", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("marked as complete! That was the last one"));
+    let content = std::fs::read_to_string(only_task_file(&dir)).unwrap();
+    assert!(content.contains("completed: true"));
+}
+
+#[test]
+fn test_recurring_task_rejects_flexible_rules_and_parents() {
+    let dir = TempDir::new().unwrap();
+    let parent = add_task_get_id(&dir, "Move house");
+    daily(&dir)
+        .args(["add", "Gym", "--every", "3 times per week", "--due", "2026-05-01"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("needs fixed days"));
+    daily(&dir)
+        .args(["add", "Pay rent", "--every", "month", "--due", "2026-05-01", "--parent", &parent])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("A recurring task can't be a subtask"));
+    daily(&dir).args(["add", "Haircut", "--every", "month", "--from-completion"]).assert().failure();
+}
+
+// ---------------------------------------------------------------------------
+// complete / uncomplete
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_complete_regular_task() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Finish report");
+    daily(&dir)
+        .args(["complete", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("marked as complete"));
+}
+
+#[test]
+fn test_complete_daily_task_shows_streak() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Morning run");
+    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    daily(&dir)
+        .args(["complete", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("completed for"));
+}
+
+#[test]
+fn test_complete_task_not_found() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir).args(["complete", "999"]).assert().failure();
+}
+
+#[test]
+fn test_complete_by_id_prefix() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Prefix task");
+    // Use just first character of id as prefix
+    let prefix = &id[..1];
+    daily(&dir)
+        .args(["complete", prefix])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_uncomplete_task() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Task to undo");
+    daily(&dir).args(["complete", &id]).assert().success();
+    daily(&dir)
+        .args(["uncomplete", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("marked as incomplete"));
+}
+
+#[test]
+fn test_uncomplete_task_not_found() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir).args(["uncomplete", "999"]).assert().failure();
+}
+
+#[test]
+fn test_uncomplete_all() {
+    let dir = TempDir::new().unwrap();
+    let id1 = add_task_get_id(&dir, "Task 1");
+    let id2 = add_task_get_id(&dir, "Task 2");
+    daily(&dir).args(["complete", &id1]).assert().success();
+    daily(&dir).args(["complete", &id2]).assert().success();
+    daily(&dir)
+        .args(["uncomplete-all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 task(s) marked as incomplete"));
+}
+
+#[test]
+fn test_uncomplete_all_when_none_completed() {
+    let dir = TempDir::new().unwrap();
+    add_task(&dir, "Task 1").success();
+    daily(&dir)
+        .args(["uncomplete-all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("0 task(s)"));
+}
+
+// ---------------------------------------------------------------------------
+// delete
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_delete_task() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Delete me");
+    daily(&dir)
+        .args(["delete", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("moved to the trash"));
+    // Confirm it's gone
+    daily(&dir)
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No tasks found."));
+}
+
+#[test]
+fn test_delete_task_not_found() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir).args(["delete", "999"]).assert().failure();
+}
+
+#[test]
+fn test_delete_all_asks_for_confirmation() {
+    let dir = TempDir::new().unwrap();
+    add_task(&dir, "Keep me").success();
+    daily(&dir)
+        .args(["delete-all"])
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Move 1 task(s) to the trash? [y/N]"))
+        .stdout(predicate::str::contains("Nothing deleted."));
+    daily(&dir).args(["list"]).assert().success().stdout(predicate::str::contains("Keep me"));
+
+    daily(&dir)
+        .args(["delete-all"])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 task(s) moved to the trash."));
+    daily(&dir).args(["list"]).assert().success().stdout(predicate::str::contains("No tasks found."));
+}
+
+#[test]
+fn test_delete_all_yes_skips_confirmation() {
+    let dir = TempDir::new().unwrap();
+    add_task(&dir, "One").success();
+    add_task(&dir, "Two").success();
+    daily(&dir)
+        .args(["delete-all", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 task(s) moved to the trash."));
+}
+
+// ---------------------------------------------------------------------------
+// archive
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_archive_completed_tasks() {
+    let dir = TempDir::new().unwrap();
+    let done = add_task_get_id(&dir, "File taxes");
+    add_task(&dir, "Still open").success();
+    daily(&dir).args(["complete", &done]).assert().success();
+
+    daily(&dir)
+        .args(["archive"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Archived 1 task(s)"));
+    daily(&dir)
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Still open"))
+        .stdout(predicate::str::contains("File taxes").not());
+    daily(&dir)
+        .args(["list", "--archived"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("File taxes"))
+        .stdout(predicate::str::contains("Still open").not());
+
+    daily(&dir).args(["unarchive", &done]).assert().success();
+    daily(&dir).args(["list"]).assert().success().stdout(predicate::str::contains("File taxes"));
+}
+
+#[test]
+fn test_archive_older_than_keeps_recent_completions() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Just done");
+    daily(&dir).args(["complete", &id]).assert().success();
+    daily(&dir)
+        .args(["archive", "--older-than", "7d"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Archived 0 task(s)"));
+}
+
+#[test]
+fn test_archive_refuses_open_task_and_habits() {
+    let dir = TempDir::new().unwrap();
+    let open = add_task_get_id(&dir, "Open");
+    daily(&dir)
+        .args(["archive", &open])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not completed"));
+
+    let habit = add_task_get_id(&dir, "Stretch");
+    daily(&dir).args(["daily", &habit, "true"]).assert().success();
+    daily(&dir).args(["complete", &habit]).assert().success();
+    daily(&dir).args(["archive", &habit]).assert().failure();
+    daily(&dir).args(["archive"]).assert().success().stdout(predicate::str::contains("Archived 0 task(s)"));
+}
+
+// ---------------------------------------------------------------------------
+// trash
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_trash_list_and_restore() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Oops");
+    daily(&dir).args(["delete", &id]).assert().success();
+    daily(&dir)
+        .args(["trash", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("[{}] Oops (deleted ", id)));
+
+    daily(&dir)
+        .args(["trash", "restore", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Task 'Oops' restored"));
+    daily(&dir).args(["list"]).assert().success().stdout(predicate::str::contains("Oops"));
+    daily(&dir).args(["trash", "list"]).assert().success().stdout(predicate::str::contains("The trash is empty."));
+}
+
+#[test]
+fn test_restored_habit_keeps_its_streak() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Meditate");
+    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    daily(&dir).args(["complete", &id]).assert().success();
+    daily(&dir).args(["delete", &id]).assert().success();
+    daily(&dir).args(["trash", "restore", &id]).assert().success();
+    daily(&dir)
+        .args(["streak", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 day"));
+}
+
+#[test]
+fn test_trash_restore_unknown_id() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir).args(["trash", "restore", "42"]).assert().failure();
+}
+
+#[test]
+fn test_trash_empty_older_than() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Recent");
+    daily(&dir).args(["delete", &id]).assert().success();
+    daily(&dir)
+        .args(["trash", "empty", "--older-than", "30d"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Permanently removed 0 task(s)"));
+    daily(&dir)
+        .args(["trash", "empty"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Permanently removed 1 task(s)"));
+    daily(&dir).args(["trash", "restore", &id]).assert().failure();
+    daily(&dir).args(["trash", "empty", "--older-than", "soon"]).assert().failure();
+}
+
+// ---------------------------------------------------------------------------
+// priority
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_priority_update() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Needs upgrade");
+    daily(&dir)
+        .args(["priority", &id, "critical"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Critical"));
+}
+
+#[test]
+fn test_priority_invalid() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Task");
+    daily(&dir)
+        .args(["priority", &id, "extreme"])
+        .assert()
+        .failure();
+}
+
+// ---------------------------------------------------------------------------
+// move
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_move_task_category() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Moveable");
+    daily(&dir)
+        .args(["move", &id, "personal"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("personal"));
+}
+
+// ---------------------------------------------------------------------------
+// daily (toggle)
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_set_task_daily() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Habit candidate");
+    daily(&dir)
+        .args(["daily", &id, "true"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("daily recurring task"));
+}
+
+#[test]
+fn test_unset_task_daily() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "No longer daily");
+    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    daily(&dir)
+        .args(["daily", &id, "false"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("no longer a daily"));
+}
+
+// ---------------------------------------------------------------------------
+// category / categories
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_create_category() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir)
+        .args(["category", "work"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Category 'work' created!"));
+}
+
+#[test]
+fn test_create_category_with_description() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir)
+        .args(["category", "health", "-d", "Physical wellbeing"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_create_category_with_identity() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir)
+        .args(["category", "fitness", "--identity", "I am someone who moves every day"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_list_categories_empty() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir)
+        .args(["categories"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No categories found."));
+}
+
+#[test]
+fn test_list_categories_shows_created() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir).args(["category", "work"]).assert().success();
+    daily(&dir).args(["category", "health"]).assert().success();
+    daily(&dir)
+        .args(["categories"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("work"))
+        .stdout(predicate::str::contains("health"));
+}
+
+// ---------------------------------------------------------------------------
+// today / day
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_today_empty() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir)
+        .args(["today"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No tasks scheduled"));
+}
+
+#[test]
+fn test_today_shows_daily_tasks() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Daily habit");
+    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    daily(&dir)
+        .args(["today"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Daily habit"));
+}
+
+#[test]
+fn test_today_shows_due_today() {
+    let dir = TempDir::new().unwrap();
+    let today = Local::now().date_naive().to_string();
+    daily(&dir)
+        .args(["add", "Due today", "-D", &today])
+        .assert()
+        .success();
+    daily(&dir)
+        .args(["today"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Due today"));
+}
+
+#[test]
+fn test_today_completed_daily_shows_done() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Done daily");
+    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    daily(&dir).args(["complete", &id]).assert().success();
+    daily(&dir)
+        .args(["today"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[+]"));
+}
+
+#[test]
+fn test_day_empty() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir)
+        .args(["day", "2026-01-01"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No tasks scheduled"));
+}
+
+#[test]
+fn test_day_invalid_date_fails() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir).args(["day", "not-a-date"]).assert().failure();
+}
+
+// ---------------------------------------------------------------------------
+// schedule
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_schedule_task_for_date() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Scheduled task");
+    daily(&dir)
+        .args(["schedule", &id, "2026-06-15"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("scheduled for 2026-06-15"));
+    daily(&dir)
+        .args(["day", "2026-06-15"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Scheduled task"));
+}
+
+#[test]
+fn test_schedule_task_not_found() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir).args(["schedule", "999", "2026-06-15"]).assert().failure();
+}
+
+// ---------------------------------------------------------------------------
+// streak
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_streak_no_daily_tasks() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir)
+        .args(["streak"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No daily habits found"));
+}
+
+#[test]
+fn test_streak_shows_daily_task() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Morning run");
+    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    daily(&dir)
+        .args(["streak"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Morning run"));
+}
+
+#[test]
+fn test_streak_after_completion() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Meditate");
+    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    daily(&dir).args(["complete", &id]).assert().success();
+    daily(&dir)
+        .args(["streak"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[done]"));
+}
+
+#[test]
+fn test_streak_specific_task_id() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Read");
+    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    daily(&dir)
+        .args(["streak", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Read"));
+}
+
+// ---------------------------------------------------------------------------
+// habits
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_habits_no_daily_tasks() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir)
+        .args(["habits"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No daily habits found"));
 }
 
 #[test]
 fn test_habits_shows_grid() {
     let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Yoga");
-    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    let id = add_task_get_id(&dir, "Yoga");
+    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    daily(&dir)
+        .args(["habits"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Yoga"))
+        .stdout(predicate::str::contains("[ ]"));
+}
+
+#[test]
+fn test_habits_completed_shows_plus() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Journal");
+    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    daily(&dir).args(["complete", &id]).assert().success();
+    daily(&dir)
+        .args(["habits"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[+]"));
+}
+
+#[test]
+fn test_habits_custom_days() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Run");
+    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    daily(&dir)
+        .args(["habits", "--days", "7"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("last 7 days"));
+}
+
+// ---------------------------------------------------------------------------
+// today-pdf
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_today_pdf_generates_file() {
+    let dir = TempDir::new().unwrap();
+    let out = dir.path().join("test.pdf");
+    daily(&dir)
+        .args(["today-pdf", "-o", out.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("PDF generated"));
+    assert!(out.exists());
+}
+
+// ---------------------------------------------------------------------------
+// claude / plan — require API key, just verify error without it
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_claude_without_api_key_fails_gracefully() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir)
+        .env_remove("ANTHROPIC_API_KEY")
+        .args(["claude", "What should I do today?"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("ANTHROPIC_API_KEY").or(predicate::str::contains("Failed")));
+}
+
+#[test]
+fn test_plan_without_api_key_fails_gracefully() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir)
+        .env_remove("ANTHROPIC_API_KEY")
+        .args(["plan", "do yoga mon, wed, fri at 7am"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("ANTHROPIC_API_KEY").or(predicate::str::contains("Failed")));
+}
+
+// ---------------------------------------------------------------------------
+// migrate
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_migrate_invalid_backend_fails() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir).args(["migrate", "--to", "postgres"]).assert().failure();
+}
+
+#[test]
+fn test_migrate_to_current_backend_fails() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir)
+        .args(["migrate", "--to", "text"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already uses the text backend"));
+}
+
+#[cfg(not(feature = "sqlite"))]
+#[test]
+fn test_migrate_to_sqlite_without_feature_fails() {
+    let dir = TempDir::new().unwrap();
+    add_task(&dir, "Keep me").success();
+    daily(&dir)
+        .args(["migrate", "--to", "sqlite"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--features sqlite"));
+    daily(&dir)
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Keep me"));
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_migrate_to_sqlite_and_back() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Stretch");
+    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    daily(&dir).args(["complete", &id]).assert().success();
+
+    daily(&dir)
+        .args(["migrate", "--to", "sqlite"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 task(s)"));
+    assert!(dir.path().join("daily.db").exists());
+
+    daily(&dir)
+        .args(["streak"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[done]"));
+    add_task(&dir, "Added in sqlite").success();
+
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    daily(&dir).args(["migrate", "--to", "text"]).assert().success();
+    assert!(!dir.path().join("daily.db").exists());
+    daily(&dir)
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Stretch"))
+        .stdout(predicate::str::contains("Added in sqlite"));
+}
+
+// ---------------------------------------------------------------------------
+// locking
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_concurrent_adds_get_unique_ids() {
+    let dir = TempDir::new().unwrap();
+    let bin = assert_cmd::cargo::cargo_bin("daily");
+    let children: Vec<_> = (0..8)
+        .map(|i| {
+            std::process::Command::new(&bin)
+                .arg("--data-dir")
+                .arg(dir.path())
+                .args(["add", &format!("Task {}", i)])
+                .stdout(std::process::Stdio::piped())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+
+    let mut ids = Vec::new();
+    for child in children {
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let id = stdout.lines().find_map(|l| l.strip_prefix("ID: ")).unwrap().trim().to_string();
+        ids.push(id);
+    }
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), 8);
+    assert_eq!(std::fs::read_dir(dir.path().join("tasks")).unwrap().count(), 8);
+}
+
+#[test]
+fn test_concurrent_edits_of_one_task_all_land() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Shared");
+    let bin = assert_cmd::cargo::cargo_bin("daily");
+    let children: Vec<_> = (0..8)
+        .map(|i| {
+            std::process::Command::new(&bin)
+                .arg("--data-dir")
+                .arg(dir.path())
+                .args(["tag", "add", &id, &format!("t{}", i)])
+                .stdout(std::process::Stdio::null())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+
+    // Each command loads, tags and saves the task; none may overwrite another.
+    let record = std::fs::read_to_string(only_task_file(&dir)).unwrap();
+    for i in 0..8 {
+        assert!(record.contains(&format!("t{}", i)), "{}", record);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// text format
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_multiline_description_survives_reload() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir)
+        .args(["add", "Write report", "-p", "low", "-d", "Outline\npriority: critical\nSend to team"])
+        .assert()
+        .success();
+    daily(&dir)
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(Priority: Low)"))
+        .stdout(predicate::str::contains("Send to team"));
+}
+
+// ---------------------------------------------------------------------------
+// doctor
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_doctor_clean() {
+    let dir = TempDir::new().unwrap();
+    add_task(&dir, "Fine").success();
+    daily(&dir)
+        .args(["doctor"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Checked 1 task(s)"))
+        .stdout(predicate::str::contains("No problems found."));
+}
+
+#[test]
+fn test_doctor_reports_then_fixes_dangling_day_task() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Short-lived");
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    daily(&dir).args(["schedule", &id, &today]).assert().success();
+    daily(&dir).args(["delete", &id]).assert().success();
+    daily(&dir).args(["trash", "empty"]).assert().success();
+
+    daily(&dir)
+        .args(["doctor"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(format!("Day {} lists missing task", today)))
+        .stdout(predicate::str::contains("[fixable with --fix]"));
+
+    daily(&dir)
+        .args(["doctor", "--fix"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[fixed]"));
+
+    daily(&dir)
+        .args(["doctor"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No problems found."));
+}
+
+#[test]
+fn test_doctor_reports_unparsable_file() {
+    let dir = TempDir::new().unwrap();
+    add_task(&dir, "Fine").success();
+    std::fs::write(dir.path().join("tasks").join("42.txt"), "title: no timestamps").unwrap();
+    daily(&dir)
+        .args(["doctor", "--fix"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("42.txt can't be read"));
+}
+
+// ---------------------------------------------------------------------------
+// handles
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_exact_handle_wins_over_longer_handles() {
+    let dir = TempDir::new().unwrap();
+    for n in 1..=11 {
+        add_task(&dir, &format!("Task {}", n)).success();
+    }
+    daily(&dir)
+        .args(["complete", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Task 1"));
+    daily(&dir)
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[✓] 1 - Task 1"))
+        .stdout(predicate::str::contains("[ ] 10 - Task 10"));
+}
+
+#[test]
+fn test_task_found_by_uuid_prefix() {
+    let dir = TempDir::new().unwrap();
+    add_task(&dir, "Stable").success();
+    let uuid = only_task_file(&dir).file_stem().unwrap().to_string_lossy().to_string();
+    assert_eq!(uuid.len(), 36, "{}", uuid);
+    daily(&dir)
+        .args(["priority", &uuid[..8], "high"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("'Stable' priority updated to High"));
+}
+
+#[test]
+fn test_habit_stack_names_anchor_by_handle() {
+    let dir = TempDir::new().unwrap();
+    let anchor = add_task_get_id(&dir, "Coffee");
+    daily(&dir)
+        .args(["add", "Journal", "--daily", "--after", &anchor])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("After: task {} (Coffee)", anchor)));
+    daily(&dir)
+        .args(["add", "Stretch", "--after", "99"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No task found"));
+}
+
+// ---------------------------------------------------------------------------
+// data format upgrades
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_new_data_dir_gets_format_version() {
+    let dir = TempDir::new().unwrap();
+    add_task(&dir, "First").success();
+    let version = std::fs::read_to_string(dir.path().join("format_version")).unwrap();
+    assert_eq!(version.trim(), "8");
+}
+
+#[test]
+fn test_old_data_dir_is_upgraded_on_first_use() {
+    let dir = TempDir::new().unwrap();
+    std::fs::create_dir_all(dir.path().join("tasks")).unwrap();
+    std::fs::write(
+        dir.path().join("tasks").join("1.txt"),
+        "id: 1\ntitle: Legacy task\npriority: High\ncategory: default\ncompleted: false\n\
+         created_at: 2026-04-01T06:00:00+00:00\nupdated_at: 2026-04-01T06:00:00+00:00\n\
+         is_daily: false\ntwo_minute: false",
+    )
+    .unwrap();
+
+    daily(&dir)
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Legacy task"))
+        .stdout(predicate::str::contains("1 - Legacy task"))
+        .stderr(predicate::str::contains("from format 1 to 8"));
+    let record = std::fs::read_to_string(only_task_file(&dir)).unwrap();
+    assert!(record.starts_with("format: 2"));
+    assert!(record.contains("handle: 1\n"));
+}
+
+#[test]
+fn test_refuses_data_from_newer_version() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("format_version"), "99\n").unwrap();
+    daily(&dir)
+        .args(["list"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("newer version of daily"));
+}
+
+// ---------------------------------------------------------------------------
+// backup / restore
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_backup_and_restore_into_new_dir() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Meditate");
+    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    daily(&dir).args(["complete", &id]).assert().success();
+    let file = dir.path().join("snapshot.json");
+    daily(&dir)
+        .args(["backup", "--output", file.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Backed up 1 task(s)"));
+
+    let other = TempDir::new().unwrap();
+    daily(&other)
+        .args(["restore", file.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 task(s)"));
+    daily(&other)
+        .args(["streak", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 day"));
+    // New tasks don't reuse restored IDs.
+    let next = add_task_get_id(&other, "Next");
+    assert_ne!(next, id);
+}
+
+#[test]
+fn test_restore_replace_drops_newer_tasks_and_can_be_undone() {
+    let dir = TempDir::new().unwrap();
+    add_task(&dir, "Before backup").success();
+    let file = dir.path().join("snapshot.json");
+    daily(&dir).args(["backup", "-o", file.to_str().unwrap()]).assert().success();
+    add_task(&dir, "After backup").success();
+
+    daily(&dir)
+        .args(["restore", file.to_str().unwrap(), "--replace"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 record(s) not in the backup removed."));
+    daily(&dir)
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Before backup"))
+        .stdout(predicate::str::contains("After backup").not());
+
+    daily(&dir).args(["undo"]).assert().success();
+    daily(&dir).args(["list"]).assert().success().stdout(predicate::str::contains("After backup"));
+}
+
+#[test]
+fn test_restore_rejects_damaged_backup_without_changes() {
+    let dir = TempDir::new().unwrap();
+    add_task(&dir, "Keep me").success();
+    let file = dir.path().join("snapshot.json");
+    daily(&dir).args(["backup", "-o", file.to_str().unwrap()]).assert().success();
+    let damaged = std::fs::read_to_string(&file).unwrap().replace("created_at: ", "created_at: not a date ");
+    std::fs::write(&file, damaged).unwrap();
+
+    let other = TempDir::new().unwrap();
+    add_task(&other, "Untouched").success();
+    daily(&other)
+        .args(["restore", file.to_str().unwrap(), "--replace"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Backup task #1 is invalid"));
+    daily(&other).args(["list"]).assert().success().stdout(predicate::str::contains("Untouched"));
+}
+
+#[test]
+fn test_auto_backup_before_destructive_command() {
+    let dir = TempDir::new().unwrap();
+    add_task(&dir, "Precious").success();
+    daily(&dir)
+        .env("DAILY_AUTO_BACKUPS", "3")
+        .args(["delete-all", "--yes"])
+        .assert()
+        .success();
+    let backups: Vec<_> = std::fs::read_dir(dir.path().join("backups")).unwrap().flatten().collect();
+    assert_eq!(backups.len(), 1);
+    assert!(std::fs::read_to_string(backups[0].path()).unwrap().contains("Precious"));
+}
+
+// ---------------------------------------------------------------------------
+// encrypt / decrypt
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_encrypt_hides_data_and_needs_passphrase() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Call the client");
+    daily(&dir).args(["complete", &id]).assert().success();
+
+    daily(&dir)
+        .args(["encrypt"])
+        .env("DAILY_PASSPHRASE", "hunter2")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Encrypted"));
+    let record = std::fs::read_to_string(only_task_file(&dir)).unwrap();
+    assert!(!record.contains("client"));
+    let log = std::fs::read_to_string(dir.path().join("history.log")).unwrap();
+    assert!(!log.contains("client"));
+
+    daily(&dir)
+        .args(["list"])
+        .env("DAILY_PASSPHRASE", "hunter2")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Call the client"));
+    daily(&dir)
+        .args(["list"])
+        .env("DAILY_PASSPHRASE", "wrong")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Wrong passphrase"));
+}
+
+#[test]
+fn test_encrypt_covers_pre_upgrade_backup() {
+    let dir = TempDir::new().unwrap();
+    std::fs::create_dir_all(dir.path().join("tasks")).unwrap();
+    std::fs::write(
+        dir.path().join("tasks").join("1.txt"),
+        "id: 1\ntitle: Secret client call\npriority: High\ncategory: default\ncompleted: false\n\
+         created_at: 2026-04-01T06:00:00+00:00\nupdated_at: 2026-04-01T06:00:00+00:00\n\
+         is_daily: false\ntwo_minute: false",
+    )
+    .unwrap();
+    daily(&dir).args(["list"]).assert().success();
+    daily(&dir).args(["encrypt"]).env("DAILY_PASSPHRASE", "hunter2").assert().success();
+
+    let copy = std::fs::read_dir(dir.path()).unwrap().flatten()
+        .find(|e| e.file_name().to_string_lossy().starts_with("backup-"))
+        .unwrap()
+        .path();
+    let record = std::fs::read_to_string(copy.join("tasks").join("1.txt")).unwrap();
+    assert!(!record.contains("Secret"), "{}", record);
+}
+
+#[test]
+fn test_encrypted_dir_keeps_working_and_decrypts() {
+    let dir = TempDir::new().unwrap();
+    add_task(&dir, "Before").success();
+    daily(&dir).args(["encrypt"]).env("DAILY_PASSPHRASE", "pw").assert().success();
+
+    daily(&dir).args(["add", "After"]).env("DAILY_PASSPHRASE", "pw").assert().success();
+    daily(&dir).args(["undo"]).env("DAILY_PASSPHRASE", "pw").assert().success();
+    daily(&dir).args(["redo"]).env("DAILY_PASSPHRASE", "pw").assert().success();
+
     daily(&dir)
-        .args(["habits"])
+        .args(["decrypt"])
+        .env("DAILY_PASSPHRASE", "pw")
         .assert()
         .success()
-        .stdout(predicate::str::contains("Yoga"))
-        .stdout(predicate::str::contains("[ ]"));
+        .stdout(predicate::str::contains("Decrypted"));
+    assert!(!dir.path().join("encryption.json").exists());
+    daily(&dir)
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Before"))
+        .stdout(predicate::str::contains("After"));
 }
 
 #[test]
-fn test_habits_completed_shows_plus() {
+fn test_keyfile_unlocks_encrypted_dir() {
     let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Journal");
-    daily(&dir).args(["daily", &id, "true"]).assert().success();
-    daily(&dir).args(["complete", &id]).assert().success();
+    let keys = TempDir::new().unwrap();
+    let keyfile = keys.path().join("daily.key");
+    std::fs::write(&keyfile, "from a file\n").unwrap();
+    add_task(&dir, "Private").success();
+    daily(&dir).args(["encrypt"]).env("DAILY_KEYFILE", &keyfile).assert().success();
+
     daily(&dir)
-        .args(["habits"])
+        .args(["config", "set", "encryption.keyfile", keyfile.to_str().unwrap()])
+        .assert()
+        .success();
+    daily(&dir).args(["list"]).assert().success().stdout(predicate::str::contains("Private"));
+    daily(&dir)
+        .args(["list"])
+        .env("DAILY_PASSPHRASE", "from a file")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_encrypted_backup_restores_elsewhere_with_passphrase() {
+    let dir = TempDir::new().unwrap();
+    add_task(&dir, "Secret plan").success();
+    daily(&dir).args(["encrypt"]).env("DAILY_PASSPHRASE", "pw").assert().success();
+    let backup = dir.path().join("out.json");
+    daily(&dir)
+        .args(["backup", "-o", backup.to_str().unwrap()])
+        .env("DAILY_PASSPHRASE", "pw")
+        .assert()
+        .success();
+    assert!(!std::fs::read_to_string(&backup).unwrap().contains("Secret"));
+
+    let other = TempDir::new().unwrap();
+    daily(&other).args(["restore", backup.to_str().unwrap()]).env("DAILY_PASSPHRASE", "nope").assert().failure();
+    daily(&other).args(["restore", backup.to_str().unwrap()]).env("DAILY_PASSPHRASE", "pw").assert().success();
+    daily(&other).args(["list"]).assert().success().stdout(predicate::str::contains("Secret plan"));
+}
+
+#[test]
+fn test_migrate_refuses_encrypted_dir() {
+    let dir = TempDir::new().unwrap();
+    add_task(&dir, "Task").success();
+    daily(&dir).args(["encrypt"]).env("DAILY_PASSPHRASE", "pw").assert().success();
+    daily(&dir)
+        .args(["migrate", "--to", "sqlite"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("daily decrypt"));
+}
+
+// ---------------------------------------------------------------------------
+// merge
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_merge_renumbers_colliding_ids() {
+    let here = TempDir::new().unwrap();
+    let there = TempDir::new().unwrap();
+    add_task(&here, "Written here").success();
+    let id = add_task_get_id(&there, "Written there");
+    daily(&there).args(["complete", &id]).assert().success();
+
+    daily(&here)
+        .args(["merge", there.path().to_str().unwrap()])
         .assert()
         .success()
-        .stdout(predicate::str::contains("[+]"));
+        .stdout(predicate::str::contains("added task 1 'Written there' as 2 (its ID was taken)"))
+        .stdout(predicate::str::contains("1 history entry added"));
+    daily(&here)
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 - Written here"))
+        .stdout(predicate::str::contains("2 - Written there"));
+
+    // Nothing new the second time, and the other directory is untouched.
+    daily(&here)
+        .args(["merge", there.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("added task").not())
+        .stdout(predicate::str::contains("1 already here"));
+    daily(&there).args(["list"]).assert().success().stdout(predicate::str::contains("Written here").not());
 }
 
 #[test]
-fn test_habits_custom_days() {
+fn test_merge_can_be_undone() {
+    let here = TempDir::new().unwrap();
+    let there = TempDir::new().unwrap();
+    add_task(&there, "From elsewhere").success();
+    daily(&here).args(["merge", there.path().to_str().unwrap()]).assert().success();
+    daily(&here).args(["undo"]).assert().success();
+    daily(&here).args(["list"]).assert().success().stdout(predicate::str::contains("From elsewhere").not());
+}
+
+#[test]
+fn test_merge_leaves_older_dir_as_it_was() {
+    let here = TempDir::new().unwrap();
+    let there = TempDir::new().unwrap();
+    let legacy = "id: 1\ntitle: Legacy task\npriority: High\ncategory: default\ncompleted: false\n\
+         created_at: 2026-04-01T06:00:00+00:00\nupdated_at: 2026-04-01T06:00:00+00:00\n\
+         is_daily: false\ntwo_minute: false";
+    std::fs::create_dir_all(there.path().join("tasks")).unwrap();
+    std::fs::write(there.path().join("tasks").join("1.txt"), legacy).unwrap();
+
+    daily(&here).args(["merge", there.path().to_str().unwrap()]).assert().success();
+    daily(&here).args(["list"]).assert().success().stdout(predicate::str::contains("1 - Legacy task"));
+
+    // Not upgraded, stamped or backed up: an older build can still use it.
+    let names: Vec<_> = std::fs::read_dir(there.path()).unwrap().flatten().map(|e| e.file_name()).collect();
+    assert_eq!(names, vec!["tasks"]);
+    assert_eq!(std::fs::read_to_string(there.path().join("tasks").join("1.txt")).unwrap(), legacy);
+}
+
+#[test]
+fn test_merge_refuses_dir_from_newer_version() {
+    let here = TempDir::new().unwrap();
+    let there = TempDir::new().unwrap();
+    std::fs::write(there.path().join("format_version"), "99\n").unwrap();
+    daily(&here)
+        .args(["merge", there.path().to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("newer version of daily"));
+}
+
+#[test]
+fn test_merge_missing_dir_fails() {
     let dir = TempDir::new().unwrap();
-    let id = add_task_get_id(&dir, "Run");
-    daily(&dir).args(["daily", &id, "true"]).assert().success();
     daily(&dir)
-        .args(["habits", "--days", "7"])
+        .args(["merge", "/definitely/not/here"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not a directory"));
+}
+
+// ---------------------------------------------------------------------------
+// sync
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_sync_between_two_machines() {
+    let remote = TempDir::new().unwrap();
+    let remote_path = remote.path().join("daily.git");
+    let laptop = TempDir::new().unwrap();
+    let desktop = TempDir::new().unwrap();
+
+    add_task(&laptop, "Written on the laptop").success();
+    daily(&laptop)
+        .args(["sync", "init", remote_path.to_str().unwrap()])
         .assert()
         .success()
-        .stdout(predicate::str::contains("last 7 days"));
+        .stdout(predicate::str::contains("Pushed local changes."));
+    daily(&desktop).args(["sync", "init", remote_path.to_str().unwrap()]).assert().success();
+    daily(&desktop).args(["list"]).assert().success().stdout(predicate::str::contains("Written on the laptop"));
+
+    // Both sides change and complete things, then sync both ways.
+    let id = add_task_get_id(&desktop, "Written on the desktop");
+    daily(&desktop).args(["complete", &id]).assert().success();
+    daily(&laptop).args(["category", "home"]).assert().success();
+    daily(&laptop).args(["complete", "1"]).assert().success();
+    daily(&desktop).args(["sync"]).assert().success();
+    daily(&laptop).args(["sync"]).assert().success().stdout(predicate::str::contains("Pulled"));
+    daily(&desktop).args(["sync"]).assert().success();
+
+    for dir in [&laptop, &desktop] {
+        daily(dir)
+            .args(["list"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Written on the laptop"))
+            .stdout(predicate::str::contains("Written on the desktop"));
+        let history = std::fs::read_to_string(dir.path().join("history.log")).unwrap();
+        assert_eq!(history.lines().count(), 2, "{}", history);
+        assert!(!history.contains("<<<<<<<"));
+    }
+    daily(&desktop).args(["sync"]).assert().success().stdout(predicate::str::contains("Already up to date."));
+}
+
+#[test]
+fn test_sync_keeps_newest_task_edit() {
+    let remote = TempDir::new().unwrap();
+    let remote_path = remote.path().join("daily.git");
+    let laptop = TempDir::new().unwrap();
+    let desktop = TempDir::new().unwrap();
+    let id = add_task_get_id(&laptop, "Plan trip");
+    daily(&laptop).args(["sync", "init", remote_path.to_str().unwrap()]).assert().success();
+    daily(&desktop).args(["sync", "init", remote_path.to_str().unwrap()]).assert().success();
+
+    daily(&laptop).args(["priority", &id, "low"]).assert().success();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    daily(&desktop).args(["priority", &id, "critical"]).assert().success();
+    daily(&laptop).args(["sync"]).assert().success();
+    daily(&desktop).args(["sync"]).assert().success();
+    daily(&laptop).args(["sync"]).assert().success();
+
+    for dir in [&laptop, &desktop] {
+        let record = std::fs::read_to_string(only_task_file(dir)).unwrap();
+        assert!(record.contains("priority: Critical"), "{}", record);
+        assert!(!record.contains("<<<<<<<"));
+    }
+}
+
+#[test]
+fn test_sync_merges_encrypted_records() {
+    let remote = TempDir::new().unwrap();
+    let remote_path = remote.path().join("daily.git");
+    let laptop = TempDir::new().unwrap();
+    let desktop = TempDir::new().unwrap();
+    let locked = |dir: &TempDir| {
+        let mut cmd = daily(dir);
+        cmd.env("DAILY_PASSPHRASE", "hunter2");
+        cmd
+    };
+    let id = add_task_get_id(&laptop, "Plan trip");
+    locked(&laptop).args(["encrypt"]).assert().success();
+    locked(&laptop).args(["sync", "init", remote_path.to_str().unwrap()]).assert().success();
+    locked(&desktop).args(["sync", "init", remote_path.to_str().unwrap()]).assert().success();
+
+    locked(&laptop).args(["priority", &id, "low"]).assert().success();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    locked(&desktop).args(["priority", &id, "critical"]).assert().success();
+    locked(&laptop).args(["sync"]).assert().success();
+    locked(&desktop).args(["sync"]).assert().success().stdout(predicate::str::contains("Pulled"));
+    locked(&laptop).args(["sync"]).assert().success();
+
+    for dir in [&laptop, &desktop] {
+        let record = std::fs::read_to_string(only_task_file(dir)).unwrap();
+        assert!(record.starts_with("enc1:"), "{}", record);
+        locked(dir)
+            .args(["show", &id])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Critical"));
+    }
+}
+
+#[test]
+fn test_sync_logs_habit_done_on_both_machines_once() {
+    let remote = TempDir::new().unwrap();
+    let remote_path = remote.path().join("daily.git");
+    let laptop = TempDir::new().unwrap();
+    let desktop = TempDir::new().unwrap();
+    let id = add_task_get_id(&laptop, "Meditate");
+    daily(&laptop).args(["daily", &id, "true"]).assert().success();
+    daily(&laptop).args(["sync", "init", remote_path.to_str().unwrap()]).assert().success();
+    daily(&desktop).args(["sync", "init", remote_path.to_str().unwrap()]).assert().success();
+
+    daily(&laptop).args(["complete", &id]).assert().success();
+    daily(&desktop).args(["complete", &id]).assert().success();
+    daily(&laptop).args(["sync"]).assert().success();
+    daily(&desktop).args(["sync"]).assert().success();
+    daily(&laptop).args(["sync"]).assert().success();
+
+    for dir in [&laptop, &desktop] {
+        let log = std::fs::read_to_string(dir.path().join("daily.log")).unwrap();
+        assert_eq!(log.lines().count(), 1, "{}", log);
+    }
+}
+
+#[test]
+fn test_sync_merge_driver_runs_from_path_with_quote() {
+    let remote = TempDir::new().unwrap();
+    let remote_path = remote.path().join("daily.git");
+    let laptop = TempDir::new().unwrap();
+    let desktop = TempDir::new().unwrap();
+    let bin_dir = TempDir::new().unwrap();
+    let bin = bin_dir.path().join("o'brien").join("daily");
+    std::fs::create_dir_all(bin.parent().unwrap()).unwrap();
+    std::fs::copy(assert_cmd::cargo::cargo_bin("daily"), &bin).unwrap();
+    let quoted = |dir: &TempDir| {
+        let mut cmd = Command::new(&bin);
+        cmd.arg("--data-dir").arg(dir.path());
+        cmd
+    };
+
+    let id = add_task_get_id(&laptop, "Plan trip");
+    quoted(&laptop).args(["sync", "init", remote_path.to_str().unwrap()]).assert().success();
+    quoted(&desktop).args(["sync", "init", remote_path.to_str().unwrap()]).assert().success();
+    daily(&laptop).args(["priority", &id, "low"]).assert().success();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    daily(&desktop).args(["priority", &id, "critical"]).assert().success();
+    quoted(&laptop).args(["sync"]).assert().success();
+    quoted(&desktop).args(["sync"]).assert().success().stdout(predicate::str::contains("Pulled"));
+
+    let record = std::fs::read_to_string(only_task_file(&desktop)).unwrap();
+    assert!(record.contains("priority: Critical"), "{}", record);
+}
+
+#[test]
+fn test_sync_renumbers_handles_taken_on_both_machines() {
+    let remote = TempDir::new().unwrap();
+    let remote_path = remote.path().join("daily.git");
+    let laptop = TempDir::new().unwrap();
+    let desktop = TempDir::new().unwrap();
+    daily(&laptop).args(["sync", "init", remote_path.to_str().unwrap()]).assert().success();
+    daily(&desktop).args(["sync", "init", remote_path.to_str().unwrap()]).assert().success();
+
+    assert_eq!(add_task_get_id(&laptop, "Laptop task"), "1");
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    assert_eq!(add_task_get_id(&desktop, "Desktop task"), "1");
+    daily(&laptop).args(["sync"]).assert().success();
+    daily(&desktop)
+        .args(["sync"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 tasks share the handle 1"));
+    daily(&laptop).args(["sync"]).assert().success();
+
+    for dir in [&laptop, &desktop] {
+        daily(dir)
+            .args(["list"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("1 - Laptop task"))
+            .stdout(predicate::str::contains("2 - Desktop task"));
+    }
+}
+
+#[test]
+fn test_sync_without_init_fails() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir)
+        .args(["sync"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("daily sync init"));
 }
 
 // ---------------------------------------------------------------------------
-// today-pdf
+// profiles
 // ---------------------------------------------------------------------------
 
 #[test]
-fn test_today_pdf_generates_file() {
+fn test_profiles_keep_tasks_apart() {
     let dir = TempDir::new().unwrap();
-    let out = dir.path().join("test.pdf");
+    add_task(&dir, "Personal errand").success();
+    daily(&dir).args(["profile", "create", "work"]).assert().success();
+    daily(&dir).args(["--profile", "work", "add", "Quarterly report"]).assert().success();
+
     daily(&dir)
-        .args(["today-pdf", "-o", out.to_str().unwrap()])
+        .args(["list"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("PDF generated"));
-    assert!(out.exists());
+        .stdout(predicate::str::contains("Personal errand"))
+        .stdout(predicate::str::contains("Quarterly report").not());
+    daily(&dir)
+        .env("DAILY_PROFILE", "work")
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Quarterly report"))
+        .stdout(predicate::str::contains("Personal errand").not());
+}
+
+#[test]
+fn test_profile_switch_and_list() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir).args(["profile", "create", "work"]).assert().success();
+    daily(&dir).args(["profile", "switch", "work"]).assert().success();
+    add_task(&dir, "Standup").success();
+    daily(&dir)
+        .args(["profile", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("  default"))
+        .stdout(predicate::str::contains("* work"));
+    daily(&dir)
+        .args(["--profile", "default", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No tasks found."));
+
+    // The active profile can't be deleted.
+    daily(&dir).args(["profile", "delete", "work", "--yes"]).assert().failure();
+    daily(&dir).args(["profile", "switch", "default"]).assert().success();
+    daily(&dir).args(["profile", "delete", "work", "--yes"]).assert().success();
+    daily(&dir).args(["--profile", "work", "list"]).assert().failure();
+}
+
+#[test]
+fn test_unknown_profile_is_an_error() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir)
+        .args(["--profile", "nope", "list"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No profile named 'nope'"));
+}
+
+#[test]
+fn test_today_all_profiles() {
+    let dir = TempDir::new().unwrap();
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    daily(&dir).args(["add", "Home chore", "--due", &today]).assert().success();
+    daily(&dir).args(["profile", "create", "work"]).assert().success();
+    daily(&dir).args(["--profile", "work", "add", "Ship release", "--due", &today]).assert().success();
+
+    daily(&dir)
+        .args(["today", "--all-profiles"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Profile: default"))
+        .stdout(predicate::str::contains("Home chore"))
+        .stdout(predicate::str::contains("Profile: work"))
+        .stdout(predicate::str::contains("Ship release"));
 }
 
 // ---------------------------------------------------------------------------
-// claude / plan — require API key, just verify error without it
+// config
 // ---------------------------------------------------------------------------
 
 #[test]
-fn test_claude_without_api_key_fails_gracefully() {
+fn test_config_set_get_and_list() {
     let dir = TempDir::new().unwrap();
     daily(&dir)
-        .env_remove("ANTHROPIC_API_KEY")
-        .args(["claude", "What should I do today?"])
+        .args(["config", "get", "defaults.priority"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("ANTHROPIC_API_KEY").or(predicate::str::contains("Failed")));
+        .success()
+        .stdout("medium\n");
+    daily(&dir)
+        .args(["config", "set", "defaults.priority", "high"])
+        .assert()
+        .success()
+        .stdout("defaults.priority = high\n");
+    assert!(dir.path().join("config.toml").exists());
+    daily(&dir)
+        .args(["config", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("defaults.priority = high"))
+        .stdout(predicate::str::contains("habits.grid_days = 21"));
 }
 
 #[test]
-fn test_plan_without_api_key_fails_gracefully() {
+fn test_config_rejects_bad_values() {
     let dir = TempDir::new().unwrap();
+    daily(&dir).args(["config", "set", "defaults.priority", "urgent"]).assert().failure();
+    daily(&dir).args(["config", "set", "no.such", "1"]).assert().failure();
     daily(&dir)
-        .env_remove("ANTHROPIC_API_KEY")
-        .args(["plan", "do yoga mon, wed, fri at 7am"])
+        .args(["config", "set", "daemon.time", "9am"])
         .assert()
         .failure()
-        .stderr(predicate::str::contains("ANTHROPIC_API_KEY").or(predicate::str::contains("Failed")));
+        .stderr(predicate::str::contains("Invalid value '9am' for daemon.time"));
+}
+
+#[test]
+fn test_config_defaults_apply_and_flags_win() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir).args(["config", "set", "defaults.priority", "critical"]).assert().success();
+    daily(&dir).args(["config", "set", "defaults.category", "work"]).assert().success();
+    add_task(&dir, "Configured").success();
+    daily(&dir).args(["add", "Flagged", "-p", "low", "-c", "home"]).assert().success();
+
+    daily(&dir)
+        .args(["list", "-c", "work"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Configured (Priority: Critical)"));
+    daily(&dir)
+        .args(["list", "-c", "home"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Flagged (Priority: Low)"));
+}
+
+#[test]
+fn test_config_grid_days_and_date_format() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Stretch");
+    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    daily(&dir).args(["config", "set", "habits.grid_days", "7"]).assert().success();
+    daily(&dir)
+        .args(["habits"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("last 7 days"));
+    daily(&dir)
+        .args(["habits", "--days", "14"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("last 14 days"));
+
+    daily(&dir).args(["config", "set", "display.date_format", "%d/%m/%Y"]).assert().success();
+    daily(&dir)
+        .args(["today"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Tasks for {}", Local::now().format("%d/%m/%Y"))));
+}
+
+#[test]
+fn test_config_auto_archive() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Old chore");
+    daily(&dir).args(["complete", &id]).assert().success();
+    // Pretend it was completed long ago.
+    let path = only_task_file(&dir);
+    let old = std::fs::read_to_string(&path).unwrap();
+    let updated = old.lines().find(|l| l.starts_with("updated_at: ")).unwrap().to_string();
+    std::fs::write(&path, old.replace(&updated, "updated_at: 2020-01-01T00:00:00+00:00")).unwrap();
+
+    daily(&dir).args(["config", "set", "archive.auto_after_days", "30"]).assert().success();
+    daily(&dir).args(["list"]).assert().success().stdout(predicate::str::contains("No tasks found."));
+    daily(&dir).args(["list", "--archived"]).assert().success().stdout(predicate::str::contains("Old chore"));
+}
+
+// ---------------------------------------------------------------------------
+// undo / redo / journal
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_undo_delete_all_restores_tasks() {
+    let dir = TempDir::new().unwrap();
+    add_task(&dir, "Buy milk").success();
+    add_task(&dir, "Call mom").success();
+    daily(&dir).args(["delete-all", "--yes"]).assert().success();
+
+    daily(&dir)
+        .args(["undo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Undid #3: daily delete-all --yes"));
+    daily(&dir)
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Buy milk"))
+        .stdout(predicate::str::contains("Call mom"));
+
+    daily(&dir).args(["redo"]).assert().success().stdout(predicate::str::contains("Redid #3"));
+    daily(&dir)
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Buy milk").not());
+}
+
+#[test]
+fn test_undo_complete_removes_log_entry() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Meditate");
+    daily(&dir).args(["daily", &id, "true"]).assert().success();
+    daily(&dir).args(["complete", &id]).assert().success();
+    daily(&dir).args(["undo"]).assert().success();
+    daily(&dir)
+        .args(["streak", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[done]").not());
+}
+
+#[test]
+fn test_journal_lists_commands_newest_first() {
+    let dir = TempDir::new().unwrap();
+    let id = add_task_get_id(&dir, "Read");
+    daily(&dir).args(["priority", &id, "high"]).assert().success();
+    daily(&dir).args(["undo"]).assert().success();
+
+    daily(&dir)
+        .args(["journal"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("daily priority {} high [undone]", id)))
+        .stdout(predicate::str::contains("changed task"))
+        .stdout(predicate::str::contains("daily add Read"));
+}
+
+#[test]
+fn test_undo_with_nothing_to_undo() {
+    let dir = TempDir::new().unwrap();
+    daily(&dir).args(["undo"]).assert().success().stdout(predicate::str::contains("Nothing to undo."));
+    daily(&dir).args(["redo"]).assert().success().stdout(predicate::str::contains("Nothing to redo."));
+}
+
+#[test]
+fn test_read_only_commands_are_not_journaled() {
+    let dir = TempDir::new().unwrap();
+    add_task(&dir, "Read").success();
+    daily(&dir).args(["list"]).assert().success();
+    daily(&dir).args(["today"]).assert().success();
+    let entries = std::fs::read_dir(dir.path().join("journal"))
+        .unwrap()
+        .filter(|e| e.as_ref().unwrap().path().extension().is_some_and(|x| x == "json"))
+        .count();
+    assert_eq!(entries, 1);
 }